@@ -78,6 +78,13 @@ const (
 	AccessHintPrefetch   = 3 // Prefetch next slot
 )
 
+// =================================
+// Index flags
+// =================================
+const (
+	IndexFlagMetadataRaw = 1 << 4 // Metadata is stored as plain JSON rather than gzip
+)
+
 // =================================
 // Capability flags
 // =================================
@@ -6,14 +6,20 @@ import (
 	"strings"
 )
 
-// Exit codes for different error types
+// Exit codes for different error types, shared with the Rust launcher
 const (
-	ExitPanic           = 101
-	ExitPSPFError       = 102
-	ExitExtractionError = 103
-	ExitExecutionError  = 104
-	ExitInvalidArgs     = 105
-	ExitIOError         = 106
+	ExitPanic              = 101
+	ExitPSPFError          = 102
+	ExitExtractionError    = 103
+	ExitExecutionError     = 104
+	ExitInvalidArgs        = 105
+	ExitIOError            = 106
+	ExitSignatureError     = 107
+	ExitBuildError         = 108
+	ExitConfigError        = 109
+	ExitDependencyError    = 110
+	ExitVerificationFailed = 111
+	ExitChecksumError      = 112 // Checksum mismatch in package data or the cached workenv
 )
 
 // ValidationLevel represents different levels of security validation
@@ -166,21 +166,13 @@ func (r *Reader) ReadMetadata() (*Metadata, error) {
 		return nil, err
 	}
 
-	// Decompress the gzipped JSON metadata
-	gr, err := gzip.NewReader(bytes.NewReader(archiveData))
+	jsonData, err := decodeMetadata(index, archiveData)
 	if err != nil {
 		return nil, err
 	}
-	defer func() {
-		if err := gr.Close(); err != nil {
-			// Log error but don't fail - already returning data
-			_ = err
-		}
-	}()
 
-	// Read and decode JSON directly
 	var metadata Metadata
-	if err := json.NewDecoder(gr).Decode(&metadata); err != nil {
+	if err := json.Unmarshal(jsonData, &metadata); err != nil {
 		return nil, err
 	}
 
@@ -188,6 +180,25 @@ func (r *Reader) ReadMetadata() (*Metadata, error) {
 	return &metadata, nil
 }
 
+// decodeMetadata returns the metadata JSON, which is gzipped unless the index
+// flags it as stored plain
+func decodeMetadata(index *PSPFIndex, data []byte) ([]byte, error) {
+	if index.Flags&IndexFlagMetadataRaw != 0 {
+		return data, nil
+	}
+	gr, err := gzip.NewReader(bytes.NewReader(data))
+	if err != nil {
+		return nil, err
+	}
+	defer func() {
+		if err := gr.Close(); err != nil {
+			// Log error but don't fail - already returning data
+			_ = err
+		}
+	}()
+	return io.ReadAll(gr)
+}
+
 // ReadMetadataArchive reads the raw metadata archive
 func (r *Reader) ReadMetadataArchive() ([]byte, error) {
 	index, err := r.ReadIndex()
@@ -2,7 +2,6 @@ package format_2025
 
 import (
 	"bytes"
-	"compress/gzip"
 	"crypto/ed25519"
 	"fmt"
 	"io"
@@ -92,20 +91,7 @@ func (r *Reader) VerifyIntegritySeal() (bool, error) {
 	}
 
 	// Extract psp.json and signature from archive
-	// Decompress the gzipped JSON metadata
-	gr, err := gzip.NewReader(bytes.NewReader(archiveData))
-	if err != nil {
-		return false, err
-	}
-	defer func() {
-		if err := gr.Close(); err != nil {
-			// Log error but don't fail - already returning data
-			_ = err
-		}
-	}()
-
-	// Read the JSON metadata
-	jsonData, err := io.ReadAll(gr)
+	jsonData, err := decodeMetadata(index, archiveData)
 	if err != nil {
 		return false, err
 	}
@@ -0,0 +1,86 @@
+//! Build benchmark: slot I/O buffer sizes
+//!
+//! Builds a package around one large uncompressed slot with different
+//! `BuildOptions::io_buffer_size` values, covering both hashing the source and
+//! copying it into the package. `default` leaves copying to the OS.
+//!
+//! Run with: `cargo bench --bench slot_copy`
+//! Set `FLAVOR_BENCH_SLOT_MB` to change the slot size (default 256), e.g. to a
+//! few thousand to measure multi-GB slots on the storage being tuned for.
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use flavor::api::{BuildOptions, build_package};
+use std::fs;
+use std::io::Write;
+
+const DEFAULT_SLOT_MB: usize = 256;
+
+const BUFFER_SIZES: [(&str, Option<usize>); 5] = [
+    ("default", None),
+    ("64KiB", Some(64 * 1024)),
+    ("1MiB", Some(1024 * 1024)),
+    ("8MiB", Some(8 * 1024 * 1024)),
+    ("64MiB", Some(64 * 1024 * 1024)),
+];
+
+fn bench_slot_copy(c: &mut Criterion) {
+    let slot_mb = std::env::var("FLAVOR_BENCH_SLOT_MB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_SLOT_MB);
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+
+    // Incompressible-looking data, written a chunk at a time
+    let slot = root.join("slot.bin");
+    let mut file = fs::File::create(&slot).unwrap();
+    let mut chunk = vec![0u8; 1024 * 1024];
+    let mut state: u32 = 0x2025_0001;
+    for _ in 0..slot_mb {
+        for byte in chunk.iter_mut() {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            *byte = (state >> 24) as u8;
+        }
+        file.write_all(&chunk).unwrap();
+    }
+    drop(file);
+
+    fs::write(root.join("launcher"), vec![0u8; 1024]).unwrap();
+    let manifest = root.join("manifest.json");
+    let manifest_json = serde_json::json!({
+        "package": {"name": "slot-copy-bench", "version": "1.0"},
+        "execution": {"command": "/bin/true"},
+        "slots": [{"id": "data", "source": slot, "target": "slot.bin"}]
+    });
+    fs::write(&manifest, manifest_json.to_string()).unwrap();
+    let output = root.join("out.psp");
+
+    let mut group = c.benchmark_group("slot_copy");
+    group.throughput(Throughput::Bytes((slot_mb * 1024 * 1024) as u64));
+    group.sample_size(10);
+
+    for (name, io_buffer_size) in BUFFER_SIZES {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &io_buffer_size,
+            |b, &io_buffer_size| {
+                b.iter(|| {
+                    let options = BuildOptions {
+                        launcher_bin: Some(root.join("launcher")),
+                        key_seed: Some("bench".to_string()),
+                        io_buffer_size,
+                        ..BuildOptions::default()
+                    };
+                    build_package(&manifest, &output, options).unwrap();
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_slot_copy);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+//! Slot decompression benchmark: gzip vs lz4
+//!
+//! Compares the extraction-side cost of the two slot compression operations on a
+//! buffer shaped like a runtime slot (mixed text and binary-ish content).
+//!
+//! Run with: `cargo bench --bench slot_decompression`
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flavor::psp::format_2025::extraction::decompress_lz4;
+use std::hint::black_box;
+use std::io::{Read, Write};
+
+const SLOT_SIZE: usize = 8 * 1024 * 1024;
+
+/// Build a deterministic, moderately compressible payload
+fn runtime_like_payload() -> Vec<u8> {
+    let mut data = Vec::with_capacity(SLOT_SIZE);
+    let mut state: u32 = 0x2025_0001;
+    let mut line = 0usize;
+    while data.len() < SLOT_SIZE {
+        if line % 4 == 0 {
+            // Pseudo-random block (bytecode, shared objects)
+            for _ in 0..256 {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                data.push((state >> 24) as u8);
+            }
+        } else {
+            // Source-like text
+            data.extend_from_slice(
+                format!("def function_{line}(arg):\n    return arg * {line}\n").as_bytes(),
+            );
+        }
+        line += 1;
+    }
+    data.truncate(SLOT_SIZE);
+    data
+}
+
+fn bench_decompression(c: &mut Criterion) {
+    let payload = runtime_like_payload();
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+    gz.write_all(&payload).unwrap();
+    let gzipped = gz.finish().unwrap();
+
+    let mut lz = lz4_flex::frame::FrameEncoder::new(Vec::new());
+    lz.write_all(&payload).unwrap();
+    let lz4ed = lz.finish().unwrap();
+
+    let mut group = c.benchmark_group("slot_decompression");
+    group.throughput(Throughput::Bytes(payload.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("gzip", |b| {
+        b.iter(|| {
+            let mut out = Vec::with_capacity(SLOT_SIZE);
+            GzDecoder::new(black_box(gzipped.as_slice()))
+                .read_to_end(&mut out)
+                .unwrap();
+            out
+        });
+    });
+
+    group.bench_function("lz4", |b| {
+        b.iter(|| decompress_lz4(black_box(&lz4ed)).unwrap());
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_decompression);
+criterion_main!(benches);
@@ -2,6 +2,7 @@
 
 use crate::exceptions::{FlavorError, Result};
 use crate::psp::{self, PackageFormat};
+use std::collections::HashMap;
 use std::path::Path;
 
 /// Options for building a package
@@ -19,6 +20,216 @@ pub struct BuildOptions {
     pub key_seed: Option<String>,
     /// Base directory for workenv resolution
     pub workenv_base: Option<std::path::PathBuf>,
+    /// Use CRC32C instead of Adler-32 for the index checksum
+    pub crc32c_index: bool,
+    /// Record a CRC32C of the whole file in the index so launchers can reject
+    /// corrupted downloads before verifying the signature
+    pub file_crc: bool,
+    /// Compression level (0-9) for inline slot content that doesn't set
+    /// `compression_level` (default 9, best); slot sources are stored as they
+    /// are, so neither level applies to them
+    pub default_compression_level: Option<u32>,
+    /// Compression level (0-9) for package metadata, independent of slot levels
+    pub metadata_compression_level: Option<u32>,
+    /// Write a JSON lockfile describing the build contents to this path
+    pub emit_lockfile: Option<std::path::PathBuf>,
+    /// Slot offset alignment in bytes (power of two, default 8); a multiple of
+    /// the page size marks the package page-aligned for mmap
+    pub slot_alignment: Option<u64>,
+    /// SBOM JSON (CycloneDX or SPDX) to embed as the `__sbom__` slot
+    pub sbom: Option<std::path::PathBuf>,
+    /// Fail the build if any slot's stored size exceeds this many bytes
+    pub max_slot_size: Option<u64>,
+    /// Fail the build if the finished package exceeds this many bytes
+    pub max_package_size: Option<u64>,
+    /// External signer for the metadata signature; when set, the key seed and
+    /// key paths above are ignored
+    pub key_provider: Option<Box<dyn psp::format_2025::keys::KeyProvider>>,
+    /// Rekor transparency log entry (JSON) for this build's metadata signature,
+    /// stored in the index reserved space. The entry must match the signature
+    /// the build produces, so it is obtained from an earlier reproducible build.
+    pub rekor_entry: Option<String>,
+    /// Validate the manifest and slots and return a size summary
+    /// ([`DryRunReport`](crate::format_2025::builder::DryRunReport)) without
+    /// creating the output file
+    pub dry_run: bool,
+    /// Write only the launcher to the output path and the package data to a
+    /// sibling `.pspfdata` file (see [`psp::format_2025::detached`])
+    pub detached_data: bool,
+    /// Fail the build, rather than warn, when a manifest command contains
+    /// shell metacharacters (commands never run in a shell)
+    pub strict_commands: bool,
+    /// Build a delta package against this base package: slots whose stored
+    /// bytes match a base slot are recorded as inherited instead of stored,
+    /// and the launcher links them from the base's workenv
+    pub base_package: Option<std::path::PathBuf>,
+    /// Encrypt every stored slot with this algorithm and key; the launcher
+    /// then needs the same key to extract
+    pub encryption: Option<psp::format_2025::encryption::SlotEncryption>,
+    /// Unix mode for the output file(s) instead of the default 0o700
+    pub output_permissions: Option<u32>,
+    /// Clear the execute bits of the output mode, for packages that are never
+    /// run directly or whose permissions are set later
+    pub no_exec: bool,
+    /// Backend hint stored in the index for readers opening the package
+    /// (0=auto, 1=mmap, 2=file, 3=stream)
+    pub access_mode: u8,
+    /// Buffer size in bytes for hashing slot sources and copying them into the
+    /// package. Unset, hashing uses an 8 MiB buffer and copying is left to the
+    /// OS (`copy_file_range` where available).
+    pub io_buffer_size: Option<usize>,
+}
+
+/// Codec used for slots re-encoded by [`repack`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlotCodec {
+    /// gzip (`OP_GZIP`), best compression unless a level is given
+    #[default]
+    Gzip,
+    /// LZ4 frame (`OP_LZ4`), faster to decompress, larger output
+    Lz4,
+    /// zstd frame (`OP_ZSTD`), level 19 unless a level is given. Encoding
+    /// needs the `zstd-encode` feature; without it, repacking fails.
+    Zstd,
+}
+
+/// Options for repacking an existing package
+#[derive(Debug, Default)]
+pub struct RepackOptions {
+    /// Codec for every compressed slot
+    pub codec: SlotCodec,
+    /// Gzip level (0-9) or zstd level (1-22), for those codecs
+    pub compression_level: Option<u32>,
+    /// Path to private key file (PEM format) used to re-sign metadata
+    pub private_key_path: Option<std::path::PathBuf>,
+    /// Path to public key file (PEM format)
+    pub public_key_path: Option<std::path::PathBuf>,
+    /// Seed for deterministic key generation
+    pub key_seed: Option<String>,
+    /// Instead of `codec`, trial-compress each compressed slot with zstd and
+    /// keep whichever of its current bytes and the zstd encoding is smaller.
+    /// Slots that don't shrink keep their stored bytes unchanged. Needs the
+    /// `zstd-encode` feature; without it, repacking fails.
+    pub optimize: bool,
+}
+
+/// What to do when two slots extract to the same file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// Abort extraction, naming the path and both slot ids
+    Error,
+    /// Later slots replace earlier files (historical behavior)
+    #[default]
+    Overwrite,
+    /// Keep the first slot's file and skip later ones
+    Skip,
+}
+
+/// How thoroughly a cached workenv's files are checked before reuse
+///
+/// Only single-file slots are checked; archive contents have no per-file
+/// checksums. A mismatch invalidates the cache, which is then re-extracted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorkenvVerification {
+    /// Trust the completion marker and package checksum (default)
+    #[default]
+    Off,
+    /// Compare every file's size and hash a few files picked at random
+    Sample,
+    /// Hash every file
+    Full,
+}
+
+/// Where the launcher reports the workenv path (see [`LaunchOptions::print_workenv`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkenvOutput {
+    Stderr,
+    Stdout,
+    /// An inherited file descriptor (Unix only), e.g. `3` from `3>path.txt`
+    Fd(u32),
+}
+
+/// Resource limits applied to the packaged process (Unix only)
+///
+/// Limits are set with `setrlimit` and lower both the soft and hard limit, so
+/// the application cannot raise them again. Values above the current hard
+/// limit are clamped to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Maximum address space in bytes (`RLIMIT_AS`); defaults to the index
+    /// `max_memory` hint when unset and the package provides one
+    pub max_memory: Option<u64>,
+    /// Maximum CPU time in seconds (`RLIMIT_CPU`)
+    pub max_cpu_seconds: Option<u64>,
+    /// Maximum number of open file descriptors (`RLIMIT_NOFILE`)
+    pub max_open_files: Option<u64>,
+}
+
+/// The prepared command, as seen by [`LaunchOptions::pre_exec_hook`]
+#[derive(Debug)]
+pub struct PreExecContext<'a> {
+    /// Resolved executable path
+    pub executable: &'a str,
+    /// Arguments after the executable
+    pub args: &'a [String],
+    /// The complete environment; nothing is inherited beyond it
+    pub env: &'a HashMap<String, String>,
+    /// Directory the command runs in
+    pub workdir: &'a Path,
+    /// Whether the command is spawned as a child rather than exec'd in place
+    pub spawn: bool,
+}
+
+type PreExecFn = dyn Fn(&PreExecContext<'_>) -> Result<()> + Send + Sync;
+
+/// Callback run in the launcher right before the command is exec'd or spawned
+pub struct PreExecHook(Box<PreExecFn>);
+
+impl PreExecHook {
+    /// Wrap a callback; an error it returns aborts the launch
+    pub fn new(hook: impl Fn(&PreExecContext<'_>) -> Result<()> + Send + Sync + 'static) -> Self {
+        Self(Box::new(hook))
+    }
+
+    pub(crate) fn call(&self, context: &PreExecContext<'_>) -> Result<()> {
+        (self.0)(context)
+    }
+}
+
+impl std::fmt::Debug for PreExecHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PreExecHook(..)")
+    }
+}
+
+type ChildPreExecFn = dyn FnMut() -> std::io::Result<()> + Send + Sync;
+
+/// Callback run in the spawned child between fork and exec (Unix only; see
+/// [`LaunchOptions::child_pre_exec`])
+pub struct ChildPreExec(Box<ChildPreExecFn>);
+
+impl ChildPreExec {
+    /// Wrap a callback for `std::os::unix::process::CommandExt::pre_exec`
+    ///
+    /// # Safety
+    ///
+    /// The callback runs in the forked child, where only async-signal-safe
+    /// operations are sound: no allocation, locking or logging. The contract
+    /// is the one documented for `CommandExt::pre_exec`.
+    #[allow(unsafe_code)]
+    pub unsafe fn new(hook: impl FnMut() -> std::io::Result<()> + Send + Sync + 'static) -> Self {
+        Self(Box::new(hook))
+    }
+
+    pub(crate) fn into_inner(self) -> Box<ChildPreExecFn> {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for ChildPreExec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ChildPreExec(..)")
+    }
 }
 
 /// Options for launching a package
@@ -26,6 +237,124 @@ pub struct BuildOptions {
 pub struct LaunchOptions {
     /// Working directory for extraction
     pub workdir: Option<String>,
+    /// Abort the launch if required `runtime.env.pass` variables are missing
+    /// (always enabled under strict validation)
+    pub strict_env: bool,
+    /// Keep the temp extraction directory when extraction or setup fails
+    /// (also enabled by `FLAVOR_KEEP_TEMP=1`)
+    pub keep_temp: bool,
+    /// How long to wait for another process's extraction (default 60s)
+    pub extraction_wait_timeout: Option<std::time::Duration>,
+    /// How many times to redo an extraction whose result fails its re-check
+    /// or hits an I/O error, e.g. on a network filesystem (default 2; also set
+    /// by `FLAVOR_EXTRACTION_RETRIES`)
+    pub extraction_retries: Option<u32>,
+    /// How to handle slots extracting to the same file
+    pub collision_policy: CollisionPolicy,
+    /// Verify package integrity and return without extracting or executing
+    /// (also enabled by `FLAVOR_VERIFY_ONLY=1`). Structural and signature checks
+    /// are always enforced; slot checksums are skipped at minimal/none validation.
+    pub verify_only: bool,
+    /// With `verify_only`, hash only slots not recorded as verified by an
+    /// earlier run, and record the ones that pass (also enabled by
+    /// `FLAVOR_VERIFY_INCREMENTAL=1`)
+    pub incremental_verify: bool,
+    /// Resource limits for the packaged process. In spawn mode they apply to the
+    /// child only; in exec mode they are set on the launcher right before exec
+    /// and persist into the replacement process. Ignored on non-Unix platforms.
+    pub resource_limits: Option<ResourceLimits>,
+    /// Restore build-time source mtimes on single-file slots (also enabled by
+    /// `FLAVOR_RESTORE_MTIME=1`). Execute bits are always restored.
+    pub restore_mtime: bool,
+    /// Umask for extracted files and workenv directories (Unix only; also set
+    /// by `FLAVOR_EXTRACTION_UMASK`). Unset keeps the owner-only modes; with a
+    /// umask, default owner bits are extended to group and others and then
+    /// masked, so `0o027` turns 0600/0700 into 0640/0750. Explicit slot
+    /// permissions are only masked.
+    pub extraction_umask: Option<u32>,
+    /// Refuse to run a package that is world-writable or sits in a directory
+    /// other users can write (Unix only). Also enabled by strict validation or
+    /// `FLAVOR_REFUSE_INSECURE_PATH=1`; `FLAVOR_REFUSE_INSECURE_PATH=0` opts out.
+    pub refuse_insecure_path: bool,
+    /// In spawn mode, forward SIGINT/SIGTERM/SIGHUP to the child and keep
+    /// waiting for it to exit (Unix only). Exec mode needs no forwarding: the
+    /// package replaces the launcher and receives signals directly.
+    pub forward_signals: bool,
+    /// Abort extraction if a compressed slot decodes to more than this many
+    /// bytes. Unset, each slot is capped at its recorded `original_size` plus
+    /// a small margin; slots without a recorded size are not capped.
+    pub max_decompressed_slot_size: Option<u64>,
+    /// Host variables copied into the package environment after `runtime.env`
+    /// is applied, e.g. `TERM` or `SSH_AUTH_SOCK`. They replace values from
+    /// `runtime.env` (including `set`), but `workenv.env` and `execution.env`
+    /// still override them. Unset host variables are skipped.
+    pub env_allowlist: Vec<String>,
+    /// Re-extract, rather than fail under strict validation, when the cached
+    /// workenv was extracted from a different build of the package (e.g. one
+    /// rebuilt in place). The checksum mismatch is still logged. Also enabled
+    /// by `FLAVOR_HEAL_ON_MISMATCH=1`.
+    pub heal_on_mismatch: bool,
+    /// Check extracted files against their slot checksums before reusing a
+    /// cached workenv (also `FLAVOR_VERIFY_WORKENV=sample|full`). This costs
+    /// extra I/O on every cached launch.
+    pub verify_workenv: WorkenvVerification,
+    /// Key for packages with encrypted slots. Falls back to
+    /// `FLAVOR_DECRYPTION_KEY` (64 hex characters).
+    pub decryption_key: Option<psp::format_2025::encryption::EncryptionKey>,
+    /// Print the final workenv path, one line, once it is ready and before the
+    /// command runs. Also set by `FLAVOR_PRINT_WORKENV` (`1`/`stderr`,
+    /// `stdout` or `fd:N`).
+    pub print_workenv: Option<WorkenvOutput>,
+    /// Run this `execution.entrypoints` entry instead of the default command;
+    /// an unknown name is an error. Falls back to `FLAVOR_ENTRYPOINT`.
+    pub entrypoint: Option<String>,
+    /// Name the package was invoked as. When no entrypoint is requested, an
+    /// entrypoint matching its basename (with or without extension) is used,
+    /// so a package symlinked as `ls` runs its `ls` entrypoint.
+    pub argv0: Option<String>,
+    /// Skip the whole-file CRC pre-check of packages built with one (also
+    /// `FLAVOR_SKIP_FILE_CRC=1`). Signature verification is unaffected.
+    pub skip_file_crc: bool,
+    /// Called in the launcher once the command is prepared, right before it is
+    /// exec'd or spawned, e.g. to log, record metrics or drop privileges. An
+    /// error aborts the launch. In exec mode it runs in the process that
+    /// becomes the package; in spawn mode it runs in the parent.
+    pub pre_exec_hook: Option<PreExecHook>,
+    /// Called in the spawned child after fork and before exec (spawn mode on
+    /// Unix only), for work that must happen in the child itself. An error
+    /// fails the spawn. Ignored, with a warning, in exec mode.
+    pub child_pre_exec: Option<ChildPreExec>,
+}
+
+/// Options for installing a package's workenv into a directory
+#[derive(Debug, Default)]
+pub struct InstallOptions {
+    /// How to handle slots extracting to the same file
+    pub collision_policy: CollisionPolicy,
+    /// Umask for installed files and directories (Unix only), as for
+    /// [`LaunchOptions::extraction_umask`]
+    pub extraction_umask: Option<u32>,
+    /// Abort if a compressed slot decodes to more than this many bytes
+    pub max_decompressed_slot_size: Option<u64>,
+    /// Key for packages with encrypted slots. Falls back to
+    /// `FLAVOR_DECRYPTION_KEY` (64 hex characters).
+    pub decryption_key: Option<psp::format_2025::encryption::EncryptionKey>,
+    /// Skip the whole-file CRC pre-check (also `FLAVOR_SKIP_FILE_CRC=1`)
+    pub skip_file_crc: bool,
+}
+
+/// Options for verifying a package
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VerifyOptions {
+    /// Check an embedded Rekor entry against the metadata signature. This is
+    /// an offline check of the entry's digest, signature and key; it does not
+    /// contact the log or verify the inclusion proof.
+    pub verify_attestation: bool,
+    /// Key obtained out-of-band. The metadata signature is checked against
+    /// it rather than the embedded key, and an embedded key that differs is
+    /// an error, since whoever could replace the package could replace that
+    /// key too. See [`load_public_key`](psp::format_2025::keys::load_public_key).
+    pub trusted_public_key: Option<ed25519_dalek::VerifyingKey>,
 }
 
 /// Result of package verification
@@ -34,86 +363,226 @@ pub struct VerifyResult {
     pub format: String,
     pub version: String,
     pub signature_valid: bool,
+    /// The index block matches its own checksum; a malformed trailer fails
+    /// verification with a `FormatError` before this is checked
+    pub index_checksum_valid: bool,
+    /// Launcher bytes match the index launcher hash (`None` if not recorded)
+    pub launcher_hash_valid: Option<bool>,
+    /// Embedded Rekor entry matches the signature (`None` if not checked or absent)
+    pub attestation_valid: Option<bool>,
     pub slot_count: usize,
     pub package_name: String,
     pub package_version: String,
 }
 
+/// Result of a single-pass [`verify_package_streaming`]
+#[derive(Debug)]
+pub struct StreamingVerifyResult {
+    /// The checks [`verify_package_with_options`] makes
+    pub result: VerifyResult,
+    /// Slots whose data doesn't match its descriptor checksum (`None` if
+    /// slots weren't checked)
+    pub mismatched_slots: Option<Vec<usize>>,
+}
+
 /// Build a PSPF package from a manifest
+///
+/// With `options.dry_run`, nothing is written and the report of what the
+/// build would produce is returned; otherwise the result is `None`.
 pub fn build_package(
     manifest_path: &Path,
     output_path: &Path,
     options: BuildOptions,
-) -> Result<()> {
+) -> Result<Option<psp::format_2025::builder::DryRunReport>> {
     // Read manifest to determine format
     let manifest_data = std::fs::read_to_string(manifest_path)?;
     let manifest: serde_json::Value = serde_json::from_str(&manifest_data)?;
 
-    // Determine format (default to PSPF/2025)
-    let format = manifest
-        .get("format")
-        .and_then(|f| f.as_str())
-        .unwrap_or("PSPF/2025");
-
-    match format {
+    match manifest_format(&manifest) {
         "PSPF/2025" => psp::format_2025::build(manifest_path, output_path, options),
-        _ => Err(FlavorError::UnsupportedFormat(format.to_string())),
+        format => Err(FlavorError::UnsupportedFormat(format.to_string())),
+    }
+}
+
+/// Build a PSPF package from a manifest read from any source (e.g. stdin)
+///
+/// Relative slot sources resolve against the current directory. Returns the
+/// dry-run report as [`build_package`] does.
+pub fn build_package_from_reader(
+    mut manifest_reader: impl std::io::Read,
+    output_path: &Path,
+    options: BuildOptions,
+) -> Result<Option<psp::format_2025::builder::DryRunReport>> {
+    let mut manifest_data = String::new();
+    manifest_reader.read_to_string(&mut manifest_data)?;
+    let manifest: serde_json::Value = serde_json::from_str(&manifest_data)?;
+
+    match manifest_format(&manifest) {
+        "PSPF/2025" => {
+            psp::format_2025::builder::build_from_str(&manifest_data, output_path, options)
+        }
+        format => Err(FlavorError::UnsupportedFormat(format.to_string())),
     }
 }
 
+/// Determine manifest format (default to PSPF/2025)
+fn manifest_format(manifest: &serde_json::Value) -> &str {
+    manifest
+        .get("format")
+        .and_then(|f| f.as_str())
+        .unwrap_or("PSPF/2025")
+}
+
 /// Launch a PSPF package
 pub fn launch_package(package_path: &Path, args: &[String], options: LaunchOptions) -> Result<i32> {
     // Detect format from package
-    let format = detect_package_format(package_path)?;
+    let format = psp::detect_format(package_path)?;
 
     match format {
         PackageFormat::PSPF2025 => psp::format_2025::launch(package_path, args, options),
     }
 }
 
+/// Install a package's workenv into `dest` without running it
+///
+/// Extraction, setup commands and shebang fixing run as for a launch, but into
+/// `dest` (which must not exist or be empty) instead of the cache, with no
+/// locking or cache records; the entrypoint is not executed. This differs from
+/// extracting slots, which copies their contents without running setup
+/// commands. Returns the absolute install directory.
+pub fn install_package(
+    package_path: &Path,
+    dest: &Path,
+    options: InstallOptions,
+) -> Result<std::path::PathBuf> {
+    match psp::detect_format(package_path)? {
+        PackageFormat::PSPF2025 => psp::format_2025::launcher::install(package_path, dest, options),
+    }
+}
+
+/// Re-encode a package's slots with a different codec, without changing contents
+///
+/// Each compressed slot is decoded with its current operation chain and encoded
+/// with `options.codec` (or, with `options.optimize`, zstd if that is smaller);
+/// self-referential and raw slots pass through. Metadata is re-signed, so
+/// supply the original key to keep the same signer. The report lists each
+/// slot's old and new encoding and size.
+pub fn repack(
+    input: &Path,
+    output: &Path,
+    options: RepackOptions,
+) -> Result<psp::format_2025::builder::RepackReport> {
+    match psp::detect_format(input)? {
+        PackageFormat::PSPF2025 => psp::format_2025::builder::repack(input, output, &options),
+    }
+}
+
+/// Compare two PSPF packages by metadata and slots
+///
+/// Build timestamps and signatures are ignored, so rebuilt-but-identical
+/// packages compare equal.
+pub fn diff_packages(a: &Path, b: &Path) -> Result<psp::format_2025::diff::PackageDiff> {
+    match (psp::detect_format(a)?, psp::detect_format(b)?) {
+        (PackageFormat::PSPF2025, PackageFormat::PSPF2025) => psp::format_2025::diff::diff(a, b),
+    }
+}
+
+/// Salvage slot data from a package whose index or trailer is damaged
+///
+/// Best-effort and heuristic: the file is scanned for slot descriptors and for
+/// gzip/tar streams, and whatever is found is written to `output_dir` as
+/// stored. The result lists what was recovered and what is known to be missing.
+pub fn recover(
+    package_path: &Path,
+    output_dir: &Path,
+) -> Result<psp::format_2025::recovery::RecoverResult> {
+    psp::format_2025::recovery::recover(package_path, output_dir)
+}
+
+/// Check the workenv cache for leftovers of interrupted launches
+///
+/// Reports workenvs marked complete whose files are missing, stale locks and
+/// temp extractions of dead processes, and checksum records without a
+/// workenv. With `repair`, the offending markers and directories are removed
+/// so the next launch extracts again.
+pub fn fsck_cache(repair: bool) -> Result<psp::format_2025::fsck::FsckReport> {
+    psp::format_2025::fsck::fsck(&crate::utils::get_cache_dir(), repair)
+}
+
 /// Verify a PSPF package
 pub fn verify_package(package_path: &Path) -> Result<VerifyResult> {
+    verify_package_with_options(package_path, VerifyOptions::default())
+}
+
+/// Verify a PSPF package, opting into additional checks
+pub fn verify_package_with_options(
+    package_path: &Path,
+    options: VerifyOptions,
+) -> Result<VerifyResult> {
     // Detect format from package
-    let format = detect_package_format(package_path)?;
+    let format = psp::detect_format(package_path)?;
 
     match format {
-        PackageFormat::PSPF2025 => psp::format_2025::verify(package_path),
+        PackageFormat::PSPF2025 => {
+            psp::format_2025::verifier::verify_with_options(package_path, options)
+        }
     }
 }
 
-/// Detect the format of a package by reading its magic bytes
-fn detect_package_format(package_path: &Path) -> Result<PackageFormat> {
-    use std::fs::File;
-    use std::io::{Read, Seek, SeekFrom};
-
-    let mut file = File::open(package_path)?;
+/// Verify a PSPF package reading the file once, front to back
+///
+/// With `check_slots`, every slot's checksum is checked in the same pass.
+/// See [`verify_streaming`](psp::format_2025::verifier::verify_streaming).
+pub fn verify_package_streaming(
+    package_path: &Path,
+    options: VerifyOptions,
+    check_slots: bool,
+) -> Result<StreamingVerifyResult> {
+    match psp::detect_format(package_path)? {
+        PackageFormat::PSPF2025 => {
+            psp::format_2025::verifier::verify_streaming(package_path, options, check_slots)
+        }
+    }
+}
 
-    // Search for PSPF magic
-    let file_size = file.metadata()?.len();
+/// Export a package's Ed25519 signature and public key as a detached signature
+///
+/// The signature is the raw 64-byte Ed25519 signature over the metadata JSON;
+/// the key can be written out with
+/// [`public_key_pem`](psp::format_2025::verifier::DetachedSignature::public_key_pem).
+pub fn export_signature(
+    package_path: &Path,
+) -> Result<psp::format_2025::verifier::DetachedSignature> {
+    psp::detect_format(package_path)?;
+    psp::format_2025::verifier::export_signature(package_path)
+}
 
-    // Search in chunks from the beginning (up to 10MB to handle large launchers)
-    // Check for MagicTrailer at end of file using minimal reads
-    if file_size >= psp::format_2025::constants::MAGIC_TRAILER_SIZE as u64 {
-        // First check for 🪄 at the very end (last 4 bytes)
-        file.seek(SeekFrom::End(-4))?;
-        let mut magic_wand = [0u8; 4];
-        file.read_exact(&mut magic_wand)?;
+/// Verify a package against a raw signature file and a trusted PEM public key
+///
+/// Slots of an encrypted package are decrypted for the check with the key
+/// from `FLAVOR_DECRYPTION_KEY`.
+pub fn verify_detached(
+    package_path: &Path,
+    signature_path: &Path,
+    public_key_path: &Path,
+) -> Result<bool> {
+    psp::detect_format(package_path)?;
+    psp::format_2025::verifier::verify_detached(package_path, signature_path, public_key_path, None)
+}
 
-        if magic_wand == *psp::format_2025::constants::MAGIC_WAND_EMOJI_BYTES {
-            // Now check for 📦 at the start of the trailer
-            file.seek(SeekFrom::End(
-                -(psp::format_2025::constants::MAGIC_TRAILER_SIZE as i64),
-            ))?;
-            let mut package_emoji = [0u8; 4];
-            file.read_exact(&mut package_emoji)?;
+/// Resolve the environment a package would launch with, without launching it
+///
+/// Applies the package's `runtime.env` operations (unset, map, set, pass) to
+/// `base_env` and returns the result.
+pub fn resolve_runtime_env(
+    package_path: &Path,
+    base_env: HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    let format = psp::detect_format(package_path)?;
 
-            if package_emoji == *psp::format_2025::constants::PACKAGE_EMOJI_BYTES {
-                return Ok(PackageFormat::PSPF2025);
-            }
+    match format {
+        PackageFormat::PSPF2025 => {
+            psp::format_2025::runtime::resolve_runtime_env(package_path, base_env)
         }
     }
-
-    Err(FlavorError::UnsupportedFormat(
-        "Unknown package format".to_string(),
-    ))
 }
@@ -1,7 +1,8 @@
 //! Flavor Rust builder binary
 
 use clap::Parser;
-use flavor::{BuildOptions, build_package, exit_codes::*};
+use flavor::psp::format_2025::encryption::{EncryptionAlgorithm, EncryptionKey, SlotEncryption};
+use flavor::{BuildOptions, build_package, build_package_from_reader, exit_codes::*};
 use std::{env, panic, path::PathBuf, process};
 
 const VERSION: &str = flavor::version::VERSION;
@@ -10,8 +11,12 @@ const VERSION: &str = flavor::version::VERSION;
 #[command(version = VERSION, about = "Build PSPF packages")]
 struct Args {
     /// Path to manifest.json
-    #[arg(short, long)]
-    manifest: PathBuf,
+    #[arg(short, long, required_unless_present = "manifest_stdin")]
+    manifest: Option<PathBuf>,
+
+    /// Read the manifest JSON from stdin instead of a file
+    #[arg(long, conflicts_with = "manifest")]
+    manifest_stdin: bool,
 
     /// Output path for PSPF bundle
     #[arg(short, long)]
@@ -40,6 +45,115 @@ struct Args {
     /// Base directory for {workenv} resolution (defaults to CWD)
     #[arg(long)]
     workenv_base: Option<PathBuf>,
+
+    /// Use CRC32C instead of Adler-32 for the index checksum
+    #[arg(long)]
+    crc32c_index: bool,
+
+    /// Record a whole-file CRC32C for a fast corruption check at launch
+    #[arg(long)]
+    file_crc: bool,
+
+    /// Default gzip level (0-9) for inline slot content without a compression_level
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..=9))]
+    compression_level: Option<u32>,
+
+    /// Gzip level (0-9) for package metadata
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..=9))]
+    metadata_compression_level: Option<u32>,
+
+    /// Write a build lockfile (defaults to OUTPUT.flavor-lock.json)
+    #[arg(long)]
+    emit_lockfile: Option<Option<PathBuf>>,
+
+    /// Slot alignment in bytes, or "page" to page-align slots for mmap
+    #[arg(long, value_parser = parse_slot_alignment)]
+    slot_alignment: Option<u64>,
+
+    /// SBOM JSON (CycloneDX or SPDX) to embed as the __sbom__ slot
+    #[arg(long)]
+    sbom: Option<PathBuf>,
+
+    /// Fail if any slot is larger than this many bytes
+    #[arg(long)]
+    max_slot_size: Option<u64>,
+
+    /// Fail if the finished package is larger than this many bytes
+    #[arg(long)]
+    max_package_size: Option<u64>,
+
+    /// Rekor log entry (JSON) for the metadata signature, embedded in the index
+    #[arg(long)]
+    rekor_entry: Option<PathBuf>,
+
+    /// Validate the manifest and report sizes without writing the package
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Write a launcher stub plus a sibling .pspfdata file instead of one package
+    #[arg(long)]
+    detached_data: bool,
+
+    /// Fail instead of warning when manifest commands contain shell metacharacters
+    #[arg(long)]
+    strict_commands: bool,
+
+    /// Build a delta package that inherits matching slots from this base package
+    #[arg(long)]
+    base_package: Option<PathBuf>,
+
+    /// Encrypt slots with this algorithm (aes256-gcm)
+    #[arg(long, requires = "encryption_key_file")]
+    encrypt: Option<EncryptionAlgorithm>,
+
+    /// File holding the 32-byte encryption key as 64 hex characters
+    #[arg(long, requires = "encrypt")]
+    encryption_key_file: Option<PathBuf>,
+
+    /// Unix mode for the output file, in octal (default 0700)
+    #[arg(long, value_parser = parse_mode)]
+    output_mode: Option<u32>,
+
+    /// Don't make the output executable
+    #[arg(long)]
+    no_exec: bool,
+
+    /// Backend readers should use for the package: auto, mmap, file or stream
+    #[arg(long, value_parser = parse_access_mode, default_value = "auto")]
+    access_mode: u8,
+
+    /// Buffer size in bytes for hashing and copying slot data (default: 8 MiB
+    /// for hashing, OS-managed copies)
+    #[arg(long)]
+    io_buffer_size: Option<usize>,
+}
+
+fn parse_slot_alignment(value: &str) -> Result<u64, String> {
+    if value == "page" {
+        return Ok(flavor::psp::format_2025::defaults::DEFAULT_PAGE_SIZE as u64);
+    }
+    value
+        .parse()
+        .map_err(|_| format!("expected a byte count or \"page\", got '{value}'"))
+}
+
+fn parse_mode(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|&mode| mode <= 0o7777)
+        .ok_or_else(|| format!("expected an octal mode such as 0644, got '{value}'"))
+}
+
+fn parse_access_mode(value: &str) -> Result<u8, String> {
+    match value {
+        "auto" => Ok(0),
+        "mmap" => Ok(1),
+        "file" => Ok(2),
+        "stream" => Ok(3),
+        _ => Err(format!(
+            "expected auto, mmap, file or stream, got '{value}'"
+        )),
+    }
 }
 
 fn main() {
@@ -77,6 +191,37 @@ fn run() -> i32 {
         flavor::logger::JsonLogger::init();
     }
 
+    let emit_lockfile = args.emit_lockfile.map(|path| {
+        path.unwrap_or_else(|| {
+            let mut sidecar = args.output.clone().into_os_string();
+            sidecar.push(".flavor-lock.json");
+            PathBuf::from(sidecar)
+        })
+    });
+
+    let rekor_entry = match args.rekor_entry.as_ref().map(std::fs::read_to_string) {
+        Some(Ok(entry)) => Some(entry),
+        Some(Err(e)) => {
+            eprintln!("Failed to read Rekor entry: {:#}", e);
+            return EXIT_CONFIG_ERROR;
+        }
+        None => None,
+    };
+
+    let encryption = match (args.encrypt, args.encryption_key_file.as_ref()) {
+        (Some(algorithm), Some(key_file)) => match std::fs::read_to_string(key_file)
+            .map_err(|e| e.to_string())
+            .and_then(|hex| EncryptionKey::from_hex(&hex).map_err(|e| e.to_string()))
+        {
+            Ok(key) => Some(SlotEncryption { algorithm, key }),
+            Err(e) => {
+                eprintln!("Failed to read encryption key: {:#}", e);
+                return EXIT_CONFIG_ERROR;
+            }
+        },
+        _ => None,
+    };
+
     let options = BuildOptions {
         launcher_bin: args.launcher_bin,
         skip_verification: false,
@@ -84,12 +229,41 @@ fn run() -> i32 {
         public_key_path: args.public_key,
         key_seed: args.key_seed,
         workenv_base: args.workenv_base,
+        crc32c_index: args.crc32c_index,
+        file_crc: args.file_crc,
+        default_compression_level: args.compression_level,
+        metadata_compression_level: args.metadata_compression_level,
+        emit_lockfile,
+        slot_alignment: args.slot_alignment,
+        sbom: args.sbom,
+        max_slot_size: args.max_slot_size,
+        max_package_size: args.max_package_size,
+        key_provider: None,
+        rekor_entry,
+        dry_run: args.dry_run,
+        detached_data: args.detached_data,
+        strict_commands: args.strict_commands,
+        base_package: args.base_package,
+        encryption,
+        output_permissions: args.output_mode,
+        no_exec: args.no_exec,
+        access_mode: args.access_mode,
+        io_buffer_size: args.io_buffer_size,
+    };
+
+    let result = match args.manifest {
+        Some(ref manifest) => build_package(manifest, &args.output, options),
+        None => build_package_from_reader(std::io::stdin().lock(), &args.output, options),
     };
 
-    match build_package(&args.manifest, &args.output, options) {
-        Ok(_) => EXIT_SUCCESS,
+    match result {
+        Ok(Some(report)) => {
+            print!("{report}");
+            EXIT_SUCCESS
+        }
+        Ok(None) => EXIT_SUCCESS,
         Err(e) => {
-            eprintln!("Build error: {}", e);
+            eprintln!("Build error: {:#}", e);
             match e.to_string() {
                 s if s.contains("manifest") || s.contains("config") => EXIT_CONFIG_ERROR,
                 s if s.contains("PSPF") || s.contains("format") => EXIT_PSPF_ERROR,
@@ -1,7 +1,7 @@
 //! Flavor Rust launcher binary
 
-use flavor::{LaunchOptions, exit_codes::*, launch_package};
-use std::{env, panic, process};
+use flavor::{FlavorError, LaunchOptions, exit_codes::*, launch_package};
+use std::{env, panic, path::Path, process};
 
 fn main() {
     // Set up panic handler to return specific exit code
@@ -49,14 +49,13 @@ fn run() -> i32 {
             path
         }
         Err(e) => {
-            log::error!("❌ Failed to get executable path: {}", e);
+            log::error!("❌ Failed to get executable path: {:#}", e);
             return EXIT_IO_ERROR;
         }
     };
 
     // Determine if running in CLI mode ONLY from the environment variable.
-    let cli_mode =
-        env::var("FLAVOR_LAUNCHER_CLI").is_ok_and(|v| v == "1" || v.to_lowercase() == "true");
+    let cli_mode = flavor::utils::is_env_true("FLAVOR_LAUNCHER_CLI");
 
     // --- CLI Mode Execution ---
     if cli_mode {
@@ -70,18 +69,66 @@ fn run() -> i32 {
             command_args[0].as_str()
         };
 
+        // Inspection commands read the package data, which a split package keeps
+        // beside the stub; `run` and `bench` resolve it themselves.
+        let data_path = match flavor::psp::format_2025::detached::resolve_data_path(&exe_path) {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                return EXIT_IO_ERROR;
+            }
+        };
+
         // Route to the appropriate CLI command.
         let exit_code = match command {
-            "info" => flavor::psp::format_2025::cli::show_info(&exe_path),
-            "verify" => flavor::psp::format_2025::cli::verify_bundle(&exe_path),
-            "metadata" => flavor::psp::format_2025::cli::show_metadata(&exe_path),
+            "info" => flavor::psp::format_2025::cli::show_info(&data_path),
+            "verify" => match command_args.get(1..).unwrap_or_default() {
+                [] => flavor::psp::format_2025::cli::verify_bundle(&data_path, None),
+                [flag, pubkey] if flag == "--pubkey" => {
+                    flavor::psp::format_2025::cli::verify_bundle(
+                        &data_path,
+                        Some(Path::new(pubkey)),
+                    )
+                }
+                _ => {
+                    eprintln!("Usage: {} verify [--pubkey FILE]", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "metadata" => match command_args.get(1..).unwrap_or_default() {
+                [] => flavor::psp::format_2025::cli::show_metadata(&data_path, "json"),
+                [flag, format] if flag == "--format" => {
+                    flavor::psp::format_2025::cli::show_metadata(&data_path, format)
+                }
+                _ => {
+                    eprintln!("Usage: {} metadata [--format json|yaml|toml]", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "env" => flavor::psp::format_2025::cli::show_env(&data_path),
+            "sbom" => flavor::psp::format_2025::cli::show_sbom(&data_path),
+            "launcher-info" => match command_args.get(1..).unwrap_or_default() {
+                [] => flavor::psp::format_2025::cli::launcher_info(&data_path, None),
+                [output] => flavor::psp::format_2025::cli::launcher_info(&data_path, Some(output)),
+                _ => {
+                    eprintln!("Usage: {} launcher-info [OUT]", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "export-sig" => flavor::psp::format_2025::cli::export_signature(
+                &data_path,
+                command_args.get(1).map(String::as_str),
+            ),
             "extract" => {
                 if command_args.len() < 3 {
-                    eprintln!("Usage: {} extract <slot_index> <output_dir>", args[0]);
+                    eprintln!(
+                        "Usage: {} extract <slot_index|slot_id> <output_dir>",
+                        args[0]
+                    );
                     EXIT_INVALID_ARGS
                 } else {
                     match flavor::psp::format_2025::cli::extract_slot(
-                        &exe_path,
+                        &data_path,
                         &command_args[1],
                         &command_args[2],
                     ) {
@@ -90,6 +137,71 @@ fn run() -> i32 {
                     }
                 }
             }
+            "ls" => match command_args.get(1..).unwrap_or_default() {
+                [] => flavor::psp::format_2025::cli::list_slot_tree(&data_path, "/"),
+                [path] => flavor::psp::format_2025::cli::list_slot_tree(&data_path, path),
+                _ => {
+                    eprintln!("Usage: {} ls [PATH]", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "tree" => match command_args.get(1..).unwrap_or_default() {
+                [] => flavor::psp::format_2025::cli::show_tree(&data_path),
+                _ => {
+                    eprintln!("Usage: {} tree", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "cat" => match command_args.get(1..).unwrap_or_default() {
+                [path] => flavor::psp::format_2025::cli::cat_slot_file(&data_path, path),
+                _ => {
+                    eprintln!("Usage: {} cat <path>", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "mount" => match command_args.get(1..).unwrap_or_default() {
+                [mountpoint] => flavor::psp::format_2025::cli::mount_slots(&data_path, mountpoint),
+                _ => {
+                    eprintln!("Usage: {} mount <dir>", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "install" => match command_args.get(1..).unwrap_or_default() {
+                [dest] => flavor::psp::format_2025::cli::install(&exe_path, dest),
+                _ => {
+                    eprintln!("Usage: {} install <dir>", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "diff" => {
+                if command_args.len() < 3 {
+                    eprintln!("Usage: {} diff <package_a> <package_b>", args[0]);
+                    EXIT_INVALID_ARGS
+                } else {
+                    flavor::psp::format_2025::cli::show_diff(
+                        Path::new(&command_args[1]),
+                        Path::new(&command_args[2]),
+                    )
+                }
+            }
+            "bench" => match command_args.get(1).map(|n| n.parse::<usize>()) {
+                None => flavor::psp::format_2025::cli::run_bench(&exe_path, 5),
+                Some(Ok(iterations)) if iterations > 0 => {
+                    flavor::psp::format_2025::cli::run_bench(&exe_path, iterations)
+                }
+                Some(_) => {
+                    eprintln!("Usage: {} bench [iterations]", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
+            "fsck" => match command_args.get(1).map(String::as_str) {
+                None => flavor::psp::format_2025::cli::fsck_cache(false),
+                Some("--repair") => flavor::psp::format_2025::cli::fsck_cache(true),
+                Some(_) => {
+                    eprintln!("Usage: {} fsck [--repair]", args[0]);
+                    EXIT_INVALID_ARGS
+                }
+            },
             "run" => {
                 // 'run' command executes the package with remaining arguments.
                 let remaining_args = if command_args.len() > 1 {
@@ -97,11 +209,14 @@ fn run() -> i32 {
                 } else {
                     vec![]
                 };
-                let options = LaunchOptions { workdir: None };
+                let options = LaunchOptions {
+                    argv0: args.first().cloned(),
+                    ..LaunchOptions::default()
+                };
                 match launch_package(&exe_path, &remaining_args, options) {
                     Ok(code) => code,
                     Err(e) => {
-                        eprintln!("Launch error: {}", e);
+                        eprintln!("Launch error: {:#}", e);
                         EXIT_EXECUTION_ERROR
                     }
                 }
@@ -111,9 +226,38 @@ fn run() -> i32 {
                 println!();
                 println!("Available commands:");
                 println!("  info              Show package information (default)");
-                println!("  verify            Verify package integrity");
-                println!("  metadata          Show raw package metadata");
-                println!("  extract INDEX DIR Extract slot to directory");
+                println!("  verify [--pubkey FILE]");
+                println!(
+                    "                    Verify package integrity, optionally against a trusted key"
+                );
+                println!("  metadata [--format json|yaml|toml]");
+                println!("                    Show package metadata (default: raw JSON)");
+                println!("  env               Show resolved runtime environment");
+                println!("  sbom              Print the embedded SBOM, if any");
+                println!(
+                    "  export-sig [OUT]  Write a detached signature and .pem key (default <package>.sig)"
+                );
+                println!("  launcher-info [OUT]");
+                println!(
+                    "                    Describe the embedded launcher, optionally writing it to OUT"
+                );
+                println!("  extract SLOT DIR  Extract slot (index or id) to directory");
+                println!(
+                    "  ls [PATH]         List slots, or a path inside them, without extracting"
+                );
+                println!(
+                    "  tree              Show the workenv the package extracts to, with sizes"
+                );
+                println!("  cat PATH          Print a file from a slot without extracting");
+                println!(
+                    "  mount DIR         Mount the slots read-only on DIR (`fuse` feature builds)"
+                );
+                println!(
+                    "  install DIR       Extract and run setup commands into DIR, without running"
+                );
+                println!("  diff A B          Compare two packages' metadata and slots");
+                println!("  bench [N]         Time N cold and warm launches (default 5)");
+                println!("  fsck [--repair]   Check the workenv cache for leftovers");
                 println!("  run [args...]     Execute package with arguments");
                 println!("  help              Show this help message");
                 println!();
@@ -128,7 +272,9 @@ fn run() -> i32 {
             }
             _ => {
                 eprintln!("Error: Unknown command '{}'", command);
-                eprintln!("Available commands: info, verify, metadata, extract, run, help");
+                eprintln!(
+                    "Available commands: info, verify, metadata, env, sbom, export-sig, launcher-info, extract, ls, tree, cat, install, diff, bench, fsck, run, help"
+                );
                 EXIT_INVALID_ARGS
             }
         };
@@ -144,7 +290,11 @@ fn run() -> i32 {
 
     // Launch the package with the provided arguments.
     let remaining_args = args[1..].to_vec();
-    let options = LaunchOptions { workdir: None };
+    // A symlink named after an entrypoint (busybox-style) selects it
+    let options = LaunchOptions {
+        argv0: args.first().cloned(),
+        ..LaunchOptions::default()
+    };
 
     log::debug!("🚀 Attempting to launch package: {:?}", exe_path);
     match launch_package(&exe_path, &remaining_args, options) {
@@ -153,48 +303,42 @@ fn run() -> i32 {
             code
         }
         Err(e) => {
-            log::error!("❌ Launch error: {}", e);
+            log::error!("❌ Launch error: {:#}", e);
 
             // Provide helpful error messages based on the error type
-            let error_msg = e.to_string();
-            if error_msg.contains("signature verification failed")
-                || error_msg.contains("Signature verification failed")
-            {
-                eprintln!("❌ Package signature verification failed");
-                eprintln!();
-                eprintln!("This package's cryptographic signature could not be verified.");
-                eprintln!(
-                    "This may indicate the package has been tampered with or was not properly signed."
-                );
-                eprintln!();
-                eprintln!(
-                    "To use different validation levels, set FLAVOR_VALIDATION environment variable:"
-                );
-                eprintln!("  export FLAVOR_VALIDATION=relaxed  # Skip signatures");
-                eprintln!("For more details, run with FLAVOR_LOG_LEVEL=debug");
-            } else if error_msg.contains("checksum") {
-                eprintln!("❌ Package integrity check failed: {}", error_msg);
-                eprintln!();
-                eprintln!("The package appears to be corrupted or modified.");
-                eprintln!();
-                eprintln!(
-                    "To use different validation levels, set FLAVOR_VALIDATION environment variable:"
-                );
-                eprintln!("  export FLAVOR_VALIDATION=none  # Skip all checks (testing only)");
-            } else {
-                eprintln!("❌ Failed to launch package: {}", error_msg);
-                eprintln!();
-                eprintln!("For more details, run with FLAVOR_LOG_LEVEL=debug");
+            match e.kind() {
+                FlavorError::SignatureVerificationFailed(_) => {
+                    eprintln!("❌ Package signature verification failed");
+                    eprintln!();
+                    eprintln!("This package's cryptographic signature could not be verified.");
+                    eprintln!(
+                        "This may indicate the package has been tampered with or was not properly signed."
+                    );
+                    eprintln!();
+                    eprintln!(
+                        "To use different validation levels, set FLAVOR_VALIDATION environment variable:"
+                    );
+                    eprintln!("  export FLAVOR_VALIDATION=relaxed  # Skip signatures");
+                    eprintln!("For more details, run with FLAVOR_LOG_LEVEL=debug");
+                }
+                FlavorError::ChecksumMismatch { .. } => {
+                    eprintln!("❌ Package integrity check failed: {:#}", e);
+                    eprintln!();
+                    eprintln!("The package appears to be corrupted or modified.");
+                    eprintln!();
+                    eprintln!(
+                        "To use different validation levels, set FLAVOR_VALIDATION environment variable:"
+                    );
+                    eprintln!("  export FLAVOR_VALIDATION=none  # Skip all checks (testing only)");
+                }
+                _ => {
+                    eprintln!("❌ Failed to launch package: {:#}", e);
+                    eprintln!();
+                    eprintln!("For more details, run with FLAVOR_LOG_LEVEL=debug");
+                }
             }
 
-            match error_msg {
-                s if s.contains("PSPF") || s.contains("format") => EXIT_PSPF_ERROR,
-                s if s.contains("signature") || s.contains("checksum") => EXIT_SIGNATURE_ERROR,
-                s if s.contains("extract") => EXIT_EXTRACTION_ERROR,
-                s if s.contains("execute") || s.contains("spawn") => EXIT_EXECUTION_ERROR,
-                s if s.contains("I/O") || s.contains("file") => EXIT_IO_ERROR,
-                _ => EXIT_EXECUTION_ERROR,
-            }
+            ExitCode::from(&e).code()
         }
     }
 }
@@ -1,5 +1,6 @@
 //! Error types for flavor
 
+use std::error::Error as _;
 use std::fmt;
 
 /// Main error type for flavor operations
@@ -11,37 +12,199 @@ pub enum FlavorError {
     /// Package verification failed
     VerificationFailed(String),
 
+    /// The package needs a newer reader than this build of flavor
+    IncompatibleFormat {
+        /// Minimum reader format version recorded in the index
+        required: u32,
+        /// Newest format version this build reads
+        supported: u32,
+    },
+
     /// Build error
     BuildError(String),
 
-    /// Launch error
+    /// Launch error (the command could not be prepared or started)
     LaunchError(String),
 
+    /// Package signature does not match its metadata
+    SignatureVerificationFailed(String),
+
+    /// Stored data does not match its recorded checksum
+    ChecksumMismatch {
+        /// What was checked, e.g. "metadata" or "slot 2"
+        subject: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// A slot index past the end of the package's slot table
+    SlotOutOfRange { index: usize, count: usize },
+
+    /// A slot descriptor pointing outside the slot data section
+    DescriptorOutOfBounds { slot: usize, reason: String },
+
+    /// Undoing a slot operation (gzip, lz4, ...) failed
+    DecompressionFailed { operation: String, reason: String },
+
+    /// Package structure is malformed (index, trailer, descriptors, operations)
+    FormatError(String),
+
+    /// Slots could not be extracted into the work environment
+    ExtractionError(String),
+
+    /// The launcher binary to build with is unset or unreadable
+    LauncherMissing(String),
+
+    /// A signing, verifying or encryption key could not be used
+    InvalidKey(String),
+
+    /// Invalid manifest, setup command or option
+    ConfigError(String),
+
+    /// A setup command exited unsuccessfully
+    CommandFailed {
+        status: Option<i32>,
+        message: String,
+    },
+
     /// IO error
     IoError(std::io::Error),
 
     /// JSON parsing error
     JsonError(serde_json::Error),
 
+    /// An error of another kind together with the lower-level error behind it
+    ///
+    /// Built with [`FlavorError::with_source`]. It reports, and maps to an
+    /// exit code, as `error`; `source` is returned by
+    /// [`std::error::Error::source`] and shown by `{:#}`.
+    WithSource {
+        error: Box<FlavorError>,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
     /// Generic error with message
+    #[deprecated(note = "return a specific variant; only errors without a kind still use Generic")]
     Generic(String),
 }
 
+impl FlavorError {
+    /// Attach the error that caused this one, keeping it as the error's source
+    ///
+    /// Use this instead of formatting the cause into the message, so callers
+    /// can walk the chain and `{:#}` prints it in full.
+    pub fn with_source(
+        self,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    ) -> Self {
+        FlavorError::WithSource {
+            error: Box::new(self),
+            source: source.into(),
+        }
+    }
+
+    /// The error with any attached sources stripped, for matching on its kind
+    pub fn kind(&self) -> &FlavorError {
+        match self {
+            FlavorError::WithSource { error, .. } => error.kind(),
+            other => other,
+        }
+    }
+
+    /// The first cause not already part of this error's own message
+    fn unreported_source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FlavorError::WithSource { source, .. } => Some(source.as_ref()),
+            FlavorError::IoError(err) => err.source(),
+            FlavorError::JsonError(err) => err.source(),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for FlavorError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            // The message, then each cause in turn
+            match self {
+                FlavorError::WithSource { error, .. } => write!(f, "{error:#}")?,
+                other => write!(f, "{other}")?,
+            }
+            let mut cause = self.unreported_source();
+            while let Some(err) = cause {
+                if let Some(err) = err.downcast_ref::<FlavorError>() {
+                    return write!(f, ": {err:#}");
+                }
+                write!(f, ": {err}")?;
+                cause = err.source();
+            }
+            return Ok(());
+        }
         match self {
             FlavorError::UnsupportedFormat(msg) => write!(f, "Unsupported format: {msg}"),
             FlavorError::VerificationFailed(msg) => write!(f, "Verification failed: {msg}"),
+            FlavorError::IncompatibleFormat {
+                required,
+                supported,
+            } => write!(
+                f,
+                "Package requires newer flavor: it needs format 0x{required:08x}, flavor {} reads up to 0x{supported:08x}",
+                crate::version::VERSION
+            ),
             FlavorError::BuildError(msg) => write!(f, "Build error: {msg}"),
             FlavorError::LaunchError(msg) => write!(f, "Launch error: {msg}"),
+            FlavorError::SignatureVerificationFailed(msg) => {
+                write!(f, "Signature verification failed: {msg}")
+            }
+            FlavorError::ChecksumMismatch {
+                subject,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum mismatch for {subject}: expected {expected}, got {actual}"
+            ),
+            FlavorError::SlotOutOfRange { index, count } => {
+                write!(f, "Slot index {index} out of range ({count} slots)")
+            }
+            FlavorError::DescriptorOutOfBounds { slot, reason } => {
+                write!(f, "Slot descriptor {slot} out of bounds: {reason}")
+            }
+            FlavorError::DecompressionFailed { operation, reason } => {
+                write!(f, "Failed to decompress {operation}: {reason}")
+            }
+            FlavorError::FormatError(msg) => write!(f, "Package format error: {msg}"),
+            FlavorError::ExtractionError(msg) => write!(f, "Extraction error: {msg}"),
+            FlavorError::LauncherMissing(msg) => write!(f, "Launcher missing: {msg}"),
+            FlavorError::InvalidKey(msg) => write!(f, "Invalid key: {msg}"),
+            FlavorError::ConfigError(msg) => write!(f, "Configuration error: {msg}"),
+            FlavorError::CommandFailed {
+                status: Some(status),
+                message,
+            } => write!(f, "Command failed with status {status}: {message}"),
+            FlavorError::CommandFailed {
+                status: None,
+                message,
+            } => write!(f, "Command killed by a signal: {message}"),
             FlavorError::IoError(err) => write!(f, "IO error: {err}"),
             FlavorError::JsonError(err) => write!(f, "JSON error: {err}"),
+            FlavorError::WithSource { error, .. } => write!(f, "{error}"),
+            #[allow(deprecated)]
             FlavorError::Generic(msg) => write!(f, "{msg}"),
         }
     }
 }
 
-impl std::error::Error for FlavorError {}
+impl std::error::Error for FlavorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FlavorError::IoError(err) => Some(err),
+            FlavorError::JsonError(err) => Some(err),
+            FlavorError::WithSource { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl From<std::io::Error> for FlavorError {
     fn from(err: std::io::Error) -> Self {
@@ -57,9 +220,42 @@ impl From<serde_json::Error> for FlavorError {
 
 impl From<anyhow::Error> for FlavorError {
     fn from(err: anyhow::Error) -> Self {
+        // The PE helpers report through anyhow, without an error kind
+        #[allow(deprecated)]
         FlavorError::Generic(err.to_string())
     }
 }
 
 /// Result type for flavor operations
 pub type Result<T> = std::result::Result<T, FlavorError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_with_source_keeps_the_chain() {
+        let read = FlavorError::ExtractionError("Failed to read slot 0".to_string())
+            .with_source(std::io::Error::other("disk gone"));
+        assert_eq!(read.to_string(), "Extraction error: Failed to read slot 0");
+        assert_eq!(
+            format!("{read:#}"),
+            "Extraction error: Failed to read slot 0: disk gone"
+        );
+        assert!(read.source().is_some_and(|s| s.to_string() == "disk gone"));
+        assert!(matches!(read.kind(), FlavorError::ExtractionError(_)));
+
+        // Nested causes are walked in order, each printed once
+        let build = FlavorError::BuildError("Repack failed".to_string()).with_source(read);
+        assert_eq!(
+            format!("{build:#}"),
+            "Build error: Repack failed: Extraction error: Failed to read slot 0: disk gone"
+        );
+        let depth = std::iter::successors(build.source(), |&e| e.source()).count();
+        assert_eq!(depth, 2);
+
+        let io = FlavorError::IoError(std::io::Error::other("denied"));
+        assert_eq!(format!("{io:#}"), io.to_string());
+    }
+}
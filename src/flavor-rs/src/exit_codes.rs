@@ -3,6 +3,8 @@
 //! These exit codes are used by both builder and launcher to provide
 //! consistent error reporting across the Flavor ecosystem.
 
+use crate::exceptions::FlavorError;
+
 /// Successful execution
 pub const EXIT_SUCCESS: i32 = 0;
 
@@ -38,3 +40,144 @@ pub const EXIT_CONFIG_ERROR: i32 = 109;
 
 /// Dependency error (missing required tools or libraries)
 pub const EXIT_DEPENDENCY_ERROR: i32 = 110;
+
+/// Verify-only launch found an integrity problem
+pub const EXIT_VERIFICATION_FAILED: i32 = 111;
+
+/// Checksum mismatch in package data or the cached workenv
+pub const EXIT_CHECKSUM_ERROR: i32 = 112;
+
+/// The exit codes above as a type
+///
+/// `From<&FlavorError>` picks the code from the error variant, never from its
+/// message, so automation can rely on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExitCode {
+    Success,
+    Error,
+    Panic,
+    Format,
+    Extraction,
+    Execution,
+    InvalidArgs,
+    Io,
+    Signature,
+    Build,
+    Config,
+    Dependency,
+    VerificationFailed,
+    Checksum,
+}
+
+impl ExitCode {
+    const ALL: [Self; 14] = [
+        Self::Success,
+        Self::Error,
+        Self::Panic,
+        Self::Format,
+        Self::Extraction,
+        Self::Execution,
+        Self::InvalidArgs,
+        Self::Io,
+        Self::Signature,
+        Self::Build,
+        Self::Config,
+        Self::Dependency,
+        Self::VerificationFailed,
+        Self::Checksum,
+    ];
+
+    /// Process exit status for this code
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Success => EXIT_SUCCESS,
+            Self::Error => EXIT_ERROR,
+            Self::Panic => EXIT_PANIC,
+            Self::Format => EXIT_PSPF_ERROR,
+            Self::Extraction => EXIT_EXTRACTION_ERROR,
+            Self::Execution => EXIT_EXECUTION_ERROR,
+            Self::InvalidArgs => EXIT_INVALID_ARGS,
+            Self::Io => EXIT_IO_ERROR,
+            Self::Signature => EXIT_SIGNATURE_ERROR,
+            Self::Build => EXIT_BUILD_ERROR,
+            Self::Config => EXIT_CONFIG_ERROR,
+            Self::Dependency => EXIT_DEPENDENCY_ERROR,
+            Self::VerificationFailed => EXIT_VERIFICATION_FAILED,
+            Self::Checksum => EXIT_CHECKSUM_ERROR,
+        }
+    }
+}
+
+impl From<ExitCode> for i32 {
+    fn from(code: ExitCode) -> Self {
+        code.code()
+    }
+}
+
+impl TryFrom<i32> for ExitCode {
+    type Error = i32;
+
+    /// Fails with the status itself when it isn't one of Flavor's codes
+    /// (e.g. an application's own exit status)
+    fn try_from(status: i32) -> Result<Self, i32> {
+        Self::ALL
+            .into_iter()
+            .find(|code| code.code() == status)
+            .ok_or(status)
+    }
+}
+
+impl From<&FlavorError> for ExitCode {
+    fn from(error: &FlavorError) -> Self {
+        match error {
+            FlavorError::UnsupportedFormat(_)
+            | FlavorError::IncompatibleFormat { .. }
+            | FlavorError::FormatError(_)
+            | FlavorError::SlotOutOfRange { .. }
+            | FlavorError::DescriptorOutOfBounds { .. }
+            | FlavorError::JsonError(_) => Self::Format,
+            FlavorError::SignatureVerificationFailed(_) => Self::Signature,
+            FlavorError::ChecksumMismatch { .. } => Self::Checksum,
+            FlavorError::VerificationFailed(_) => Self::VerificationFailed,
+            FlavorError::ExtractionError(_) | FlavorError::DecompressionFailed { .. } => {
+                Self::Extraction
+            }
+            FlavorError::LaunchError(_) | FlavorError::CommandFailed { .. } => Self::Execution,
+            FlavorError::IoError(_) => Self::Io,
+            FlavorError::BuildError(_) => Self::Build,
+            FlavorError::InvalidKey(_) | FlavorError::ConfigError(_) => Self::Config,
+            FlavorError::LauncherMissing(_) => Self::Dependency,
+            FlavorError::WithSource { error, .. } => Self::from(error.as_ref()),
+            #[allow(deprecated)]
+            FlavorError::Generic(_) => Self::Error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_conversions() {
+        for code in ExitCode::ALL {
+            assert_eq!(ExitCode::try_from(i32::from(code)), Ok(code));
+        }
+        assert_eq!(ExitCode::try_from(42), Err(42));
+
+        let checksum = FlavorError::ChecksumMismatch {
+            subject: "metadata".to_string(),
+            expected: "00".to_string(),
+            actual: "ff".to_string(),
+        };
+        assert_eq!(ExitCode::from(&checksum).code(), EXIT_CHECKSUM_ERROR);
+        // Mapping ignores the message text
+        let signature = FlavorError::SignatureVerificationFailed("checksum".to_string());
+        assert_eq!(ExitCode::from(&signature), ExitCode::Signature);
+        let io = FlavorError::IoError(std::io::Error::other("extract"));
+        assert_eq!(ExitCode::from(&io), ExitCode::Io);
+        // An attached source doesn't change the kind
+        let caused = FlavorError::ExtractionError("slot 0".to_string()).with_source(io);
+        assert_eq!(ExitCode::from(&caused), ExitCode::Extraction);
+    }
+}
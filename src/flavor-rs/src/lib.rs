@@ -68,7 +68,13 @@ pub mod version;
 use std::sync::atomic::AtomicU32;
 
 // Re-export main API functions
-pub use api::{BuildOptions, LaunchOptions, build_package, launch_package, verify_package};
+pub use api::{
+    BuildOptions, ChildPreExec, CollisionPolicy, InstallOptions, LaunchOptions, PreExecContext,
+    PreExecHook, RepackOptions, ResourceLimits, SlotCodec, StreamingVerifyResult, VerifyOptions,
+    WorkenvOutput, WorkenvVerification, build_package, build_package_from_reader, diff_packages,
+    export_signature, install_package, launch_package, recover, repack, resolve_runtime_env,
+    verify_detached, verify_package, verify_package_streaming, verify_package_with_options,
+};
 pub use exceptions::FlavorError;
 pub use utils::get_platform_string;
 
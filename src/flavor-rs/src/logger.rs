@@ -1,8 +1,10 @@
 //! JSON structured logging implementation for Flavor
 
 use chrono::{Local, Utc};
+use log::kv::{self, Key, Value, VisitSource};
 use log::{Level, Log, Metadata, Record};
-use serde_json::json;
+use serde_json::{Map, json};
+use std::borrow::Cow;
 use std::env;
 use std::fs::OpenOptions;
 use std::io::{self, Write};
@@ -13,6 +15,7 @@ use std::sync::Mutex;
 pub struct JsonLogger {
     level: Level,
     target_file: Mutex<Option<std::fs::File>>,
+    strip_emoji: bool,
 }
 
 impl JsonLogger {
@@ -27,6 +30,7 @@ impl JsonLogger {
         JsonLogger {
             level,
             target_file: Mutex::new(target_file),
+            strip_emoji: emoji_disabled(),
         }
     }
 
@@ -56,12 +60,15 @@ impl JsonLogger {
             };
 
             // Use standard env_logger with custom format to add 🦀 prefix
+            let strip_emoji = emoji_disabled();
             env_logger::Builder::new()
                 .filter_level(level_filter)
-                .format(|buf, record| {
+                .format(move |buf, record| {
                     use std::io::Write;
 
-                    write!(buf, "🦀 ")?;
+                    if !strip_emoji {
+                        write!(buf, "🦀 ")?;
+                    }
                     write!(
                         buf,
                         "[{} {} {}] ",
@@ -69,7 +76,11 @@ impl JsonLogger {
                         record.level(),
                         record.target()
                     )?;
-                    writeln!(buf, "{}", record.args())
+                    if strip_emoji {
+                        writeln!(buf, "{}", strip_leading_emoji(&record.args().to_string()))
+                    } else {
+                        writeln!(buf, "{}", record.args())
+                    }
                 })
                 .init();
             return (actual_level.to_string(), source.to_string());
@@ -114,17 +125,32 @@ impl Log for JsonLogger {
             return;
         }
 
+        let message = record.args().to_string();
+        let message = if self.strip_emoji {
+            strip_leading_emoji(&message)
+        } else {
+            Cow::Borrowed(message.as_str())
+        };
+
         // Build JSON log entry
-        let log_entry = json!({
+        let mut log_entry = json!({
             "@timestamp": Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
             "@level": record.level().to_string().to_lowercase(),
-            "@message": record.args().to_string(),
+            "@message": message,
             "@module": record.target(),
             "@pid": std::process::id(),
             "@file": record.file().unwrap_or("unknown"),
             "@line": record.line().unwrap_or(0),
         });
 
+        // Merge structured key-value fields (e.g. `debug!(slot_index = i; "...")`)
+        let mut fields = FieldCollector(Map::new());
+        if record.key_values().visit(&mut fields).is_ok() {
+            if let Some(entry) = log_entry.as_object_mut() {
+                entry.extend(fields.0);
+            }
+        }
+
         let json_string = format!(
             "{}\n",
             serde_json::to_string(&log_entry).unwrap_or_default()
@@ -157,9 +183,84 @@ impl Log for JsonLogger {
     }
 }
 
+/// Collects a record's key-value pairs as JSON fields
+struct FieldCollector(Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for FieldCollector {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let json_value = if let Some(n) = value.to_u64() {
+            json!(n)
+        } else if let Some(n) = value.to_i64() {
+            json!(n)
+        } else if let Some(f) = value.to_f64() {
+            json!(f)
+        } else if let Some(b) = value.to_bool() {
+            json!(b)
+        } else {
+            json!(value.to_string())
+        };
+        self.0.insert(key.as_str().to_string(), json_value);
+        Ok(())
+    }
+}
+
 /// Helper to check if JSON logging is enabled
 pub fn is_json_logging() -> bool {
     env::var("FLAVOR_LOG_LEVEL")
         .map(|v| v.starts_with("json"))
         .unwrap_or(false)
 }
+
+/// Whether log messages are written without emoji
+///
+/// Set at build time by the `no-emoji` feature or at run time by
+/// `FLAVOR_NO_EMOJI=1`, for log pipelines that mangle or can't index them.
+pub fn emoji_disabled() -> bool {
+    cfg!(feature = "no-emoji") || crate::utils::is_env_true("FLAVOR_NO_EMOJI")
+}
+
+/// Drop the emoji a log message starts with, and the whitespace after them
+///
+/// Only a leading run is removed; emoji later in the message are kept.
+pub fn strip_leading_emoji(message: &str) -> Cow<'_, str> {
+    let stripped = message.trim_start_matches(|c: char| is_emoji(c) || c.is_whitespace());
+    if stripped.len() == message.trim_start().len() {
+        Cow::Borrowed(message)
+    } else {
+        Cow::Owned(stripped.to_string())
+    }
+}
+
+/// Pictographs, symbols and the joiners and selectors that combine them
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{1F000}'..='\u{1FAFF}'
+            | '\u{2300}'..='\u{23FF}'
+            | '\u{2600}'..='\u{27BF}'
+            | '\u{2B00}'..='\u{2BFF}'
+            | '\u{2139}'
+            | '\u{200D}'
+            | '\u{20E3}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{E0020}'..='\u{E007F}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_leading_emoji() {
+        assert_eq!(strip_leading_emoji("📦 Package: app v1"), "Package: app v1");
+        assert_eq!(
+            strip_leading_emoji("⚠️ Failed to fix some shebangs"),
+            "Failed to fix some shebangs"
+        );
+        assert_eq!(strip_leading_emoji("🦀🦀🦀 Hello 🦀🦀🦀"), "Hello 🦀🦀🦀");
+        assert_eq!(strip_leading_emoji("  ✅ done"), "done");
+        assert_eq!(strip_leading_emoji("Rename failed: x"), "Rename failed: x");
+        assert!(matches!(strip_leading_emoji("plain"), Cow::Borrowed(_)));
+    }
+}
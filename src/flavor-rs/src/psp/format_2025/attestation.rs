@@ -0,0 +1,203 @@
+//! Transparency log attestations for PSPF/2025 packages
+//!
+//! A package can carry the Rekor log entry recording its metadata signature.
+//! The entry is produced from the signature, so it cannot live in the signed
+//! metadata; it is stored in the index reserved space instead (see
+//! [`Index::attestation`](super::index::Index::attestation)).
+//!
+//! Verification here is offline: the entry must be a `hashedrekord` whose
+//! signature, public key and SHA-256 digest match the package. Proving that
+//! the log actually included the entry (the inclusion proof and signed entry
+//! timestamp) needs the log's public key and, for a fresh proof, network
+//! access; that is left to online tooling such as `rekor-cli verify`.
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::keys::ED25519_SPKI_PREFIX;
+use crate::exceptions::{FlavorError, Result};
+
+/// A Rekor log entry as returned by the Rekor API or `rekor-cli get --format json`
+#[derive(Debug, Clone)]
+pub struct RekorEntry {
+    /// Entry UUID, when the entry was given in the `{uuid: entry}` API form
+    pub uuid: Option<String>,
+    /// Position of the entry in the log
+    pub log_index: u64,
+    /// Identifier (SHA-256 of the public key) of the log
+    pub log_id: String,
+    /// Unix time the log integrated the entry
+    pub integrated_time: i64,
+    /// Decoded entry body
+    body: Value,
+}
+
+impl RekorEntry {
+    /// Parse an entry from JSON
+    ///
+    /// Accepts both a bare entry object and the `{uuid: entry}` map returned by
+    /// the Rekor API.
+    pub fn from_json(json: &[u8]) -> Result<Self> {
+        let value: Value = serde_json::from_slice(json)?;
+        let (uuid, entry) = match value.as_object() {
+            Some(map) if map.len() == 1 && !map.contains_key("body") => {
+                let (uuid, entry) = map
+                    .iter()
+                    .next()
+                    .map_or((None, &value), |(k, v)| (Some(k), v));
+                (uuid.cloned(), entry)
+            }
+            _ => (None, &value),
+        };
+
+        let field = |name: &str| {
+            entry.get(name).ok_or_else(|| {
+                FlavorError::VerificationFailed(format!("Rekor entry is missing '{name}'"))
+            })
+        };
+        let body = field("body")?
+            .as_str()
+            .and_then(|b| STANDARD.decode(b).ok())
+            .ok_or_else(|| {
+                FlavorError::VerificationFailed("Rekor entry body is not base64".into())
+            })?;
+
+        Ok(Self {
+            uuid,
+            log_index: field("logIndex")?.as_u64().unwrap_or_default(),
+            log_id: field("logID")?.as_str().unwrap_or_default().to_string(),
+            integrated_time: field("integratedTime")?.as_i64().unwrap_or_default(),
+            body: serde_json::from_slice(&body)?,
+        })
+    }
+
+    /// Check that the entry records `signature` by `public_key` over `metadata_json`
+    ///
+    /// This is the offline check described in the module docs; it does not
+    /// prove the log included the entry.
+    pub fn verify(
+        &self,
+        metadata_json: &[u8],
+        signature: &Signature,
+        public_key: &VerifyingKey,
+    ) -> Result<()> {
+        let fail = |reason: &str| {
+            Err(FlavorError::VerificationFailed(format!(
+                "Rekor entry {}: {reason}",
+                self.log_index
+            )))
+        };
+
+        if self.body["kind"] != "hashedrekord" {
+            return fail("not a hashedrekord entry");
+        }
+        let spec = &self.body["spec"];
+
+        let hash = &spec["data"]["hash"];
+        let digest = hex::encode(Sha256::digest(metadata_json));
+        if hash["algorithm"] != "sha256" || hash["value"] != digest.as_str() {
+            return fail("digest does not match the package metadata");
+        }
+
+        let logged_signature = decode_field(&spec["signature"]["content"]);
+        if logged_signature.as_deref() != Some(&signature.to_bytes()[..]) {
+            return fail("signature does not match the package signature");
+        }
+
+        let logged_key = decode_field(&spec["signature"]["publicKey"]["content"])
+            .and_then(|pem_bytes| pem::parse(pem_bytes).ok())
+            .and_then(|pem| {
+                pem.contents()
+                    .strip_prefix(&ED25519_SPKI_PREFIX)
+                    .map(<[u8]>::to_vec)
+            });
+        if logged_key.as_deref() != Some(public_key.as_bytes()) {
+            return fail("public key does not match the package key");
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode a base64 string field of the entry body
+fn decode_field(value: &Value) -> Option<Vec<u8>> {
+    value.as_str().and_then(|s| STANDARD.decode(s).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn entry_for(metadata: &[u8], key: &SigningKey) -> Vec<u8> {
+        let mut spki = ED25519_SPKI_PREFIX.to_vec();
+        spki.extend_from_slice(key.verifying_key().as_bytes());
+        let pem = pem::encode(&pem::Pem::new("PUBLIC KEY", spki));
+        let body = serde_json::json!({
+            "apiVersion": "0.0.1",
+            "kind": "hashedrekord",
+            "spec": {
+                "data": {"hash": {"algorithm": "sha256", "value": hex::encode(Sha256::digest(metadata))}},
+                "signature": {
+                    "content": STANDARD.encode(key.sign(metadata).to_bytes()),
+                    "publicKey": {"content": STANDARD.encode(pem)}
+                }
+            }
+        });
+        serde_json::to_vec(&serde_json::json!({
+            "24296fb24b8ad77a": {
+                "body": STANDARD.encode(body.to_string()),
+                "integratedTime": 1_700_000_000,
+                "logID": "c0d23d6ad406973f",
+                "logIndex": 42
+            }
+        }))
+        .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_entry_matches_only_its_own_signature() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let metadata = br#"{"format": "PSPF/2025"}"#;
+        let entry = RekorEntry::from_json(&entry_for(metadata, &key));
+        assert!(
+            entry
+                .as_ref()
+                .is_ok_and(|e| e.log_index == 42 && e.uuid.as_deref() == Some("24296fb24b8ad77a"))
+        );
+        let Ok(entry) = entry else { return };
+
+        let signature = key.sign(metadata);
+        assert!(
+            entry
+                .verify(metadata, &signature, &key.verifying_key())
+                .is_ok()
+        );
+        assert!(
+            entry
+                .verify(b"{}", &signature, &key.verifying_key())
+                .is_err()
+        );
+        let other = SigningKey::from_bytes(&[8; 32]);
+        assert!(
+            entry
+                .verify(metadata, &other.sign(metadata), &other.verifying_key())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_malformed_entries_are_rejected() {
+        assert!(RekorEntry::from_json(b"not json").is_err());
+        assert!(RekorEntry::from_json(br#"{"logIndex": 1}"#).is_err());
+        assert!(
+            RekorEntry::from_json(
+                br#"{"body": "%%%", "logIndex": 1, "logID": "", "integratedTime": 0}"#
+            )
+            .is_err()
+        );
+    }
+}
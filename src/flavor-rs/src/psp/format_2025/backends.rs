@@ -5,16 +5,73 @@ use log::{debug, trace};
 use memmap2::Mmap;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
 use super::defaults::{ACCESS_AUTO, ACCESS_FILE, ACCESS_MMAP, ACCESS_STREAM, DEFAULT_CHUNK_SIZE};
 use super::slots::SlotDescriptor;
 use crate::exceptions::{FlavorError, Result};
 
+/// Snapshot of a backend's I/O counters
+///
+/// Cache counters only move for backends with a read cache (`FileBackend`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackendStats {
+    /// Bytes returned by `read_at` and `view_at`
+    pub bytes_read: u64,
+    /// Number of `read_at` and `view_at` calls
+    pub read_calls: u64,
+    /// Reads served from the cache
+    pub cache_hits: u64,
+    /// Cacheable reads that went to the file
+    pub cache_misses: u64,
+}
+
+/// Running I/O counters behind [`BackendStats`]
+///
+/// Relaxed atomics keep the bookkeeping to a few uncontended adds per read.
+#[derive(Debug, Default)]
+struct IoCounters {
+    bytes_read: AtomicU64,
+    read_calls: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl IoCounters {
+    fn record_read(&self, bytes: usize) {
+        self.read_calls.fetch_add(1, Ordering::Relaxed);
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn record_cache(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> BackendStats {
+        BackendStats {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            read_calls: self.read_calls.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// Trait for PSPF bundle access backends
 pub trait Backend: Send + Sync {
+    /// Short name of the backend kind, for diagnostics
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
     /// Open the bundle file
     fn open(&mut self, path: &Path) -> Result<()>;
 
@@ -31,9 +88,20 @@ pub trait Backend: Send + Sync {
 
     /// Get a view of data without copying (if supported)
     fn view_at(&self, _offset: u64, _size: usize) -> Result<&[u8]> {
-        Err(FlavorError::Generic(
-            "View not supported by this backend".into(),
-        ))
+        Err(FlavorError::IoError(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "View not supported by this backend",
+        )))
+    }
+
+    /// Total size of the bundle, if known without consulting the filesystem
+    fn data_size(&self) -> Option<u64> {
+        None
+    }
+
+    /// Snapshot of the I/O counters since the backend was created
+    fn stats(&self) -> BackendStats {
+        BackendStats::default()
     }
 }
 
@@ -42,6 +110,7 @@ pub struct MMapBackend {
     file: Option<File>,
     mmap: Option<Mmap>,
     path: Option<std::path::PathBuf>,
+    stats: IoCounters,
 }
 
 impl std::fmt::Debug for MMapBackend {
@@ -72,6 +141,7 @@ impl MMapBackend {
             file: None,
             mmap: None,
             path: None,
+            stats: IoCounters::default(),
         }
     }
 
@@ -91,6 +161,10 @@ impl MMapBackend {
 }
 
 impl Backend for MMapBackend {
+    fn name(&self) -> &'static str {
+        "mmap"
+    }
+
     fn open(&mut self, path: &Path) -> Result<()> {
         let timer = Instant::now();
         let file = File::open(path).map_err(FlavorError::IoError)?;
@@ -131,17 +205,26 @@ impl Backend for MMapBackend {
             let mut buffer = vec![0u8; size];
             file.read_exact(&mut buffer).map_err(FlavorError::IoError)?;
             trace!("✅ Safe file read {} bytes in {:?}", size, timer.elapsed());
+            self.stats.record_read(size);
             Ok(buffer)
         } else {
-            Err(FlavorError::Generic("Backend not opened".into()))
+            Err(FlavorError::IoError(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Backend not opened",
+            )))
         }
     }
 
     fn view_at(&self, _offset: u64, _size: usize) -> Result<&[u8]> {
         // Zero-copy view not available without memory mapping
-        Err(FlavorError::Generic(
-            "View not supported by safe file backend".into(),
-        ))
+        Err(FlavorError::IoError(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "View not supported by safe file backend",
+        )))
+    }
+
+    fn stats(&self) -> BackendStats {
+        self.stats.snapshot()
     }
 }
 
@@ -150,6 +233,7 @@ pub struct FileBackend {
     file: Option<File>,
     path: Option<std::path::PathBuf>,
     cache: HashMap<(u64, usize), Vec<u8>>,
+    stats: IoCounters,
 }
 
 impl std::fmt::Debug for FileBackend {
@@ -174,11 +258,16 @@ impl FileBackend {
             file: None,
             path: None,
             cache: HashMap::new(),
+            stats: IoCounters::default(),
         }
     }
 }
 
 impl Backend for FileBackend {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
     fn open(&mut self, path: &Path) -> Result<()> {
         let timer = Instant::now();
         let file = File::open(path).map_err(FlavorError::IoError)?;
@@ -212,6 +301,8 @@ impl Backend for FileBackend {
         let cache_key = (offset, size);
         if let Some(cached) = self.cache.get(&cache_key) {
             trace!("⚡ Cache hit for offset={}, size={}", offset, size);
+            self.stats.record_cache(true);
+            self.stats.record_read(size);
             return Ok(cached.clone());
         }
 
@@ -223,9 +314,11 @@ impl Backend for FileBackend {
             let mut buffer = vec![0u8; size];
             file.read_exact(&mut buffer).map_err(FlavorError::IoError)?;
             trace!("✅ File read {} bytes in {:?}", size, timer.elapsed());
+            self.stats.record_read(size);
 
             // Cache small reads
             if size <= 4096 {
+                self.stats.record_cache(false);
                 self.cache.insert(cache_key, buffer.clone());
 
                 // Limit cache size
@@ -240,9 +333,104 @@ impl Backend for FileBackend {
 
             Ok(buffer)
         } else {
-            Err(FlavorError::Generic("Backend not opened".into()))
+            Err(FlavorError::IoError(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Backend not opened",
+            )))
         }
     }
+
+    fn stats(&self) -> BackendStats {
+        self.stats.snapshot()
+    }
+}
+
+/// In-memory backend - holds the whole bundle, e.g. when read from a pipe
+pub struct MemoryBackend {
+    data: Vec<u8>,
+    stats: IoCounters,
+}
+
+impl std::fmt::Debug for MemoryBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryBackend")
+            .field("data", &format!("<{} bytes>", self.data.len()))
+            .finish()
+    }
+}
+
+impl MemoryBackend {
+    /// Create a backend over an already-buffered bundle
+    pub fn new(data: Vec<u8>) -> Self {
+        MemoryBackend {
+            data,
+            stats: IoCounters::default(),
+        }
+    }
+
+    fn range(&self, offset: u64, size: usize) -> Result<std::ops::Range<usize>> {
+        let start = usize::try_from(offset)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Offset out of range"))?;
+        let end = start
+            .checked_add(size)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "Read past end of buffer: offset={}, size={}, len={}",
+                        offset,
+                        size,
+                        self.data.len()
+                    ),
+                )
+            })?;
+        Ok(start..end)
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn open(&mut self, path: &Path) -> Result<()> {
+        let timer = Instant::now();
+        self.data = std::fs::read(path).map_err(FlavorError::IoError)?;
+        debug!(
+            "🧠 Memory backend loaded {} ({} bytes) in {:?}",
+            path.display(),
+            self.data.len(),
+            timer.elapsed()
+        );
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.data = Vec::new();
+        Ok(())
+    }
+
+    fn read_at(&mut self, offset: u64, size: usize) -> Result<Vec<u8>> {
+        trace!("🧠 Memory read_at: offset={}, size={}", offset, size);
+        let range = self.range(offset, size)?;
+        self.stats.record_read(size);
+        Ok(self.data[range].to_vec())
+    }
+
+    fn view_at(&self, offset: u64, size: usize) -> Result<&[u8]> {
+        let range = self.range(offset, size)?;
+        self.stats.record_read(size);
+        Ok(&self.data[range])
+    }
+
+    fn data_size(&self) -> Option<u64> {
+        Some(self.data.len() as u64)
+    }
+
+    fn stats(&self) -> BackendStats {
+        self.stats.snapshot()
+    }
 }
 
 /// Streaming backend - never loads full slots into memory
@@ -250,6 +438,7 @@ pub struct StreamBackend {
     file: Option<File>,
     path: Option<std::path::PathBuf>,
     chunk_size: usize,
+    stats: IoCounters,
 }
 
 impl std::fmt::Debug for StreamBackend {
@@ -268,6 +457,7 @@ impl StreamBackend {
             file: None,
             path: None,
             chunk_size,
+            stats: IoCounters::default(),
         }
     }
 
@@ -303,6 +493,10 @@ impl StreamBackend {
 }
 
 impl Backend for StreamBackend {
+    fn name(&self) -> &'static str {
+        "stream"
+    }
+
     fn open(&mut self, path: &Path) -> Result<()> {
         let file = File::open(path).map_err(FlavorError::IoError)?;
 
@@ -328,10 +522,14 @@ impl Backend for StreamBackend {
 
             let mut buffer = vec![0u8; read_size];
             file.read_exact(&mut buffer).map_err(FlavorError::IoError)?;
+            self.stats.record_read(read_size);
 
             Ok(buffer)
         } else {
-            Err(FlavorError::Generic("Backend not opened".into()))
+            Err(FlavorError::IoError(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Backend not opened",
+            )))
         }
     }
 
@@ -340,6 +538,10 @@ impl Backend for StreamBackend {
         let size = std::cmp::min(descriptor.size as usize, self.chunk_size);
         self.read_at(descriptor.offset, size)
     }
+
+    fn stats(&self) -> BackendStats {
+        self.stats.snapshot()
+    }
 }
 
 /// Hybrid backend - uses mmap for index/metadata, file I/O for slots
@@ -348,6 +550,7 @@ pub struct HybridBackend {
     header_mmap: Option<Mmap>,
     path: Option<std::path::PathBuf>,
     header_size: usize,
+    stats: IoCounters,
 }
 
 impl std::fmt::Debug for HybridBackend {
@@ -374,6 +577,7 @@ impl HybridBackend {
             header_mmap: None,
             path: None,
             header_size,
+            stats: IoCounters::default(),
         }
     }
 
@@ -383,6 +587,10 @@ impl HybridBackend {
 }
 
 impl Backend for HybridBackend {
+    fn name(&self) -> &'static str {
+        "hybrid"
+    }
+
     fn open(&mut self, path: &Path) -> Result<()> {
         let file = File::open(path).map_err(FlavorError::IoError)?;
 
@@ -415,18 +623,27 @@ impl Backend for HybridBackend {
 
             let mut buffer = vec![0u8; size];
             file.read_exact(&mut buffer).map_err(FlavorError::IoError)?;
+            self.stats.record_read(size);
 
             Ok(buffer)
         } else {
-            Err(FlavorError::Generic("Backend not opened".into()))
+            Err(FlavorError::IoError(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "Backend not opened",
+            )))
         }
     }
 
     fn view_at(&self, _offset: u64, _size: usize) -> Result<&[u8]> {
         // Zero-copy view not available without memory mapping
-        Err(FlavorError::Generic(
-            "View not available in safe file backend".into(),
-        ))
+        Err(FlavorError::IoError(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "View not available in safe file backend",
+        )))
+    }
+
+    fn stats(&self) -> BackendStats {
+        self.stats.snapshot()
     }
 }
 
@@ -436,25 +653,10 @@ pub fn create_backend(mode: u8, path: Option<&Path>) -> Box<dyn Backend> {
 
     if mode == ACCESS_AUTO {
         // Auto-select based on file size and platform
-        if let Some(p) = path {
-            if let Ok(metadata) = std::fs::metadata(p) {
-                let file_size = metadata.len();
-
-                // Use mmap for files over 1MB
-                if file_size > 1024 * 1024 {
-                    mode = ACCESS_MMAP;
-                // Use streaming for very large files
-                } else if file_size > 100 * 1024 * 1024 {
-                    mode = ACCESS_STREAM;
-                } else {
-                    mode = ACCESS_FILE;
-                }
-            } else {
-                mode = ACCESS_FILE;
-            }
-        } else {
-            mode = ACCESS_FILE;
-        }
+        mode = path
+            .and_then(|p| std::fs::metadata(p).ok())
+            .map(|metadata| select_mode_for_size(metadata.len()))
+            .unwrap_or(ACCESS_FILE);
     }
 
     // Create the appropriate backend
@@ -466,4 +668,121 @@ pub fn create_backend(mode: u8, path: Option<&Path>) -> Box<dyn Backend> {
     }
 }
 
+/// Size above which mmap is preferred over plain file I/O
+const MMAP_THRESHOLD: u64 = 1024 * 1024;
+/// Size above which streaming is preferred for sequential access
+const STREAM_THRESHOLD: u64 = 100 * 1024 * 1024;
+
+/// Pick a backend mode purely from file size
+fn select_mode_for_size(file_size: u64) -> u8 {
+    // Check the larger threshold first so very large files actually stream
+    if file_size > STREAM_THRESHOLD {
+        ACCESS_STREAM
+    } else if file_size > MMAP_THRESHOLD {
+        ACCESS_MMAP
+    } else {
+        ACCESS_FILE
+    }
+}
+
+/// How a caller intends to read a bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessPattern {
+    /// No preference; select by size and index hints
+    Auto,
+    /// Scattered reads (descriptors, individual slots)
+    Random,
+    /// Front-to-back reads (full extraction)
+    Sequential,
+}
+
+/// Select a backend mode from file size, index hints, and intended access pattern
+///
+/// `access_mode` and `cache_strategy` are the raw index fields
+/// (access_mode: 0=auto, 1=mmap, 2=file, 3=stream;
+/// cache_strategy: 0=none, 1=lazy, 2=eager, 3=critical).
+/// An explicit access mode in the index always wins.
+pub fn select_backend_mode(
+    file_size: u64,
+    pattern: AccessPattern,
+    access_mode: u8,
+    cache_strategy: u8,
+) -> u8 {
+    match access_mode {
+        1 => return ACCESS_MMAP,
+        2 => return ACCESS_FILE,
+        3 => return ACCESS_STREAM,
+        _ => {}
+    }
+
+    // Eager/critical caching wants the package resident in memory
+    if cache_strategy >= 2 && file_size > MMAP_THRESHOLD {
+        return ACCESS_MMAP;
+    }
+
+    match pattern {
+        AccessPattern::Auto | AccessPattern::Sequential => select_mode_for_size(file_size),
+        // Streaming penalises seeks, so random access never streams
+        AccessPattern::Random if file_size > MMAP_THRESHOLD => ACCESS_MMAP,
+        AccessPattern::Random => ACCESS_FILE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_selection_reaches_stream() {
+        assert_eq!(select_mode_for_size(512), ACCESS_FILE);
+        assert_eq!(select_mode_for_size(2 * MMAP_THRESHOLD), ACCESS_MMAP);
+        assert_eq!(select_mode_for_size(2 * STREAM_THRESHOLD), ACCESS_STREAM);
+    }
+
+    #[test]
+    fn test_hints_and_pattern_selection() {
+        let large = 2 * STREAM_THRESHOLD;
+        assert_eq!(
+            select_backend_mode(large, AccessPattern::Random, 0, 0),
+            ACCESS_MMAP
+        );
+        assert_eq!(
+            select_backend_mode(large, AccessPattern::Random, 2, 0),
+            ACCESS_FILE
+        );
+        assert_eq!(
+            select_backend_mode(large, AccessPattern::Sequential, 0, 3),
+            ACCESS_MMAP
+        );
+    }
+
+    #[test]
+    fn test_file_backend_counts_reads_and_cache_hits() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let path = dir.path().join("bundle");
+        assert!(std::fs::write(&path, vec![7u8; 8192]).is_ok());
+
+        let mut backend = FileBackend::new();
+        assert!(backend.open(&path).is_ok());
+        assert!(backend.read_at(0, 64).is_ok());
+        assert!(backend.read_at(0, 64).is_ok());
+        assert!(backend.read_at(64, 8000).is_ok());
+        assert_eq!(
+            backend.stats(),
+            BackendStats {
+                bytes_read: 8128,
+                read_calls: 3,
+                cache_hits: 1,
+                cache_misses: 1,
+            }
+        );
+
+        let memory = MemoryBackend::new(vec![0u8; 16]);
+        assert!(memory.view_at(4, 8).is_ok());
+        assert_eq!(memory.stats().bytes_read, 8);
+    }
+}
+
 // 📦💾🗺️🪄
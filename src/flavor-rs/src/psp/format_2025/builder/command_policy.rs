@@ -0,0 +1,157 @@
+//! Build-time checks for the commands and environment a manifest declares
+//!
+//! Commands are never run through a shell: they are split with POSIX quoting
+//! rules and executed directly. Shell syntax such as pipes or `$VAR` is
+//! therefore passed to the program literally, which usually means the author
+//! expected a shell, or that a manifest is trying to smuggle one in.
+
+use super::super::execution::split_command;
+use super::super::manifest::BuildManifest;
+use crate::exceptions::{FlavorError, Result};
+use log::warn;
+use serde_json::Value;
+
+/// Setup command types the launcher knows how to run
+const SETUP_COMMAND_TYPES: &[&str] = &["execute", "enumerate_and_execute", "write_file", "chmod"];
+
+/// Characters with special meaning to a shell but not to the launcher
+const SHELL_METACHARACTERS: &[char] = &[';', '|', '&', '`', '$', '<', '>', '\n'];
+
+/// Check every command and environment entry in the manifest
+///
+/// Unknown setup command types, unbalanced quoting and malformed environment
+/// names always fail, since the launcher would reject them at runtime. Shell
+/// metacharacters are a warning, or an error when `strict` is set.
+pub(super) fn validate_commands(manifest: &BuildManifest, strict: bool) -> Result<()> {
+    check_command("execution.command", &manifest.execution.command, strict)?;
+    if split_command(&manifest.execution.command)?.is_empty() {
+        return Err(FlavorError::BuildError(
+            "execution.command is empty".to_string(),
+        ));
+    }
+
+    for (name, command) in &manifest.execution.entrypoints {
+        let context = format!("execution.entrypoints.{name}");
+        if name.is_empty() || name.contains(['/', '\\', '\0']) {
+            return Err(FlavorError::BuildError(format!(
+                "{context}: entrypoint names must be non-empty and contain no path separators"
+            )));
+        }
+        check_command(&context, command, strict)?;
+        if split_command(command)?.is_empty() {
+            return Err(FlavorError::BuildError(format!("{context} is empty")));
+        }
+    }
+
+    if let Some(argv0) = &manifest.execution.argv0 {
+        if argv0.is_empty() || argv0.contains('\0') {
+            return Err(FlavorError::BuildError(
+                "execution.argv0 must be non-empty and contain no NUL bytes".to_string(),
+            ));
+        }
+    }
+
+    for (key, value) in &manifest.execution.env {
+        if key.is_empty() || key.contains(['=', '\0']) || value.contains('\0') {
+            return Err(FlavorError::BuildError(format!(
+                "execution.env entry '{key}' is not a valid environment variable"
+            )));
+        }
+    }
+
+    let commands = manifest
+        .setup_commands
+        .iter()
+        .map(|cmd| ("setup_commands", cmd))
+        .chain(
+            manifest
+                .shutdown_commands
+                .iter()
+                .map(|cmd| ("shutdown_commands", cmd)),
+        );
+    for (i, (list, cmd)) in commands.enumerate() {
+        let context = format!("{list}[{i}]");
+        let cmd_type = cmd.get("type").and_then(Value::as_str).unwrap_or_default();
+        if !SETUP_COMMAND_TYPES.contains(&cmd_type) {
+            return Err(FlavorError::BuildError(format!(
+                "{context} has unsupported type '{cmd_type}' (expected one of: {})",
+                SETUP_COMMAND_TYPES.join(", ")
+            )));
+        }
+        if let Some(command) = cmd.get("command").and_then(Value::as_str) {
+            check_command(&context, command, strict)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reject unparseable quoting and flag shell syntax in one command
+fn check_command(context: &str, command: &str, strict: bool) -> Result<()> {
+    split_command(command).map_err(|e| FlavorError::BuildError(format!("{context}: {e}")))?;
+
+    let Some(found) = command.chars().find(|c| SHELL_METACHARACTERS.contains(c)) else {
+        return Ok(());
+    };
+    let message = format!(
+        "{context} contains shell metacharacter {found:?}; commands do not run in a shell, so it is passed literally"
+    );
+    if strict {
+        return Err(FlavorError::BuildError(message));
+    }
+    warn!("⚠️ {message}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(command: &str, setup: Value) -> Option<BuildManifest> {
+        serde_json::from_value(serde_json::json!({
+            "package": {"name": "policy-test", "version": "1.0"},
+            "execution": {"command": command},
+            "slots": [],
+            "setup_commands": setup
+        }))
+        .ok()
+    }
+
+    #[test]
+    fn test_command_policy() {
+        let quoted = manifest(
+            r#""{workenv}/my app/bin/run" --config "{workenv}/my config.toml""#,
+            serde_json::json!([{"type": "chmod", "path": "{workenv}/bin/*"}]),
+        );
+        assert!(quoted.is_some());
+        let Some(quoted) = quoted else {
+            return;
+        };
+        assert!(validate_commands(&quoted, true).is_ok());
+
+        let piped = manifest("{workenv}/bin/run | tee log", serde_json::json!([]));
+        assert!(piped.is_some());
+        let Some(piped) = piped else {
+            return;
+        };
+        assert!(validate_commands(&piped, false).is_ok());
+        assert!(validate_commands(&piped, true).is_err());
+
+        let unbalanced = manifest(r#""{workenv}/bin/run"#, serde_json::json!([]));
+        assert!(unbalanced.is_some());
+        let Some(unbalanced) = unbalanced else {
+            return;
+        };
+        assert!(validate_commands(&unbalanced, false).is_err());
+
+        let unknown = manifest(
+            "{workenv}/bin/run",
+            serde_json::json!([{"type": "shell", "command": "rm -rf /"}]),
+        );
+        assert!(unknown.is_some());
+        let Some(unknown) = unknown else {
+            return;
+        };
+        assert!(validate_commands(&unknown, false).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! Delta packages: slots shared with a base package are referenced, not stored
+//!
+//! When a build names a base package, every slot whose stored bytes and
+//! operations match a slot of the base is recorded as inherited. The delta
+//! keeps the slot's metadata but none of its data; the launcher copies the
+//! slot's files from the base's extracted workenv instead, checking each one
+//! against the digest recorded here.
+
+use super::super::constants::{OP_TAR, SBOM_SLOT_ID};
+use super::super::detached::resolve_data_path;
+use super::super::execution::resolve_slot_target;
+use super::super::metadata::InheritedSlot;
+use super::super::operations::unpack_operations;
+use super::super::paths::WorkenvPaths;
+use super::super::reader::Reader;
+use crate::exceptions::{FlavorError, Result};
+use log::{debug, info};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Slots of a base package that a delta package can inherit
+#[derive(Debug, Default)]
+pub(super) struct BasePackage {
+    slots: Vec<(u64, InheritedSlot)>, // (packed operations, inherited record)
+}
+
+impl BasePackage {
+    /// Read the inheritable slots of the package at `path`
+    ///
+    /// Slots that don't stay in the base's workenv (init slots, the SBOM,
+    /// self-referential and inherited slots) or that extract outside it are
+    /// left out.
+    pub(super) fn open(path: &Path) -> Result<Self> {
+        let mut reader = Reader::new(&resolve_data_path(path)?)?;
+        let package_checksum = reader.package_cache_key()?;
+        let metadata = reader.read_metadata()?.clone();
+        let descriptors = reader.read_slot_descriptors()?;
+        let package = WorkenvPaths::new(PathBuf::new(), path).name().to_string();
+
+        let mut slots = Vec::new();
+        for (i, (slot, descriptor)) in metadata.slots.iter().zip(&descriptors).enumerate() {
+            if slot.checksum.is_empty()
+                || slot.inherited.is_some()
+                || slot.id == SBOM_SLOT_ID
+                || slot.lifecycle == "init"
+            {
+                continue;
+            }
+
+            let entries = if unpack_operations(descriptor.operations).contains(&OP_TAR) {
+                let mut archive = Vec::new();
+                reader.copy_slot_to(i, &mut archive)?;
+                archive_files(&archive)?
+            } else {
+                let mut hasher = Sha256::new();
+                reader.copy_slot_to(i, &mut hasher)?;
+                vec![(resolve_slot_target(&slot.target), sha256_digest(hasher))]
+            };
+            if entries.iter().any(|(file, _)| !is_workenv_relative(file)) {
+                debug!("⏭️ Base slot '{}' extracts outside the workenv", slot.id);
+                continue;
+            }
+            if entries.iter().any(|(_, digest)| digest.is_empty()) {
+                debug!(
+                    "⏭️ Base slot '{}' holds entries that can't be verified",
+                    slot.id
+                );
+                continue;
+            }
+            let (files, file_checksums) = entries.into_iter().unzip();
+
+            slots.push((
+                descriptor.operations,
+                InheritedSlot {
+                    package: package.clone(),
+                    package_checksum: package_checksum.clone(),
+                    slot_id: slot.id.clone(),
+                    checksum: slot.checksum.clone(),
+                    files,
+                    file_checksums,
+                },
+            ));
+        }
+
+        info!(
+            "🧬 Base package {package}: {} of {} slots can be inherited",
+            slots.len(),
+            metadata.slots.len()
+        );
+        Ok(Self { slots })
+    }
+
+    /// The base slot storing the same bytes with the same operations
+    pub(super) fn find(&self, checksum: &str, operations: u64) -> Option<&InheritedSlot> {
+        self.slots
+            .iter()
+            .find(|(ops, slot)| slot.checksum == checksum && *ops == operations)
+            .map(|(_, slot)| slot)
+    }
+}
+
+/// Files (not directories) a tar slot extracts, relative to the workenv, with
+/// their digests
+///
+/// Regular files are digested as `sha256:<hex>` of their contents and symlinks
+/// as `symlink:<target>`; hard links take the digest of the file they link to.
+/// Any other entry gets an empty digest, which keeps the slot from being
+/// inherited.
+fn archive_files(archive: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut files: Vec<(String, String)> = Vec::new();
+    let mut digests = HashMap::new();
+    for entry in tar::Archive::new(archive).entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            continue;
+        }
+        let path = utf8_path(&entry.path()?)?;
+        let digest = if entry_type.is_file() {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut entry, &mut hasher)?;
+            sha256_digest(hasher)
+        } else if entry_type.is_symlink() {
+            match entry.link_name()? {
+                Some(target) => format!("symlink:{}", utf8_path(&target)?),
+                None => String::new(),
+            }
+        } else if entry_type.is_hard_link() {
+            match entry.link_name()? {
+                Some(target) => digests
+                    .get(&utf8_path(&target)?)
+                    .cloned()
+                    .unwrap_or_default(),
+                None => String::new(),
+            }
+        } else {
+            String::new()
+        };
+        digests.insert(path.clone(), digest.clone());
+        files.push((path, digest));
+    }
+    Ok(files)
+}
+
+/// `path` as a string, without `.` components
+fn utf8_path(path: &Path) -> Result<String> {
+    let path: PathBuf = path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect();
+    path.to_str().map(str::to_string).ok_or_else(|| {
+        FlavorError::BuildError(format!("Non-UTF-8 path in base slot: {}", path.display()))
+    })
+}
+
+/// `sha256:<hex>` of what was written to `hasher`
+fn sha256_digest(hasher: Sha256) -> String {
+    format!("sha256:{}", hex::encode(hasher.finalize()))
+}
+
+/// Whether `file` stays inside the workenv it is joined to
+fn is_workenv_relative(file: &str) -> bool {
+    let path = Path::new(file);
+    !file.is_empty() && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
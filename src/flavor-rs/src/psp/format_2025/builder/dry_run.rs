@@ -0,0 +1,151 @@
+//! Dry-run builds: size and checksum a package without writing it
+
+use super::super::constants::{
+    HEADER_SIZE, MAGIC_TRAILER_SIZE, SLOT_ALIGNMENT, SLOT_DESCRIPTOR_SIZE,
+};
+use super::super::index::Index;
+use super::super::keys::{KeyProvider, LocalKeyProvider};
+use super::super::manifest::BuildManifest;
+use super::super::metadata::SbomInfo;
+use super::super::slots::align_offset;
+use super::delta::BasePackage;
+use super::metadata::{compress_metadata_into, create_metadata, sign_metadata};
+use super::slot_processor::SlotProcessor;
+use crate::api::BuildOptions;
+use crate::exceptions::{FlavorError, Result};
+use log::info;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What a build would produce, without the package itself
+///
+/// Returned by a build with `BuildOptions::dry_run`; its `Display` is the
+/// summary the builder CLI prints.
+#[derive(Debug)]
+pub struct DryRunReport {
+    pub package: String,
+    /// (slot id, operations, stored size) per slot
+    pub slots: Vec<(String, String, u64)>,
+    pub metadata_size: u64,
+    pub package_size: u64,
+    pub launcher_size: u64,
+    /// Where a split build would put the package data
+    pub data_file: Option<PathBuf>,
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🧪 Dry run: {} (no output written)", self.package)?;
+        writeln!(f, "  Slots: {}", self.slots.len())?;
+        for (i, (id, operations, size)) in self.slots.iter().enumerate() {
+            let codec = if operations.is_empty() {
+                "none"
+            } else {
+                operations
+            };
+            writeln!(f, "    [{i}] {id}: {size} bytes ({codec})")?;
+        }
+        let slot_bytes: u64 = self.slots.iter().map(|(_, _, size)| size).sum();
+        writeln!(f, "  Slot data: {slot_bytes} bytes")?;
+        writeln!(f, "  Metadata: {} bytes", self.metadata_size)?;
+        match self.data_file {
+            Some(ref data_file) => {
+                writeln!(f, "  Launcher stub: {} bytes", self.launcher_size)?;
+                writeln!(f, "  Data file: {}", data_file.display())?;
+                writeln!(f, "  Estimated data file size: {} bytes", self.package_size)
+            }
+            None => writeln!(f, "  Estimated package size: {} bytes", self.package_size),
+        }
+    }
+}
+
+/// Run every build step that doesn't touch the output file
+///
+/// Slots are read, checksummed and sized and the metadata is signed, so a
+/// dry run fails on the same manifest problems a real build would. For a
+/// split build, `data_file` is the sibling data file and the size estimate is
+/// for that file.
+pub(super) fn dry_run(
+    manifest: &BuildManifest,
+    sbom: Option<SbomInfo>,
+    slot_alignment: u64,
+    options: &BuildOptions,
+    data_file: Option<&Path>,
+) -> Result<DryRunReport> {
+    info!("🧪 Dry run: validating {}", manifest.package.name);
+    let launcher_data = super::load_launcher(options, manifest.launcher_version.as_deref())?;
+    let launcher_size = launcher_data.len() as u64;
+
+    let mut metadata = create_metadata(manifest, launcher_size, &launcher_data, options)?;
+    let mut slot_processor =
+        SlotProcessor::new(manifest.slots.clone(), options.default_compression_level)
+            .with_max_slot_size(options.max_slot_size)
+            .with_base(
+                options
+                    .base_package
+                    .as_deref()
+                    .map(BasePackage::open)
+                    .transpose()?,
+            );
+    slot_processor.process_slots()?;
+    metadata.slots = slot_processor.metadata_slots;
+    metadata.sbom = sbom;
+
+    // Sign into a scratch index; only the compressed length matters here
+    let local_provider;
+    let key_provider: &dyn KeyProvider = if let Some(provider) = options.key_provider.as_deref() {
+        provider
+    } else {
+        local_provider = LocalKeyProvider::from_options(options)?;
+        &local_provider
+    };
+    let metadata_json = sign_metadata(&metadata, key_provider, &mut Index::new())?;
+    let (compressed_size, _) = compress_metadata_into(
+        std::io::sink(),
+        &metadata_json,
+        options.metadata_compression_level,
+    )?;
+    // Metadata gzip doesn't shrink is stored as plain JSON
+    let metadata_size = compressed_size.min(metadata_json.len() as u64);
+
+    // Mirror the layout finalization writes; split data files have no launcher
+    let data_offset = if data_file.is_some() {
+        0
+    } else {
+        launcher_size
+    };
+    let descriptors = &slot_processor.slot_descriptors;
+    let mut end = align_offset(
+        data_offset + HEADER_SIZE as u64 + metadata_size,
+        SLOT_ALIGNMENT,
+    ) + (descriptors.len() * SLOT_DESCRIPTOR_SIZE) as u64;
+    for (descriptor, path) in descriptors.iter().zip(&slot_processor.slot_paths) {
+        if !path.as_os_str().is_empty() {
+            end = align_offset(end, slot_alignment) + descriptor.size;
+        }
+    }
+    let package_size = end + MAGIC_TRAILER_SIZE as u64;
+
+    if let Some(limit) = options
+        .max_package_size
+        .filter(|&limit| package_size > limit)
+    {
+        return Err(FlavorError::BuildError(format!(
+            "Package {} would be {package_size} bytes, exceeding max_package_size of {limit} bytes",
+            manifest.package.name
+        )));
+    }
+
+    Ok(DryRunReport {
+        package: manifest.package.name.clone(),
+        slots: metadata
+            .slots
+            .iter()
+            .map(|slot| (slot.id.clone(), slot.operations.clone(), slot.size as u64))
+            .collect(),
+        metadata_size,
+        package_size,
+        launcher_size,
+        data_file: data_file.map(Path::to_path_buf),
+    })
+}
@@ -4,42 +4,67 @@ use super::super::constants::{
     MAGIC_TRAILER_SIZE, MAGIC_WAND_EMOJI_BYTES, PACKAGE_EMOJI_BYTES, SLOT_ALIGNMENT,
     SLOT_DESCRIPTOR_SIZE,
 };
+use super::super::defaults::{INDEX_FLAG_FILE_CRC, INDEX_FLAG_METADATA_RAW};
+use super::super::encryption::{Encryptor, seal_into};
+use super::super::file_crc::compute_file_crc;
 use super::super::index::Index;
 use super::super::manifest::BuildManifest;
 use super::super::slots::{SlotDescriptor, align_offset};
+use super::metadata::{HashingWriter, compress_metadata_into};
 use crate::api::BuildOptions;
-use crate::exceptions::Result;
+use crate::exceptions::{FlavorError, Result};
 use log::{debug, info, trace};
+use sha2::{Digest, Sha256};
 use std::fs::File;
-use std::io::{self, Seek, SeekFrom, Write};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-/// Write metadata to output file
+/// Compress signed metadata straight into the output file and record its
+/// offset, size and checksum in the index
+///
+/// Metadata that gzip doesn't shrink is rewritten as plain JSON and flagged
+/// with `INDEX_FLAG_METADATA_RAW`, so readers skip the decompression.
 pub(super) fn write_metadata_bytes(
     out: &mut File,
-    compressed: &[u8],
+    metadata_json: &[u8],
+    compression_level: Option<u32>,
     index: &mut Index,
 ) -> Result<()> {
     let metadata_pos = out.stream_position()?;
     debug!("📝 Writing metadata at position {:#x}", metadata_pos);
 
-    out.write_all(compressed)?;
+    let (mut size, mut checksum) =
+        compress_metadata_into(&mut *out, metadata_json, compression_level)?;
+    let raw_size = metadata_json.len() as u64;
+    if size >= raw_size {
+        debug!(
+            "📝 Gzip doesn't shrink the metadata ({raw_size} -> {size} bytes), storing it as JSON"
+        );
+        out.seek(SeekFrom::Start(metadata_pos))?;
+        out.write_all(metadata_json)?;
+        out.set_len(metadata_pos + raw_size)?;
+        size = raw_size;
+        checksum = Sha256::digest(metadata_json).into();
+        index.flags |= INDEX_FLAG_METADATA_RAW;
+    } else {
+        index.flags &= !INDEX_FLAG_METADATA_RAW;
+    }
     let metadata_end = out.stream_position()?;
 
     index.metadata_offset = metadata_pos;
-    index.metadata_size = compressed.len() as u64;
+    index.metadata_size = size;
+    index.metadata_checksum = checksum;
 
     debug!(
         "📝 Wrote metadata: start={:#x}, size={}, end={:#x}",
-        metadata_pos,
-        compressed.len(),
-        metadata_end
+        metadata_pos, size, metadata_end
     );
 
     // Verify position math
     assert_eq!(
         metadata_end,
-        metadata_pos + compressed.len() as u64,
+        metadata_pos + size,
         "Metadata end position mismatch!"
     );
 
@@ -88,12 +113,21 @@ pub(super) fn reserve_descriptor_space(
 }
 
 /// Stream slot data from files to output
+///
+/// With an `encryptor`, each slot is sealed chunk by chunk as it is copied
+/// (see [`super::super::encryption`]); the descriptor's size and checksum are
+/// updated to describe the sealed bytes. Otherwise slots are copied through a
+/// `buffer_size` write buffer, or left to `io::copy` (which can use
+/// `copy_file_range`) when it is unset.
 pub(super) fn stream_slot_data(
     out: &mut File,
     descriptors: &mut [SlotDescriptor],
     slot_paths: &[PathBuf],
+    alignment: u64,
+    encryptor: Option<&dyn Encryptor>,
+    buffer_size: Option<usize>,
 ) -> Result<()> {
-    trace!("📦 Streaming slot data to output");
+    trace!("📦 Streaming slot data to output ({alignment}-byte alignment)");
 
     for (i, (descriptor, slot_path)) in descriptors.iter_mut().zip(slot_paths).enumerate() {
         // Skip empty paths (self-referential slots)
@@ -105,7 +139,7 @@ pub(super) fn stream_slot_data(
 
         // Align position
         let current = out.stream_position()?;
-        let aligned = align_offset(current, SLOT_ALIGNMENT);
+        let aligned = align_offset(current, alignment);
         if aligned > current {
             out.write_all(&vec![0u8; (aligned - current) as usize])?;
         }
@@ -114,13 +148,42 @@ pub(super) fn stream_slot_data(
         let slot_offset = out.stream_position()?;
         descriptor.offset = slot_offset;
 
-        // Stream file directly to output
-        let mut slot_file = File::open(slot_path)?;
-        let bytes_copied = io::copy(&mut slot_file, out)?;
+        let write_timer = Instant::now();
+        let bytes_copied = if let Some(encryptor) = encryptor {
+            let mut slot_file = io::BufReader::new(File::open(slot_path)?);
+            let mut writer = HashingWriter::new(BufWriter::new(&mut *out));
+            seal_into(encryptor, descriptor.id, &mut slot_file, &mut writer)?;
+            writer.flush()?;
+            let hash = writer.hasher.finalize();
+            let mut first = [0u8; 8];
+            first.copy_from_slice(&hash[..8]);
+            descriptor.size = writer.written;
+            descriptor.checksum = u64::from_le_bytes(first);
+            trace!("🔐 Sealed slot {} with {}", i, encryptor.algorithm());
+            writer.written
+        } else {
+            // Stream file directly to output
+            let mut slot_file = File::open(slot_path)?;
+            match buffer_size {
+                Some(capacity) => {
+                    let mut writer = BufWriter::with_capacity(capacity, &mut *out);
+                    let copied = io::copy(&mut slot_file, &mut writer)?;
+                    writer.flush()?;
+                    copied
+                }
+                None => io::copy(&mut slot_file, out)?,
+            }
+        };
+        let write_duration = write_timer.elapsed();
+        let original_size = descriptor.original_size; // Copy to avoid unaligned access
 
         debug!(
-            "📍 Wrote slot {}: offset={:#x}, size={} bytes",
-            i, slot_offset, bytes_copied
+            slot_index = i,
+            stored_size = bytes_copied,
+            original_size = original_size,
+            write_us = write_duration.as_micros();
+            "📍 Wrote slot {}: offset={:#x}, size={} bytes in {:?}",
+            i, slot_offset, bytes_copied, write_duration
         );
     }
 
@@ -163,25 +226,19 @@ pub(super) fn finalize_package(
     options: &BuildOptions,
 ) -> Result<()> {
     trace!("🎬 Finalizing package with MagicTrailer");
-
-    // Update package size before writing MagicTrailer
-    index.package_size = end_pos + MAGIC_TRAILER_SIZE as u64;
-
-    // Write MagicTrailer (8200 bytes: 📦 + index + 🪄)
-    out.write_all(PACKAGE_EMOJI_BYTES)?;
-    write_index(out, index)?;
-    out.write_all(MAGIC_WAND_EMOJI_BYTES)?;
-
-    // Make the output file executable
-    #[cfg(unix)]
+    let package_size = end_pos + MAGIC_TRAILER_SIZE as u64;
+    if let Some(limit) = options
+        .max_package_size
+        .filter(|&limit| package_size > limit)
     {
-        use super::super::defaults::DEFAULT_DIR_PERMS;
-        use std::fs;
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(output_path)?.permissions();
-        perms.set_mode(DEFAULT_DIR_PERMS as u32);
-        fs::set_permissions(output_path, perms)?;
+        // Don't leave a trailer-less package behind for something to pick up
+        let _ = std::fs::remove_file(output_path);
+        return Err(FlavorError::BuildError(format!(
+            "Package {} would be {package_size} bytes, exceeding max_package_size of {limit} bytes",
+            manifest.package.name
+        )));
     }
+    write_trailer(out, index, end_pos, output_path, output_mode(options)?)?;
 
     // Log success message
     log::info!("✅ Successfully built PSPF bundle: {output_path:?}");
@@ -204,12 +261,72 @@ pub(super) fn finalize_package(
     Ok(())
 }
 
+/// Write the MagicTrailer after `end_pos` and give the output file `mode`
+pub(super) fn write_trailer(
+    out: &mut File,
+    index: &mut Index,
+    end_pos: u64,
+    output_path: &Path,
+    mode: u32,
+) -> Result<()> {
+    // Update package size before writing MagicTrailer
+    index.package_size = end_pos + MAGIC_TRAILER_SIZE as u64;
+
+    // Write MagicTrailer (8200 bytes: 📦 + index + 🪄)
+    out.write_all(PACKAGE_EMOJI_BYTES)?;
+    write_index(out, index)?;
+    out.write_all(MAGIC_WAND_EMOJI_BYTES)?;
+
+    // The file CRC covers the finished file, so patch it into the index afterwards
+    if index.flags & INDEX_FLAG_FILE_CRC != 0 {
+        out.flush()?;
+        let crc = compute_file_crc(output_path)?;
+        trace!("🔢 Whole-file CRC32C 0x{crc:08x}");
+        index.set_file_crc(crc);
+        out.seek(SeekFrom::Start(end_pos + PACKAGE_EMOJI_BYTES.len() as u64))?;
+        write_index(out, index)?;
+        out.seek(SeekFrom::End(0))?;
+    }
+
+    set_output_mode(output_path, mode)
+}
+
+/// Unix mode for build outputs: `output_permissions` (default 0o700), without
+/// execute bits under `no_exec`
+pub(super) fn output_mode(options: &BuildOptions) -> Result<u32> {
+    use super::super::defaults::DEFAULT_DIR_PERMS;
+
+    let mode = options
+        .output_permissions
+        .unwrap_or(u32::from(DEFAULT_DIR_PERMS));
+    if mode > 0o7777 {
+        return Err(FlavorError::ConfigError(format!(
+            "Output permissions {mode:o} are not a file mode"
+        )));
+    }
+    Ok(if options.no_exec { mode & !0o111 } else { mode })
+}
+
+/// Set a build output's mode (Unix only)
+pub(super) fn set_output_mode(path: &Path, mode: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+
+    Ok(())
+}
+
 /// Write index with calculated checksum
 fn write_index(out: &mut File, index: &mut Index) -> Result<()> {
     // Calculate checksum with placeholder set to 0
     let mut bytes = index.pack();
     bytes[4..8].copy_from_slice(&[0, 0, 0, 0]);
-    let checksum = adler::adler32_slice(&bytes);
+    let checksum = Index::calculate_checksum(index.flags, &bytes);
 
     // Update the index structure with the calculated checksum
     index.index_checksum = checksum;
@@ -220,3 +337,95 @@ fn write_index(out: &mut File, index: &mut Index) -> Result<()> {
     out.write_all(&final_bytes)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_slot_data_page_alignment() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+
+        let slot_paths: Vec<PathBuf> = (0..3)
+            .map(|i| {
+                let path = dir.path().join(format!("slot{i}"));
+                assert!(std::fs::write(&path, vec![i as u8; 1000 + i]).is_ok());
+                path
+            })
+            .collect();
+        let mut descriptors: Vec<SlotDescriptor> =
+            (0..3).map(|i| SlotDescriptor::new(i as u64)).collect();
+
+        let out = File::create(dir.path().join("package"));
+        assert!(out.is_ok());
+        let Ok(mut out) = out else { return };
+        assert!(out.write_all(&[0u8; 123]).is_ok());
+
+        assert!(
+            stream_slot_data(&mut out, &mut descriptors, &slot_paths, 4096, None, None).is_ok()
+        );
+        for descriptor in &descriptors {
+            let offset = descriptor.offset;
+            assert_eq!(offset % 4096, 0, "offset {offset:#x} not page-aligned");
+        }
+    }
+
+    #[test]
+    fn test_tiny_metadata_is_stored_uncompressed() {
+        use super::super::super::reader::Reader;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let path = dir.path().join("package");
+        let json = serde_json::json!({
+            "format": "PSPF/2025",
+            "package": {"name": "tiny", "version": "1"},
+            "slots": [],
+            "execution": {"command": "true"}
+        })
+        .to_string();
+
+        let out = File::create(&path);
+        assert!(out.is_ok());
+        let Ok(mut out) = out else {
+            return;
+        };
+        let mut index = Index::new();
+        assert!(write_metadata_bytes(&mut out, json.as_bytes(), None, &mut index).is_ok());
+        assert!(!index.metadata_compressed());
+        let size = index.metadata_size;
+        assert_eq!(size, json.len() as u64);
+        assert!(write_trailer(&mut out, &mut index, size, &path, 0o600).is_ok());
+        drop(out);
+        assert!(std::fs::read(&path).is_ok_and(|data| data.starts_with(json.as_bytes())));
+
+        let reader = Reader::new(&path);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        assert!(
+            reader
+                .read_metadata()
+                .is_ok_and(|m| m.package.name == "tiny")
+        );
+        let mut streamed = Vec::new();
+        assert!(reader.stream_metadata_to(&mut streamed).is_ok());
+        assert_eq!(streamed, json.as_bytes());
+
+        // Metadata gzip does shrink is compressed, clearing a stale flag
+        let out = File::create(dir.path().join("large"));
+        assert!(out.is_ok());
+        let Ok(mut out) = out else {
+            return;
+        };
+        let large = format!("{{\"padding\": \"{}\"}}", "x".repeat(4096));
+        assert!(write_metadata_bytes(&mut out, large.as_bytes(), None, &mut index).is_ok());
+        assert!(index.metadata_compressed());
+        let size = index.metadata_size;
+        assert!(size < large.len() as u64);
+    }
+}
@@ -19,12 +19,12 @@ pub fn load_keys_from_files(
     File::open(private_key_path)?.read_to_end(&mut private_key_data)?;
 
     let pem = parse(&private_key_data)
-        .map_err(|e| FlavorError::Generic(format!("Failed to parse private key PEM: {e}")))?;
+        .map_err(|e| FlavorError::InvalidKey("Failed to parse private key PEM".into()).with_source(e))?;
 
     // Try to parse as raw Ed25519 (32 bytes) or PKCS8
     let signing_key = if pem.contents().len() == 32 {
         let key_array: [u8; 32] = pem.contents().try_into().map_err(|_| {
-            FlavorError::Generic("Private key must be exactly 32 bytes".to_string())
+            FlavorError::InvalidKey("Private key must be exactly 32 bytes".to_string())
         })?;
         SigningKey::from_bytes(&key_array)
     } else {
@@ -33,12 +33,12 @@ pub fn load_keys_from_files(
             // Assume PKCS8 and extract the actual key bytes (last 32 bytes typically)
             &pem.contents()[pem.contents().len() - 32..]
         } else {
-            return Err(FlavorError::Generic("Invalid private key size".to_string()));
+            return Err(FlavorError::InvalidKey("Invalid private key size".to_string()));
         };
         SigningKey::from_bytes(
             key_bytes
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid key size".to_string()))?,
+                .map_err(|_| FlavorError::InvalidKey("Invalid key size".to_string()))?,
         )
     };
 
@@ -49,13 +49,13 @@ pub fn load_keys_from_files(
         File::open(public_path)?.read_to_end(&mut public_key_data)?;
 
         let pem = parse(&public_key_data)
-            .map_err(|e| FlavorError::Generic(format!("Failed to parse public key PEM: {e}")))?;
+            .map_err(|e| FlavorError::InvalidKey("Failed to parse public key PEM".into()).with_source(e))?;
 
         let key_array: [u8; 32] = pem.contents().try_into().map_err(|_| {
-            FlavorError::Generic("Public key must be exactly 32 bytes".to_string())
+            FlavorError::InvalidKey("Public key must be exactly 32 bytes".to_string())
         })?;
         VerifyingKey::from_bytes(&key_array)
-            .map_err(|e| FlavorError::Generic(format!("Invalid public key: {e}")))?
+            .map_err(|e| FlavorError::InvalidKey("Invalid public key".into()).with_source(e))?
     } else {
         // Derive from private key
         signing_key.verifying_key()
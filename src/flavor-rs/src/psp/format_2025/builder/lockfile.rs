@@ -0,0 +1,135 @@
+//! Build lockfile emission
+//!
+//! Writes a JSON sidecar recording exactly what went into a package so it can
+//! be audited and diffed without parsing the binary.
+
+use super::super::index::Index;
+use super::super::metadata::Metadata;
+use super::super::slots::SlotDescriptor;
+use crate::api::BuildOptions;
+use crate::exceptions::Result;
+use log::{debug, info};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lockfile format identifier
+const LOCKFILE_FORMAT: &str = "flavor-lock/1";
+
+#[derive(Debug, Serialize)]
+struct Lockfile {
+    format: &'static str,
+    package: LockedPackage,
+    build_timestamp: String,
+    public_key: String,
+    launcher: LockedLauncher,
+    slots: Vec<LockedSlot>,
+}
+
+#[derive(Debug, Serialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    size: u64,
+    index_checksum: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LockedLauncher {
+    path: Option<String>,
+    version: String,
+    size: u64,
+    checksum: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LockedSlot {
+    index: usize,
+    id: String,
+    source: String,
+    resolved_path: Option<String>,
+    target: String,
+    size: u64,
+    original_size: u64,
+    checksum: String,
+    operations: String,
+}
+
+/// Record a path relative to the current directory when possible
+fn relative_path(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Write the lockfile for a finished build
+pub(super) fn write_lockfile(
+    lockfile_path: &Path,
+    metadata: &Metadata,
+    descriptors: &[SlotDescriptor],
+    slot_paths: &[PathBuf],
+    index: &Index,
+    options: &BuildOptions,
+) -> Result<()> {
+    let slots = metadata
+        .slots
+        .iter()
+        .zip(descriptors)
+        .zip(slot_paths)
+        .map(|((slot, descriptor), path)| LockedSlot {
+            index: slot.index,
+            id: slot.id.clone(),
+            source: slot.source.clone(),
+            // Inline slots are staged in a scratch file that's gone after the build
+            resolved_path: (!slot.source.is_empty() && !path.as_os_str().is_empty())
+                .then(|| relative_path(path)),
+            target: slot.target.clone(),
+            size: descriptor.size,
+            original_size: descriptor.original_size,
+            checksum: slot.checksum.clone(),
+            operations: slot.operations.clone(),
+        })
+        .collect();
+
+    let launcher_path = options
+        .launcher_bin
+        .clone()
+        .or_else(|| std::env::var("FLAVOR_LAUNCHER_BIN").ok().map(PathBuf::from));
+    let launcher = metadata.launcher.as_ref();
+
+    let lockfile = Lockfile {
+        format: LOCKFILE_FORMAT,
+        package: LockedPackage {
+            name: metadata.package.name.clone(),
+            version: metadata.package.version.clone(),
+            size: index.package_size,
+            index_checksum: format!("{}:{:08x}", index.checksum_algorithm(), {
+                index.index_checksum
+            }),
+        },
+        build_timestamp: metadata
+            .build
+            .as_ref()
+            .map(|b| b.timestamp.clone())
+            .unwrap_or_default(),
+        public_key: hex::encode(index.public_key),
+        launcher: LockedLauncher {
+            path: launcher_path.as_deref().map(relative_path),
+            version: launcher.map(|l| l.tool_version.clone()).unwrap_or_default(),
+            size: index.launcher_size,
+            checksum: launcher.map(|l| l.checksum.clone()).unwrap_or_default(),
+        },
+        slots,
+    };
+
+    let json = serde_json::to_string_pretty(&lockfile)?;
+    fs::write(lockfile_path, json)?;
+    debug!("🔏 Lockfile covers {} slots", descriptors.len());
+    info!("🔏 Wrote build lockfile: {}", lockfile_path.display());
+    Ok(())
+}
+
+// 📦🔏📋🪄
@@ -1,17 +1,21 @@
 //! Metadata creation and compression
 
+use super::super::attestation::RekorEntry;
 use super::super::checksums::{ChecksumAlgorithm, calculate_checksum};
 use super::super::index::Index;
+use super::super::keys::KeyProvider;
 use super::super::manifest::BuildManifest;
 use super::super::metadata::{
     BuildInfo, CacheValidationInfo, CompatibilityInfo, ExecutionInfo, IntegritySealInfo,
-    LauncherInfo, Metadata, PackageInfo, PlatformInfo, RuntimeInfo, VerificationInfo, WorkenvInfo,
+    LauncherInfo, METADATA_SCHEMA_VERSION, Metadata, PackageInfo, PlatformInfo, RuntimeInfo,
+    VerificationInfo, WorkenvInfo,
 };
 use crate::api::BuildOptions;
 use crate::exceptions::{FlavorError, Result};
-use ed25519_dalek::{Signature, Signer};
+use ed25519_dalek::{Signature, VerifyingKey};
 use log::trace;
-use std::io::Write;
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
 use std::path::PathBuf;
 
 /// Get build timestamp and host information
@@ -55,12 +59,13 @@ pub(super) fn create_metadata(
     // Calculate launcher checksum
     let launcher_checksum =
         calculate_checksum(launcher_data, ChecksumAlgorithm::Sha256).map_err(|e| {
-            FlavorError::Generic(format!("Failed to calculate launcher checksum: {}", e))
+            FlavorError::BuildError("Failed to calculate launcher checksum".into()).with_source(e)
         })?;
 
     Ok(Metadata {
         format: "PSPF/2025".to_string(),
         format_version: Some("1.0.0".to_string()),
+        metadata_schema_version: Some(METADATA_SCHEMA_VERSION),
         package: PackageInfo {
             name: manifest.package.name.clone(),
             version: manifest.package.version.clone(),
@@ -70,6 +75,9 @@ pub(super) fn create_metadata(
             primary_slot: 0,
             command: manifest.execution.command.clone(),
             env: manifest.execution.env.clone(),
+            workdir: manifest.execution.workdir.clone(),
+            entrypoints: manifest.execution.entrypoints.clone(),
+            argv0: manifest.execution.argv0.clone(),
         },
         verification: Some(VerificationInfo {
             integrity_seal: IntegritySealInfo {
@@ -129,41 +137,133 @@ pub(super) fn create_metadata(
             .as_ref()
             .and_then(|v| serde_json::from_value::<WorkenvInfo>(v.clone()).ok()),
         setup_commands: manifest.setup_commands.clone(),
+        shutdown_commands: manifest.shutdown_commands.clone(),
+        sbom: None,
+        extra: serde_json::Map::new(),
     })
 }
 
-/// Compress and sign metadata
-pub(super) fn compress_and_sign_metadata(
+/// Serialize metadata exactly as it is signed
+fn metadata_json(metadata: &Metadata) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec_pretty(metadata)?)
+}
+
+/// Check a Rekor entry against the signed metadata and store it in the index
+///
+/// Must run after [`sign_metadata`]; an entry logged for an
+/// earlier build (different metadata or key) fails the build.
+pub(super) fn attach_rekor_entry(
+    entry_json: &str,
     metadata: &Metadata,
-    signing_key: &ed25519_dalek::SigningKey,
+    index: &mut Index,
+) -> Result<()> {
+    let entry = RekorEntry::from_json(entry_json.as_bytes())?;
+    let mut signature = [0u8; 64];
+    signature.copy_from_slice(&index.integrity_signature[..64]);
+    let public_key = VerifyingKey::from_bytes(&index.public_key)
+        .map_err(|e| FlavorError::BuildError("Invalid public key".into()).with_source(e))?;
+
+    entry
+        .verify(
+            &metadata_json(metadata)?,
+            &Signature::from_bytes(&signature),
+            &public_key,
+        )
+        .map_err(|e| FlavorError::BuildError(e.to_string()))?;
+    trace!(
+        "🪵 Rekor entry {} matches the metadata signature",
+        entry.log_index
+    );
+
+    // Stored compact to leave room in the reserved space
+    let compact = serde_json::to_vec(&serde_json::from_str::<serde_json::Value>(entry_json)?)?;
+    index.set_attestation(&compact)
+}
+
+/// Sign metadata, returning the JSON the signature covers
+pub(super) fn sign_metadata(
+    metadata: &Metadata,
+    key_provider: &dyn KeyProvider,
     index: &mut Index,
 ) -> Result<Vec<u8>> {
     trace!("📝 Creating and signing metadata");
 
     // Create JSON
-    let metadata_json = serde_json::to_vec_pretty(metadata)?;
+    let metadata_json = metadata_json(metadata)?;
 
     // Sign the metadata
-    let signature: Signature = signing_key.sign(&metadata_json);
+    let signature: Signature = key_provider.sign(&metadata_json)?;
     index.integrity_signature[..64].copy_from_slice(signature.to_bytes().as_ref());
+    Ok(metadata_json)
+}
+
+/// Gzip signed metadata JSON into `out`, returning the compressed size and
+/// its SHA-256
+///
+/// The compressed bytes are hashed as they are written rather than buffered.
+pub(super) fn compress_metadata_into(
+    out: impl Write,
+    metadata_json: &[u8],
+    compression_level: Option<u32>,
+) -> Result<(u64, [u8; 32])> {
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
 
-    // Compress with gzip
-    let mut compressed = Vec::new();
-    {
-        use flate2::Compression;
-        use flate2::write::GzEncoder;
+    let level = compression_level.map_or_else(Compression::default, Compression::new);
+    let mut encoder = GzEncoder::new(HashingWriter::new(out), level);
+    encoder.write_all(metadata_json)?;
+    let writer = encoder.finish()?;
+    Ok((writer.written, writer.hasher.finalize().into()))
+}
+
+/// Counts and hashes the bytes passing through to `inner`
+pub(super) struct HashingWriter<W> {
+    inner: W,
+    pub(super) hasher: Sha256,
+    pub(super) written: u64,
+}
 
-        let mut encoder = GzEncoder::new(&mut compressed, Compression::default());
-        encoder.write_all(&metadata_json)?;
-        encoder.finish()?;
+impl<W: Write> HashingWriter<W> {
+    pub(super) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            written: 0,
+        }
     }
+}
 
-    // Calculate checksum (SHA-256 - full 32 bytes)
-    use sha2::{Digest, Sha256};
-    let mut hasher = Sha256::new();
-    hasher.update(&compressed);
-    let checksum_bytes: [u8; 32] = hasher.finalize().into();
-    index.metadata_checksum = checksum_bytes;
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.written += n as u64;
+        Ok(n)
+    }
 
-    Ok(compressed)
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_compress_metadata_into_hashes_what_it_writes() {
+        let json = br#"{"package":{"name":"streamed","version":"1.0"}}"#.repeat(500);
+        let mut compressed = Vec::new();
+        let written = compress_metadata_into(&mut compressed, &json, Some(9));
+        assert!(written.as_ref().is_ok_and(|(size, checksum)| {
+            *size == compressed.len() as u64
+                && *checksum == <[u8; 32]>::from(Sha256::digest(&compressed))
+        }));
+
+        let mut decoded = Vec::new();
+        let read = flate2::read::GzDecoder::new(compressed.as_slice()).read_to_end(&mut decoded);
+        assert!(read.is_ok());
+        assert_eq!(decoded, json);
+    }
 }
@@ -1,144 +1,400 @@
 //! PSPF/2025 package builder
 
+mod command_policy;
+mod delta;
+mod dry_run;
 mod finalization;
+mod lockfile;
 mod metadata;
+mod repack;
 mod slot_processor;
 
+use command_policy::validate_commands;
+use delta::BasePackage;
 use finalization::{
-    finalize_package, reserve_descriptor_space, stream_slot_data, write_descriptor_table,
-    write_metadata_bytes,
+    finalize_package, output_mode, reserve_descriptor_space, set_output_mode, stream_slot_data,
+    write_descriptor_table, write_metadata_bytes,
 };
-use metadata::{compress_and_sign_metadata, create_metadata};
-use slot_processor::SlotProcessor;
+use lockfile::write_lockfile;
+use metadata::{attach_rekor_entry, create_metadata, sign_metadata};
+use slot_processor::{SlotProcessor, drop_missing_optional_slots, expand_slot_env};
 
-use super::constants::HEADER_SIZE;
-use super::defaults::{CAPABILITY_MMAP, CAPABILITY_SIGNED};
+pub use dry_run::DryRunReport;
+pub use repack::{RepackReport, SlotRepack, repack};
+
+use super::constants::{HEADER_SIZE, SBOM_SLOT_ID, SLOT_ALIGNMENT};
+use super::defaults::{
+    CAPABILITY_ENCRYPTED, CAPABILITY_MMAP, CAPABILITY_PAGE_ALIGNED, CAPABILITY_SIGNED,
+    DEFAULT_PAGE_SIZE, INDEX_FLAG_CRC32C, INDEX_FLAG_FILE_CRC,
+};
+use super::detached::{data_suffix, detached_data_path};
 use super::index::Index;
-use super::keys::load_or_generate_keys;
-use super::manifest::BuildManifest;
+use super::keys::{KeyProvider, LocalKeyProvider};
+use super::manifest::{BuildManifest, ManifestSlot, read_manifest, resolve_includes};
+use super::metadata::SbomInfo;
 use crate::api::BuildOptions;
 use crate::exceptions::{FlavorError, Result};
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use std::fs::{self, File};
 use std::io::{Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 /// Build a PSPF/2025 package
-pub fn build(manifest_path: &Path, output_path: &Path, options: BuildOptions) -> Result<()> {
+///
+/// With `options.dry_run`, nothing is written and the report of what the
+/// build would produce is returned instead.
+pub fn build(
+    manifest_path: &Path,
+    output_path: &Path,
+    options: BuildOptions,
+) -> Result<Option<DryRunReport>> {
+    info!("🔨 Building PSPF/2025 package from: {manifest_path:?}");
+    let manifest_timer = Instant::now();
+    let manifest = read_manifest(manifest_path)?;
+    trace!("✅ Manifest parsed in {:?}", manifest_timer.elapsed());
+    build_manifest(manifest, output_path, options)
+}
+
+/// Build a PSPF/2025 package from manifest JSON that is already in memory
+///
+/// Manifest `includes` resolve against the current directory. Returns the
+/// dry-run report as [`build`] does.
+pub fn build_from_str(
+    manifest_data: &str,
+    output_path: &Path,
+    options: BuildOptions,
+) -> Result<Option<DryRunReport>> {
+    build_manifest(parse_manifest(manifest_data)?, output_path, options)
+}
+
+fn build_manifest(
+    mut manifest: BuildManifest,
+    output_path: &Path,
+    options: BuildOptions,
+) -> Result<Option<DryRunReport>> {
     let _start_time = Instant::now();
     info!("🦀🦀🦀 Hello from Flavor's Rust Builder 🦀🦀🦀");
     info!("PSPF Rust Builder starting...");
-    info!("🔨 Building PSPF/2025 package from: {manifest_path:?}");
     trace!("🔍 Build options: {:?}", options);
 
     // Phase 1: Initialize package components
-    let manifest = read_manifest(manifest_path)?;
-    let mut out = File::create(output_path)?;
-    trace!("📄 Created output file: {:?}", output_path);
+    validate_commands(&manifest, options.strict_commands)?;
+    expand_slot_env(&mut manifest.slots)?;
+    drop_missing_optional_slots(&mut manifest.slots)?;
+    let sbom = match options.sbom {
+        Some(ref sbom_path) => Some(add_sbom_slot(&mut manifest, sbom_path)?),
+        None => None,
+    };
+    let slot_alignment = resolve_slot_alignment(&options)?;
+    let mode = output_mode(&options)?;
+    if options.access_mode > 3 {
+        return Err(FlavorError::ConfigError(format!(
+            "Invalid access mode {}: expected 0 (auto), 1 (mmap), 2 (file) or 3 (stream)",
+            options.access_mode
+        )));
+    }
+    if options.io_buffer_size == Some(0) {
+        return Err(FlavorError::ConfigError(
+            "I/O buffer size must be at least one byte".to_string(),
+        ));
+    }
+    // Split packages keep the launcher as-is and put everything else in a sibling file
+    let data_path = if options.detached_data {
+        detached_data_path(output_path, &data_suffix())
+    } else {
+        output_path.to_path_buf()
+    };
+    if options.dry_run {
+        let data_file = options.detached_data.then_some(data_path.as_path());
+        let report = dry_run::dry_run(&manifest, sbom, slot_alignment, &options, data_file)?;
+        return Ok(Some(report));
+    }
+    // Load the launcher and process the slots before creating any file, so a
+    // version mismatch or an oversized slot leaves none behind
+    let launcher_data = load_launcher(&options, manifest.launcher_version.as_deref())?;
+    let launcher_size = launcher_data.len() as u64;
+
+    // Phase 2: Process slots and create metadata
+    let mut metadata = create_metadata(&manifest, launcher_size, &launcher_data, &options)?;
+
+    // Use the new SlotProcessor for all slot processing
+    let mut slot_processor =
+        SlotProcessor::new(manifest.slots.clone(), options.default_compression_level)
+            .with_max_slot_size(options.max_slot_size)
+            .with_io_buffer_size(options.io_buffer_size)
+            .with_base(
+                options
+                    .base_package
+                    .as_deref()
+                    .map(BasePackage::open)
+                    .transpose()?,
+            );
+    slot_processor.process_slots()?;
+    metadata.slots = slot_processor.metadata_slots;
+    metadata.sbom = sbom;
+
+    let mut out = File::create(&data_path)?;
+    trace!("📄 Created output file: {:?}", data_path);
+
+    // Phase 3: Write launcher and setup index
+    if options.detached_data {
+        info!("✂️ Writing launcher stub; package data goes to {data_path:?}");
+        write_launcher(&mut File::create(output_path)?, &launcher_data)?;
+        set_output_mode(output_path, mode)?;
+    } else {
+        write_launcher(&mut out, &launcher_data)?;
+    }
+    let data_offset = if options.detached_data {
+        0
+    } else {
+        launcher_size
+    };
+    let local_provider;
+    let key_provider: &dyn KeyProvider = if let Some(provider) = options.key_provider.as_deref() {
+        info!("🔑 Signing metadata with external key provider");
+        provider
+    } else {
+        local_provider = LocalKeyProvider::from_options(&options)?;
+        &local_provider
+    };
+    let public_key = key_provider.public_key();
+    let mut index = initialize_index(data_offset, &public_key, &options);
+    if slot_alignment % DEFAULT_PAGE_SIZE as u64 == 0 {
+        debug!(
+            "📐 Aligning slots to {} bytes (page-aligned)",
+            slot_alignment
+        );
+        index.capabilities |= CAPABILITY_PAGE_ALIGNED;
+        index.page_size = slot_alignment as u32;
+    }
 
-    // Phase 2: Write launcher and setup index
-    let (launcher_size, launcher_data) = write_launcher(&mut out, &options)?;
-    let (signing_key, public_key) = load_or_generate_keys(&options)?;
-    let mut index = initialize_index(launcher_size, &public_key);
+    record_file_hashes(&mut index, &launcher_data, &options)?;
 
     // Skip index block space
-    let data_start = launcher_size + HEADER_SIZE as u64;
+    let data_start = data_offset + HEADER_SIZE as u64;
     out.seek(SeekFrom::Start(data_start))?;
     debug!(
         "📍 Data section starts at {:#x} (after launcher {:#x} + index 512)",
-        data_start, launcher_size
+        data_start, data_offset
     );
 
-    // Phase 3: Process slots and create metadata
-    let mut metadata = create_metadata(&manifest, launcher_size, &launcher_data, &options)?;
-
-    // Use the new SlotProcessor for all slot processing
-    let mut slot_processor = SlotProcessor::new(manifest.slots.clone());
-    slot_processor.process_slots()?;
-    metadata.slots = slot_processor.metadata_slots;
-
     // Phase 4: Write metadata and setup index
-    let compressed_metadata = compress_and_sign_metadata(&metadata, &signing_key, &mut index)?;
-    write_metadata_bytes(&mut out, &compressed_metadata, &mut index)?;
+    let metadata_json = sign_metadata(&metadata, key_provider, &mut index)?;
+    if let Some(ref entry) = options.rekor_entry {
+        info!("🪵 Embedding Rekor transparency log entry");
+        attach_rekor_entry(entry, &metadata, &mut index)?;
+    }
+    write_metadata_bytes(
+        &mut out,
+        &metadata_json,
+        options.metadata_compression_level,
+        &mut index,
+    )?;
 
     // Phase 5: Reserve space for descriptor table
     let descriptor_table_offset =
         reserve_descriptor_space(&mut out, &slot_processor.slot_descriptors, &mut index)?;
 
     // Phase 6: Write slot data and update descriptors
+    let encryptor = options
+        .encryption
+        .as_ref()
+        .map(|encryption| encryption.algorithm.encryptor(&encryption.key));
     let mut slot_descriptors = slot_processor.slot_descriptors;
-    stream_slot_data(&mut out, &mut slot_descriptors, &slot_processor.slot_paths)?;
+    stream_slot_data(
+        &mut out,
+        &mut slot_descriptors,
+        &slot_processor.slot_paths,
+        slot_alignment,
+        encryptor.as_deref(),
+        options.io_buffer_size,
+    )?;
 
     // Phase 7: Write descriptor table at reserved location
     let end_pos = write_descriptor_table(&mut out, &slot_descriptors, descriptor_table_offset)?;
 
     // Phase 8: Finalize package with MagicTrailer
-    finalize_package(
-        &mut out,
-        &mut index,
-        end_pos,
-        output_path,
-        &manifest,
-        &options,
-    )?;
+    let finalized = finalize_package(
+        &mut out, &mut index, end_pos, &data_path, &manifest, &options,
+    );
+    if finalized.is_err() && options.detached_data {
+        // The data file is already gone; don't leave its stub behind either
+        let _ = fs::remove_file(output_path);
+    }
+    finalized?;
 
     // Phase 9: Convert to PE resource embedding if needed (Windows + Go launcher)
     drop(out); // Close the file before resource embedding
-    if should_use_resource_embedding(&launcher_data)? {
+    if !options.detached_data && should_use_resource_embedding(&launcher_data)? {
         info!("🪟 Converting to PE resource embedding (Windows Go launcher)");
         convert_to_resource_embedding(output_path, launcher_size)?;
         info!("✅ Successfully embedded PSPF as PE resource");
     }
 
-    Ok(())
+    // Phase 10: Emit lockfile sidecar if requested, once the package is final
+    if let Some(ref lockfile_path) = options.emit_lockfile {
+        write_lockfile(
+            lockfile_path,
+            &metadata,
+            &slot_descriptors,
+            &slot_processor.slot_paths,
+            &index,
+            &options,
+        )?;
+    }
+
+    Ok(None)
 }
 
 /// Read and parse the build manifest
-fn read_manifest(manifest_path: &Path) -> Result<BuildManifest> {
+fn parse_manifest(manifest_data: &str) -> Result<BuildManifest> {
     let manifest_timer = Instant::now();
-    let manifest_data = fs::read_to_string(manifest_path)?;
-    let manifest: BuildManifest = serde_json::from_str(&manifest_data)
-        .map_err(|e| FlavorError::Generic(format!("Failed to parse manifest: {e}")))?;
+    let parse_error = |e: serde_json::Error| {
+        FlavorError::ConfigError("Failed to parse manifest".into()).with_source(e)
+    };
+    let value = serde_json::from_str(manifest_data).map_err(parse_error)?;
+    let value = resolve_includes(value, &std::env::current_dir()?)?;
+    let manifest: BuildManifest = serde_json::from_value(value).map_err(parse_error)?;
     trace!("✅ Manifest parsed in {:?}", manifest_timer.elapsed());
     Ok(manifest)
 }
 
-/// Write launcher binary to output file
-fn write_launcher(out: &mut File, options: &BuildOptions) -> Result<(u64, Vec<u8>)> {
+/// Append the SBOM as a raw `__sbom__` slot and describe it for metadata
+fn add_sbom_slot(manifest: &mut BuildManifest, sbom_path: &Path) -> Result<SbomInfo> {
+    if manifest.slots.iter().any(|slot| slot.id == SBOM_SLOT_ID) {
+        return Err(FlavorError::BuildError(format!(
+            "Manifest already has a slot with reserved id '{SBOM_SLOT_ID}'"
+        )));
+    }
+
+    let sbom: serde_json::Value = serde_json::from_slice(&fs::read(sbom_path)?).map_err(|e| {
+        FlavorError::BuildError(format!(
+            "SBOM {} is not valid JSON: {e}",
+            sbom_path.display()
+        ))
+    })?;
+    let format = if sbom.get("bomFormat").and_then(|f| f.as_str()) == Some("CycloneDX") {
+        "cyclonedx"
+    } else if sbom.get("spdxVersion").is_some() {
+        "spdx"
+    } else {
+        "unknown"
+    };
+    info!("📋 Embedding {format} SBOM from {}", sbom_path.display());
+
+    manifest.slots.push(ManifestSlot {
+        slot: None,
+        id: SBOM_SLOT_ID.to_string(),
+        source: sbom_path.to_string_lossy().into_owned(),
+        content: None,
+        target: "sbom.json".to_string(),
+        operations: String::new(),
+        purpose: "config".to_string(),
+        lifecycle: "lazy".to_string(),
+        permissions: None,
+        resolution: None,
+        compression_level: None,
+        required: true,
+        condition: None,
+        annotations: Default::default(),
+    });
+
+    Ok(SbomInfo {
+        slot: manifest.slots.len() - 1,
+        format: format.to_string(),
+    })
+}
+
+/// Load the launcher binary, checked against `expected_version` and ready to embed
+fn load_launcher(options: &BuildOptions, expected_version: Option<&str>) -> Result<Vec<u8>> {
     let launcher_timer = Instant::now();
-    let launcher_data = get_launcher(options)?;
+    let launcher_data = get_launcher(options, expected_version)?;
 
     // Process launcher for Windows PE compatibility if needed
     let launcher_data = super::pe_utils::process_launcher_for_pspf(launcher_data)?;
 
-    let launcher_size = launcher_data.len() as u64;
     debug!(
         "🚀 Loaded and processed launcher: {} bytes in {:?}",
-        launcher_size,
+        launcher_data.len(),
         launcher_timer.elapsed()
     );
+    Ok(launcher_data)
+}
 
+/// Write launcher binary to output file
+fn write_launcher(out: &mut File, launcher_data: &[u8]) -> Result<()> {
     let write_timer = Instant::now();
-    out.write_all(&launcher_data)?;
+    out.write_all(launcher_data)?;
     trace!("✍️ Wrote launcher in {:?}", write_timer.elapsed());
+    Ok(())
+}
 
-    Ok((launcher_size, launcher_data))
+/// Resolve and validate the requested slot alignment
+fn resolve_slot_alignment(options: &BuildOptions) -> Result<u64> {
+    let alignment = options.slot_alignment.unwrap_or(SLOT_ALIGNMENT);
+    if alignment < SLOT_ALIGNMENT || !alignment.is_power_of_two() || alignment > u64::from(u32::MAX)
+    {
+        return Err(FlavorError::BuildError(format!(
+            "Invalid slot alignment {alignment}: must be a power of two of at least {SLOT_ALIGNMENT}"
+        )));
+    }
+    Ok(alignment)
 }
 
 /// Initialize the index structure
-fn initialize_index(launcher_size: u64, public_key: &ed25519_dalek::VerifyingKey) -> Index {
+fn initialize_index(
+    launcher_size: u64,
+    public_key: &ed25519_dalek::VerifyingKey,
+    options: &BuildOptions,
+) -> Index {
     trace!("📦 Creating PSPF/2025 index structure");
     let mut index = Index::new();
     index.launcher_size = launcher_size;
     index.public_key.copy_from_slice(public_key.as_bytes());
     index.capabilities = CAPABILITY_MMAP | CAPABILITY_SIGNED;
+    index.access_mode = options.access_mode;
+    if options.crc32c_index {
+        trace!("🔢 Using CRC32C for index checksum");
+        index.flags |= INDEX_FLAG_CRC32C;
+    }
+    if let Some(ref encryption) = options.encryption {
+        info!("🔐 Encrypting slots with {}", encryption.algorithm);
+        index.encryption_type = encryption.algorithm.encryption_type();
+        index.capabilities |= CAPABILITY_ENCRYPTED;
+    }
 
     index
 }
 
-/// Get launcher binary data
-fn get_launcher(options: &BuildOptions) -> Result<Vec<u8>> {
+/// Flag the launcher hash and whole-file CRC in the index, where the layout allows
+fn record_file_hashes(
+    index: &mut Index,
+    launcher_data: &[u8],
+    options: &BuildOptions,
+) -> Result<()> {
+    // Resource embedding rewrites the launcher region, so only hash appended launchers
+    let resource_embedded = !options.detached_data && should_use_resource_embedding(launcher_data)?;
+    if !options.detached_data && !resource_embedded {
+        use sha2::{Digest, Sha256};
+        index.set_launcher_hash(Sha256::digest(launcher_data).into());
+        trace!("🔏 Recorded launcher hash in index");
+    }
+    if options.file_crc {
+        if resource_embedded {
+            warn!(
+                "⚠️ Skipping the file CRC: PE resource embedding rewrites the file after it is computed"
+            );
+        } else {
+            // Computed once the trailer is written
+            index.flags |= INDEX_FLAG_FILE_CRC;
+        }
+    }
+    Ok(())
+}
+
+/// Get launcher binary data, checking its version against `expected_version`
+fn get_launcher(options: &BuildOptions, expected_version: Option<&str>) -> Result<Vec<u8>> {
     // Priority order:
     // 1. Explicit launcher_bin from options
     // 2. FLAVOR_LAUNCHER_BIN environment variable
@@ -149,34 +405,24 @@ fn get_launcher(options: &BuildOptions) -> Result<Vec<u8>> {
     } else if let Ok(explicit_path) = std::env::var("FLAVOR_LAUNCHER_BIN") {
         PathBuf::from(explicit_path)
     } else {
-        return Err(FlavorError::Generic(
+        return Err(FlavorError::LauncherMissing(
             "Launcher binary path must be specified via --launcher-bin or FLAVOR_LAUNCHER_BIN environment variable".to_string()
         ));
     };
 
     info!("🚀 Loading launcher: {}", launcher_path.display());
 
-    // Check launcher version
-    let version_output = std::process::Command::new(&launcher_path)
-        .arg("--version")
-        .output();
-
-    match version_output {
-        Ok(output) => {
-            let version_str = String::from_utf8_lossy(&output.stdout);
-            let version_str = version_str.trim();
-            if !version_str.is_empty() {
-                info!("🔍 Launcher version: {}", version_str);
-            }
-        }
-        Err(e) => {
-            debug!("⚠️ Failed to get launcher version: {}", e);
-        }
+    let version = launcher_version(&launcher_path);
+    if let Some(ref version) = version {
+        info!("🔍 Launcher version: {}", version);
+    }
+    if let Some(expected) = expected_version {
+        check_launcher_version(expected, version.as_deref(), &launcher_path)?;
     }
 
     // Just try to read the file - let the OS handle PATH resolution
     fs::read(&launcher_path).map_err(|e| {
-        FlavorError::Generic(format!(
+        FlavorError::LauncherMissing(format!(
             "Failed to read launcher '{}': {}",
             launcher_path.display(),
             e
@@ -184,6 +430,58 @@ fn get_launcher(options: &BuildOptions) -> Result<Vec<u8>> {
     })
 }
 
+/// The launcher's trimmed `--version` output, if it printed one
+fn launcher_version(launcher_path: &Path) -> Option<String> {
+    match std::process::Command::new(launcher_path)
+        .arg("--version")
+        .output()
+    {
+        Ok(output) => {
+            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            (!version.is_empty()).then_some(version)
+        }
+        Err(e) => {
+            debug!("⚠️ Failed to get launcher version: {}", e);
+            None
+        }
+    }
+}
+
+/// Fail unless the launcher reported the pinned `launcher_version`
+///
+/// Surrounding whitespace is ignored on both sides. An expected value ending
+/// in `*` matches any version that starts with the rest of it.
+fn check_launcher_version(
+    expected: &str,
+    actual: Option<&str>,
+    launcher_path: &Path,
+) -> Result<()> {
+    let expected = expected.trim();
+    if expected.is_empty() || expected == "*" {
+        return Err(FlavorError::ConfigError(
+            "launcher_version must not be empty".to_string(),
+        ));
+    }
+    let Some(actual) = actual else {
+        return Err(FlavorError::BuildError(format!(
+            "Launcher version mismatch for {}: expected '{expected}', but it reported no version",
+            launcher_path.display()
+        )));
+    };
+    let matches = match expected.strip_suffix('*') {
+        Some(prefix) => actual.starts_with(prefix.trim_end()),
+        None => actual == expected,
+    };
+    if !matches {
+        return Err(FlavorError::BuildError(format!(
+            "Launcher version mismatch for {}: expected '{expected}', found '{actual}'",
+            launcher_path.display()
+        )));
+    }
+    debug!("✅ Launcher version matches pinned '{expected}'");
+    Ok(())
+}
+
 /// Determines if PE resource embedding should be used.
 ///
 /// TEMPORARILY DISABLED: The Windows UpdateResourceW API corrupts Go binaries
@@ -227,7 +525,7 @@ fn convert_to_resource_embedding(file_path: &Path, launcher_size: u64) -> Result
     let pspf_data: Vec<u8> = file_data[launcher_size as usize..].to_vec();
 
     if pspf_data.is_empty() {
-        return Err(FlavorError::Generic(
+        return Err(FlavorError::BuildError(
             "No PSPF data found after launcher".to_string(),
         ));
     }
@@ -252,7 +550,7 @@ fn convert_to_resource_embedding(file_path: &Path, launcher_size: u64) -> Result
     // Verify the truncation was successful
     let truncated_size = fs::metadata(file_path)?.len();
     if truncated_size != launcher_size {
-        return Err(FlavorError::Generic(format!(
+        return Err(FlavorError::BuildError(format!(
             "File truncation failed: expected {} bytes, got {} bytes",
             launcher_size, truncated_size
         )));
@@ -272,3 +570,829 @@ fn convert_to_resource_embedding(file_path: &Path, launcher_size: u64) -> Result
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::psp::format_2025::reader::Reader;
+
+    #[test]
+    fn test_sbom_is_embedded_as_reserved_slot() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let sbom = br#"{"bomFormat": "CycloneDX", "specVersion": "1.5", "components": []}"#;
+        assert!(fs::write(root.join("sbom.cdx.json"), sbom).is_ok());
+        assert!(fs::write(root.join("app.sh"), b"echo hi\n").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+
+        let manifest = serde_json::json!({
+            "package": {"name": "sbom-test", "version": "1.0"},
+            "execution": {"command": "/bin/sh {workenv}/app.sh"},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        });
+        let output = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("sbom".to_string()),
+            sbom: Some(root.join("sbom.cdx.json")),
+            ..BuildOptions::default()
+        };
+        assert!(build_from_str(&manifest.to_string(), &output, options).is_ok());
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        assert!(reader.read_metadata().is_ok_and(|m| {
+            m.sbom
+                .as_ref()
+                .is_some_and(|s| s.slot == 1 && s.format == "cyclonedx")
+        }));
+        let found = reader.find_slot_by_name(SBOM_SLOT_ID);
+        assert!(matches!(found, Ok(Some((1, _)))));
+
+        let mut contents = Vec::new();
+        assert!(reader.copy_slot_to(1, &mut contents).is_ok());
+        assert_eq!(contents, sbom);
+    }
+
+    #[test]
+    fn test_encrypted_slots_round_trip() {
+        use crate::psp::format_2025::encryption::{
+            ENCRYPTION_AES256_GCM, EncryptionAlgorithm, EncryptionKey, SlotEncryption,
+        };
+        use crate::psp::format_2025::verifier::verify_slot_checksums;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let secret = b"api_key = \"hunter2\"\n".repeat(50);
+        assert!(fs::write(root.join("secret.toml"), &secret).is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "encrypted-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "secret", "source": root.join("secret.toml"), "target": "secret.toml"}]
+        });
+        let output = root.join("out.psp");
+        let key = EncryptionKey::from_bytes([0x42; 32]);
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("encrypted".to_string()),
+            encryption: Some(SlotEncryption {
+                algorithm: EncryptionAlgorithm::Aes256Gcm,
+                key: key.clone(),
+            }),
+            ..BuildOptions::default()
+        };
+        assert!(build_from_str(&manifest.to_string(), &output, options).is_ok());
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        assert!(reader.read_index().is_ok_and(|index| {
+            index.encryption_type == ENCRYPTION_AES256_GCM
+                && index.capabilities & CAPABILITY_ENCRYPTED != 0
+        }));
+        // Stored bytes are sealed but still verifiable without the key
+        assert!(verify_slot_checksums(&mut reader).is_ok_and(|bad| bad.is_empty()));
+        let stored = fs::read(&output);
+        assert!(stored.is_ok_and(|bytes| !bytes.windows(16).any(|w| w == &secret[..16])));
+
+        let mut contents = Vec::new();
+        let missing_key = reader.copy_slot_to(0, &mut contents);
+        assert!(missing_key.is_err_and(|e| e.to_string().contains("decryption key")));
+
+        assert!(
+            reader
+                .set_decryption_key(&EncryptionKey::from_bytes([0x24; 32]))
+                .is_ok()
+        );
+        let wrong_key = reader.copy_slot_to(0, &mut contents);
+        assert!(wrong_key.is_err_and(|e| e.to_string().contains("wrong key")));
+
+        assert!(reader.set_decryption_key(&key).is_ok());
+        assert!(reader.copy_slot_to(0, &mut contents).is_ok());
+        assert_eq!(contents, secret);
+    }
+
+    #[test]
+    fn test_file_crc_detects_corruption() {
+        use crate::psp::format_2025::file_crc::check_file_crc;
+        use crate::psp::format_2025::verifier::verify;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("app.txt"), b"payload".repeat(100)).is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "crc-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "app", "source": root.join("app.txt"), "target": "app.txt"}]
+        });
+        let output = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("crc".to_string()),
+            file_crc: true,
+            ..BuildOptions::default()
+        };
+        assert!(build_from_str(&manifest.to_string(), &output, options).is_ok());
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        let index = reader.read_index().cloned();
+        assert!(index.is_ok());
+        let Ok(index) = index else {
+            return;
+        };
+        assert!(index.file_crc().is_some());
+        assert!(check_file_crc(&output, &index).is_ok());
+        // Patching the CRC in kept the index checksum and signature intact
+        assert!(verify(&output).is_ok_and(|result| result.signature_valid));
+
+        let bytes = fs::read(&output);
+        assert!(bytes.is_ok());
+        let Ok(mut bytes) = bytes else {
+            return;
+        };
+        bytes[2000] ^= 0x01;
+        assert!(fs::write(&output, &bytes).is_ok());
+        assert!(check_file_crc(&output, &index).is_err_and(|e| e.to_string().contains("CRC32C")));
+    }
+
+    #[test]
+    fn test_size_limits_fail_the_build() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("big.bin"), vec![7u8; 4096]).is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "limits-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "big", "source": root.join("big.bin"), "target": "big.bin"}]
+        })
+        .to_string();
+        let output = root.join("out.psp");
+        let options = |max_slot_size, max_package_size| BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("limits".to_string()),
+            max_slot_size,
+            max_package_size,
+            ..BuildOptions::default()
+        };
+
+        let result = build_from_str(&manifest, &output, options(Some(4095), None));
+        assert!(matches!(result, Err(FlavorError::BuildError(ref m)) if m.contains("'big'")));
+        assert!(!output.exists());
+
+        let result = build_from_str(&manifest, &output, options(Some(4096), Some(8192)));
+        assert!(
+            matches!(result, Err(FlavorError::BuildError(ref m)) if m.contains("max_package_size"))
+        );
+        assert!(!output.exists());
+
+        assert!(build_from_str(&manifest, &output, options(Some(4096), Some(1 << 20))).is_ok());
+    }
+
+    #[test]
+    fn test_io_buffer_size_keeps_slot_data() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        assert!(fs::write(root.join("data.bin"), &data).is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "buffers", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "data", "source": root.join("data.bin"), "target": "data.bin"}]
+        })
+        .to_string();
+        let output = root.join("out.psp");
+        let options = |io_buffer_size| BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("buffers".to_string()),
+            io_buffer_size,
+            ..BuildOptions::default()
+        };
+
+        // Buffers smaller than, and larger than, the slot
+        for size in [Some(7), Some(1 << 20), None] {
+            assert!(build_from_str(&manifest, &output, options(size)).is_ok());
+            assert!(
+                super::super::verifier::verify(&output).is_ok_and(|result| result.signature_valid)
+            );
+            let reader = Reader::new(&output);
+            assert!(reader.is_ok());
+            let Ok(mut reader) = reader else { return };
+            let descriptors = reader.read_slot_descriptors();
+            assert!(descriptors.is_ok());
+            let Ok(descriptors) = descriptors else { return };
+            assert!(
+                reader
+                    .read_slot(&descriptors[0])
+                    .is_ok_and(|slot| slot == data)
+            );
+        }
+
+        let result = build_from_str(&manifest, &output, options(Some(0)));
+        assert!(matches!(result, Err(FlavorError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_missing_optional_slots_are_skipped() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("a.txt"), b"a").is_ok());
+        assert!(fs::write(root.join("c.txt"), b"c").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let slot = |id: &str, required: bool| {
+            serde_json::json!({
+                "id": id,
+                "source": root.join(format!("{id}.txt")),
+                "target": format!("{id}.txt"),
+                "required": required
+            })
+        };
+        let manifest = |b_required| {
+            serde_json::json!({
+                "package": {"name": "optional-test", "version": "1.0"},
+                "execution": {"command": "/bin/true"},
+                "slots": [slot("a", true), slot("b", b_required), slot("c", false)]
+            })
+            .to_string()
+        };
+        let output = root.join("out.psp");
+        let options = || BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("optional".to_string()),
+            ..BuildOptions::default()
+        };
+
+        assert!(build_from_str(&manifest(true), &output, options()).is_err());
+        assert!(build_from_str(&manifest(false), &output, options()).is_ok());
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        assert!(reader.read_index().is_ok_and(|index| index.slot_count == 2));
+        assert!(reader.read_metadata().is_ok_and(|m| {
+            let ids: Vec<_> = m.slots.iter().map(|s| (s.index, s.id.as_str())).collect();
+            ids == [(0, "a"), (1, "c")]
+        }));
+    }
+
+    /// Set in the environment of the test binary re-run by
+    /// [`test_dry_run_writes_nothing_and_estimates_size`]
+    const DRY_RUN_CHILD_ENV: &str = "FLAVOR_TEST_DRY_RUN_CHILD";
+
+    /// The part of [`test_dry_run_writes_nothing_and_estimates_size`] that
+    /// needs `SOURCE_DATE_EPOCH` pinned; does nothing unless run as its child
+    #[test]
+    fn dry_run_size_child() {
+        if std::env::var_os(DRY_RUN_CHILD_ENV).is_none() {
+            return;
+        }
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("a.txt"), vec![b'a'; 5000]).is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "dry-run-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+        })
+        .to_string();
+        let output = root.join("out.psp");
+        let options = |dry_run| BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("dry-run".to_string()),
+            dry_run,
+            ..BuildOptions::default()
+        };
+
+        let report = build_from_str(&manifest, &output, options(true));
+        assert!(!output.exists());
+        assert!(report.as_ref().is_ok_and(Option::is_some));
+        let Ok(Some(report)) = report else { return };
+        assert!(report.to_string().contains("Estimated package size"));
+
+        assert!(build_from_str(&manifest, &output, options(false)).is_ok_and(|r| r.is_none()));
+        let built = fs::metadata(&output);
+        assert!(built.is_ok());
+        let Ok(built) = built else { return };
+        assert_eq!(report.package_size, built.len());
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing_and_estimates_size() {
+        let exe = std::env::current_exe();
+        assert!(exe.is_ok());
+        let Ok(exe) = exe else { return };
+        // The build timestamp is part of the metadata, so both builds need the
+        // same one for the sizes to match exactly
+        let output = std::process::Command::new(exe)
+            .args([
+                "--exact",
+                "psp::format_2025::builder::tests::dry_run_size_child",
+                "--test-threads=1",
+            ])
+            .env(DRY_RUN_CHILD_ENV, "1")
+            .env("SOURCE_DATE_EPOCH", "1700000000")
+            .output();
+        assert!(output.is_ok());
+        let Ok(output) = output else { return };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "child output: {stdout}");
+        assert!(stdout.contains("1 passed"), "child output: {stdout}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        assert!(fs::write(root.join("a.txt"), b"a").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "modes", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+        })
+        .to_string();
+        let output = root.join("out.psp");
+        let build = |output_permissions, no_exec| {
+            let options = BuildOptions {
+                launcher_bin: Some(root.join("launcher")),
+                key_seed: Some("modes".to_string()),
+                output_permissions,
+                no_exec,
+                ..BuildOptions::default()
+            };
+            build_from_str(&manifest, &output, options)
+                .and_then(|_| Ok(fs::metadata(&output)?.permissions().mode() & 0o7777))
+        };
+
+        assert!(build(None, false).is_ok_and(|mode| mode == 0o700));
+        assert!(build(None, true).is_ok_and(|mode| mode == 0o600));
+        assert!(build(Some(0o755), false).is_ok_and(|mode| mode == 0o755));
+        assert!(build(Some(0o755), true).is_ok_and(|mode| mode == 0o644));
+        assert!(
+            build(Some(0o17777), false).is_err_and(|e| matches!(e, FlavorError::ConfigError(_)))
+        );
+    }
+
+    #[test]
+    fn test_check_launcher_version() {
+        let path = Path::new("launcher");
+        let check = |expected, actual| check_launcher_version(expected, actual, path);
+        let actual = Some("flavor-rs-launcher 0.3.0");
+
+        assert!(check(" flavor-rs-launcher 0.3.0\n", actual).is_ok());
+        assert!(check("flavor-rs-launcher 0.3*", actual).is_ok());
+        assert!(check("flavor-rs-launcher 0.2*", actual).is_err());
+        assert!(
+            check("flavor-rs-launcher 0.3.1", actual).is_err_and(|e| matches!(
+                e,
+                FlavorError::BuildError(ref msg)
+                    if msg.contains("expected 'flavor-rs-launcher 0.3.1', found 'flavor-rs-launcher 0.3.0'")
+            ))
+        );
+        assert!(check("0.3.0", None).is_err_and(|e| matches!(e, FlavorError::BuildError(_))));
+        assert!(check(" ", actual).is_err_and(|e| matches!(e, FlavorError::ConfigError(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pinned_launcher_version_fails_the_build() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        assert!(fs::write(root.join("a.txt"), b"a").is_ok());
+        let launcher = root.join("launcher");
+        assert!(fs::write(&launcher, b"#!/bin/sh\necho 'flavor-rs-launcher 0.3.0'\n").is_ok());
+        assert!(fs::set_permissions(&launcher, fs::Permissions::from_mode(0o755)).is_ok());
+        let build = |pin: &str, output: &str, detached_data: bool| {
+            let manifest = serde_json::json!({
+                "package": {"name": "pinned", "version": "1.0"},
+                "execution": {"command": "/bin/true"},
+                "launcher_version": pin,
+                "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+            })
+            .to_string();
+            let options = BuildOptions {
+                launcher_bin: Some(launcher.clone()),
+                key_seed: Some("pinned".to_string()),
+                detached_data,
+                ..BuildOptions::default()
+            };
+            build_from_str(&manifest, &root.join(output), options)
+        };
+
+        assert!(build("flavor-rs-launcher 0.3.0", "out.psp", false).is_ok());
+        assert!(build("flavor-rs-launcher 0.*", "out.psp", false).is_ok());
+        assert!(
+            build("flavor-go-launcher 0.3.0", "out.psp", false)
+                .is_err_and(|e| matches!(e, FlavorError::BuildError(_)))
+        );
+
+        // A mismatch is caught before the stub or data file is created
+        assert!(build("flavor-go-launcher 0.3.0", "split.psp", true).is_err());
+        assert!(!root.join("split.psp").exists());
+        assert!(!detached_data_path(&root.join("split.psp"), &data_suffix()).exists());
+    }
+
+    #[test]
+    fn test_detached_data_build_splits_launcher_and_data() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("a.txt"), b"detached").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0x5au8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "detached-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+        })
+        .to_string();
+        let output = root.join("app");
+        let options = |max_package_size| BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("detached".to_string()),
+            detached_data: true,
+            max_package_size,
+            ..BuildOptions::default()
+        };
+
+        // Dry runs size the data file, not a launcher-prefixed package
+        let parsed = parse_manifest(&manifest);
+        assert!(parsed.is_ok());
+        let Ok(parsed) = parsed else {
+            return;
+        };
+        let data_file = root.join("app.pspfdata");
+        let report = dry_run::dry_run(
+            &parsed,
+            None,
+            SLOT_ALIGNMENT,
+            &options(None),
+            Some(&data_file),
+        );
+        assert!(report.as_ref().is_ok_and(|r| {
+            r.launcher_size == 1024 && r.to_string().contains("Estimated data file size")
+        }));
+        let Ok(report) = report else { return };
+
+        // An oversized build leaves neither the data file nor the stub
+        assert!(build_from_str(&manifest, &output, options(Some(1024))).is_err());
+        assert!(!output.exists() && !data_file.exists());
+
+        assert!(build_from_str(&manifest, &output, options(None)).is_ok());
+        assert!(fs::read(&output).is_ok_and(|stub| stub == vec![0x5au8; 1024]));
+        assert!(
+            fs::metadata(&data_file)
+                .is_ok_and(|m| m.len().abs_diff(report.package_size) <= 2 * SLOT_ALIGNMENT)
+        );
+
+        let data = super::super::detached::resolve_data_path(&output);
+        assert!(data.as_ref().is_ok_and(|p| p == &data_file));
+        let Ok(data) = data else { return };
+        assert!(super::super::verifier::verify(&data).is_ok_and(|r| r.signature_valid));
+        let reader = Reader::new(&data);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        assert!(
+            reader
+                .read_index()
+                .is_ok_and(|index| index.launcher_size == 0)
+        );
+        assert!(
+            reader
+                .read_metadata()
+                .is_ok_and(|m| m.package.name == "detached-test")
+        );
+    }
+
+    #[test]
+    fn test_delta_package_inherits_base_slots() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("runtime.bin"), vec![0x42u8; 64 * 1024]).is_ok());
+        assert!(fs::write(root.join("base.sh"), b"echo base\n").is_ok());
+        assert!(fs::write(root.join("delta.sh"), b"echo delta\n").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = |app: &str| {
+            serde_json::json!({
+                "package": {"name": app, "version": "1.0"},
+                "execution": {"command": "/bin/sh {workenv}/app.sh"},
+                "slots": [
+                    {"id": "runtime", "source": root.join("runtime.bin"), "target": "lib/runtime.bin"},
+                    {"id": "app", "source": root.join(format!("{app}.sh")), "target": "app.sh"}
+                ]
+            })
+            .to_string()
+        };
+        let options = |base_package| BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("delta".to_string()),
+            base_package,
+            ..BuildOptions::default()
+        };
+
+        let base = root.join("base.psp");
+        let delta = root.join("delta.psp");
+        assert!(build_from_str(&manifest("base"), &base, options(None)).is_ok());
+        assert!(build_from_str(&manifest("delta"), &delta, options(Some(base.clone()))).is_ok());
+
+        let reader = Reader::new(&delta);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let metadata = reader.read_metadata().cloned();
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else { return };
+        let runtime_digest = format!(
+            "sha256:{}",
+            hex::encode(<sha2::Sha256 as sha2::Digest>::digest(vec![
+                0x42u8;
+                64 * 1024
+            ]))
+        );
+        let inherited = metadata.slots[0].inherited.as_ref();
+        assert!(inherited.is_some_and(|slot| {
+            slot.package == "base"
+                && slot.slot_id == "runtime"
+                && slot.files == ["lib/runtime.bin"]
+                && slot.file_checksums == [runtime_digest.as_str()]
+        }));
+        assert!(metadata.slots[1].inherited.is_none());
+        assert!(super::super::verifier::verify(&delta).is_ok_and(|r| r.signature_valid));
+
+        let sizes = fs::metadata(&base).and_then(|b| Ok((b.len(), fs::metadata(&delta)?.len())));
+        assert!(sizes.is_ok_and(|(base, delta)| delta + 32 * 1024 < base));
+    }
+
+    #[derive(Debug)]
+    struct CountingProvider {
+        inner: LocalKeyProvider,
+        calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl KeyProvider for CountingProvider {
+        fn sign(&self, message: &[u8]) -> Result<ed25519_dalek::Signature> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.sign(message)
+        }
+
+        fn public_key(&self) -> ed25519_dalek::VerifyingKey {
+            self.inner.public_key()
+        }
+    }
+
+    #[test]
+    fn test_key_provider_signs_metadata() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("app.sh"), b"echo hi\n").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "kms-test", "version": "1.0"},
+            "execution": {"command": "/bin/sh {workenv}/app.sh"},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        });
+        let (signing_key, verifying_key) = super::super::keys::generate_keys_from_seed("kms");
+        let calls = std::sync::Arc::default();
+        let provider = CountingProvider {
+            inner: LocalKeyProvider::new(signing_key),
+            calls: std::sync::Arc::clone(&calls),
+        };
+
+        let output = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("ignored".to_string()),
+            key_provider: Some(Box::new(provider)),
+            ..BuildOptions::default()
+        };
+        assert!(build_from_str(&manifest.to_string(), &output, options).is_ok());
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        assert!(
+            reader
+                .read_index()
+                .is_ok_and(|index| index.public_key == *verifying_key.as_bytes())
+        );
+        let result = super::super::verifier::verify(&output);
+        assert!(result.is_ok_and(|r| r.signature_valid));
+    }
+
+    #[test]
+    fn test_slot_annotations_round_trip() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("a.txt"), b"annotated").is_ok());
+        assert!(fs::write(root.join("b.txt"), b"plain").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "annotations", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "a", "source": root.join("a.txt"), "target": "a.txt",
+                 "annotations": {"license": "MIT", "origin": "https://example.com/a"}},
+                {"id": "b", "source": root.join("b.txt"), "target": "b.txt"}
+            ]
+        });
+        let output = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("annotations".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(build_from_str(&manifest.to_string(), &output, options).is_ok());
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let metadata = reader.read_metadata();
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else { return };
+        let annotations = &metadata.slots[0].annotations;
+        assert_eq!(annotations.get("license").map(String::as_str), Some("MIT"));
+        assert_eq!(
+            annotations.get("origin").map(String::as_str),
+            Some("https://example.com/a")
+        );
+        // Unannotated slots don't carry an empty map
+        assert!(metadata.slots[1].annotations.is_empty());
+        assert!(metadata.slots[1].extra.is_empty());
+        let json = serde_json::to_value(&metadata.slots[1]);
+        assert!(json.is_ok_and(|json| json.get("annotations").is_none()));
+    }
+
+    #[test]
+    fn test_source_attributes_skip_encoded_sources() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("plain.txt"), b"plain").is_ok());
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        assert!(gz.write_all(b"packed").is_ok());
+        let packed = gz.finish();
+        assert!(packed.is_ok_and(|packed| fs::write(root.join("packed.gz"), packed).is_ok()));
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "attributes", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "plain", "source": root.join("plain.txt"), "target": "plain.txt"},
+                {"id": "packed", "source": root.join("packed.gz"), "target": "packed.txt",
+                 "operations": "gzip"}
+            ]
+        });
+        let output = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("attributes".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(build_from_str(&manifest.to_string(), &output, options).is_ok());
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let metadata = reader.read_metadata();
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else { return };
+        assert!(metadata.slots[0].source_mode.is_some());
+        assert!(metadata.slots[0].source_mtime.is_some());
+        assert!(metadata.slots[1].source_mode.is_none());
+        assert!(metadata.slots[1].source_mtime.is_none());
+    }
+
+    #[test]
+    fn test_inline_slot_content() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let options = || BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("inline".to_string()),
+            ..BuildOptions::default()
+        };
+        let manifest = |slots: serde_json::Value| {
+            serde_json::json!({
+                "package": {"name": "inline", "version": "1.0"},
+                "execution": {"command": "/bin/true"},
+                "slots": slots
+            })
+            .to_string()
+        };
+
+        let output = root.join("out.psp");
+        let slots = serde_json::json!([
+            {"id": "plain", "content": "key = 1\n", "target": "etc/plain.toml"},
+            {"id": "bytes", "content": {"base64": "AAEC/w=="}, "target": "data.bin",
+             "operations": "gzip", "compression_level": "fast"},
+            {"id": "tree", "content": "print('hi')\n", "target": "{workenv}/lib/app.py",
+             "operations": "tar.gz"},
+            {"id": "file", "source": root.join("launcher"), "target": "launcher",
+             "compression_level": "best"}
+        ]);
+        assert!(build_from_str(&manifest(slots), &output, options()).is_ok());
+
+        let reader = Reader::new(&output);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let mut decoded = Vec::new();
+        assert!(reader.copy_slot_to(0, &mut decoded).is_ok());
+        assert_eq!(decoded, b"key = 1\n");
+        decoded.clear();
+        assert!(reader.copy_slot_to(1, &mut decoded).is_ok());
+        assert_eq!(decoded, [0, 1, 2, 255]);
+        // The level is recorded only where the builder compressed the data
+        let levels = reader.read_metadata().map(|m| {
+            m.slots
+                .iter()
+                .map(|s| s.compression_level)
+                .collect::<Vec<_>>()
+        });
+        assert!(levels.is_ok_and(|levels| levels == [None, Some(1), None, None]));
+        decoded.clear();
+        assert!(reader.copy_slot_to(2, &mut decoded).is_ok());
+        let mut archive = tar::Archive::new(decoded.as_slice());
+        let names: Vec<_> = archive
+            .entries()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.path().ok().map(|p| p.display().to_string()))
+            .collect();
+        assert_eq!(names, ["lib/app.py"]);
+
+        // Exactly one of `source` and `content`
+        let both = serde_json::json!([
+            {"id": "x", "source": "x.txt", "content": "x", "target": "x.txt"}
+        ]);
+        let neither = serde_json::json!([{"id": "x", "target": "x.txt"}]);
+        for slots in [both, neither] {
+            let result = build_from_str(&manifest(slots), &root.join("bad.psp"), options());
+            assert!(result.is_err_and(|e| e.to_string().contains("Slot 'x'")));
+        }
+    }
+}
@@ -0,0 +1,541 @@
+//! Re-encode an existing package's slots with a different codec
+//!
+//! With [`RepackOptions::optimize`], each compressed slot is instead
+//! trial-compressed with zstd and keeps whichever encoding is smaller.
+
+use super::finalization::{
+    reserve_descriptor_space, stream_slot_data, write_descriptor_table, write_metadata_bytes,
+    write_trailer,
+};
+use super::metadata::sign_metadata;
+
+use super::super::checksums::{ChecksumAlgorithm, calculate_checksum};
+use super::super::constants::{HEADER_SIZE, OP_GZIP, OP_LZ4, OP_TAR, OP_ZSTD, SLOT_ALIGNMENT};
+use super::super::defaults::{CAPABILITY_PAGE_ALIGNED, DEFAULT_DIR_PERMS};
+use super::super::encryption::ENCRYPTION_NONE;
+use super::super::keys::{KeyProvider, LocalKeyProvider};
+use super::super::operations::{operations_to_string, pack_operations, unpack_operations};
+use super::super::reader::Reader;
+use crate::api::{BuildOptions, RepackOptions, SlotCodec};
+use crate::exceptions::{FlavorError, Result};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use log::{debug, info};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// zstd level tried by [`RepackOptions::optimize`], and used for
+/// [`SlotCodec::Zstd`] unless a level is given
+const OPTIMIZE_ZSTD_LEVEL: u32 = 19;
+
+/// What [`repack`] did to one stored slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlotRepack {
+    pub index: usize,
+    pub id: String,
+    pub old_operations: String,
+    pub new_operations: String,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+impl SlotRepack {
+    /// New stored size as a fraction of the old one
+    pub fn ratio(&self) -> f64 {
+        if self.old_size == 0 {
+            1.0
+        } else {
+            self.new_size as f64 / self.old_size as f64
+        }
+    }
+}
+
+/// Per-slot results of a [`repack`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepackReport {
+    pub slots: Vec<SlotRepack>,
+}
+
+impl RepackReport {
+    /// Stored bytes saved across all slots (negative if the package grew)
+    pub fn bytes_saved(&self) -> i64 {
+        self.slots
+            .iter()
+            .map(|slot| slot.old_size as i64 - slot.new_size as i64)
+            .sum()
+    }
+}
+
+impl fmt::Display for RepackReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for slot in &self.slots {
+            let operations = if slot.old_operations == slot.new_operations {
+                format!("{} (kept)", display_operations(&slot.old_operations))
+            } else {
+                format!(
+                    "{} -> {}",
+                    display_operations(&slot.old_operations),
+                    display_operations(&slot.new_operations)
+                )
+            };
+            writeln!(
+                f,
+                "  slot {} '{}': {operations}, {} -> {} bytes ({:.1}%)",
+                slot.index,
+                slot.id,
+                slot.old_size,
+                slot.new_size,
+                slot.ratio() * 100.0
+            )?;
+        }
+        writeln!(f, "Total saved: {} bytes", self.bytes_saved())
+    }
+}
+
+fn display_operations(operations: &str) -> &str {
+    if operations.is_empty() {
+        "raw"
+    } else {
+        operations
+    }
+}
+
+/// Repack `input` into `output`, re-encoding every compressed slot with `options.codec`
+///
+/// Slot contents are decoded with their current operation chain and encoded
+/// again, keeping `tar` where present. Self-referential and raw slots are copied
+/// unchanged. The launcher and package settings are kept; metadata is re-signed
+/// with the configured key (or a fresh one).
+pub fn repack(input: &Path, output: &Path, options: &RepackOptions) -> Result<RepackReport> {
+    if options.optimize {
+        info!("🔁 Repacking {input:?} -> {output:?}, keeping zstd where it is smaller");
+    } else {
+        info!(
+            "🔁 Repacking {input:?} -> {output:?} with {:?}",
+            options.codec
+        );
+    }
+
+    let mut reader = Reader::new(input)?;
+    let mut index = reader.read_index()?.clone();
+    if index.encryption_type != ENCRYPTION_NONE {
+        return Err(FlavorError::BuildError(
+            "Cannot repack: the package has encrypted slots".to_string(),
+        ));
+    }
+    let mut metadata = reader.read_metadata()?.clone();
+    let mut descriptors = reader.read_slot_descriptors()?;
+    if metadata.slots.len() != descriptors.len() {
+        return Err(FlavorError::BuildError(format!(
+            "Cannot repack: metadata lists {} slots but the package has {} descriptors",
+            metadata.slots.len(),
+            descriptors.len()
+        )));
+    }
+
+    let alignment = if index.capabilities & CAPABILITY_PAGE_ALIGNED != 0 && index.page_size > 0 {
+        u64::from(index.page_size)
+    } else {
+        SLOT_ALIGNMENT
+    };
+
+    // Re-encode slots into a scratch directory so they can be streamed like a build
+    let scratch = tempfile::tempdir()?;
+    let mut slot_paths = Vec::with_capacity(descriptors.len());
+    let mut report = RepackReport::default();
+    for (i, (descriptor, slot)) in descriptors.iter_mut().zip(&mut metadata.slots).enumerate() {
+        if slot.self_ref == Some(true) || slot.inherited.is_some() {
+            debug!("⏭️  Slot {i} has no stored data, passing through");
+            slot_paths.push(PathBuf::new());
+            continue;
+        }
+
+        let path = scratch.path().join(format!("slot{i}"));
+        let operations = unpack_operations(descriptor.operations);
+        let old_size = descriptor.size;
+        let mut outcome = SlotRepack {
+            index: i,
+            id: slot.id.clone(),
+            old_operations: operations_to_string(descriptor.operations),
+            new_operations: operations_to_string(descriptor.operations),
+            old_size,
+            new_size: old_size,
+        };
+        if operations.is_empty() {
+            debug!("⏭️  Slot {i} is raw, passing through");
+            std::fs::write(&path, reader.read_slot(descriptor)?)?;
+            slot_paths.push(path);
+            report.slots.push(outcome);
+            continue;
+        }
+
+        let mut decoded = Vec::new();
+        reader.copy_slot_to(i, &mut decoded)?;
+
+        let mut new_operations = Vec::new();
+        if operations.contains(&OP_TAR) {
+            new_operations.push(OP_TAR);
+        }
+        let encoded = if options.optimize {
+            let trial = zstd_encode(&decoded, OPTIMIZE_ZSTD_LEVEL)?;
+            if trial.len() as u64 >= old_size {
+                debug!(
+                    "⏭️  Slot {i} '{}': zstd gives {} bytes, keeping {old_size} stored bytes",
+                    slot.id,
+                    trial.len()
+                );
+                std::fs::write(&path, reader.read_slot(descriptor)?)?;
+                slot_paths.push(path);
+                report.slots.push(outcome);
+                continue;
+            }
+            new_operations.push(OP_ZSTD);
+            slot.compression_level = None;
+            trial
+        } else {
+            match options.codec {
+                SlotCodec::Gzip => {
+                    new_operations.push(OP_GZIP);
+                    let level = options
+                        .compression_level
+                        .map_or_else(Compression::best, Compression::new);
+                    let mut encoder = GzEncoder::new(Vec::new(), level);
+                    encoder.write_all(&decoded)?;
+                    slot.compression_level = Some(level.level());
+                    encoder.finish()?
+                }
+                SlotCodec::Lz4 => {
+                    new_operations.push(OP_LZ4);
+                    let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                    encoder.write_all(&decoded)?;
+                    slot.compression_level = None;
+                    encoder.finish().map_err(|e| {
+                        FlavorError::BuildError("LZ4 compression failed".into()).with_source(e)
+                    })?
+                }
+                SlotCodec::Zstd => {
+                    new_operations.push(OP_ZSTD);
+                    let level = options.compression_level.unwrap_or(OPTIMIZE_ZSTD_LEVEL);
+                    slot.compression_level = Some(level);
+                    zstd_encode(&decoded, level)?
+                }
+            }
+        };
+
+        let packed = pack_operations(&new_operations);
+        debug!(
+            "🔁 Slot {i} '{}': {} -> {}, {} -> {} bytes",
+            slot.id,
+            operations_to_string(descriptor.operations),
+            operations_to_string(packed),
+            old_size,
+            encoded.len()
+        );
+
+        let digest = Sha256::digest(&encoded);
+        descriptor.operations = packed;
+        descriptor.size = encoded.len() as u64;
+        descriptor.original_size = decoded.len() as u64;
+        descriptor.checksum = u64::from_le_bytes([
+            digest[0], digest[1], digest[2], digest[3], digest[4], digest[5], digest[6], digest[7],
+        ]);
+        slot.operations = operations_to_string(packed);
+        slot.size = encoded.len() as i64;
+        slot.checksum =
+            calculate_checksum(encoded.as_slice(), ChecksumAlgorithm::Sha256).map_err(|e| {
+                FlavorError::BuildError(format!("Failed to checksum slot {i}")).with_source(e)
+            })?;
+
+        outcome.new_operations = slot.operations.clone();
+        outcome.new_size = encoded.len() as u64;
+        report.slots.push(outcome);
+        std::fs::write(&path, &encoded)?;
+        slot_paths.push(path);
+    }
+
+    // Keep the launcher byte-for-byte, so its recorded hash stays valid
+    let mut launcher = Vec::new();
+    File::open(input)?
+        .take(index.launcher_size)
+        .read_to_end(&mut launcher)?;
+
+    let key_provider = LocalKeyProvider::from_options(&BuildOptions {
+        private_key_path: options.private_key_path.clone(),
+        public_key_path: options.public_key_path.clone(),
+        key_seed: options.key_seed.clone(),
+        ..BuildOptions::default()
+    })?;
+    index
+        .public_key
+        .copy_from_slice(key_provider.public_key().as_bytes());
+
+    let mut out = File::create(output)?;
+    out.write_all(&launcher)?;
+    out.seek(SeekFrom::Start(index.launcher_size + HEADER_SIZE as u64))?;
+
+    let metadata_json = sign_metadata(&metadata, &key_provider, &mut index)?;
+    write_metadata_bytes(&mut out, &metadata_json, None, &mut index)?;
+    let descriptor_table_offset = reserve_descriptor_space(&mut out, &descriptors, &mut index)?;
+    stream_slot_data(
+        &mut out,
+        &mut descriptors,
+        &slot_paths,
+        alignment,
+        None,
+        None,
+    )?;
+    let end_pos = write_descriptor_table(&mut out, &descriptors, descriptor_table_offset)?;
+    write_trailer(
+        &mut out,
+        &mut index,
+        end_pos,
+        output,
+        u32::from(DEFAULT_DIR_PERMS),
+    )?;
+
+    let package_size = index.package_size;
+    info!(
+        "✅ Repacked {} slots into {output:?} ({package_size} bytes, {} bytes saved)",
+        descriptors.len(),
+        report.bytes_saved()
+    );
+    Ok(report)
+}
+
+/// Encode `data` as a zstd frame at `level`
+#[cfg(feature = "zstd-encode")]
+fn zstd_encode(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    let level = i32::try_from(level)
+        .ok()
+        .filter(|level| zstd::compression_level_range().contains(level))
+        .ok_or_else(|| FlavorError::ConfigError(format!("Invalid zstd level {level}")))?;
+    zstd::stream::encode_all(data, level)
+        .map_err(|e| FlavorError::BuildError("zstd compression failed".into()).with_source(e))
+}
+
+/// zstd encoding links the C library, so it is only built with `zstd-encode`
+#[cfg(not(feature = "zstd-encode"))]
+fn zstd_encode(_data: &[u8], _level: u32) -> Result<Vec<u8>> {
+    Err(FlavorError::BuildError(
+        "zstd encoding needs flavor built with the `zstd-encode` feature".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn read_tree(root: &Path) -> HashMap<PathBuf, Vec<u8>> {
+        let mut files = HashMap::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let entries = std::fs::read_dir(&dir);
+            assert!(entries.is_ok());
+            let Ok(entries) = entries else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if let (Ok(rel), Ok(data)) = (path.strip_prefix(root), std::fs::read(&path))
+                {
+                    files.insert(rel.to_path_buf(), data);
+                }
+            }
+        }
+        files
+    }
+
+    fn extract_all(package: &Path, dest: &Path) -> bool {
+        let reader = Reader::new(package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return false;
+        };
+        let count = reader.read_slot_descriptors().map_or(0, |d| d.len());
+        (0..count).all(|i| reader.extract_slot(i, dest).is_ok())
+    }
+
+    #[test]
+    fn test_repack_to_lz4_and_zstd_extracts_identically() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let mut tar_builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        let mut header = tar::Header::new_gnu();
+        let body = b"print('hello')\n";
+        header.set_size(body.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        assert!(
+            tar_builder
+                .append_data(&mut header, "lib/app.py", &body[..])
+                .is_ok()
+        );
+        let tgz = tar_builder.into_inner().and_then(|gz| gz.finish());
+        assert!(tgz.is_ok());
+        let Ok(tgz) = tgz else { return };
+        assert!(std::fs::write(root.join("lib.tgz"), tgz).is_ok());
+        assert!(std::fs::write(root.join("config.json"), b"{\"debug\": false}").is_ok());
+
+        let mut launcher = b"#!/bin/sh\nexit 0\n".to_vec();
+        launcher.resize(4096, 0);
+        assert!(std::fs::write(root.join("launcher"), launcher).is_ok());
+
+        let manifest = serde_json::json!({
+            "package": {"name": "repack-test", "version": "1.0"},
+            "execution": {"command": "{workenv}/lib/app.py"},
+            "slots": [
+                {"id": "lib", "source": root.join("lib.tgz"), "target": "lib", "operations": "tar,gzip"},
+                {"id": "config", "source": root.join("config.json"), "target": "config.json"}
+            ]
+        });
+        let original = root.join("original.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("repack".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::build_from_str(&manifest.to_string(), &original, options).is_ok());
+
+        let repacked = root.join("repacked.psp");
+        let options = RepackOptions {
+            codec: SlotCodec::Lz4,
+            key_seed: Some("repack".to_string()),
+            ..RepackOptions::default()
+        };
+        assert!(repack(&original, &repacked, &options).is_ok());
+        assert!(crate::api::verify_package(&repacked).is_ok_and(|r| r.signature_valid));
+
+        let reader = Reader::new(&repacked);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        let descriptors = reader.read_slot_descriptors();
+        assert!(descriptors.is_ok_and(|d| {
+            operations_to_string(d[0].operations) == "tar,lz4" && d[1].operations == 0
+        }));
+
+        assert!(extract_all(&original, &root.join("a")));
+        assert!(extract_all(&repacked, &root.join("b")));
+        let (a, b) = (read_tree(&root.join("a")), read_tree(&root.join("b")));
+        assert_eq!(a.len(), 2);
+        assert_eq!(a, b);
+
+        // zstd encoding is only built with `zstd-encode`; without it repack fails
+        let zstd_repacked = root.join("zstd.psp");
+        let options = RepackOptions {
+            codec: SlotCodec::Zstd,
+            key_seed: Some("repack".to_string()),
+            ..RepackOptions::default()
+        };
+        let result = repack(&original, &zstd_repacked, &options);
+        if cfg!(feature = "zstd-encode") {
+            assert!(result.is_ok_and(|r| r.slots[0].new_operations == "tar,zstd"));
+            assert!(extract_all(&zstd_repacked, &root.join("c")));
+            assert_eq!(read_tree(&root.join("c")), a);
+        } else {
+            assert!(result.is_err());
+            assert!(!zstd_repacked.exists());
+        }
+    }
+
+    #[cfg(feature = "zstd-encode")]
+    #[test]
+    fn test_optimize_keeps_the_smaller_encoding() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let text: Vec<u8> = (0..2000)
+            .flat_map(|i| format!("line {} of a very repetitive log\n", i % 50).into_bytes())
+            .collect();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::fast());
+        assert!(gz.write_all(&text).is_ok());
+        let gzipped = gz.finish();
+        assert!(gzipped.is_ok());
+        let Ok(gzipped) = gzipped else { return };
+        let zstded = zstd_encode(&text, OPTIMIZE_ZSTD_LEVEL);
+        assert!(zstded.is_ok());
+        let Ok(zstded) = zstded else {
+            return;
+        };
+        assert!(std::fs::write(root.join("log.gz"), &gzipped).is_ok());
+        assert!(std::fs::write(root.join("log.zst"), &zstded).is_ok());
+        assert!(std::fs::write(root.join("raw.txt"), b"raw").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+
+        let manifest = serde_json::json!({
+            "package": {"name": "optimize-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "gz", "source": root.join("log.gz"), "target": "gz.log", "operations": "gzip"},
+                {"id": "zst", "source": root.join("log.zst"), "target": "zst.log", "operations": "zstd"},
+                {"id": "raw", "source": root.join("raw.txt"), "target": "raw.txt"}
+            ]
+        });
+        let original = root.join("original.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("optimize".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::build_from_str(&manifest.to_string(), &original, options).is_ok());
+
+        let repacked = root.join("repacked.psp");
+        let options = RepackOptions {
+            optimize: true,
+            key_seed: Some("optimize".to_string()),
+            ..RepackOptions::default()
+        };
+        let report = repack(&original, &repacked, &options);
+        assert!(crate::api::verify_package(&repacked).is_ok_and(|r| r.signature_valid));
+        assert!(report.is_ok());
+        let Ok(report) = report else { return };
+
+        // gzip loses to zstd; the slot already at zstd's size and the raw slot are kept
+        assert_eq!(report.slots.len(), 3);
+        assert_eq!(report.slots[0].new_operations, "zstd");
+        assert!(report.slots[0].new_size < report.slots[0].old_size);
+        assert_eq!(report.slots[1].new_operations, "zstd");
+        assert_eq!(report.slots[1].new_size, zstded.len() as u64);
+        assert_eq!(report.slots[2].new_operations, "");
+        assert_eq!(
+            report.bytes_saved(),
+            (report.slots[0].old_size - report.slots[0].new_size) as i64
+        );
+        assert!(report.to_string().contains("slot 1 'zst': zstd (kept)"));
+
+        let reader = Reader::new(&repacked);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        let descriptors = reader.read_slot_descriptors();
+        assert!(descriptors.is_ok());
+        let Ok(descriptors) = descriptors else {
+            return;
+        };
+        assert!(
+            reader
+                .read_slot(&descriptors[1])
+                .is_ok_and(|stored| stored == zstded)
+        );
+
+        assert!(extract_all(&original, &root.join("a")));
+        assert!(extract_all(&repacked, &root.join("b")));
+        let (a, b) = (read_tree(&root.join("a")), read_tree(&root.join("b")));
+        assert_eq!(a.len(), 3);
+        assert_eq!(a, b);
+    }
+}
@@ -1,14 +1,22 @@
 //! Slot processing and validation
 
 use super::super::checksums::{ChecksumAlgorithm, calculate_checksum};
-use super::super::constants::{OP_GZIP, OP_TAR};
-use super::super::defaults::DEFAULT_FILE_PERMS;
+use super::super::condition::Condition;
+use super::super::constants::OP_TAR;
+use super::super::defaults::{DEFAULT_FILE_PERMS, DEFAULT_IO_BUFFER_SIZE};
+use super::super::execution::resolve_slot_target;
+use super::super::extraction::decode_slot_stream;
 use super::super::manifest::ManifestSlot;
 use super::super::metadata::SlotMetadata;
-use super::super::operations::pack_operations;
-use super::super::slots::SlotDescriptor;
+use super::super::operations::{is_compressed, string_to_operations, unpack_operations};
+use super::super::packaging::process_slot_data;
+use super::super::slots::{
+    SlotDescriptor, default_slot_permissions, lifecycle_from_str, purpose_from_str,
+    slot_permissions,
+};
+use super::delta::BasePackage;
 use crate::exceptions::{FlavorError, Result};
-use log::{debug, error, info, trace};
+use log::{debug, error, info, trace, warn};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
@@ -25,26 +33,313 @@ fn is_self_referential(source: &str) -> bool {
     source == SELF_REF_MARKER
 }
 
+/// Size of a slot once its compression operations are undone
+///
+/// Slot sources are stored as-is, so compressed sources are decoded once at
+/// build time to record the `original_size` extraction checks against.
+fn decoded_slot_size(slot_path: &Path, descriptor: &SlotDescriptor, index: usize) -> Result<u64> {
+    let operations = unpack_operations(descriptor.operations);
+    if !is_compressed(&operations) {
+        return Ok(descriptor.size);
+    }
+
+    let source = Box::new(BufReader::new(File::open(slot_path)?));
+    let mut decoded = decode_slot_stream(source, &operations, index)?;
+    std::io::copy(&mut decoded, &mut std::io::sink()).map_err(|e| {
+        FlavorError::BuildError(format!(
+            "Slot {index} ({}) does not decode with its operations: {e}",
+            slot_path.display()
+        ))
+    })
+}
+
+/// A manifest slot's mode, naming the slot if its `permissions` are invalid
+fn slot_mode(slot: &ManifestSlot) -> Result<u16> {
+    slot_permissions(slot.permissions.as_deref(), &slot.target, &slot.purpose)
+        .map_err(|e| FlavorError::BuildError(format!("Slot '{}'", slot.id)).with_source(e))
+}
+
+/// Encode a slot's inline content as its operations say
+///
+/// With `tar` the content becomes the archive's one entry, at the slot's
+/// resolved target; a final gzip or LZ4 step is then applied as for any slot.
+fn encode_inline_content(
+    data: &[u8],
+    slot: &ManifestSlot,
+    compression_level: Option<u32>,
+) -> Result<Vec<u8>> {
+    let operations = unpack_operations(string_to_operations(&slot.operations)?);
+    let archived;
+    let data = if operations.contains(&OP_TAR) {
+        let mode = u32::from(slot_mode(slot)?);
+        let mtime = std::env::var("SOURCE_DATE_EPOCH")
+            .ok()
+            .and_then(|e| e.parse::<u64>().ok())
+            .unwrap_or(0);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(mode);
+        header.set_mtime(mtime);
+        let entry_name = resolve_slot_target(&slot.target);
+        let mut tarball = tar::Builder::new(Vec::new());
+        tarball.append_data(&mut header, entry_name.trim_start_matches("./"), data)?;
+        archived = tarball.into_inner()?;
+        &archived[..]
+    } else {
+        data
+    };
+
+    let level = compression_level.map_or_else(flate2::Compression::best, flate2::Compression::new);
+    let (encoded, _) = process_slot_data(data, &slot.operations, level).map_err(|e| {
+        FlavorError::BuildError(format!(
+            "Cannot encode inline content of slot '{}'",
+            slot.id
+        ))
+        .with_source(e)
+    })?;
+    Ok(encoded)
+}
+
+/// Capture the source file's mode (octal) and mtime for faithful restore
+///
+/// The mtime is clamped to `SOURCE_DATE_EPOCH` when set so reproducible
+/// builds don't pick up checkout timestamps.
+fn source_attributes(path: &Path) -> (Option<String>, Option<i64>) {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return (None, None);
+    };
+
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        Some(format!("{:04o}", meta.permissions().mode() & 0o7777))
+    };
+    #[cfg(not(unix))]
+    let mode = None;
+
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .and_then(|d| i64::try_from(d.as_secs()).ok());
+    let epoch = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|e| e.parse::<i64>().ok());
+    let mtime = match (mtime, epoch) {
+        (Some(m), Some(e)) => Some(m.min(e)),
+        (m, _) => m,
+    };
+
+    (mode, mtime)
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` from the build environment
+///
+/// Runtime placeholders such as `{workenv}` have no `$` and pass through
+/// untouched. An unset variable without a default is an error; as in the
+/// shell, the default also replaces a variable that is set but empty.
+fn expand_build_env(value: &str) -> std::result::Result<String, String> {
+    expand_vars(value, |name| std::env::var(name).ok())
+}
+
+/// [`expand_build_env`], with variables looked up by `lookup`
+fn expand_vars(
+    value: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> std::result::Result<String, String> {
+    let pattern = regex::Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}")
+        .map_err(|e| e.to_string())?;
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut last = 0;
+    for caps in pattern.captures_iter(value) {
+        let (Some(whole), Some(name)) = (caps.get(0), caps.get(1)) else {
+            continue;
+        };
+        expanded.push_str(&value[last..whole.start()]);
+        let default = caps.get(2).map(|d| d.as_str());
+        match (lookup(name.as_str()), default) {
+            (Some(var), Some(default)) if var.is_empty() => expanded.push_str(default),
+            (Some(var), _) => expanded.push_str(&var),
+            (None, Some(default)) => expanded.push_str(default),
+            (None, None) => {
+                return Err(format!(
+                    "environment variable '{}' is not set (use ${{{}:-default}} for a fallback)",
+                    name.as_str(),
+                    name.as_str()
+                ));
+            }
+        }
+        last = whole.end();
+    }
+    expanded.push_str(&value[last..]);
+    Ok(expanded)
+}
+
+/// Expand build environment variables in every slot's source and target
+pub(super) fn expand_slot_env(slots: &mut [ManifestSlot]) -> Result<()> {
+    for slot in slots.iter_mut() {
+        let context = |e: String| FlavorError::BuildError(format!("Slot '{}': {e}", slot.id));
+        let source = expand_build_env(&slot.source).map_err(context)?;
+        let target = expand_build_env(&slot.target).map_err(context)?;
+        if source != slot.source || target != slot.target {
+            debug!(
+                "🔤 Slot '{}': expanded {} -> {}, {} -> {}",
+                slot.id, slot.source, source, slot.target, target
+            );
+        }
+        (slot.source, slot.target) = (source, target);
+    }
+    Ok(())
+}
+
+/// Drop optional slots whose source file is missing
+///
+/// Runs before slot indices are assigned, so the remaining slots stay
+/// contiguous and the slots after a skipped one shift down. A declared `slot`
+/// position that matched the manifest as written is renumbered to match.
+pub(super) fn drop_missing_optional_slots(slots: &mut Vec<ManifestSlot>) -> Result<()> {
+    let mut kept = Vec::with_capacity(slots.len());
+    for (position, mut slot) in slots.drain(..).enumerate() {
+        if !slot.required
+            && slot.content.is_none()
+            && !is_self_referential(&slot.source)
+            && !resolve_slot_path(&slot.source)?.exists()
+        {
+            info!(
+                "⏭️ Skipping optional slot '{}': source {} not found",
+                slot.id, slot.source
+            );
+            continue;
+        }
+        if slot.slot == Some(position as i32) {
+            slot.slot = Some(kept.len() as i32);
+        }
+        kept.push(slot);
+    }
+    *slots = kept;
+    Ok(())
+}
+
+/// Resolve a slot source, substituting `{workenv}` with the build base directory
+fn resolve_slot_path(source: &str) -> Result<PathBuf> {
+    let slot_path = if source.contains("{workenv}") {
+        // Priority: 1. FLAVOR_WORKENV_BASE env var, 2. Current working directory
+        let base_dir = if let Ok(env_base) = std::env::var("FLAVOR_WORKENV_BASE") {
+            info!("🔍 Using FLAVOR_WORKENV_BASE: {}", env_base);
+            PathBuf::from(env_base)
+        } else {
+            let cwd = std::env::current_dir()?;
+            info!("🔍 No FLAVOR_WORKENV_BASE, using CWD: {}", cwd.display());
+            cwd
+        };
+        let resolved = source.replace("{workenv}", base_dir.to_str().unwrap_or("."));
+        info!(
+            "📍 Resolved slot path: {} -> {} (base: {})",
+            source,
+            resolved,
+            base_dir.display()
+        );
+        PathBuf::from(resolved)
+    } else {
+        info!("📍 Slot path has no {{workenv}}: {}", source);
+        PathBuf::from(source)
+    };
+
+    info!("Attempting to open slot file at: {:?}", slot_path);
+    Ok(slot_path)
+}
+
 /// Process and validate slot data
 pub(super) struct SlotProcessor {
     pub(super) manifest_slots: Vec<ManifestSlot>,
     pub(super) slot_descriptors: Vec<SlotDescriptor>,
     pub(super) metadata_slots: Vec<SlotMetadata>,
     pub(super) slot_paths: Vec<PathBuf>,
+    default_compression_level: Option<u32>,
+    max_slot_size: Option<u64>,
+    io_buffer_size: usize,
+    base: Option<BasePackage>,
+    /// Holds inline slot content, encoded, until the slots are streamed
+    scratch: Option<tempfile::TempDir>,
 }
 
 impl SlotProcessor {
-    pub(super) fn new(manifest_slots: Vec<ManifestSlot>) -> Self {
+    pub(super) fn new(
+        manifest_slots: Vec<ManifestSlot>,
+        default_compression_level: Option<u32>,
+    ) -> Self {
         Self {
             manifest_slots,
             slot_descriptors: Vec::new(),
             metadata_slots: Vec::new(),
             slot_paths: Vec::new(),
+            default_compression_level,
+            max_slot_size: None,
+            io_buffer_size: DEFAULT_IO_BUFFER_SIZE,
+            base: None,
+            scratch: None,
         }
     }
 
+    /// Reject slots whose stored size exceeds `limit` bytes
+    pub(super) fn with_max_slot_size(mut self, limit: Option<u64>) -> Self {
+        self.max_slot_size = limit;
+        self
+    }
+
+    /// Read slot sources through a buffer of `size` bytes (default 8 MiB)
+    pub(super) fn with_io_buffer_size(mut self, size: Option<usize>) -> Self {
+        self.io_buffer_size = size.unwrap_or(DEFAULT_IO_BUFFER_SIZE);
+        self
+    }
+
+    /// Inherit slots whose stored bytes match a slot of `base` instead of storing them
+    pub(super) fn with_base(mut self, base: Option<BasePackage>) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Resolve a slot's compression level, falling back to the build default
+    ///
+    /// Only inline content is compressed by the builder; the level is still
+    /// checked for every slot so a typo fails the build.
+    fn resolve_compression_level(&self, slot: &ManifestSlot) -> Result<Option<u32>> {
+        match &slot.compression_level {
+            Some(level) => level
+                .level()
+                .map(Some)
+                .map_err(|e| FlavorError::BuildError(format!("Slot '{}': {}", slot.id, e))),
+            None => Ok(self.default_compression_level),
+        }
+    }
+
+    /// Write a slot's encoded inline content to the scratch directory
+    fn stage_inline_slot(
+        &mut self,
+        index: usize,
+        data: &[u8],
+        compression_level: Option<u32>,
+    ) -> Result<PathBuf> {
+        let encoded = encode_inline_content(data, &self.manifest_slots[index], compression_level)?;
+        let scratch = match &self.scratch {
+            Some(scratch) => scratch,
+            None => self.scratch.insert(tempfile::tempdir()?),
+        };
+        let path = scratch.path().join(format!("slot{index}"));
+        std::fs::write(&path, encoded)?;
+        debug!(
+            "📝 Slot {index}: staged {} bytes of inline content",
+            data.len()
+        );
+        Ok(path)
+    }
+
     pub(super) fn process_slots(&mut self) -> Result<()> {
         debug!("🎰 Processing {} slots", self.manifest_slots.len());
+        if self.manifest_slots.is_empty() {
+            info!("📭 No slots: the package only runs its command");
+        }
         let slots_timer = Instant::now();
 
         // Process slots one by one
@@ -66,6 +361,12 @@ impl SlotProcessor {
                 }
             }
 
+            if let Some(condition) = &slot.condition {
+                Condition::parse(condition)
+                    .map_err(|e| FlavorError::ConfigError(format!("Slot '{}': {}", slot.id, e)))?;
+            }
+            let inline_data = slot.inline_data()?;
+
             // Check if this is a self-referential slot
             if is_self_referential(&slot.source) {
                 info!(
@@ -88,11 +389,19 @@ impl SlotProcessor {
                         .permissions
                         .clone()
                         .or_else(|| Some(format!("{:04o}", DEFAULT_FILE_PERMS))),
+                    default_permissions: slot.permissions.is_none().then_some(true),
                     resolution: slot
                         .resolution
                         .clone()
                         .or_else(|| Some("build".to_string())),
                     self_ref: Some(true), // Mark as self-referential
+                    compression_level: None,
+                    source_mode: None,
+                    source_mtime: None,
+                    inherited: None,
+                    condition: slot.condition.clone(),
+                    annotations: slot.annotations.clone(),
+                    extra: serde_json::Map::new(),
                 };
                 self.metadata_slots.push(slot_meta);
 
@@ -123,13 +432,95 @@ impl SlotProcessor {
             }
 
             // Normal slot processing (non-self-ref)
-            // Resolve slot path
-            let slot_path = self.resolve_slot_path(&slot.source)?;
+            let compression_level = self.resolve_compression_level(slot)?;
+            let operations = string_to_operations(&slot.operations)?;
+
+            // Resolve slot path, staging inline content as a file
+            let slot_path = match &inline_data {
+                Some(data) => self.stage_inline_slot(i, data, compression_level)?,
+                None => resolve_slot_path(&self.manifest_slots[i].source)?,
+            };
+            let slot = &self.manifest_slots[i];
+            // Sources are stored as they are, so only inline content is compressed here
+            let compression_level = if inline_data.is_some() {
+                compression_level
+            } else {
+                if slot.compression_level.is_some() {
+                    warn!(
+                        "⚠️ Slot '{}': compression_level has no effect, {} is stored as it is",
+                        slot.id, slot.source
+                    );
+                }
+                None
+            };
 
             // Calculate checksums and size
             let (file_size, sha256_checksum, sha256_u64) =
                 self.calculate_slot_checksums(&slot_path, i)?;
 
+            if let Some(inherited) = self
+                .base
+                .as_ref()
+                .and_then(|base| base.find(&sha256_checksum, operations))
+            {
+                info!(
+                    "🧬 Slot {} '{}' inherited from base slot '{}' ({} bytes not stored)",
+                    i, slot.id, inherited.slot_id, file_size
+                );
+                let slot_meta = SlotMetadata {
+                    index: i,
+                    id: slot.id.clone(),
+                    source: slot.source.clone(),
+                    target: slot.target.clone(),
+                    size: 0,                   // Data lives in the base package
+                    checksum: String::new(),   // Recorded in `inherited`
+                    operations: String::new(), // Nothing to decode
+                    purpose: slot.purpose.clone(),
+                    lifecycle: slot.lifecycle.clone(),
+                    permissions: None, // Files keep the base workenv's modes
+                    default_permissions: None,
+                    resolution: slot
+                        .resolution
+                        .clone()
+                        .or_else(|| Some("build".to_string())),
+                    self_ref: None,
+                    compression_level: None,
+                    source_mode: None,
+                    source_mtime: None,
+                    inherited: Some(inherited.clone()),
+                    condition: slot.condition.clone(),
+                    annotations: slot.annotations.clone(),
+                    extra: serde_json::Map::new(),
+                };
+                self.metadata_slots.push(slot_meta);
+
+                // Keep the name, purpose and lifecycle but no data
+                let mut descriptor = self.create_slot_descriptor(i, slot, 0, 0)?;
+                descriptor.operations = 0;
+                self.slot_descriptors.push(descriptor);
+                self.slot_paths.push(PathBuf::new());
+                continue;
+            }
+            if let Some(limit) = self.max_slot_size.filter(|&limit| file_size > limit) {
+                error!(
+                    "❌ Slot {} '{}' is {} bytes, over the {} byte limit",
+                    i, slot.id, file_size, limit
+                );
+                return Err(FlavorError::BuildError(format!(
+                    "Slot {i} '{}' ({}) is {file_size} bytes, exceeding max_slot_size of {limit} bytes",
+                    slot.id,
+                    slot_path.display()
+                )));
+            }
+
+            // An encoded source is an archive of the slot, so its attributes
+            // say nothing about the extracted files
+            let (source_mode, source_mtime) = if inline_data.is_some() || operations != 0 {
+                (None, None)
+            } else {
+                source_attributes(&slot_path)
+            };
+
             // Create metadata entry
             let slot_meta = SlotMetadata {
                 index: i,
@@ -141,20 +532,31 @@ impl SlotProcessor {
                 operations: slot.operations.clone(),
                 purpose: slot.purpose.clone(),
                 lifecycle: slot.lifecycle.clone(),
-                permissions: slot
-                    .permissions
-                    .clone()
-                    .or_else(|| Some(format!("{:04o}", DEFAULT_FILE_PERMS))),
+                permissions: slot.permissions.clone().or_else(|| {
+                    Some(format!(
+                        "{:04o}",
+                        default_slot_permissions(&slot.target, &slot.purpose)
+                    ))
+                }),
+                default_permissions: slot.permissions.is_none().then_some(true),
                 resolution: slot
                     .resolution
                     .clone()
                     .or_else(|| Some("build".to_string())),
                 self_ref: None, // Normal slot, not self-referential
+                compression_level,
+                source_mode,
+                source_mtime,
+                inherited: None,
+                condition: slot.condition.clone(),
+                annotations: slot.annotations.clone(),
+                extra: serde_json::Map::new(),
             };
             self.metadata_slots.push(slot_meta);
 
             // Create descriptor
-            let descriptor = self.create_slot_descriptor(i, slot, file_size, sha256_u64)?;
+            let mut descriptor = self.create_slot_descriptor(i, slot, file_size, sha256_u64)?;
+            descriptor.original_size = decoded_slot_size(&slot_path, &descriptor, i)?;
             self.slot_descriptors.push(descriptor);
 
             // Store path for later streaming
@@ -169,43 +571,13 @@ impl SlotProcessor {
         Ok(())
     }
 
-    fn resolve_slot_path(&self, source: &str) -> Result<PathBuf> {
-        let slot_path = if source.contains("{workenv}") {
-            // Priority: 1. FLAVOR_WORKENV_BASE env var, 2. Current working directory
-            let base_dir = if let Ok(env_base) = std::env::var("FLAVOR_WORKENV_BASE") {
-                info!("🔍 Using FLAVOR_WORKENV_BASE: {}", env_base);
-                PathBuf::from(env_base)
-            } else {
-                let cwd = std::env::current_dir().map_err(|e| {
-                    FlavorError::Generic(format!("Failed to get current directory: {}", e))
-                })?;
-                info!("🔍 No FLAVOR_WORKENV_BASE, using CWD: {}", cwd.display());
-                cwd
-            };
-            let resolved = source.replace("{workenv}", base_dir.to_str().unwrap_or("."));
-            info!(
-                "📍 Resolved slot path: {} -> {} (base: {})",
-                source,
-                resolved,
-                base_dir.display()
-            );
-            PathBuf::from(resolved)
-        } else {
-            info!("📍 Slot path has no {{workenv}}: {}", source);
-            PathBuf::from(source)
-        };
-
-        info!("Attempting to open slot file at: {:?}", slot_path);
-        Ok(slot_path)
-    }
-
     fn calculate_slot_checksums(
         &self,
         slot_path: &Path,
         index: usize,
     ) -> Result<(u64, String, u64)> {
         let slot_file = File::open(slot_path).map_err(|e| {
-            FlavorError::Generic(format!(
+            FlavorError::BuildError(format!(
                 "Failed to open slot {}: {}",
                 slot_path.display(),
                 e
@@ -218,10 +590,10 @@ impl SlotProcessor {
 
         // Calculate SHA-256 checksum
         let checksum_timer = Instant::now();
-        let mut reader = BufReader::with_capacity(8 * 1024 * 1024, slot_file);
+        let mut reader = BufReader::with_capacity(self.io_buffer_size, slot_file);
         let sha256_checksum_str = calculate_checksum(&mut reader, ChecksumAlgorithm::Sha256)
             .map_err(|e| {
-                FlavorError::Generic(format!(
+                FlavorError::BuildError(format!(
                     "Failed to calculate SHA256 for slot {}: {}",
                     index, e
                 ))
@@ -232,7 +604,7 @@ impl SlotProcessor {
             .strip_prefix("sha256:")
             .and_then(|hex_str| hex::decode(hex_str).ok())
             .ok_or_else(|| {
-                FlavorError::Generic(format!(
+                FlavorError::BuildError(format!(
                     "Invalid SHA256 checksum format: {}",
                     sha256_checksum_str
                 ))
@@ -242,7 +614,7 @@ impl SlotProcessor {
         let sha256_u64 = u64::from_le_bytes(
             sha256_bytes[..8]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("SHA256 hash too short".into()))?,
+                .map_err(|_| FlavorError::BuildError("SHA256 hash too short".into()))?,
         );
 
         trace!("☑️ Checksums calculated in {:?}", checksum_timer.elapsed());
@@ -262,72 +634,18 @@ impl SlotProcessor {
         file_size: u64,
         sha256_checksum: u64,
     ) -> Result<SlotDescriptor> {
-        // Parse operations from comma-separated string (e.g., "tar,gzip")
-        let operations = if slot.operations.is_empty()
-            || slot.operations == "none"
-            || slot.operations == "raw"
-        {
-            vec![]
-        } else if slot.operations == "tgz" {
-            // Special case for "tgz" shorthand
-            vec![OP_TAR, OP_GZIP]
-        } else {
-            // Parse comma-separated operations
-            slot.operations
-                .split(',')
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .filter_map(|s| match s {
-                    "tar" => Some(OP_TAR),
-                    "gzip" => Some(OP_GZIP),
-                    _ => {
-                        log::warn!("Unknown operation: {}, skipping", s);
-                        None
-                    }
-                })
-                .collect::<Vec<u8>>()
-        };
-
-        // Map purpose string to byte value
-        let purpose_value = match slot.purpose.as_str() {
-            "payload" => 0,
-            "runtime" => 1,
-            "tool" => 2,
-            _ => 0,
-        };
-
-        // Map lifecycle string to byte value
-        let lifecycle_value = match slot.lifecycle.as_str() {
-            "init" => 0,
-            "startup" => 1,
-            "runtime" => 2,
-            "shutdown" => 3,
-            "cache" => 4,
-            "temp" => 5,
-            "lazy" => 6,
-            "eager" => 7,
-            "dev" => 8,
-            "config" => 9,
-            "platform" => 10,
-            _ => 2,
-        };
-
         // Create descriptor
         let mut descriptor = SlotDescriptor::new(index as u64);
         descriptor = descriptor.with_name(&slot.id);
         descriptor.size = file_size;
         descriptor.original_size = file_size;
         descriptor.checksum = sha256_checksum;
-        descriptor.operations = pack_operations(&operations);
-        descriptor.purpose = purpose_value;
-        descriptor.lifecycle = lifecycle_value;
+        descriptor.operations = string_to_operations(&slot.operations)?;
+        descriptor.purpose = purpose_from_str(&slot.purpose);
+        descriptor.lifecycle = lifecycle_from_str(&slot.lifecycle);
 
-        // Parse permissions
-        let perms = if let Some(ref perm_str) = slot.permissions {
-            u16::from_str_radix(perm_str.trim_start_matches('0'), 8).unwrap_or(DEFAULT_FILE_PERMS)
-        } else {
-            DEFAULT_FILE_PERMS
-        };
+        // Executables in bin/ and tool/code slots default to 0700
+        let perms = slot_mode(slot)?;
         descriptor.permissions = (perms & 0xFF) as u8;
         descriptor.permissions_high = ((perms >> 8) & 0xFF) as u8;
 
@@ -339,3 +657,32 @@ impl SlotProcessor {
         Ok(descriptor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_build_env() {
+        let expand = |value| {
+            expand_vars(value, |name| match name {
+                "APP" => Some("flavor".to_string()),
+                "EMPTY" => Some(String::new()),
+                _ => None,
+            })
+        };
+        assert_eq!(
+            expand("${APP}/dist/{workenv}"),
+            Ok("flavor/dist/{workenv}".into())
+        );
+        assert_eq!(
+            expand("${UNSET:-build}/a-${APP:-x}-${EMPTY:-y}"),
+            Ok("build/a-flavor-y".into())
+        );
+        assert_eq!(
+            expand("$HOME and ${ unclosed"),
+            Ok("$HOME and ${ unclosed".into())
+        );
+        assert!(expand("${UNSET}/artifact.tar").is_err_and(|e| e.contains("'UNSET'")));
+    }
+}
@@ -1,6 +1,8 @@
 //! CLI command handlers for PSPF/2025 packages
 
 use crate::psp::format_2025::reader::Reader;
+use crate::psp::format_2025::slot_tree::{ROOT_INODE, SlotTree};
+use std::collections::HashMap;
 use std::path::Path;
 
 /// Show package information
@@ -10,7 +12,7 @@ pub fn show_info(exe_path: &Path) -> i32 {
     let mut reader = match Reader::new(exe_path) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("Error: Failed to create reader: {}", e);
+            eprintln!("Error: Failed to create reader: {:#}", e);
             return 1;
         }
     };
@@ -19,7 +21,7 @@ pub fn show_info(exe_path: &Path) -> i32 {
     let index = match reader.read_index() {
         Ok(idx) => idx.clone(),
         Err(e) => {
-            eprintln!("Error: Failed to read index: {}", e);
+            eprintln!("Error: Failed to read index: {:#}", e);
             return 1;
         }
     };
@@ -27,7 +29,7 @@ pub fn show_info(exe_path: &Path) -> i32 {
     let metadata = match reader.read_metadata() {
         Ok(m) => m.clone(),
         Err(e) => {
-            eprintln!("Error: Failed to read metadata: {}", e);
+            eprintln!("Error: Failed to read metadata: {:#}", e);
             return 1;
         }
     };
@@ -48,9 +50,13 @@ pub fn show_info(exe_path: &Path) -> i32 {
 
     for slot in &metadata.slots {
         total_size += slot.size;
-        if !slot.operations.is_empty() && slot.operations != "none" {
-            *codec_types.entry(slot.operations.clone()).or_insert(0) += 1;
-        }
+        // Group spellings of the same chain ("tgz", "tar,gzip") together
+        let codec = match super::operations::string_to_operations(&slot.operations) {
+            Ok(0) => continue,
+            Ok(packed) => super::operations::operations_to_string(packed),
+            Err(_) => slot.operations.clone(),
+        };
+        *codec_types.entry(codec).or_insert(0) += 1;
     }
 
     let codec_info = if codec_types.is_empty() {
@@ -69,6 +75,7 @@ pub fn show_info(exe_path: &Path) -> i32 {
 
     // Copy packed fields to local variables to avoid unaligned access.
     let format_version = index.format_version;
+    let cache_key = index.cache_key();
 
     // Display info
     println!("📦 Package Information:");
@@ -84,140 +91,337 @@ pub fn show_info(exe_path: &Path) -> i32 {
     }
     println!();
     println!("📊 Package Details:");
-    println!("  Slots: {} ({})", metadata.slots.len(), codec_info);
+    if metadata.slots.is_empty() {
+        println!("  Slots: 0 (command only)");
+    } else {
+        println!("  Slots: {} ({})", metadata.slots.len(), codec_info);
+    }
     println!("  Total Size: {:.2} MB", total_size as f64 / 1_048_576.0);
     println!("  Verified: {}", verified);
+    println!("  Cache Key: {}", cache_key);
     println!();
     println!("🚀 Execution:");
     println!("  Command: {}", metadata.execution.command);
 
+    let annotated: Vec<_> = metadata
+        .slots
+        .iter()
+        .filter(|slot| !slot.annotations.is_empty())
+        .collect();
+    if !annotated.is_empty() {
+        println!();
+        println!("🏷️ Slot Annotations:");
+        for slot in annotated {
+            println!("  {}:", slot.id);
+            for (key, value) in &slot.annotations {
+                println!("    {}: {}", key, value);
+            }
+        }
+    }
+
     0
 }
 
-/// Show raw metadata as JSON
-pub fn show_metadata(exe_path: &Path) -> i32 {
+/// Show package metadata in `format`: `json`, `yaml` or `toml`
+///
+/// JSON is printed as stored. YAML and TOML re-serialize the parsed metadata
+/// and need the `yaml` and `toml` features.
+pub fn show_metadata(exe_path: &Path, format: &str) -> i32 {
     let mut reader = match Reader::new(exe_path) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("Error: Failed to create reader: {}", e);
+            eprintln!("Error: Failed to create reader: {:#}", e);
             return 1;
         }
     };
 
+    if format == "json" {
+        // Output raw JSON metadata as stored, without a parse round-trip
+        let mut stdout = std::io::stdout().lock();
+        return match reader.stream_metadata_to(&mut stdout) {
+            Ok(_) => {
+                println!();
+                0
+            }
+            Err(e) => {
+                eprintln!("Error: Failed to read metadata: {:#}", e);
+                1
+            }
+        };
+    }
+
     let metadata = match reader.read_metadata() {
-        Ok(m) => m.clone(),
+        Ok(metadata) => metadata,
         Err(e) => {
-            eprintln!("Error: Failed to read metadata: {}", e);
+            eprintln!("Error: Failed to read metadata: {:#}", e);
             return 1;
         }
     };
-
-    // Output raw JSON metadata
-    match serde_json::to_string_pretty(&metadata) {
-        Ok(json) => {
-            println!("{}", json);
+    match render_metadata(metadata, format) {
+        Ok(rendered) => {
+            print!("{rendered}");
             0
         }
         Err(e) => {
-            eprintln!("Error: Failed to encode metadata: {}", e);
+            eprintln!("Error: {:#}", e);
             1
         }
     }
 }
 
+/// Serialize parsed metadata as YAML or TOML
+#[cfg_attr(not(any(feature = "yaml", feature = "toml")), allow(unused_variables))]
+fn render_metadata(
+    metadata: &crate::psp::format_2025::metadata::Metadata,
+    format: &str,
+) -> std::result::Result<String, String> {
+    match format {
+        #[cfg(feature = "yaml")]
+        "yaml" => serde_yaml::to_string(metadata).map_err(|e| e.to_string()),
+        #[cfg(feature = "toml")]
+        "toml" => toml::to_string_pretty(metadata).map_err(|e| e.to_string()),
+        #[cfg(not(feature = "yaml"))]
+        "yaml" => Err("YAML output needs a launcher built with the `yaml` feature".to_string()),
+        #[cfg(not(feature = "toml"))]
+        "toml" => Err("TOML output needs a launcher built with the `toml` feature".to_string()),
+        _ => Err(format!(
+            "Unknown metadata format '{format}' (expected json, yaml or toml)"
+        )),
+    }
+}
+
+/// Show the environment the package would launch with
+pub fn show_env(exe_path: &Path) -> i32 {
+    let base_env = std::env::vars().collect();
+    let env_map = match crate::psp::format_2025::runtime::resolve_runtime_env(exe_path, base_env) {
+        Ok(env_map) => env_map,
+        Err(e) => {
+            eprintln!("Error: Failed to resolve runtime environment: {:#}", e);
+            return 1;
+        }
+    };
+
+    print!("{}", render_env(&env_map));
+    0
+}
+
+/// `KEY=VALUE` lines sorted by key, one per variable
+fn render_env(env_map: &HashMap<String, String>) -> String {
+    let mut keys: Vec<_> = env_map.keys().collect();
+    keys.sort();
+    keys.into_iter()
+        .map(|key| format!("{}={}\n", key, env_map[key]))
+        .collect()
+}
+
 /// Verify bundle integrity
-pub fn verify_bundle(exe_path: &Path) -> i32 {
+///
+/// Runs every check and reports all problems at once rather than stopping at
+/// the first failure. With `pubkey`, the signature must also verify against
+/// that PEM public key.
+pub fn verify_bundle(exe_path: &Path, pubkey: Option<&Path>) -> i32 {
     println!("🔍 Verifying PSPF package: {:?}", exe_path);
 
     let mut reader = match Reader::new(exe_path) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("Error: Failed to create reader: {}", e);
+            eprintln!("Error: Failed to create reader: {:#}", e);
             return 1;
         }
     };
 
-    let mut errors = Vec::new();
+    let diagnostics = reader.collect_diagnostics();
+    print!("{}", diagnostics);
 
-    // Reading index and metadata performs the necessary checksum checks.
-    let _index = match reader.read_index() {
-        Ok(idx) => {
-            let format_version = idx.format_version; // Copy to avoid unaligned access
-            println!("  ✓ Valid PSPF magic");
-            println!("  ✓ Format version: {:04x}", format_version);
-            println!("  ✓ Index checksum valid");
-            idx.clone()
+    let mut problems = diagnostics.problems();
+    if let Some(pubkey) = pubkey {
+        let trusted = match crate::psp::format_2025::keys::load_public_key(pubkey) {
+            Ok(key) => key,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                return 1;
+            }
+        };
+        let options = crate::api::VerifyOptions {
+            trusted_public_key: Some(trusted),
+            ..crate::api::VerifyOptions::default()
+        };
+        match crate::api::verify_package_with_options(exe_path, options) {
+            Ok(result) if result.signature_valid => {
+                println!("✓ Signature matches trusted key {}", pubkey.display());
+            }
+            Ok(_) => problems.push(format!(
+                "signature does not verify against trusted key {}",
+                pubkey.display()
+            )),
+            Err(e) => problems.push(e.to_string()),
         }
+    }
+
+    if problems.is_empty() {
+        println!("\n✓ Bundle verification passed");
+        0
+    } else {
+        println!("\n✗ Bundle verification failed:");
+        for problem in &problems {
+            println!("  - {}", problem);
+        }
+        1
+    }
+}
+
+/// Write the package signature to a detached `.sig` file and its key to a `.pem`
+///
+/// The signature defaults to `<package>.sig` next to the package; the public
+/// key is written beside it with a `.pem` extension.
+pub fn export_signature(exe_path: &Path, output: Option<&str>) -> i32 {
+    let output = output.map_or_else(
+        || {
+            let mut path = exe_path.as_os_str().to_owned();
+            path.push(".sig");
+            std::path::PathBuf::from(path)
+        },
+        std::path::PathBuf::from,
+    );
+
+    let signature = match crate::api::export_signature(exe_path) {
+        Ok(signature) => signature,
         Err(e) => {
-            println!("  ✗ Index verification failed");
-            errors.push(format!("Index error: {}", e));
+            eprintln!("Error: Failed to export signature: {:#}", e);
             return 1;
         }
     };
+    let key_output = output.with_extension("pem");
+    let written = std::fs::write(&output, signature.signature)
+        .and_then(|()| std::fs::write(&key_output, signature.public_key_pem()));
+    match written {
+        Ok(()) => {
+            println!("✓ Wrote detached signature: {}", output.display());
+            println!("✓ Wrote public key: {}", key_output.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to write signature: {:#}", e);
+            1
+        }
+    }
+}
 
-    let metadata = match reader.read_metadata() {
-        Ok(m) => {
-            println!("  ✓ Metadata checksum valid");
-            m.clone()
+/// Describe the embedded launcher, optionally writing it to `output`
+///
+/// Fails when the launcher doesn't match the hash recorded in the index; the
+/// file is still written, so a mismatching launcher can be examined.
+pub fn launcher_info(exe_path: &Path, output: Option<&str>) -> i32 {
+    use sha2::{Digest, Sha256};
+
+    let mut reader = match Reader::new(exe_path) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: Failed to create reader: {:#}", e);
+            return 1;
         }
+    };
+    let launcher = match reader.read_launcher() {
+        Ok(launcher) => launcher,
         Err(e) => {
-            println!("  ✗ Metadata verification failed");
-            errors.push(format!("Metadata error: {}", e));
+            eprintln!("Error: Failed to read launcher: {:#}", e);
             return 1;
         }
     };
+    if launcher.is_empty() {
+        println!("No embedded launcher (split package data file)");
+        return 0;
+    }
 
-    // Check slots
-    match reader.read_slot_descriptors() {
-        Ok(descriptors) => {
-            if descriptors.len() == metadata.slots.len() {
-                println!("  ✓ All {} slot descriptors valid", metadata.slots.len());
-            } else {
-                errors.push(format!(
-                    "Slot descriptor count mismatch: expected {}, got {}",
-                    metadata.slots.len(),
-                    descriptors.len()
-                ));
-            }
+    let hash: [u8; 32] = Sha256::digest(&launcher).into();
+    let recorded = reader
+        .read_index()
+        .ok()
+        .and_then(|index| index.launcher_hash());
+    println!("🚀 Launcher:");
+    println!("  Type: {}", launcher_type_of(&launcher));
+    println!("  Size: {} bytes", launcher.len());
+    println!("  SHA-256: {}", hex::encode(hash));
+    let matches = match recorded {
+        Some(expected) if expected == hash => {
+            println!("  Index hash: ✓ matches");
+            true
         }
-        Err(e) => {
-            errors.push(format!("Failed to read slot descriptors: {}", e));
+        Some(expected) => {
+            println!("  Index hash: ✗ expected {}", hex::encode(expected));
+            false
+        }
+        None => {
+            println!("  Index hash: not recorded");
+            true
         }
+    };
+
+    if let Some(output) = output {
+        if let Err(e) = std::fs::write(output, &launcher) {
+            eprintln!("Error: Failed to write launcher: {:#}", e);
+            return 1;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(output, std::fs::Permissions::from_mode(0o755));
+        }
+        println!("✓ Wrote launcher: {}", output);
     }
 
-    if errors.is_empty() {
-        println!("\n✓ Bundle verification passed");
-        0
-    } else {
-        println!("\n✗ Bundle verification failed:");
-        for err in &errors {
-            println!("  - {}", err);
+    if matches { 0 } else { 1 }
+}
+
+/// Install the package's workenv into `dest` without running it
+pub fn install(exe_path: &Path, dest: &str) -> i32 {
+    match crate::api::install_package(
+        exe_path,
+        Path::new(dest),
+        crate::api::InstallOptions::default(),
+    ) {
+        Ok(installed) => {
+            println!("✓ Installed to {}", installed.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to install: {:#}", e);
+            1
         }
-        1
     }
 }
 
 /// Extract a specific slot
 pub fn extract_slot(exe_path: &Path, slot_str: &str, output_dir: &str) -> i32 {
-    let slot_index = if let Ok(idx) = slot_str.parse::<usize>() {
-        idx
-    } else {
-        eprintln!("Error: Invalid slot index: {}", slot_str);
-        return 1;
-    };
-
     let mut reader = match Reader::new(exe_path) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("Error: Failed to create reader: {}", e);
+            eprintln!("Error: Failed to create reader: {:#}", e);
             return 1;
         }
     };
 
+    // Accept either a numeric slot index or a slot id
+    let slot_index = if let Ok(idx) = slot_str.parse::<usize>() {
+        idx
+    } else {
+        match reader.find_slot_by_name(slot_str) {
+            Ok(Some((idx, _))) => idx,
+            Ok(None) => {
+                eprintln!("Error: No slot with index or id: {}", slot_str);
+                return 1;
+            }
+            Err(e) => {
+                eprintln!("Error: Failed to look up slot: {:#}", e);
+                return 1;
+            }
+        }
+    };
+
     let metadata = match reader.read_metadata() {
         Ok(m) => m,
         Err(e) => {
-            eprintln!("Error: Failed to read metadata: {}", e);
+            eprintln!("Error: Failed to read metadata: {:#}", e);
             return 1;
         }
     };
@@ -239,7 +443,7 @@ pub fn extract_slot(exe_path: &Path, slot_str: &str, output_dir: &str) -> i32 {
     // Create output directory
     let output_path = Path::new(output_dir);
     if let Err(e) = std::fs::create_dir_all(output_path) {
-        eprintln!("Error: Failed to create output directory: {}", e);
+        eprintln!("Error: Failed to create output directory: {:#}", e);
         return 1;
     }
 
@@ -250,7 +454,233 @@ pub fn extract_slot(exe_path: &Path, slot_str: &str, output_dir: &str) -> i32 {
             0
         }
         Err(e) => {
-            eprintln!("Error: Failed to extract slot: {}", e);
+            eprintln!("Error: Failed to extract slot: {:#}", e);
+            1
+        }
+    }
+}
+
+/// Open a package's slots as a [`SlotTree`], laid out by `build`
+fn open_slot_tree(
+    exe_path: &Path,
+    build: fn(&mut Reader) -> crate::exceptions::Result<SlotTree>,
+) -> Option<(Reader, SlotTree)> {
+    let mut reader = match Reader::new(exe_path) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: Failed to create reader: {:#}", e);
+            return None;
+        }
+    };
+    match build(&mut reader) {
+        Ok(tree) => Some((reader, tree)),
+        Err(e) => {
+            eprintln!("Error: Failed to read slots: {:#}", e);
+            None
+        }
+    }
+}
+
+/// List a directory of the package's slot tree, without extracting anything
+pub fn list_slot_tree(exe_path: &Path, path: &str) -> i32 {
+    use super::slot_tree::NodeKind;
+
+    let Some((_, tree)) = open_slot_tree(exe_path, SlotTree::build) else {
+        return 1;
+    };
+    let Some(node) = tree.resolve(Path::new(path)).and_then(|ino| tree.get(ino)) else {
+        eprintln!("Error: No such path in package: {}", path);
+        return 1;
+    };
+    let entries = match &node.kind {
+        NodeKind::Directory(entries) => entries.values().filter_map(|&ino| tree.get(ino)).collect(),
+        _ => vec![node],
+    };
+    for entry in entries {
+        let (kind, suffix) = match &entry.kind {
+            NodeKind::Directory(_) => ('d', "/".to_string()),
+            NodeKind::File { .. } => ('-', String::new()),
+            NodeKind::Symlink(target) => ('l', format!(" -> {}", target.display())),
+        };
+        println!(
+            "{kind}{:04o} {:>12}  {}{suffix}",
+            entry.mode,
+            entry.size(),
+            entry.name
+        );
+    }
+    0
+}
+
+/// Print the workenv the package extracts to as a tree, without extracting
+///
+/// Tar slots are listed from their entry headers and single-file slots at
+/// their targets. Slots that don't land in the workenv are listed after it.
+pub fn show_tree(exe_path: &Path) -> i32 {
+    let Some((mut reader, tree)) = open_slot_tree(exe_path, SlotTree::build_workenv) else {
+        return 1;
+    };
+    println!(".");
+    let mut totals = TreeTotals::default();
+    print_tree_level(&tree, ROOT_INODE, "", &mut totals);
+    println!();
+    println!(
+        "{} directories, {} files, {} bytes",
+        totals.directories, totals.files, totals.bytes
+    );
+
+    if !tree.omitted().is_empty() {
+        let Ok(metadata) = reader.read_metadata() else {
+            return 0;
+        };
+        println!();
+        println!("Not in the workenv:");
+        for slot in tree.omitted().iter().filter_map(|&i| metadata.slots.get(i)) {
+            match &slot.inherited {
+                Some(inherited) => println!(
+                    "  {} (inherited from {}: {} files)",
+                    slot.id,
+                    inherited.package,
+                    inherited.files.len()
+                ),
+                None => println!(
+                    "  {} -> {}",
+                    slot.id,
+                    super::execution::resolve_slot_target(&slot.target)
+                ),
+            }
+        }
+    }
+    0
+}
+
+/// Counts for the summary line of [`show_tree`]
+#[derive(Default)]
+struct TreeTotals {
+    directories: usize,
+    files: usize,
+    bytes: u64,
+}
+
+/// Print the entries of directory `dir`, recursing into subdirectories
+fn print_tree_level(tree: &SlotTree, dir: u64, prefix: &str, totals: &mut TreeTotals) {
+    use super::slot_tree::NodeKind;
+
+    let Some(NodeKind::Directory(entries)) = tree.get(dir).map(|node| &node.kind) else {
+        return;
+    };
+    let count = entries.len();
+    for (position, &ino) in entries.values().enumerate() {
+        let Some(node) = tree.get(ino) else {
+            continue;
+        };
+        let last = position + 1 == count;
+        let branch = if last { "└── " } else { "├── " };
+        match &node.kind {
+            NodeKind::Directory(_) => {
+                println!("{prefix}{branch}{}/", node.name);
+                totals.directories += 1;
+                let indent = if last { "    " } else { "│   " };
+                print_tree_level(tree, ino, &format!("{prefix}{indent}"), totals);
+            }
+            NodeKind::File { size, .. } => {
+                println!("{prefix}{branch}{} ({size} bytes)", node.name);
+                totals.files += 1;
+                totals.bytes += size;
+            }
+            NodeKind::Symlink(target) => {
+                println!("{prefix}{branch}{} -> {}", node.name, target.display());
+            }
+        }
+    }
+}
+
+/// Write a file from the package's slot tree to stdout
+pub fn cat_slot_file(exe_path: &Path, path: &str) -> i32 {
+    let Some((mut reader, tree)) = open_slot_tree(exe_path, SlotTree::build) else {
+        return 1;
+    };
+    let Some(ino) = tree.resolve(Path::new(path)) else {
+        eprintln!("Error: No such path in package: {}", path);
+        return 1;
+    };
+    match tree.copy_to(&mut reader, ino, &mut std::io::stdout().lock()) {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: Failed to read {}: {:#}", path, e);
+            1
+        }
+    }
+}
+
+/// Mount the package's slot tree read-only on `mountpoint` until unmounted
+pub fn mount_slots(exe_path: &Path, mountpoint: &str) -> i32 {
+    #[cfg(all(unix, feature = "fuse"))]
+    {
+        match super::mount::mount(exe_path, Path::new(mountpoint)) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Error: Failed to mount {}: {:#}", mountpoint, e);
+                1
+            }
+        }
+    }
+    #[cfg(not(all(unix, feature = "fuse")))]
+    {
+        let _ = (exe_path, mountpoint);
+        eprintln!("Error: Mounting needs a launcher built with the `fuse` feature (Unix only)");
+        1
+    }
+}
+
+/// Show differences between two packages
+pub fn show_diff(a: &Path, b: &Path) -> i32 {
+    match crate::api::diff_packages(a, b) {
+        Ok(diff) => {
+            print!("{diff}");
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to diff packages: {:#}", e);
+            1
+        }
+    }
+}
+
+/// Check the workenv cache and print what was found (or fixed)
+pub fn fsck_cache(repair: bool) -> i32 {
+    match crate::api::fsck_cache(repair) {
+        Ok(report) => {
+            print!("{report}");
+            if report.issues.is_empty() || report.repaired {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: Cache check failed: {:#}", e);
+            1
+        }
+    }
+}
+
+/// Time cold and warm launches and print the report as JSON
+pub fn run_bench(exe_path: &Path, iterations: usize) -> i32 {
+    let report = match super::launcher::bench(exe_path, iterations) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: Benchmark failed: {:#}", e);
+            return 1;
+        }
+    };
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => {
+            println!("{json}");
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to serialize benchmark report: {:#}", e);
             1
         }
     }
@@ -271,21 +701,164 @@ fn detect_launcher_type(exe_path: &Path) -> String {
         return "unknown".to_string();
     }
 
-    let header_str = String::from_utf8_lossy(&buffer);
+    launcher_type_of(&buffer).to_string()
+}
+
+/// Launcher type from its PE layout or the marker strings in its bytes
+fn launcher_type_of(data: &[u8]) -> &'static str {
+    // Only PE launchers can be told apart by layout
+    let pe_type = super::pe_utils::get_launcher_type(data);
+    if pe_type != "unknown" {
+        return pe_type;
+    }
+
+    let header_str = String::from_utf8_lossy(data);
 
-    if header_str.contains("go.buildid") || header_str.contains("runtime.main") {
-        "go".to_string()
-    } else if header_str.contains("rust_panic") || header_str.contains("_ZN") {
-        "rust".to_string()
+    // Rust first: this crate's own launcher carries the Go markers as literals
+    if header_str.contains("rust_panic") || header_str.contains("_ZN") {
+        "rust"
+    } else if header_str.contains("go.buildid") || header_str.contains("runtime.main") {
+        "go"
     } else if header_str.starts_with("#!/usr/bin/env python")
         || header_str.starts_with("#!/usr/bin/python")
     {
-        "python".to_string()
+        "python"
     } else if header_str.starts_with("#!/usr/bin/env node")
         || header_str.starts_with("#!/usr/bin/node")
     {
-        "node".to_string()
+        "node"
     } else {
-        "unknown".to_string()
+        "unknown"
+    }
+}
+
+/// Print the embedded SBOM without extracting the package
+pub fn show_sbom(exe_path: &Path) -> i32 {
+    use super::constants::SBOM_SLOT_ID;
+
+    let mut reader = match Reader::new(exe_path) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: Failed to create reader: {:#}", e);
+            return 1;
+        }
+    };
+
+    let slot_index = match reader.find_slot_by_name(SBOM_SLOT_ID) {
+        Ok(Some((idx, _))) => idx,
+        Ok(None) => {
+            eprintln!("Error: Package has no embedded SBOM");
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to look up SBOM slot: {:#}", e);
+            return 1;
+        }
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    match reader.copy_slot_to(slot_index, &mut stdout) {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: Failed to read SBOM: {:#}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BuildOptions;
+
+    #[test]
+    fn test_render_metadata() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("app.sh"), b"#!/bin/sh\n").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "formats", "version": "2.1"},
+            "execution": {"command": "{workenv}/app.sh", "env": {"MODE": "prod"}},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("formats".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        let metadata = reader.read_metadata();
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else {
+            return;
+        };
+
+        let yaml = render_metadata(metadata, "yaml");
+        #[cfg(feature = "yaml")]
+        assert!(yaml.is_ok_and(|s| s.contains("name: formats") && s.contains("MODE: prod")));
+        #[cfg(not(feature = "yaml"))]
+        assert!(yaml.is_err_and(|e| e.contains("`yaml` feature")));
+
+        let toml = render_metadata(metadata, "toml");
+        #[cfg(feature = "toml")]
+        assert!(toml.is_ok_and(|s| s.contains("[package]") && s.contains("name = \"formats\"")));
+        #[cfg(not(feature = "toml"))]
+        assert!(toml.is_err_and(|e| e.contains("`toml` feature")));
+
+        assert!(render_metadata(metadata, "xml").is_err_and(|e| e.contains("Unknown")));
+    }
+
+    #[test]
+    fn test_env_output_applies_runtime_env() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("app.sh"), b"#!/bin/sh\n").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "env", "version": "1.0"},
+            "execution": {"command": "{workenv}/app.sh"},
+            "runtime": {"env": {
+                "unset": ["SECRET_*"],
+                "map": {"OLD_NAME": "NEW_NAME"},
+                "set": {"MODE": "prod"}
+            }},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("env".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let base_env = HashMap::from([
+            ("HOME".to_string(), "/home/user".to_string()),
+            ("MODE".to_string(), "dev".to_string()),
+            ("OLD_NAME".to_string(), "kept".to_string()),
+            ("SECRET_TOKEN".to_string(), "hidden".to_string()),
+        ]);
+        let env_map = crate::api::resolve_runtime_env(&package, base_env);
+        assert!(env_map.is_ok());
+        let Ok(env_map) = env_map else { return };
+        assert_eq!(
+            render_env(&env_map),
+            "HOME=/home/user\nMODE=prod\nNEW_NAME=kept\n"
+        );
     }
 }
@@ -0,0 +1,157 @@
+//! Slot conditions, deciding at extraction time whether a slot is wanted
+//!
+//! A condition is a list of clauses joined by `&&` and `||`. `&&` binds
+//! tighter than `||` and there are no parentheses. Each clause is one of:
+//!
+//! - `env:NAME`: the environment variable `NAME` is set and not empty
+//! - `env:NAME=VALUE`: `NAME` is set to exactly `VALUE`
+//! - `platform:VALUE`: [`get_platform_string`] is `VALUE` (e.g. `linux_amd64`),
+//!   or its OS part is (e.g. `linux`)
+//! - `!CLAUSE`: the clause does not hold
+//!
+//! Whitespace around clauses is ignored; values are taken literally and
+//! cannot contain `&&` or `||`.
+
+use crate::exceptions::{FlavorError, Result};
+use crate::utils::get_platform_string;
+use std::env;
+
+/// A parsed slot condition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    /// Alternatives (`||`), each a list of clauses that must all hold (`&&`)
+    any_of: Vec<Vec<Clause>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Clause {
+    negated: bool,
+    test: Test,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Test {
+    EnvSet(String),
+    EnvEquals(String, String),
+    Platform(String),
+}
+
+impl Condition {
+    /// Parse a condition, failing with `ConfigError` on malformed input
+    pub fn parse(expr: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            FlavorError::ConfigError(format!("Invalid condition '{expr}': {reason}"))
+        };
+        let any_of = expr
+            .split("||")
+            .map(|alternative| {
+                alternative
+                    .split("&&")
+                    .map(|clause| Clause::parse(clause.trim()).map_err(invalid))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { any_of })
+    }
+
+    /// Evaluate against the launcher's own environment and platform
+    pub fn is_met(&self) -> bool {
+        self.evaluate(|name| env::var(name).ok(), &get_platform_string())
+    }
+
+    /// Evaluate with the given variable lookup and platform string
+    pub fn evaluate(&self, env: impl Fn(&str) -> Option<String>, platform: &str) -> bool {
+        self.any_of.iter().any(|clauses| {
+            clauses.iter().all(|clause| {
+                let holds = match &clause.test {
+                    Test::EnvSet(name) => env(name).is_some_and(|v| !v.is_empty()),
+                    Test::EnvEquals(name, value) => env(name).is_some_and(|v| v == *value),
+                    Test::Platform(value) => {
+                        platform == value || platform.split('_').next() == Some(value.as_str())
+                    }
+                };
+                holds != clause.negated
+            })
+        })
+    }
+}
+
+impl Clause {
+    fn parse(clause: &str) -> std::result::Result<Self, &'static str> {
+        let (negated, clause) = match clause.strip_prefix('!') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, clause),
+        };
+        let test = match clause.split_once(':') {
+            Some(("env", spec)) => {
+                let (name, value) = match spec.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (spec, None),
+                };
+                if name.is_empty() || name.contains(char::is_whitespace) || name.contains('\0') {
+                    return Err("env clauses need a variable name");
+                }
+                match value {
+                    Some(value) => Test::EnvEquals(name.to_string(), value.to_string()),
+                    None => Test::EnvSet(name.to_string()),
+                }
+            }
+            Some(("platform", value)) if !value.is_empty() => Test::Platform(value.to_string()),
+            Some(("platform", _)) => return Err("platform clauses need a value"),
+            _ if clause.is_empty() => return Err("empty clause"),
+            _ => return Err("clauses must start with 'env:' or 'platform:'"),
+        };
+        Ok(Self { negated, test })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_condition_evaluation() {
+        let env = |name: &str| match name {
+            "FLAVOR_GPU" => Some("cuda".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        let met = |expr: &str| Condition::parse(expr).is_ok_and(|c| c.evaluate(env, "linux_amd64"));
+
+        assert!(met("env:FLAVOR_GPU"));
+        assert!(!met("env:EMPTY"));
+        assert!(!met("env:MISSING"));
+        assert!(met("env:FLAVOR_GPU=cuda"));
+        assert!(!met("env:FLAVOR_GPU=rocm"));
+        assert!(met("!env:MISSING"));
+        assert!(met("platform:linux"));
+        assert!(met("platform:linux_amd64"));
+        assert!(!met("platform:darwin"));
+        assert!(!met("platform:linux_arm64"));
+
+        // && binds tighter than ||
+        assert!(met(
+            "platform:darwin && env:FLAVOR_GPU || env:FLAVOR_GPU=cuda"
+        ));
+        assert!(!met("platform:linux && env:MISSING || platform:windows"));
+        assert!(met(" platform:linux&&! env:MISSING "));
+    }
+
+    #[test]
+    fn test_condition_parse_errors() {
+        for expr in [
+            "",
+            "env:",
+            "gpu",
+            "platform:",
+            "env:A &&",
+            "|| env:A",
+            "os:linux",
+        ] {
+            assert!(
+                Condition::parse(expr).is_err_and(|e| matches!(e, FlavorError::ConfigError(_))),
+                "{expr}"
+            );
+        }
+    }
+}
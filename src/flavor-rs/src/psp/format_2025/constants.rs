@@ -16,6 +16,9 @@ pub const SLOT_DESCRIPTOR_SIZE: usize = 64; // Slot descriptor size
 pub const MAGIC_TRAILER_SIZE: usize = 8200; // 📦 (4) + index (8192) + 🪄 (4)
 pub const SLOT_ALIGNMENT: u64 = 8; // Slots must be 8-byte aligned
 
+// Well-known slot ids
+pub const SBOM_SLOT_ID: &str = "__sbom__"; // Embedded SBOM (CycloneDX/SPDX JSON), never extracted
+
 // Operation codes - part of format spec
 pub const OP_NONE: u8 = 0x00; // No operation
 pub const OP_TAR: u8 = 0x01; // POSIX TAR archive (REQUIRED)
@@ -23,6 +26,7 @@ pub const OP_GZIP: u8 = 0x10; // GZIP compression (REQUIRED)
 pub const OP_BZIP2: u8 = 0x13; // BZIP2 compression (REQUIRED)
 pub const OP_XZ: u8 = 0x16; // XZ/LZMA2 compression (REQUIRED)
 pub const OP_ZSTD: u8 = 0x1B; // Zstandard compression (REQUIRED)
+pub const OP_LZ4: u8 = 0x1E; // LZ4 frame compression (OPTIONAL, fast decompression)
 
 // Purpose types - part of format spec
 #[allow(non_upper_case_globals)]
@@ -12,6 +12,7 @@ use std::fs;
 use std::path::Path;
 
 use log::{debug, trace};
+use serde::Serialize;
 
 use super::index::Index;
 use super::reader::Reader;
@@ -72,7 +73,7 @@ pub fn debug_dump(reader: &mut Reader, output_dir: &Path) -> Result<()> {
             trace!("  🎰 {} slots defined", metadata.slots.len());
         }
         Err(e) => {
-            debug!("❌ Failed to parse metadata: {e}");
+            debug!("❌ Failed to parse metadata: {e:#}");
         }
     }
 
@@ -186,23 +187,46 @@ fn analyze_slots(reader: &mut Reader, output_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Identify slot content type
-fn identify_slot_content(slot_index: usize, preview: &[u8]) {
+/// Content type recognised from a slot's leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlotContent {
+    Gzip,
+    Tar,
+    Zip,
+    Json,
+    Unknown,
+}
+
+/// Recognise slot content from its first bytes (512 are enough for tar)
+#[must_use]
+pub fn detect_slot_content(preview: &[u8]) -> SlotContent {
     if preview.starts_with(b"\x1f\x8b") {
-        trace!("    🎈 Slot {slot_index} is gzip compressed");
-    } else if preview.starts_with(b"ustar")
-        || (preview.len() > 257 && &preview[257..262] == b"ustar")
-    {
-        trace!("    📦 Slot {slot_index} is a tar archive");
+        SlotContent::Gzip
+    } else if preview.starts_with(b"ustar") || preview.get(257..262) == Some(b"ustar") {
+        SlotContent::Tar
     } else if preview.starts_with(b"PK") {
-        trace!("    🗜️ Slot {slot_index} is a zip file");
+        SlotContent::Zip
     } else if preview.starts_with(b"{") || preview.starts_with(b"[") {
-        trace!("    📄 Slot {slot_index} looks like JSON (unexpected!)");
-        debug!(
-            "    ⚠️ Preview: {}",
-            String::from_utf8_lossy(&preview[..50.min(preview.len())])
-        );
+        SlotContent::Json
     } else {
-        trace!("    📄 Slot {slot_index} has unknown format");
+        SlotContent::Unknown
+    }
+}
+
+/// Identify slot content type
+fn identify_slot_content(slot_index: usize, preview: &[u8]) {
+    match detect_slot_content(preview) {
+        SlotContent::Gzip => trace!("    🎈 Slot {slot_index} is gzip compressed"),
+        SlotContent::Tar => trace!("    📦 Slot {slot_index} is a tar archive"),
+        SlotContent::Zip => trace!("    🗜️ Slot {slot_index} is a zip file"),
+        SlotContent::Json => {
+            trace!("    📄 Slot {slot_index} looks like JSON (unexpected!)");
+            debug!(
+                "    ⚠️ Preview: {}",
+                String::from_utf8_lossy(&preview[..50.min(preview.len())])
+            );
+        }
+        SlotContent::Unknown => trace!("    📄 Slot {slot_index} has unknown format"),
     }
 }
@@ -33,19 +33,59 @@ pub const DEFAULT_FILE_PERMS: u16 = 0o600; // Read/write for owner only
 pub const DEFAULT_EXECUTABLE_PERMS: u16 = 0o700; // Read/write/execute for owner only
 pub const DEFAULT_DIR_PERMS: u16 = 0o700; // Read/write/execute for owner only
 
+/// Mode for an extracted file under an optional extraction umask
+///
+/// `explicit` is a mode the manifest set; `None` uses the builder default
+/// `default` (0600, or 0700 for executables). Without a umask the mode applies
+/// unchanged. With one, standard umask rules are used: an explicit mode is
+/// only masked, never widened, while a default has its owner bits requested
+/// for group and others too before masking, so 0600 becomes `0666 & !umask`
+/// and 0700 becomes `0777 & !umask`.
+pub fn extraction_file_mode(explicit: Option<u32>, default: u32, umask: Option<u32>) -> u32 {
+    apply_umask(explicit, default, umask)
+}
+
+/// Mode for a workenv directory under an optional extraction umask (default 0700)
+pub fn extraction_dir_mode(explicit: Option<u32>, umask: Option<u32>) -> u32 {
+    apply_umask(explicit, u32::from(DEFAULT_DIR_PERMS), umask)
+}
+
+fn apply_umask(explicit: Option<u32>, default: u32, umask: Option<u32>) -> u32 {
+    let Some(umask) = umask else {
+        return explicit.unwrap_or(default);
+    };
+    if let Some(mode) = explicit {
+        return mode & !umask;
+    }
+    let owner = default & 0o700;
+    ((default & 0o7000) | owner | (owner >> 3) | (owner >> 6)) & !umask
+}
+
+/// Parse an octal umask such as `022` or `0027`
+pub fn parse_umask(value: &str) -> Option<u32> {
+    u32::from_str_radix(value.trim(), 8)
+        .ok()
+        .filter(|&umask| umask <= 0o777)
+}
+
 // =================================
 // Disk and memory defaults
 // =================================
 pub const DEFAULT_DISK_SPACE_MULTIPLIER: u64 = 2; // Require 2x compressed size for extraction
+pub const DEFAULT_WORKENV_SAMPLE_FILES: usize = 4; // Files hashed by sampled workenv verification
 pub const DEFAULT_MAX_MEMORY: u64 = 128 * 1024 * 1024; // 128MB
+pub const DEFAULT_DECOMPRESSION_MARGIN: u64 = 64 * 1024; // Slack over a slot's recorded original_size
+pub const DEFAULT_TRAILER_SEARCH_WINDOW: u64 = 1024 * 1024; // End of file searched for a trailer with bytes after it
 pub const DEFAULT_MIN_MEMORY: u64 = 8 * 1024 * 1024; // 8MB
 pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024; // 64KB for streaming
+pub const DEFAULT_IO_BUFFER_SIZE: usize = 8 * 1024 * 1024; // 8MB read buffer for hashing slots
 
 // =================================
 // Path constants
 // =================================
 pub const DEFAULT_PSPF_HIDDEN_PREFIX: &str = ".";
 pub const DEFAULT_PSPF_SUFFIX: &str = ".pspf";
+pub const DEFAULT_DETACHED_DATA_SUFFIX: &str = ".pspfdata"; // Sibling data file of a launcher stub
 pub const DEFAULT_INSTANCE_DIR: &str = "instance";
 pub const DEFAULT_PACKAGE_DIR: &str = "package";
 pub const DEFAULT_TMP_DIR: &str = "tmp";
@@ -56,6 +96,8 @@ pub const DEFAULT_COMPLETE_FILE: &str = "complete";
 pub const DEFAULT_PACKAGE_CHECKSUM_FILE: &str = "package.checksum";
 pub const DEFAULT_PSP_METADATA_FILE: &str = "psp.json";
 pub const DEFAULT_INDEX_METADATA_FILE: &str = "index.json";
+pub const DEFAULT_VERIFIED_SLOTS_FILE: &str = "verified_slots.json";
+pub const DEFAULT_VERIFY_KEY_FILE: &str = ".verify-key"; // Per-user key signing verification records
 pub const DEFAULT_CACHE_SUBDIR: &str = ".cache/flavor/workenv";
 
 // =================================
@@ -120,6 +162,15 @@ pub const ACCESS_HINT_RANDOM: u8 = 1; // Random access pattern
 pub const ACCESS_HINT_ONCE: u8 = 2; // Access once then discard
 pub const ACCESS_HINT_PREFETCH: u8 = 3; // Prefetch next slot
 
+// =================================
+// Index flags
+// =================================
+pub const INDEX_FLAG_CRC32C: u32 = 1 << 0; // index_checksum is CRC32C instead of Adler-32
+pub const INDEX_FLAG_LAUNCHER_HASH: u32 = 1 << 1; // reserved[0..32] holds SHA-256 of the launcher
+pub const INDEX_FLAG_ATTESTATION: u32 = 1 << 2; // reserved[32..36] length + reserved[36..] transparency log entry
+pub const INDEX_FLAG_FILE_CRC: u32 = 1 << 3; // padding1[0..4] holds a CRC32C of the whole package file
+pub const INDEX_FLAG_METADATA_RAW: u32 = 1 << 4; // metadata is stored as plain JSON rather than gzip
+
 // =================================
 // Capability flags
 // =================================
@@ -164,6 +215,12 @@ pub const DEFAULT_PACKAGE_AUTHOR: &str = "Unknown";
 // =================================
 pub const DEFAULT_EXTRACT_VERIFY: bool = true;
 pub const DEFAULT_EXTRACT_OVERWRITE: bool = false;
+pub const DEFAULT_EXTRACTION_WAIT_TIMEOUT_SECS: u64 = 60; // Wait for another process's extraction
+pub const EXTRACTION_WAIT_INITIAL_BACKOFF_MS: u64 = 10;
+pub const EXTRACTION_WAIT_MAX_BACKOFF_MS: u64 = 500;
+pub const DEFAULT_EXTRACTION_RETRIES: u32 = 2; // Re-extractions after a failed post-extraction check
+pub const EXTRACTION_RETRY_BACKOFF_MS: u64 = 250; // Doubles with each retry
+pub const KEPT_TEMP_RETENTION_SECS: u64 = 7 * 24 * 60 * 60; // FLAVOR_KEEP_TEMP directories
 
 // =================================
 // Launcher defaults
@@ -0,0 +1,127 @@
+//! Detached package data
+//!
+//! A split package is a plain launcher stub plus a sibling data file holding
+//! the PSPF index, metadata and slots (laid out as a package with an empty
+//! launcher region). The data file for an executable is resolved in order:
+//!
+//! 1. The executable itself, when it ends with a MagicTrailer.
+//! 2. `FLAVOR_DATA_FILE`, relative paths resolved against the executable's
+//!    directory; it must exist.
+//! 3. `<stem><suffix>` next to the executable, where the suffix is
+//!    `FLAVOR_DATA_SUFFIX` or `.pspfdata` (`app.exe` → `app.pspfdata`).
+//!
+//! If none of these apply the executable is returned unchanged, so the reader
+//! reports the usual missing-trailer error for it.
+
+use log::{debug, trace};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::constants::MAGIC_WAND_EMOJI_BYTES;
+use super::defaults::DEFAULT_DETACHED_DATA_SUFFIX;
+use crate::exceptions::{FlavorError, Result};
+
+/// Suffix of detached data files, honouring `FLAVOR_DATA_SUFFIX`
+pub fn data_suffix() -> String {
+    std::env::var("FLAVOR_DATA_SUFFIX")
+        .ok()
+        .filter(|suffix| !suffix.is_empty())
+        .unwrap_or_else(|| DEFAULT_DETACHED_DATA_SUFFIX.to_string())
+}
+
+/// Sibling data file path for a launcher stub
+pub fn detached_data_path(exe_path: &Path, suffix: &str) -> PathBuf {
+    let stem = exe_path.file_stem().unwrap_or(exe_path.as_os_str());
+    let mut name = stem.to_os_string();
+    name.push(suffix);
+    exe_path.with_file_name(name)
+}
+
+/// Resolve the file holding the PSPF data for `exe_path`
+///
+/// See the module docs for the resolution order.
+pub fn resolve_data_path(exe_path: &Path) -> Result<PathBuf> {
+    if has_trailer(exe_path) {
+        trace!("📦 {} carries its own PSPF data", exe_path.display());
+        return Ok(exe_path.to_path_buf());
+    }
+
+    if let Ok(explicit) = std::env::var("FLAVOR_DATA_FILE") {
+        let explicit = PathBuf::from(explicit);
+        let path = match exe_path.parent() {
+            Some(dir) if explicit.is_relative() => dir.join(explicit),
+            _ => explicit,
+        };
+        if !path.is_file() {
+            return Err(FlavorError::ConfigError(format!(
+                "FLAVOR_DATA_FILE points to a missing data file: {}",
+                path.display()
+            )));
+        }
+        debug!(
+            "📎 Using data file from FLAVOR_DATA_FILE: {}",
+            path.display()
+        );
+        return Ok(path);
+    }
+
+    let sibling = detached_data_path(exe_path, &data_suffix());
+    if sibling.is_file() {
+        debug!("📎 Using detached data file: {}", sibling.display());
+        return Ok(sibling);
+    }
+
+    trace!(
+        "No MagicTrailer in {} and no detached data at {}",
+        exe_path.display(),
+        sibling.display()
+    );
+    Ok(exe_path.to_path_buf())
+}
+
+/// Whether the file ends with the MagicTrailer's closing 🪄
+fn has_trailer(path: &Path) -> bool {
+    let mut tail = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| {
+            file.seek(SeekFrom::End(-(tail.len() as i64)))?;
+            file.read_exact(&mut tail)
+        })
+        .is_ok_and(|()| tail == MAGIC_WAND_EMOJI_BYTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detached_data_path_replaces_extension() {
+        assert_eq!(
+            detached_data_path(Path::new("/opt/app.exe"), ".pspfdata"),
+            PathBuf::from("/opt/app.pspfdata")
+        );
+        assert_eq!(
+            detached_data_path(Path::new("bin/tool"), ".data"),
+            PathBuf::from("bin/tool.data")
+        );
+    }
+
+    #[test]
+    fn test_resolution_prefers_embedded_data() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let exe = dir.path().join("app");
+        let data = dir.path().join("app.pspfdata");
+
+        assert!(std::fs::write(&exe, b"stub").is_ok());
+        assert!(resolve_data_path(&exe).is_ok_and(|p| p == exe));
+
+        assert!(std::fs::write(&data, MAGIC_WAND_EMOJI_BYTES).is_ok());
+        assert!(resolve_data_path(&exe).is_ok_and(|p| p == data));
+
+        assert!(std::fs::write(&exe, MAGIC_WAND_EMOJI_BYTES).is_ok());
+        assert!(resolve_data_path(&exe).is_ok_and(|p| p == exe));
+    }
+}
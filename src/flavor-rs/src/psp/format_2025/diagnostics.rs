@@ -0,0 +1,149 @@
+//! Whole-package diagnostics that collect every problem instead of failing fast
+
+use super::metadata::Metadata;
+use crate::exceptions::{FlavorError, Result};
+use serde::Serialize;
+use std::fmt;
+
+/// Outcome of a single diagnostic check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    Failed(String),
+    /// The check could not run because an earlier stage failed
+    Skipped(String),
+}
+
+impl CheckStatus {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, CheckStatus::Failed(_))
+    }
+}
+
+/// A problem with one slot descriptor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DescriptorIssue {
+    pub slot: usize,
+    pub offset: u64,
+    pub problem: String,
+}
+
+/// Every problem found in a package, gathered in one pass
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageDiagnostics {
+    /// MagicTrailer bookends and index parsing
+    pub trailer: CheckStatus,
+    /// Format version recorded in the index, if it could be read
+    pub format_version: Option<u32>,
+    pub index_checksum: CheckStatus,
+    pub metadata_checksum: CheckStatus,
+    /// Metadata decompression and JSON parsing
+    pub metadata: CheckStatus,
+    pub descriptors: Vec<DescriptorIssue>,
+    /// Index `slot_count` vs parsed descriptors vs metadata slots
+    pub slot_count: CheckStatus,
+}
+
+impl PackageDiagnostics {
+    /// Diagnostics for a package whose index could not be read at all
+    pub(crate) fn index_unavailable(reason: String) -> Self {
+        let skipped = || CheckStatus::Skipped("index unavailable".to_string());
+        Self {
+            trailer: CheckStatus::Failed(reason),
+            format_version: None,
+            index_checksum: skipped(),
+            metadata_checksum: skipped(),
+            metadata: skipped(),
+            descriptors: Vec::new(),
+            slot_count: skipped(),
+        }
+    }
+
+    /// True when no check failed
+    pub fn is_healthy(&self) -> bool {
+        self.problems().is_empty()
+    }
+
+    /// Human-readable list of every failure
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, status) in self.checks() {
+            if let CheckStatus::Failed(reason) = status {
+                problems.push(format!("{name}: {reason}"));
+            }
+        }
+        for issue in &self.descriptors {
+            problems.push(format!(
+                "descriptor {} at {:#x}: {}",
+                issue.slot, issue.offset, issue.problem
+            ));
+        }
+        problems
+    }
+
+    fn checks(&self) -> [(&'static str, &CheckStatus); 5] {
+        [
+            ("index", &self.trailer),
+            ("index checksum", &self.index_checksum),
+            ("metadata checksum", &self.metadata_checksum),
+            ("metadata", &self.metadata),
+            ("slot count", &self.slot_count),
+        ]
+    }
+}
+
+impl fmt::Display for PackageDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, status) in self.checks() {
+            match status {
+                CheckStatus::Passed => writeln!(f, "  ✓ {name}")?,
+                CheckStatus::Failed(reason) => writeln!(f, "  ✗ {name}: {reason}")?,
+                CheckStatus::Skipped(reason) => writeln!(f, "  - {name}: skipped ({reason})")?,
+            }
+            // Only set once the index parsed, so it follows the index line
+            if let (Some(version), "index") = (self.format_version, name) {
+                writeln!(f, "  ✓ Format version: {version:04x}")?;
+            }
+        }
+        if self.descriptors.is_empty() {
+            writeln!(f, "  ✓ slot descriptors")?;
+        }
+        for issue in &self.descriptors {
+            writeln!(
+                f,
+                "  ✗ descriptor {} at {:#x}: {}",
+                issue.slot, issue.offset, issue.problem
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode metadata bytes (gzip or plain JSON) without checksum enforcement
+pub(crate) fn decode_metadata(data: &[u8]) -> Result<Metadata> {
+    if data.starts_with(b"\x1f\x8b") {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let mut json_data = String::new();
+        GzDecoder::new(data).read_to_string(&mut json_data)?;
+        Metadata::from_json(&json_data)
+    } else {
+        let json_str = std::str::from_utf8(data)
+            .map_err(|e| FlavorError::FormatError(format!("Invalid UTF-8: {e}")))?;
+        Metadata::from_json(json_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unavailable_index_reports_single_problem() {
+        let diagnostics = PackageDiagnostics::index_unavailable("file too small".to_string());
+        assert!(!diagnostics.is_healthy());
+        assert_eq!(diagnostics.problems(), vec!["index: file too small"]);
+    }
+}
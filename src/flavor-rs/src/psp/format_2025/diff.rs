@@ -0,0 +1,247 @@
+//! Structural comparison of two PSPF/2025 packages
+//!
+//! Compares metadata and slots rather than bytes, so packages that differ only
+//! in build timestamps or signatures compare equal.
+
+use super::metadata::{Metadata, SlotMetadata};
+use super::reader::Reader;
+use crate::exceptions::Result;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+
+/// A single field that differs between two packages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl FieldChange {
+    fn new(field: impl Into<String>, old: Option<String>, new: Option<String>) -> Self {
+        Self {
+            field: field.into(),
+            old,
+            new,
+        }
+    }
+}
+
+/// How a slot differs between two packages (matched by id)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SlotChange {
+    Added {
+        id: String,
+    },
+    Removed {
+        id: String,
+    },
+    Changed {
+        id: String,
+        fields: Vec<FieldChange>,
+    },
+}
+
+/// Differences between two packages
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PackageDiff {
+    pub metadata: Vec<FieldChange>,
+    pub slots: Vec<SlotChange>,
+    pub launcher: Vec<FieldChange>,
+}
+
+impl PackageDiff {
+    /// True if the packages are equivalent
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty() && self.slots.is_empty() && self.launcher.is_empty()
+    }
+}
+
+/// Push a change if the two values differ
+fn compare<T: PartialEq + ToString>(changes: &mut Vec<FieldChange>, field: &str, old: T, new: T) {
+    if old != new {
+        changes.push(FieldChange::new(
+            field,
+            Some(old.to_string()),
+            Some(new.to_string()),
+        ));
+    }
+}
+
+fn diff_metadata(a: &Metadata, b: &Metadata) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    compare(
+        &mut changes,
+        "package.name",
+        &a.package.name,
+        &b.package.name,
+    );
+    compare(
+        &mut changes,
+        "package.version",
+        &a.package.version,
+        &b.package.version,
+    );
+    compare(
+        &mut changes,
+        "execution.command",
+        &a.execution.command,
+        &b.execution.command,
+    );
+
+    let keys: BTreeSet<_> = a
+        .execution
+        .env
+        .keys()
+        .chain(b.execution.env.keys())
+        .collect();
+    for key in keys {
+        let old = a.execution.env.get(key).cloned();
+        let new = b.execution.env.get(key).cloned();
+        if old != new {
+            changes.push(FieldChange::new(format!("execution.env.{key}"), old, new));
+        }
+    }
+
+    changes
+}
+
+fn diff_slot(a: &SlotMetadata, b: &SlotMetadata) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    compare(&mut changes, "checksum", &a.checksum, &b.checksum);
+    compare(&mut changes, "size", a.size, b.size);
+    compare(&mut changes, "operations", &a.operations, &b.operations);
+    compare(&mut changes, "target", &a.target, &b.target);
+    compare(&mut changes, "purpose", &a.purpose, &b.purpose);
+    compare(&mut changes, "lifecycle", &a.lifecycle, &b.lifecycle);
+    changes
+}
+
+fn diff_slots(a: &[SlotMetadata], b: &[SlotMetadata]) -> Vec<SlotChange> {
+    let mut changes = Vec::new();
+
+    for old in a {
+        match b.iter().find(|slot| slot.id == old.id) {
+            None => changes.push(SlotChange::Removed { id: old.id.clone() }),
+            Some(new) => {
+                let fields = diff_slot(old, new);
+                if !fields.is_empty() {
+                    changes.push(SlotChange::Changed {
+                        id: old.id.clone(),
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+
+    for new in b
+        .iter()
+        .filter(|slot| !a.iter().any(|old| old.id == slot.id))
+    {
+        changes.push(SlotChange::Added { id: new.id.clone() });
+    }
+
+    changes
+}
+
+fn diff_launcher(a: &Metadata, b: &Metadata) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    match (&a.launcher, &b.launcher) {
+        (Some(old), Some(new)) => {
+            compare(&mut changes, "launcher.tool", &old.tool, &new.tool);
+            compare(
+                &mut changes,
+                "launcher.tool_version",
+                &old.tool_version,
+                &new.tool_version,
+            );
+            compare(&mut changes, "launcher.size", old.size, new.size);
+            compare(
+                &mut changes,
+                "launcher.checksum",
+                &old.checksum,
+                &new.checksum,
+            );
+        }
+        (None, None) => {}
+        (old, new) => changes.push(FieldChange::new(
+            "launcher",
+            old.as_ref().map(|l| l.tool.clone()),
+            new.as_ref().map(|l| l.tool.clone()),
+        )),
+    }
+    changes
+}
+
+/// Compare the metadata of two packages
+pub fn diff_metadata_pair(a: &Metadata, b: &Metadata) -> PackageDiff {
+    PackageDiff {
+        metadata: diff_metadata(a, b),
+        slots: diff_slots(&a.slots, &b.slots),
+        launcher: diff_launcher(a, b),
+    }
+}
+
+/// Compare two PSPF/2025 packages
+pub fn diff(a: &Path, b: &Path) -> Result<PackageDiff> {
+    let a_metadata = Reader::new(a)?.read_metadata()?.clone();
+    let b_metadata = Reader::new(b)?.read_metadata()?.clone();
+    Ok(diff_metadata_pair(&a_metadata, &b_metadata))
+}
+
+fn fmt_value(value: Option<&String>) -> &str {
+    value.map_or("(none)", String::as_str)
+}
+
+fn fmt_fields(f: &mut fmt::Formatter<'_>, indent: &str, fields: &[FieldChange]) -> fmt::Result {
+    for change in fields {
+        writeln!(
+            f,
+            "{indent}{}: {} -> {}",
+            change.field,
+            fmt_value(change.old.as_ref()),
+            fmt_value(change.new.as_ref())
+        )?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for PackageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences");
+        }
+
+        if !self.metadata.is_empty() {
+            writeln!(f, "Metadata:")?;
+            fmt_fields(f, "  ~ ", &self.metadata)?;
+        }
+
+        if !self.slots.is_empty() {
+            writeln!(f, "Slots:")?;
+            for change in &self.slots {
+                match change {
+                    SlotChange::Added { id } => writeln!(f, "  + {id}")?,
+                    SlotChange::Removed { id } => writeln!(f, "  - {id}")?,
+                    SlotChange::Changed { id, fields } => {
+                        writeln!(f, "  ~ {id}")?;
+                        fmt_fields(f, "      ", fields)?;
+                    }
+                }
+            }
+        }
+
+        if !self.launcher.is_empty() {
+            writeln!(f, "Launcher:")?;
+            fmt_fields(f, "  ~ ", &self.launcher)?;
+        }
+
+        Ok(())
+    }
+}
+
+// 📦🔍📋🪄
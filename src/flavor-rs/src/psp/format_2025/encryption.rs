@@ -0,0 +1,375 @@
+//! Slot encryption
+//!
+//! When `index.encryption_type` is set, every stored slot is sealed after its
+//! operation chain has been applied: a fresh random base nonce, then the slot
+//! split into [`SEAL_CHUNK_SIZE`] chunks, each stored as AEAD ciphertext with
+//! its tag. A chunk's nonce is the base nonce with its index XORed into the
+//! last four bytes. The associated data binds the descriptor's slot id, the
+//! chunk index and whether it is the final chunk, so slots can't be swapped
+//! between positions and chunks can't be reordered or truncated. Descriptor
+//! checksums cover the sealed bytes, which keeps `verify` working without the key.
+
+use crate::exceptions::{FlavorError, Result};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::RngCore;
+use std::fmt;
+use std::io::{Read, Write};
+
+/// `index.encryption_type` for unencrypted packages
+pub const ENCRYPTION_NONE: u8 = 0;
+/// `index.encryption_type` for AES-256-GCM
+pub const ENCRYPTION_AES256_GCM: u8 = 1;
+
+/// Nonce bytes stored in front of each sealed slot
+pub const NONCE_SIZE: usize = 12;
+/// Authentication tag bytes appended to each sealed chunk
+pub const TAG_SIZE: usize = 16;
+/// Plaintext bytes sealed per chunk; only the final chunk may be shorter
+pub const SEAL_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A 256-bit slot encryption key
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Parse a key written as 64 hex characters (surrounding whitespace ignored)
+    pub fn from_hex(hex_key: &str) -> Result<Self> {
+        let bytes = hex::decode(hex_key.trim())
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                FlavorError::InvalidKey(
+                    "Encryption key must be 32 bytes written as 64 hex characters".to_string(),
+                )
+            })?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(<redacted>)")
+    }
+}
+
+/// Cipher used for slot encryption
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EncryptionAlgorithm {
+    Aes256Gcm,
+}
+
+impl EncryptionAlgorithm {
+    /// Algorithm recorded as `encryption_type` in the index, if known
+    pub fn from_type(encryption_type: u8) -> Option<Self> {
+        match encryption_type {
+            ENCRYPTION_AES256_GCM => Some(Self::Aes256Gcm),
+            _ => None,
+        }
+    }
+
+    /// Value for `index.encryption_type`
+    pub fn encryption_type(self) -> u8 {
+        match self {
+            Self::Aes256Gcm => ENCRYPTION_AES256_GCM,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Aes256Gcm => "aes256-gcm",
+        }
+    }
+
+    /// Create the cipher for `key`
+    pub fn encryptor(self, key: &EncryptionKey) -> Box<dyn Encryptor> {
+        match self {
+            Self::Aes256Gcm => Box::new(Aes256GcmEncryptor::new(key)),
+        }
+    }
+}
+
+impl std::str::FromStr for EncryptionAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "aes256-gcm" | "aes-256-gcm" | "aes256gcm" => Ok(Self::Aes256Gcm),
+            _ => Err(format!(
+                "unknown encryption algorithm '{s}' (expected aes256-gcm)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for EncryptionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Slot encryption requested for a build
+#[derive(Debug, Clone)]
+pub struct SlotEncryption {
+    pub algorithm: EncryptionAlgorithm,
+    pub key: EncryptionKey,
+}
+
+/// An AEAD cipher with 96-bit nonces
+///
+/// Implementations only supply the cipher; nonce handling and the sealed slot
+/// layout are shared by [`seal`] and [`open`].
+pub trait Encryptor: fmt::Debug + Send + Sync {
+    fn algorithm(&self) -> EncryptionAlgorithm;
+
+    /// Encrypt `plaintext`, returning the ciphertext followed by the tag
+    fn encrypt(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decrypt ciphertext followed by its tag, failing if authentication fails
+    fn decrypt(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// [`Encryptor`] for AES-256-GCM
+pub struct Aes256GcmEncryptor {
+    cipher: Aes256Gcm,
+}
+
+impl Aes256GcmEncryptor {
+    pub fn new(key: &EncryptionKey) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(&key.0.into()),
+        }
+    }
+}
+
+impl fmt::Debug for Aes256GcmEncryptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Aes256GcmEncryptor")
+    }
+}
+
+impl Encryptor for Aes256GcmEncryptor {
+    fn algorithm(&self) -> EncryptionAlgorithm {
+        EncryptionAlgorithm::Aes256Gcm
+    }
+
+    fn encrypt(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let payload = Payload {
+            msg: plaintext,
+            aad,
+        };
+        self.cipher
+            .encrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| FlavorError::BuildError("AES-256-GCM encryption failed".to_string()))
+    }
+
+    fn decrypt(&self, nonce: &[u8; NONCE_SIZE], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let payload = Payload {
+            msg: ciphertext,
+            aad,
+        };
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| {
+                FlavorError::VerificationFailed(
+                    "Slot decryption failed: wrong key or corrupted data".to_string(),
+                )
+            })
+    }
+}
+
+/// Key from `explicit`, else from `FLAVOR_DECRYPTION_KEY` (64 hex characters)
+pub fn resolve_decryption_key(explicit: Option<&EncryptionKey>) -> Result<Option<EncryptionKey>> {
+    match explicit {
+        Some(key) => Ok(Some(key.clone())),
+        None => std::env::var("FLAVOR_DECRYPTION_KEY")
+            .ok()
+            .map(|hex| EncryptionKey::from_hex(&hex))
+            .transpose(),
+    }
+}
+
+/// Nonce of chunk `chunk` under `base`
+fn chunk_nonce(base: &[u8; NONCE_SIZE], chunk: u32) -> [u8; NONCE_SIZE] {
+    let mut nonce = *base;
+    for (byte, counter) in nonce[NONCE_SIZE - 4..].iter_mut().zip(chunk.to_be_bytes()) {
+        *byte ^= counter;
+    }
+    nonce
+}
+
+/// Associated data binding a sealed chunk to its slot id and position
+fn chunk_aad(slot_id: u64, chunk: u32, last: bool) -> [u8; 13] {
+    let mut aad = [0u8; 13];
+    aad[..8].copy_from_slice(&slot_id.to_le_bytes());
+    aad[8..12].copy_from_slice(&chunk.to_le_bytes());
+    aad[12] = u8::from(last);
+    aad
+}
+
+/// Replace `buf` with the next chunk of `source`, empty once it is exhausted
+fn read_chunk(source: &mut impl Read, buf: &mut Vec<u8>) -> Result<()> {
+    buf.clear();
+    (&mut *source)
+        .take(SEAL_CHUNK_SIZE as u64)
+        .read_to_end(buf)?;
+    Ok(())
+}
+
+/// Encrypt a slot's stored bytes from `source` into `sink` under a fresh
+/// random nonce, one chunk at a time
+///
+/// Returns the number of sealed bytes written.
+pub fn seal_into(
+    encryptor: &dyn Encryptor,
+    slot_id: u64,
+    source: &mut impl Read,
+    sink: &mut impl Write,
+) -> Result<u64> {
+    let mut base = [0u8; NONCE_SIZE];
+    rand::thread_rng().fill_bytes(&mut base);
+    sink.write_all(&base)?;
+    let mut written = NONCE_SIZE as u64;
+
+    let mut current = Vec::with_capacity(SEAL_CHUNK_SIZE);
+    let mut next = Vec::with_capacity(SEAL_CHUNK_SIZE);
+    read_chunk(source, &mut current)?;
+    let mut chunk = 0u32;
+    loop {
+        // An empty chunk after a full one means the full one was the last
+        read_chunk(source, &mut next)?;
+        let last = next.is_empty();
+        let aad = chunk_aad(slot_id, chunk, last);
+        let ciphertext = encryptor.encrypt(&chunk_nonce(&base, chunk), &aad, &current)?;
+        sink.write_all(&ciphertext)?;
+        written += ciphertext.len() as u64;
+        if last {
+            return Ok(written);
+        }
+        std::mem::swap(&mut current, &mut next);
+        chunk = chunk.checked_add(1).ok_or_else(|| {
+            FlavorError::BuildError(format!("Slot {slot_id} is too large to encrypt"))
+        })?;
+    }
+}
+
+/// Encrypt a slot's stored bytes under a fresh random nonce
+pub fn seal(encryptor: &dyn Encryptor, slot_id: u64, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut sealed = Vec::with_capacity(NONCE_SIZE + plaintext.len() + TAG_SIZE);
+    seal_into(encryptor, slot_id, &mut &plaintext[..], &mut sealed)?;
+    Ok(sealed)
+}
+
+/// Decrypt a slot sealed by [`seal`] or [`seal_into`]
+pub fn open(encryptor: &dyn Encryptor, slot_id: u64, sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_SIZE + TAG_SIZE {
+        return Err(FlavorError::VerificationFailed(format!(
+            "Encrypted slot {slot_id} is {} bytes, too short for a nonce and tag",
+            sealed.len()
+        )));
+    }
+    let (base, mut rest) = sealed.split_at(NONCE_SIZE);
+    let base: &[u8; NONCE_SIZE] = base
+        .try_into()
+        .map_err(|_| FlavorError::FormatError("Invalid nonce length".to_string()))?;
+
+    let mut plaintext = Vec::with_capacity(rest.len());
+    let mut chunk = 0u32;
+    loop {
+        let take = rest.len().min(SEAL_CHUNK_SIZE + TAG_SIZE);
+        let last = take == rest.len();
+        if take < TAG_SIZE {
+            return Err(FlavorError::VerificationFailed(format!(
+                "Encrypted slot {slot_id} ends in a truncated chunk"
+            )));
+        }
+        let (ciphertext, remaining) = rest.split_at(take);
+        let aad = chunk_aad(slot_id, chunk, last);
+        let opened = encryptor
+            .decrypt(&chunk_nonce(base, chunk), &aad, ciphertext)
+            .map_err(|e| match e {
+                FlavorError::VerificationFailed(msg) => {
+                    FlavorError::VerificationFailed(format!("Slot {slot_id}: {msg}"))
+                }
+                e => e,
+            })?;
+        plaintext.extend_from_slice(&opened);
+        if last {
+            return Ok(plaintext);
+        }
+        rest = remaining;
+        chunk = chunk.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aes256_gcm_round_trip_and_wrong_key() {
+        let key = EncryptionKey::from_bytes([7u8; 32]);
+        let encryptor = EncryptionAlgorithm::Aes256Gcm.encryptor(&key);
+        let plaintext = b"slot payload".repeat(100);
+
+        let sealed = seal(encryptor.as_ref(), 3, &plaintext);
+        assert!(sealed.is_ok());
+        let Ok(sealed) = sealed else { return };
+        assert_eq!(sealed.len(), plaintext.len() + NONCE_SIZE + TAG_SIZE);
+        assert!(open(encryptor.as_ref(), 3, &sealed).is_ok_and(|p| p == plaintext));
+
+        // Same plaintext, fresh nonce
+        assert!(seal(encryptor.as_ref(), 3, &plaintext).is_ok_and(|again| again != sealed));
+
+        let wrong = EncryptionAlgorithm::Aes256Gcm.encryptor(&EncryptionKey::from_bytes([8; 32]));
+        let err = open(wrong.as_ref(), 3, &sealed);
+        assert!(err.is_err_and(|e| e.to_string().contains("wrong key")));
+
+        // Bound to the slot position
+        assert!(open(encryptor.as_ref(), 4, &sealed).is_err());
+        assert!(open(encryptor.as_ref(), 3, &sealed[..20]).is_err());
+    }
+
+    #[test]
+    fn test_chunked_seal_detects_truncation() {
+        let key = EncryptionKey::from_bytes([9u8; 32]);
+        let encryptor = EncryptionAlgorithm::Aes256Gcm.encryptor(&key);
+
+        for size in [0, SEAL_CHUNK_SIZE, 2 * SEAL_CHUNK_SIZE + 5] {
+            let plaintext: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+            let sealed = seal(encryptor.as_ref(), 1, &plaintext);
+            assert!(sealed.is_ok());
+            let Ok(sealed) = sealed else { return };
+            let chunks = size.div_ceil(SEAL_CHUNK_SIZE).max(1);
+            assert_eq!(sealed.len(), NONCE_SIZE + size + chunks * TAG_SIZE);
+            assert!(open(encryptor.as_ref(), 1, &sealed).is_ok_and(|p| p == plaintext));
+        }
+
+        // Dropping whole trailing chunks is caught by the final-chunk flag
+        let plaintext = vec![7u8; 2 * SEAL_CHUNK_SIZE];
+        let sealed = seal(encryptor.as_ref(), 1, &plaintext);
+        assert!(sealed.is_ok());
+        let Ok(sealed) = sealed else { return };
+        let first_chunk = NONCE_SIZE + SEAL_CHUNK_SIZE + TAG_SIZE;
+        assert!(open(encryptor.as_ref(), 1, &sealed[..first_chunk]).is_err());
+    }
+
+    #[test]
+    fn test_encryption_key_from_hex() {
+        assert!(
+            EncryptionKey::from_hex(&format!("{}\n", "ab".repeat(32)))
+                .is_ok_and(|key| key == EncryptionKey::from_bytes([0xab; 32]))
+        );
+        assert!(EncryptionKey::from_hex("abcd").is_err());
+        assert!(EncryptionKey::from_hex(&"zz".repeat(32)).is_err());
+        assert_eq!(
+            format!("{:?}", EncryptionKey::from_bytes([1; 32])),
+            "EncryptionKey(<redacted>)"
+        );
+    }
+}
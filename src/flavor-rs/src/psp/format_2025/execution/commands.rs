@@ -24,15 +24,15 @@ pub fn execute_setup_commands(
     for (i, cmd_value) in commands.iter().enumerate() {
         debug!("🔧 Executing setup command {}/{}", i + 1, commands.len());
 
-        let cmd_obj = cmd_value
-            .as_object()
-            .ok_or_else(|| FlavorError::Generic("Setup command must be an object".to_string()))?;
+        let cmd_obj = cmd_value.as_object().ok_or_else(|| {
+            FlavorError::ConfigError("Setup command must be an object".to_string())
+        })?;
 
         let cmd_type = cmd_obj
             .get("type")
             .and_then(|v| v.as_str())
             .ok_or_else(|| {
-                FlavorError::Generic("Setup command missing 'type' field".to_string())
+                FlavorError::ConfigError("Setup command missing 'type' field".to_string())
             })?;
 
         match cmd_type {
@@ -41,7 +41,9 @@ pub fn execute_setup_commands(
                     .get("command")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
-                        FlavorError::Generic("Execute command missing 'command' field".to_string())
+                        FlavorError::ConfigError(
+                            "Execute command missing 'command' field".to_string(),
+                        )
                     })?;
 
                 execute_command(command, workenv_dir, package, user_cwd, exec_env)?;
@@ -53,7 +55,7 @@ pub fn execute_setup_commands(
                         .get("command")
                         .and_then(|v| v.as_str())
                         .ok_or_else(|| {
-                            FlavorError::Generic(
+                            FlavorError::ConfigError(
                                 "Enumerate command missing 'command' field".to_string(),
                             )
                         })?;
@@ -62,7 +64,7 @@ pub fn execute_setup_commands(
                     .get("enumerate")
                     .and_then(|v| v.as_object())
                     .ok_or_else(|| {
-                        FlavorError::Generic(
+                        FlavorError::ConfigError(
                             "Enumerate command missing 'enumerate' field".to_string(),
                         )
                     })?;
@@ -71,14 +73,14 @@ pub fn execute_setup_commands(
                     .get("path")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
-                        FlavorError::Generic("Enumerate missing 'path' field".to_string())
+                        FlavorError::ConfigError("Enumerate missing 'path' field".to_string())
                     })?;
 
                 let pattern = enumerate
                     .get("pattern")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
-                        FlavorError::Generic("Enumerate missing 'pattern' field".to_string())
+                        FlavorError::ConfigError("Enumerate missing 'pattern' field".to_string())
                     })?;
 
                 let enum_path = substitute_placeholders(path, workenv_dir, package);
@@ -87,18 +89,16 @@ pub fn execute_setup_commands(
                 debug!("📁 Enumerating files matching: {glob_pattern}");
 
                 for entry in glob(&glob_pattern)
-                    .map_err(|e| FlavorError::Generic(format!("Glob error: {e}")))?
+                    .map_err(|e| FlavorError::ConfigError(format!("Glob error: {e}")))?
                 {
                     match entry {
                         Ok(path) => {
-                            let command = format!("{} {}", base_command, path.display());
-                            execute_command(&command, workenv_dir, package, user_cwd, exec_env)?;
-                        }
-                        Err(e) => {
-                            return Err(FlavorError::Generic(format!(
-                                "Failed to enumerate files: {e}"
-                            )));
+                            // Appended as its own word so paths with spaces stay intact
+                            let mut words = command_words(base_command, workenv_dir, package)?;
+                            words.push(path.display().to_string());
+                            execute_words(&words, workenv_dir, user_cwd, exec_env)?;
                         }
+                        Err(e) => return Err(FlavorError::IoError(e.into_error())),
                     }
                 }
             }
@@ -108,14 +108,16 @@ pub fn execute_setup_commands(
                     .get("path")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
-                        FlavorError::Generic("Write file command missing 'path' field".to_string())
+                        FlavorError::ConfigError(
+                            "Write file command missing 'path' field".to_string(),
+                        )
                     })?;
 
                 let content = cmd_obj
                     .get("content")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
-                        FlavorError::Generic(
+                        FlavorError::ConfigError(
                             "Write file command missing 'content' field".to_string(),
                         )
                     })?;
@@ -142,7 +144,7 @@ pub fn execute_setup_commands(
                             .get("path")
                             .and_then(|v| v.as_str())
                             .ok_or_else(|| {
-                                FlavorError::Generic(
+                                FlavorError::ConfigError(
                                     "chmod command missing 'path' field".to_string(),
                                 )
                             })?;
@@ -215,7 +217,7 @@ pub fn execute_setup_commands(
             }
 
             _ => {
-                return Err(FlavorError::Generic(format!(
+                return Err(FlavorError::ConfigError(format!(
                     "Unknown setup command type: {cmd_type}"
                 )));
             }
@@ -225,6 +227,31 @@ pub fn execute_setup_commands(
     Ok(())
 }
 
+/// Split a command line into words using POSIX shell quoting rules
+///
+/// Only quoting and escapes are interpreted; nothing is expanded and no shell
+/// is involved, so `"{workenv}/my tool" --flag` yields two words.
+pub fn split_command(command: &str) -> Result<Vec<String>> {
+    shlex::split(command).ok_or_else(|| {
+        FlavorError::ConfigError(format!("Unbalanced quoting in command: {command}"))
+    })
+}
+
+/// Split a command and substitute placeholders in each word
+///
+/// Substituting after splitting keeps placeholder values that contain spaces
+/// (such as a workenv under a spaced home directory) in a single word.
+pub(crate) fn command_words(
+    command: &str,
+    workenv_dir: &Path,
+    package: &PackageInfo,
+) -> Result<Vec<String>> {
+    Ok(split_command(command)?
+        .iter()
+        .map(|word| substitute_placeholders(word, workenv_dir, package))
+        .collect())
+}
+
 /// Execute a command
 pub fn execute_command(
     command: &str,
@@ -233,14 +260,22 @@ pub fn execute_command(
     user_cwd: &Path,
     exec_env: &HashMap<String, String>,
 ) -> Result<()> {
-    let command = substitute_placeholders(command, workenv_dir, package);
-    let parts: Vec<_> = command.split_whitespace().collect();
+    let words = command_words(command, workenv_dir, package)?;
+    execute_words(&words, workenv_dir, user_cwd, exec_env)
+}
 
-    if parts.is_empty() {
+/// Run an already-split command
+fn execute_words(
+    words: &[String],
+    workenv_dir: &Path,
+    user_cwd: &Path,
+    exec_env: &HashMap<String, String>,
+) -> Result<()> {
+    let Some((cmd, args)) = words.split_first() else {
         return Ok(());
-    }
-
-    run_command(parts[0], &parts[1..], workenv_dir, user_cwd, exec_env)
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_command(cmd, &args, workenv_dir, user_cwd, exec_env)
 }
 
 /// Run a command with arguments
@@ -296,12 +331,10 @@ pub fn run_command(
             info!("Command stdout:\n{stdout}");
         }
 
-        return Err(FlavorError::Generic(format!(
-            "Command failed with status {}: {}\n{}",
-            output.status.code().unwrap_or(-1),
-            cmd,
-            stderr
-        )));
+        return Err(FlavorError::CommandFailed {
+            status: output.status.code(),
+            message: format!("{cmd}\n{stderr}"),
+        });
     }
 
     Ok(())
@@ -314,12 +347,12 @@ pub fn execute_main_command(
     env: HashMap<String, String>,
     workdir: &Path,
 ) -> Result<i32> {
-    let parts: Vec<_> = command.split_whitespace().collect();
+    let parts = split_command(command)?;
     if parts.is_empty() {
         return Ok(0);
     }
 
-    let resolved_cmd = resolve_executable(parts[0]);
+    let resolved_cmd = resolve_executable(&parts[0]);
     let mut cmd = Command::new(&resolved_cmd);
 
     // Add command arguments
@@ -341,3 +374,52 @@ pub fn execute_main_command(
 
     Ok(status.code().unwrap_or(1))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package() -> PackageInfo {
+        PackageInfo {
+            name: "demo".to_string(),
+            version: "1.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_command_words_keep_spaced_paths_together() {
+        let words = command_words(
+            r#"{workenv}/bin/run --config "{workenv}/my config.toml" 'two words'"#,
+            Path::new("/tmp/my workenv"),
+            &package(),
+        );
+        assert!(words.is_ok_and(|w| w
+            == [
+                "/tmp/my workenv/bin/run",
+                "--config",
+                "/tmp/my workenv/my config.toml",
+                "two words",
+            ]));
+        assert!(split_command(r#"run "unterminated"#).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_command_with_spaced_workenv() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let workenv = dir.path().join("work env");
+        assert!(fs::create_dir_all(&workenv).is_ok());
+
+        let result = execute_command(
+            r#"touch "{workenv}/made it""#,
+            &workenv,
+            &package(),
+            dir.path(),
+            &HashMap::new(),
+        );
+        assert!(result.is_ok());
+        assert!(workenv.join("made it").is_file());
+    }
+}
@@ -5,8 +5,13 @@ mod placeholders;
 mod validation;
 
 // Re-export public API
-pub use commands::{execute_command, execute_main_command, execute_setup_commands, run_command};
-pub use placeholders::substitute_placeholders;
+pub(crate) use commands::command_words;
+pub use commands::{
+    execute_command, execute_main_command, execute_setup_commands, run_command, split_command,
+};
+pub use placeholders::{resolve_slot_target, substitute_placeholders};
+pub(crate) use validation::single_file_slot_targets;
 pub use validation::{
-    IndexMetadata, check_workenv_validity_full, save_index_metadata, save_package_checksum,
+    IndexMetadata, check_workenv_validity_full, modified_workenv_files, save_index_metadata,
+    save_package_checksum,
 };
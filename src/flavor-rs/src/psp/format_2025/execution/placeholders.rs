@@ -1,20 +1,155 @@
 //! Placeholder substitution utilities
+//!
+//! Supported placeholders:
+//! - `{workenv}`: the extracted work environment
+//! - `{package_name}`, `{version}`: from package metadata
+//! - `{home}`: the user's home directory
+//! - `{cache}`: the flavor cache directory (`FLAVOR_CACHE`, `XDG_CACHE_HOME/flavor`, `~/.cache/flavor`)
+//! - `{config}`: the flavor config directory (`XDG_CONFIG_HOME/flavor`, `~/.config/flavor`)
+//!
+//! Text is expanded in a single pass, so substituted values are never expanded
+//! again. `{{` emits a literal `{`, and unknown `{...}` sequences are left as written.
 
 use super::super::metadata::PackageInfo;
+use crate::utils::{get_cache_dir, get_config_dir, get_home_dir};
 use log::warn;
 use std::path::Path;
 
 /// Substitute placeholders in text
 pub fn substitute_placeholders(text: &str, workenv_dir: &Path, package: &PackageInfo) -> String {
-    let workenv_string;
-    let workenv_str = if let Some(s) = workenv_dir.to_str() {
-        s
-    } else {
-        warn!("Work environment path contains non-UTF8 characters, using lossy conversion");
-        workenv_string = workenv_dir.to_string_lossy().into_owned();
-        &workenv_string
+    expand(text, |name| match name {
+        "workenv" => Some(path_to_string(workenv_dir)),
+        "package_name" => Some(package.name.clone()),
+        "version" => Some(package.version.clone()),
+        _ => user_dir(name),
+    })
+}
+
+/// Resolve a slot target to a path relative to the workenv
+///
+/// `{workenv}` prefixes are dropped; `{home}`, `{cache}` and `{config}` expand
+/// to absolute paths, which place the slot outside the workenv.
+pub fn resolve_slot_target(target: &str) -> String {
+    let target = target.replace("{workenv}/", "").replace("{workenv}", "");
+    expand(&target, user_dir)
+}
+
+/// Directory for a user-level placeholder (`home`, `cache`, `config`)
+fn user_dir(name: &str) -> Option<String> {
+    let dir = match name {
+        "home" => {
+            let home = get_home_dir();
+            if home.is_none() {
+                warn!("Cannot resolve {{home}}: no home directory in the environment");
+            }
+            home?
+        }
+        "cache" => get_cache_dir(),
+        "config" => get_config_dir(),
+        _ => return None,
     };
-    text.replace("{workenv}", workenv_str)
-        .replace("{package_name}", &package.name)
-        .replace("{version}", &package.version)
+    Some(path_to_string(&dir))
+}
+
+fn path_to_string(path: &Path) -> String {
+    if let Some(s) = path.to_str() {
+        s.to_string()
+    } else {
+        warn!("Path {path:?} contains non-UTF8 characters, using lossy conversion");
+        path.to_string_lossy().into_owned()
+    }
+}
+
+/// Expand `{name}` placeholders in one left-to-right pass
+fn expand(text: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        if let Some(escaped) = after.strip_prefix('{') {
+            out.push('{');
+            rest = escaped;
+            continue;
+        }
+
+        let replacement = after
+            .find('}')
+            .and_then(|end| lookup(&after[..end]).map(|value| (value, end)));
+        if let Some((value, end)) = replacement {
+            out.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            out.push('{');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package() -> PackageInfo {
+        PackageInfo {
+            name: "demo".to_string(),
+            version: "1.2.3".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_workenv_and_package_placeholders() {
+        let text = substitute_placeholders(
+            "{workenv}/bin/{package_name}-{version}",
+            Path::new("/tmp/we"),
+            &package(),
+        );
+        assert_eq!(text, "/tmp/we/bin/demo-1.2.3");
+    }
+
+    #[test]
+    fn test_user_dir_placeholders() {
+        let workenv = Path::new("/tmp/we");
+        assert_eq!(
+            substitute_placeholders("{cache}/tool", workenv, &package()),
+            format!("{}/tool", get_cache_dir().display())
+        );
+        assert_eq!(
+            substitute_placeholders("{config}/tool.toml", workenv, &package()),
+            format!("{}/tool.toml", get_config_dir().display())
+        );
+        if let Some(home) = get_home_dir() {
+            assert_eq!(
+                substitute_placeholders("{home}/.toolrc", workenv, &package()),
+                format!("{}/.toolrc", home.display())
+            );
+        }
+    }
+
+    #[test]
+    fn test_escaping_and_unknown_placeholders() {
+        let text = substitute_placeholders(
+            "echo {{workenv} ${HOME} {unknown} {{}",
+            Path::new("/tmp/we"),
+            &package(),
+        );
+        assert_eq!(text, "echo {workenv} ${HOME} {unknown} {}");
+
+        // Values are not expanded a second time
+        let text = substitute_placeholders("{workenv}", Path::new("/tmp/{version}"), &package());
+        assert_eq!(text, "/tmp/{version}");
+    }
+
+    #[test]
+    fn test_resolve_slot_target() {
+        assert_eq!(resolve_slot_target("{workenv}/bin/tool"), "bin/tool");
+        assert_eq!(resolve_slot_target("lib"), "lib");
+        assert_eq!(
+            resolve_slot_target("{config}/demo.toml"),
+            format!("{}/demo.toml", get_config_dir().display())
+        );
+    }
 }
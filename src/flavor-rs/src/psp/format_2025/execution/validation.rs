@@ -1,25 +1,45 @@
 //! Validation and checksum management
 
-use super::super::index::Index;
-use super::super::metadata::Metadata;
+use super::super::checksums::{ChecksumAlgorithm, calculate_checksum};
+use super::super::constants::{OP_TAR, SBOM_SLOT_ID};
+use super::super::defaults::{DEFAULT_WORKENV_SAMPLE_FILES, ValidationLevel, get_validation_level};
+use super::super::index::{Index, cache_key};
+use super::super::metadata::{Metadata, SlotMetadata};
+use super::super::operations::{string_to_operations, unpack_operations};
 use super::super::paths::WorkenvPaths;
+use super::placeholders::resolve_slot_target;
+use crate::api::WorkenvVerification;
 use crate::exceptions::{FlavorError, Result};
 use log::{debug, warn};
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::io::BufReader;
+use std::path::Path;
 
 /// Validate package checksum against cached value
+///
+/// A mismatch fails under `Strict` validation unless `heal_on_mismatch` is
+/// set, in which case the cache is reported invalid so it gets re-extracted.
 pub(super) fn validate_package_checksum(
     paths: &WorkenvPaths,
     current_checksum: u32,
+    validation_level: ValidationLevel,
+    heal_on_mismatch: bool,
 ) -> Result<bool> {
     let checksum_path = paths.checksum_file();
 
     // Read stored checksum
     match fs::read_to_string(&checksum_path) {
+        Ok(data) if !is_checksum_record(data.trim()) => {
+            // A truncated or garbled record says nothing about the package
+            debug!("⚠️ Ignoring malformed cached checksum: {:?}", data);
+            Ok(false)
+        }
         Ok(data) => {
             let stored_checksum = data.trim();
-            let current_checksum_str = format!("{:08x}", current_checksum);
+            let current_checksum_str = cache_key(current_checksum);
 
             if stored_checksum == current_checksum_str {
                 debug!(
@@ -29,9 +49,6 @@ pub(super) fn validate_package_checksum(
                 Ok(true)
             } else {
                 // Checksum mismatch - this is a potential security issue
-                use crate::psp::format_2025::defaults::{ValidationLevel, get_validation_level};
-
-                let validation_level = get_validation_level();
                 match validation_level {
                     ValidationLevel::None | ValidationLevel::Minimal => {
                         warn!(
@@ -76,13 +93,20 @@ pub(super) fn validate_package_checksum(
                             current_checksum_str
                         );
                         log::error!("🚨 Cache may be compromised or package has changed");
+                        if heal_on_mismatch {
+                            log::error!(
+                                "🚨 Discarding the cache and re-extracting (heal_on_mismatch)"
+                            );
+                            return Ok(false);
+                        }
                         log::error!(
                             "🚨 Refusing to continue. Set FLAVOR_VALIDATION=relaxed to bypass (NOT RECOMMENDED)"
                         );
-                        Err(FlavorError::Generic(format!(
-                            "package checksum mismatch: cached={}, current={}",
-                            stored_checksum, current_checksum_str
-                        )))
+                        Err(FlavorError::ChecksumMismatch {
+                            subject: "cached workenv package".to_string(),
+                            expected: current_checksum_str.to_string(),
+                            actual: stored_checksum.to_string(),
+                        })
                     }
                 }
             }
@@ -98,15 +122,20 @@ pub(super) fn validate_package_checksum(
     }
 }
 
+/// Whether a stored checksum is a complete record as written by [`save_package_checksum`]
+fn is_checksum_record(stored: &str) -> bool {
+    stored.len() == 8 && stored.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
 /// Save package checksum to cache
 pub fn save_package_checksum(paths: &WorkenvPaths, checksum: u32) -> Result<()> {
     let instance_dir = paths.instance();
     fs::create_dir_all(&instance_dir)?;
 
     let checksum_path = paths.checksum_file();
-    let checksum_str = format!("{:08x}", checksum);
+    let checksum_str = cache_key(checksum);
 
-    fs::write(&checksum_path, &checksum_str)?;
+    crate::utils::write_atomic(&checksum_path, checksum_str.as_bytes())?;
     debug!("💾 Saved package checksum: {}", checksum_str);
 
     Ok(())
@@ -165,7 +194,7 @@ pub fn save_index_metadata(paths: &WorkenvPaths, index: &Index) -> Result<()> {
         slot_table_size,
         slot_count,
         flags,
-        index_checksum: format!("{:08x}", index_checksum_val),
+        index_checksum: cache_key(index_checksum_val),
         metadata_checksum: hex::encode(metadata_checksum),
         build_timestamp,
         page_size,
@@ -183,18 +212,236 @@ pub fn save_index_metadata(paths: &WorkenvPaths, index: &Index) -> Result<()> {
 }
 
 /// Check if work environment is valid using checksums
+///
+/// With `heal_on_mismatch`, a cache extracted from a different build of the
+/// package is invalid rather than an error, even under strict validation.
+/// With `verification`, extracted files that no longer match their slot
+/// checksums also make the cache invalid.
 pub fn check_workenv_validity_full(
     paths: &WorkenvPaths,
     index: &Index,
-    _metadata: &Metadata,
+    metadata: &Metadata,
+    heal_on_mismatch: bool,
+    verification: WorkenvVerification,
 ) -> Result<bool> {
     // First check if extraction is complete
-    let complete_path = paths.complete_file();
-    if !complete_path.exists() {
+    if !super::super::locking::is_extraction_complete(paths) {
         debug!("🔍 No extraction completion marker found");
         return Ok(false);
     }
 
     // Check package checksum
-    validate_package_checksum(paths, index.index_checksum)
+    if !validate_package_checksum(
+        paths,
+        index.index_checksum,
+        get_validation_level(),
+        heal_on_mismatch,
+    )? {
+        return Ok(false);
+    }
+
+    let modified = modified_workenv_files(&paths.workenv(), metadata, verification);
+    if !modified.is_empty() {
+        warn!(
+            "⚠️ Extracted files no longer match the package: {}",
+            modified.join(", ")
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Single-file slots with a relative target, paired with that target
+///
+/// Archive slots unpack into the workenv root rather than their target, init
+/// slots are removed after setup, and conditional slots may never have been
+/// extracted, so none of them can be checked by path.
+pub(crate) fn single_file_slot_targets(metadata: &Metadata) -> Vec<(&SlotMetadata, String)> {
+    metadata
+        .slots
+        .iter()
+        .filter(|slot| {
+            slot.id != SBOM_SLOT_ID
+                && slot.lifecycle != "init"
+                && slot.self_ref != Some(true)
+                && slot.condition.is_none()
+                && string_to_operations(&slot.operations)
+                    .is_ok_and(|packed| !unpack_operations(packed).contains(&OP_TAR))
+        })
+        .map(|slot| (slot, resolve_slot_target(&slot.target)))
+        .filter(|(_, target)| Path::new(target).is_relative())
+        .collect()
+}
+
+/// Targets in `workenv` whose contents differ from their slot checksum
+///
+/// `Sample` compares every file's size but hashes only a few; `Full` hashes
+/// them all. Only raw slots are checked, since the slot checksum and size
+/// describe the stored bytes, which for a compressed slot aren't what was
+/// extracted. Targets written by more than one slot are skipped, since which
+/// slot won depends on the collision policy, as are files directly in `bin/`,
+/// whose shebangs the launcher rewrites after extraction.
+pub fn modified_workenv_files(
+    workenv: &Path,
+    metadata: &Metadata,
+    verification: WorkenvVerification,
+) -> Vec<String> {
+    if verification == WorkenvVerification::Off {
+        return Vec::new();
+    }
+
+    let targets = single_file_slot_targets(metadata);
+    let mut claims: HashMap<&str, usize> = HashMap::new();
+    for (_, target) in &targets {
+        *claims.entry(target.as_str()).or_default() += 1;
+    }
+    let mut checked: Vec<_> = targets
+        .iter()
+        .filter(|(slot, target)| {
+            claims.get(target.as_str()) == Some(&1)
+                && slot.inherited.is_none()
+                && slot.operations.is_empty()
+                && Path::new(target).parent() != Some(Path::new("bin"))
+                && slot.checksum.starts_with("sha256:")
+        })
+        .collect();
+
+    let mut modified = Vec::new();
+    if verification == WorkenvVerification::Sample {
+        checked.retain(|(slot, target)| {
+            let size_matches =
+                fs::metadata(workenv.join(target)).is_ok_and(|file| file.len() == slot.size as u64);
+            if !size_matches {
+                modified.push(target.clone());
+            }
+            size_matches
+        });
+        checked.shuffle(&mut rand::thread_rng());
+        checked.truncate(DEFAULT_WORKENV_SAMPLE_FILES);
+    }
+    debug!("🔍 Hashing {} extracted files", checked.len());
+
+    for (slot, target) in checked {
+        let checksum = fs::File::open(workenv.join(target))
+            .and_then(|file| calculate_checksum(BufReader::new(file), ChecksumAlgorithm::Sha256));
+        if !checksum.is_ok_and(|checksum| checksum == slot.checksum) {
+            modified.push(target.clone());
+        }
+    }
+    modified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::locking::{is_extraction_complete, mark_extraction_complete};
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_partial_markers_are_treated_as_absent() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let paths = WorkenvPaths::new(dir.path().to_path_buf(), Path::new("demo.psp"));
+
+        let strict =
+            |checksum| validate_package_checksum(&paths, checksum, ValidationLevel::Strict, false);
+
+        assert!(save_package_checksum(&paths, 0x1234_abcd).is_ok());
+        assert!(strict(0x1234_abcd).is_ok_and(|valid| valid));
+        // A record cut short mid-write must not be compared, even under strict validation
+        assert!(fs::write(paths.checksum_file(), "1234").is_ok());
+        assert!(strict(0x1234_abcd).is_ok_and(|valid| !valid));
+
+        assert!(mark_extraction_complete(&paths).is_ok());
+        assert!(is_extraction_complete(&paths));
+        assert!(fs::write(paths.complete_file(), "").is_ok());
+        assert!(!is_extraction_complete(&paths));
+    }
+
+    #[test]
+    fn test_modified_workenv_files() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let workenv = dir.path();
+
+        let slot = |id: &str, target: &str, operations: &str, contents: &[u8]| {
+            serde_json::json!({"slot": 0, "id": id, "source": target, "target": target,
+                "size": contents.len(),
+                "checksum": format!("sha256:{}", hex::encode(<sha2::Sha256 as sha2::Digest>::digest(contents))),
+                "operations": operations, "purpose": "data", "lifecycle": "runtime"})
+        };
+        let metadata = serde_json::json!({
+            "format": "PSPF/2025",
+            "package": {"name": "deep", "version": "1.0"},
+            "slots": [slot("app", "{workenv}/bin/app", "", b"#!/bin/sh\n"),
+                      slot("data", "data.txt", "", b"original"),
+                      slot("conf", "conf.txt", "gzip", b"compressed"),
+                      slot("lib", "lib.tar", "tar|gzip", b"archive")],
+            "execution": {"primary_slot": 0, "command": "{workenv}/bin/app"}
+        });
+        let metadata = serde_json::from_value::<Metadata>(metadata);
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else { return };
+        assert!(fs::create_dir_all(workenv.join("bin")).is_ok());
+        assert!(fs::write(workenv.join("bin/app"), b"#!/bin/sh\n").is_ok());
+        assert!(fs::write(workenv.join("data.txt"), b"original").is_ok());
+        // Compressed slots' checksums describe the stored bytes, not these
+        assert!(fs::write(workenv.join("conf.txt"), b"decompressed").is_ok());
+
+        for mode in [WorkenvVerification::Sample, WorkenvVerification::Full] {
+            assert!(modified_workenv_files(workenv, &metadata, mode).is_empty());
+        }
+
+        // Same size, different contents: only a full check is sure to notice
+        assert!(fs::write(workenv.join("data.txt"), b"tampered").is_ok());
+        assert_eq!(
+            modified_workenv_files(workenv, &metadata, WorkenvVerification::Full),
+            vec!["data.txt"]
+        );
+        assert!(modified_workenv_files(workenv, &metadata, WorkenvVerification::Off).is_empty());
+
+        // Size changes are always caught by sampling
+        assert!(fs::write(workenv.join("data.txt"), b"truncated").is_ok());
+        let sampled = modified_workenv_files(workenv, &metadata, WorkenvVerification::Sample);
+        assert_eq!(sampled, vec!["data.txt"]);
+
+        // Scripts in bin/ have their shebangs rewritten, so they aren't checked
+        assert!(fs::write(workenv.join("bin/app"), b"#!/opt/workenv/bin/sh\n").is_ok());
+        assert_eq!(
+            modified_workenv_files(workenv, &metadata, WorkenvVerification::Full),
+            vec!["data.txt"]
+        );
+    }
+
+    #[test]
+    fn test_heal_on_mismatch_invalidates_instead_of_failing() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let paths = WorkenvPaths::new(dir.path().to_path_buf(), Path::new("demo.psp"));
+        assert!(save_package_checksum(&paths, 0x1234_abcd).is_ok());
+
+        let rebuilt = 0x5678_ef01;
+        assert!(
+            validate_package_checksum(&paths, rebuilt, ValidationLevel::Strict, false).is_err()
+        );
+        assert!(
+            validate_package_checksum(&paths, rebuilt, ValidationLevel::Strict, true)
+                .is_ok_and(|valid| !valid)
+        );
+    }
+
+    #[test]
+    fn test_cache_key_matches_saved_checksum() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let paths = WorkenvPaths::new(dir.path().to_path_buf(), Path::new("demo.psp"));
+
+        assert_eq!(cache_key(0xabcd), "0000abcd");
+        assert!(save_package_checksum(&paths, 0xabcd).is_ok());
+        assert!(fs::read_to_string(paths.checksum_file()).is_ok_and(|s| s == cache_key(0xabcd)));
+    }
 }
@@ -11,20 +11,127 @@
 #![allow(clippy::items_after_statements)]
 #![allow(clippy::unnecessary_debug_formatting)]
 
+use std::collections::HashMap;
 use std::fs;
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use flate2::read::GzDecoder;
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use tar::Archive;
 
+use super::defaults::{DEFAULT_DECOMPRESSION_MARGIN, ValidationLevel, get_validation_level};
 #[cfg(unix)]
-use super::defaults::DEFAULT_DIR_PERMS;
+use super::defaults::{DEFAULT_FILE_PERMS, extraction_dir_mode, extraction_file_mode};
+use super::execution::resolve_slot_target;
 use super::reader::Reader;
 use super::slots::SlotDescriptor;
+use crate::api::CollisionPolicy;
 use crate::exceptions::{FlavorError, Result};
 
+/// Tracks files written across slot extractions and applies a collision policy
+#[derive(Debug, Default)]
+pub struct ExtractionTracker {
+    policy: CollisionPolicy,
+    written: HashMap<PathBuf, String>,
+    restore_mtime: bool,
+    umask: Option<u32>,
+    max_decompressed_size: Option<u64>,
+}
+
+/// Source file attributes recorded at build time for single-file slots
+#[derive(Debug, Default, Clone, Copy)]
+struct SourceAttributes {
+    mode: Option<u32>,
+    mtime: Option<i64>,
+    /// The descriptor permissions are the builder default, which an
+    /// extraction umask may widen; explicit ones are only ever masked
+    default_permissions: bool,
+}
+
+impl ExtractionTracker {
+    /// Create a tracker with the given collision policy
+    #[must_use]
+    pub fn new(policy: CollisionPolicy) -> Self {
+        Self {
+            policy,
+            written: HashMap::new(),
+            restore_mtime: false,
+            umask: None,
+            max_decompressed_size: None,
+        }
+    }
+
+    /// Apply `umask` to extracted single-file slots and the directories created for them
+    ///
+    /// `None` keeps the owner-only defaults.
+    #[must_use]
+    pub fn with_umask(mut self, umask: Option<u32>) -> Self {
+        self.umask = umask;
+        self
+    }
+
+    /// Cap the decoded size of every compressed slot at `limit` bytes
+    ///
+    /// `None` caps each slot at its recorded `original_size` plus
+    /// `DEFAULT_DECOMPRESSION_MARGIN` instead, except at validation level
+    /// `none`, which trusts the recorded size as little as the data.
+    #[must_use]
+    pub fn with_max_decompressed_size(mut self, limit: Option<u64>) -> Self {
+        self.max_decompressed_size = limit;
+        self
+    }
+
+    /// Also restore the source mtime recorded at build time on single-file slots
+    ///
+    /// Off by default so extracted trees stay independent of build-host timestamps.
+    #[must_use]
+    pub fn with_restore_mtime(mut self, restore_mtime: bool) -> Self {
+        self.restore_mtime = restore_mtime;
+        self
+    }
+
+    /// Record that `slot_id` wants to write `path`; returns whether to write it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under `CollisionPolicy::Error` if another slot already wrote `path`
+    pub fn claim(&mut self, path: &Path, slot_id: &str) -> Result<bool> {
+        // Normalize away `./` so tar entries and slot targets compare equal
+        let path: PathBuf = path
+            .components()
+            .filter(|c| !matches!(c, std::path::Component::CurDir))
+            .collect();
+        let Some(previous) = self.written.get(&path) else {
+            self.written.insert(path, slot_id.to_string());
+            return Ok(true);
+        };
+
+        match self.policy {
+            CollisionPolicy::Error => Err(FlavorError::ExtractionError(format!(
+                "Extraction collision: {} is written by slot '{previous}' and slot '{slot_id}'",
+                path.display()
+            ))),
+            CollisionPolicy::Skip => {
+                warn!(
+                    "⚠️ Skipping {} from slot '{slot_id}' (already written by slot '{previous}')",
+                    path.display()
+                );
+                Ok(false)
+            }
+            CollisionPolicy::Overwrite => {
+                debug!(
+                    "♻️ Slot '{slot_id}' overwrites {} from slot '{previous}'",
+                    path.display()
+                );
+                self.written.insert(path, slot_id.to_string());
+                Ok(true)
+            }
+        }
+    }
+}
+
 /// Extract a slot to the specified directory
 ///
 /// # Errors
@@ -34,6 +141,25 @@ use crate::exceptions::{FlavorError, Result};
 /// - Extraction fails
 /// - File operations fail
 pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) -> Result<()> {
+    extract_slot_tracked(
+        reader,
+        slot_index,
+        dest_dir,
+        &mut ExtractionTracker::default(),
+    )
+}
+
+/// Extract a slot, checking written files against a shared [`ExtractionTracker`]
+///
+/// # Errors
+///
+/// Returns an error if extraction fails or the tracker rejects a collision
+pub fn extract_slot_tracked(
+    reader: &mut Reader,
+    slot_index: usize,
+    dest_dir: &Path,
+    tracker: &mut ExtractionTracker,
+) -> Result<()> {
     trace!("🎯 Extracting slot {slot_index} to {dest_dir:?}");
 
     // Get descriptors
@@ -46,9 +172,10 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
             slot_index,
             descriptors.len() - 1
         );
-        return Err(FlavorError::Generic(format!(
-            "Slot index {slot_index} out of range"
-        )));
+        return Err(FlavorError::SlotOutOfRange {
+            index: slot_index,
+            count: descriptors.len(),
+        });
     }
 
     let descriptor = &descriptors[slot_index];
@@ -73,9 +200,14 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
     );
 
     // Process data based on operations
-    use crate::psp::format_2025::constants::{OP_GZIP, OP_TAR};
+    use crate::psp::format_2025::constants::{OP_GZIP, OP_LZ4, OP_TAR, OP_ZSTD};
 
     let mut processed_data = slot_data;
+    let decode_timer = Instant::now();
+    let validation_level = get_validation_level();
+    let decode_limit = tracker
+        .max_decompressed_size
+        .or_else(|| default_decode_limit(descriptor, &operations, validation_level));
 
     // Apply operations in reverse order (since they're applied forward during packing)
     for &op in operations.iter().rev() {
@@ -83,11 +215,35 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
             OP_GZIP => {
                 // Decompress gzip
                 trace!("🗜️ Decompressing GZIP operation for slot {slot_index}");
-                let mut decoder = GzDecoder::new(&processed_data[..]);
-                let mut decompressed = Vec::new();
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .map_err(|e| FlavorError::Generic(format!("Failed to decompress GZIP: {e}")))?;
+                let decoder = GzDecoder::new(&processed_data[..]);
+                let decompressed = read_limited(decoder, decode_limit, slot_index)
+                    .map_err(|e| with_context(e, "Failed to decompress GZIP"))?;
+                trace!(
+                    "✅ Decompressed {} -> {} bytes",
+                    processed_data.len(),
+                    decompressed.len()
+                );
+                decompressed
+            }
+            OP_LZ4 => {
+                // Decompress LZ4 frame
+                trace!("⚡ Decompressing LZ4 operation for slot {slot_index}");
+                let decoder = lz4_flex::frame::FrameDecoder::new(&processed_data[..]);
+                let decompressed = read_limited(decoder, decode_limit, slot_index)
+                    .map_err(|e| with_context(e, "Failed to decompress LZ4"))?;
+                trace!(
+                    "✅ Decompressed {} -> {} bytes",
+                    processed_data.len(),
+                    decompressed.len()
+                );
+                decompressed
+            }
+            OP_ZSTD => {
+                // Decompress zstd frame
+                trace!("🗜️ Decompressing ZSTD operation for slot {slot_index}");
+                let decoder = zstd_decoder(&processed_data[..], slot_index)?;
+                let decompressed = read_limited(decoder, decode_limit, slot_index)
+                    .map_err(|e| with_context(e, "Failed to decompress ZSTD"))?;
                 trace!(
                     "✅ Decompressed {} -> {} bytes",
                     processed_data.len(),
@@ -102,7 +258,7 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
             }
             unknown_op => {
                 error!("❌ FATAL: Unknown operation {unknown_op} for slot {slot_index}");
-                return Err(FlavorError::Generic(format!(
+                return Err(FlavorError::FormatError(format!(
                     "Unknown operation {unknown_op} for slot {slot_index}"
                 )));
             }
@@ -110,6 +266,14 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
     }
 
     let decompressed_data = processed_data;
+    let decompress_duration = decode_timer.elapsed();
+    check_original_size(
+        descriptor,
+        &operations,
+        decompressed_data.len(),
+        slot_index,
+        validation_level,
+    )?;
 
     trace!(
         "📊 Slot {} decompressed size: {} bytes",
@@ -121,7 +285,7 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
     let metadata = reader.read_metadata()?;
 
     // Get slot info from metadata
-    let (slot_id, mut slot_target, slot_operations, slot_purpose) =
+    let (slot_id, mut slot_target, slot_operations, slot_purpose, source) =
         if slot_index < metadata.slots.len() {
             let slot_info = &metadata.slots[slot_index];
             (
@@ -129,6 +293,14 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
                 slot_info.target.clone(),
                 slot_info.operations.clone(),
                 slot_info.purpose.clone(),
+                SourceAttributes {
+                    mode: slot_info
+                        .source_mode
+                        .as_deref()
+                        .and_then(|m| u32::from_str_radix(m, 8).ok()),
+                    mtime: slot_info.source_mtime.filter(|_| tracker.restore_mtime),
+                    default_permissions: slot_info.default_permissions == Some(true),
+                },
             )
         } else {
             (
@@ -136,15 +308,16 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
                 format!("slot_{slot_index}"),
                 String::new(),
                 String::new(),
+                SourceAttributes::default(),
             )
         };
 
-    // Substitute {workenv} placeholder in target path
+    // Substitute placeholders in target path
     // Since we're already extracting to dest_dir (which IS the workenv),
-    // we need to remove the {workenv}/ prefix from the target
-    if slot_target.contains("{workenv}") {
-        slot_target = slot_target.replace("{workenv}/", "");
-        slot_target = slot_target.replace("{workenv}", "");
+    // the {workenv}/ prefix is dropped; {home}, {cache} and {config} become
+    // absolute paths outside the workenv
+    if slot_target.contains('{') {
+        slot_target = resolve_slot_target(&slot_target);
     }
 
     debug!(
@@ -152,31 +325,210 @@ pub fn extract_slot(reader: &mut Reader, slot_index: usize, dest_dir: &Path) ->
     );
 
     // Process based on operations
+    let write_timer = Instant::now();
     if operations.contains(&OP_TAR) {
         // Has TAR operation - extract as tarball
         if !is_tarball(&decompressed_data) {
             error!("❌ FATAL: Slot {slot_index} has TAR operation but data is not a tarball!");
-            return Err(FlavorError::Generic(format!(
+            return Err(FlavorError::FormatError(format!(
                 "Operation mismatch: slot {slot_index} has TAR operation but is not a tar archive"
             )));
         }
         debug!("📦 Slot {slot_index} is a tar archive, extracting...");
-        extract_tarball(&decompressed_data, dest_dir)?;
+        extract_tarball_tracked(&decompressed_data, dest_dir, tracker, &slot_id)?;
     } else {
         // No TAR operation - treat as single file
-        let target_path = dest_dir.join(&slot_target);
-        extract_single_file(&decompressed_data, &target_path, &descriptors, slot_index)?;
+        if tracker.claim(Path::new(&slot_target), &slot_id)? {
+            let target_path = dest_dir.join(&slot_target);
+            extract_single_file(
+                &decompressed_data,
+                &target_path,
+                &descriptors,
+                slot_index,
+                source,
+                tracker.umask,
+            )?;
+        }
     }
+    let write_duration = write_timer.elapsed();
+
+    // Structured fields are emitted as JSON keys by JsonLogger
+    debug!(
+        slot_index = slot_index,
+        slot_id = slot_id.as_str(),
+        stored_size = desc_size,
+        original_size = decompressed_data.len(),
+        decompress_us = decompress_duration.as_micros(),
+        write_us = write_duration.as_micros();
+        "⏱️ Slot {slot_index} ({slot_id}) extracted: decompress {decompress_duration:?}, write {write_duration:?}"
+    );
 
     Ok(())
 }
 
+/// The descriptor's `original_size`, if one was recorded
+///
+/// A zero `original_size` means none was recorded. Older builders recorded the
+/// stored size for compressed slots, so for those a value equal to `size` is
+/// not trusted either.
+fn recorded_original_size(descriptor: &SlotDescriptor, operations: &[u8]) -> Option<u64> {
+    let (original, stored) = (descriptor.original_size, descriptor.size);
+    let compressed = super::operations::is_compressed(operations);
+    (original != 0 && !(compressed && original == stored)).then_some(original)
+}
+
+/// Decoded size cap for a slot without an explicit limit
+///
+/// The recorded `original_size` plus `DEFAULT_DECOMPRESSION_MARGIN`; no cap at
+/// validation level `none`, or when no size was recorded.
+fn default_decode_limit(
+    descriptor: &SlotDescriptor,
+    operations: &[u8],
+    level: ValidationLevel,
+) -> Option<u64> {
+    if matches!(level, ValidationLevel::None) {
+        return None;
+    }
+    recorded_original_size(descriptor, operations)
+        .map(|size| size.saturating_add(DEFAULT_DECOMPRESSION_MARGIN))
+}
+
+/// Read a decoder to the end, failing once it yields more than `limit` bytes
+///
+/// Only `limit + 1` bytes are ever buffered, so an over-expanding slot is
+/// rejected before it can exhaust memory.
+///
+/// # Errors
+///
+/// Returns an error if decoding fails or the output exceeds `limit`
+fn read_limited(decoder: impl Read, limit: Option<u64>, slot_index: usize) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    decoder
+        .take(limit.map_or(u64::MAX, |limit| limit.saturating_add(1)))
+        .read_to_end(&mut decompressed)?;
+    if let Some(limit) = limit.filter(|&limit| decompressed.len() as u64 > limit) {
+        error!("❌ Slot {slot_index} decompresses past the {limit} byte limit");
+        return Err(FlavorError::VerificationFailed(format!(
+            "Slot {slot_index} decompresses to more than {limit} bytes"
+        )));
+    }
+    Ok(decompressed)
+}
+
+/// Wrap decoder I/O errors with context, leaving limit violations as they are
+fn with_context(error: FlavorError, context: &str) -> FlavorError {
+    match error {
+        FlavorError::IoError(e) => FlavorError::ExtractionError(context.to_string()).with_source(e),
+        other => other,
+    }
+}
+
+/// Compare a decoded slot's length with the descriptor's `original_size`
+///
+/// Slots without a recorded size (see [`recorded_original_size`]) pass. A
+/// mismatch fails under `Strict` and `Standard`, warns under `Relaxed` and
+/// `Minimal`, and is ignored under `None`.
+///
+/// # Errors
+///
+/// Returns an error if the sizes differ and the validation level enforces it
+fn check_original_size(
+    descriptor: &SlotDescriptor,
+    operations: &[u8],
+    decoded_len: usize,
+    slot_index: usize,
+    level: ValidationLevel,
+) -> Result<()> {
+    let Some(expected) = recorded_original_size(descriptor, operations) else {
+        return Ok(());
+    };
+    if expected == decoded_len as u64 {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Slot {slot_index} decoded to {decoded_len} bytes, descriptor records original_size {expected}"
+    );
+    match level {
+        ValidationLevel::Strict | ValidationLevel::Standard => {
+            error!("❌ {message}");
+            Err(FlavorError::VerificationFailed(message))
+        }
+        ValidationLevel::Relaxed | ValidationLevel::Minimal => {
+            warn!("⚠️ {message}, continuing due to validation level: {level:?}");
+            Ok(())
+        }
+        ValidationLevel::None => Ok(()),
+    }
+}
+
+/// Wrap a raw slot stream in decoders for its operation chain
+///
+/// Operations are undone in reverse order. `OP_TAR` is passed through, so a tar
+/// slot yields the archive bytes rather than being unpacked.
+///
+/// # Errors
+///
+/// Returns an error if the chain contains an unknown operation
+pub fn decode_slot_stream<'a>(
+    source: Box<dyn Read + 'a>,
+    operations: &[u8],
+    slot_index: usize,
+) -> Result<Box<dyn Read + 'a>> {
+    use crate::psp::format_2025::constants::{OP_GZIP, OP_LZ4, OP_TAR, OP_ZSTD};
+
+    let mut stream = source;
+    for &op in operations.iter().rev() {
+        stream = match op {
+            OP_GZIP => Box::new(GzDecoder::new(stream)),
+            OP_LZ4 => Box::new(lz4_flex::frame::FrameDecoder::new(stream)),
+            OP_ZSTD => Box::new(zstd_decoder(stream, slot_index)?),
+            OP_TAR => stream,
+            unknown_op => {
+                return Err(FlavorError::FormatError(format!(
+                    "Unknown operation {unknown_op} for slot {slot_index}"
+                )));
+            }
+        };
+    }
+    Ok(stream)
+}
+
+/// Decoder for a zstd frame read from `source`
+///
+/// Uses the pure-Rust `ruzstd` decoder, so the launcher links no C code.
+fn zstd_decoder<R: Read>(
+    source: R,
+    slot_index: usize,
+) -> Result<ruzstd::decoding::StreamingDecoder<R, ruzstd::decoding::FrameDecoder>> {
+    ruzstd::decoding::StreamingDecoder::new(source).map_err(|e| FlavorError::DecompressionFailed {
+        operation: "zstd".to_string(),
+        reason: format!("slot {slot_index}: {e}"),
+    })
+}
+
+/// Decompress an LZ4 frame
+///
+/// # Errors
+///
+/// Returns an error if the data is not a valid LZ4 frame
+pub fn decompress_lz4(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = lz4_flex::frame::FrameDecoder::new(data);
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed).map_err(|e| {
+        FlavorError::ExtractionError("Failed to decompress LZ4".into()).with_source(e)
+    })?;
+    Ok(decompressed)
+}
+
 /// Extract a single gzipped file
 fn extract_single_file(
     decompressed_data: &[u8],
     dest_dir: &Path,
     descriptors: &[SlotDescriptor],
     slot_index: usize,
+    source: SourceAttributes,
+    umask: Option<u32>,
 ) -> Result<()> {
     // This is a single gzipped file (not a tarball)
     // Per PSPF spec: OP_GZIP = single file that has been gzipped
@@ -185,7 +537,7 @@ fn extract_single_file(
 
     // Create parent directory if needed (secure permissions)
     if let Some(parent) = dest_dir.parent() {
-        create_parent_directory(parent)?;
+        create_parent_directory(parent, umask)?;
     } else {
         debug!("⚠️ No parent directory for dest_dir: {dest_dir:?}");
     }
@@ -193,19 +545,38 @@ fn extract_single_file(
     // Write the file directly to the specified path
     write_file_with_logging(dest_dir, decompressed_data)?;
 
+    // Restore mtime before permissions, which may drop write access
+    if let Some(mtime) = source.mtime {
+        restore_mtime(dest_dir, mtime)?;
+    }
+
     // Set file permissions based on descriptor or defaults
-    set_file_permissions(dest_dir, descriptors, slot_index)?;
+    set_file_permissions(
+        dest_dir,
+        descriptors,
+        slot_index,
+        source.default_permissions,
+        umask,
+    )?;
+
+    // Keep scripts that were executable at build time executable
+    #[cfg(unix)]
+    if let Some(mode) = source.mode {
+        restore_exec_bit(dest_dir, mode, source.default_permissions, umask)?;
+    }
 
     Ok(())
 }
 
 /// Create a parent directory with secure permissions
-fn create_parent_directory(parent: &Path) -> Result<()> {
+fn create_parent_directory(parent: &Path, umask: Option<u32>) -> Result<()> {
     debug!("📁 Creating parent directory for single file: {parent:?}");
     fs::create_dir_all(parent)?;
     debug!("✅ Created parent directory: {parent:?}");
 
     // Set secure directory permissions
+    #[cfg(not(unix))]
+    let _ = umask;
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
@@ -213,7 +584,7 @@ fn create_parent_directory(parent: &Path) -> Result<()> {
         if parent.exists() {
             match fs::set_permissions(
                 parent,
-                fs::Permissions::from_mode(u32::from(DEFAULT_DIR_PERMS)),
+                fs::Permissions::from_mode(extraction_dir_mode(None, umask)),
             ) {
                 Ok(()) => debug!("✅ Set permissions on parent directory"),
                 Err(e) => debug!("⚠️ Could not set permissions on parent directory: {e}"),
@@ -234,30 +605,85 @@ fn write_file_with_logging(path: &Path, data: &[u8]) -> Result<()> {
         }
         Err(e) => {
             error!("❌ Failed to write file {path:?}: {e}");
-            Err(FlavorError::Generic(format!("Failed to write file: {e}")))
+            Err(FlavorError::ExtractionError(format!(
+                "Failed to write {}: {e}",
+                path.display()
+            )))
         }
     }
 }
 
+/// Set the file's mtime to the recorded source mtime (unix seconds)
+fn restore_mtime(path: &Path, mtime: i64) -> Result<()> {
+    let Ok(secs) = u64::try_from(mtime) else {
+        debug!("⚠️ Ignoring pre-epoch source mtime {mtime} for {path:?}");
+        return Ok(());
+    };
+    let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(modified))
+        .map_err(|e| {
+            FlavorError::ExtractionError(format!("Failed to restore mtime on {path:?}"))
+                .with_source(e)
+        })?;
+    debug!("🕰️ Restored mtime {mtime} on {path:?}");
+    Ok(())
+}
+
+/// Add owner-execute when the source file had any execute bit set
+///
+/// Under an extraction umask, execute is added for everyone the umask allows,
+/// unless the slot set its permissions explicitly.
+#[cfg(unix)]
+fn restore_exec_bit(
+    path: &Path,
+    source_mode: u32,
+    default_permissions: bool,
+    umask: Option<u32>,
+) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if source_mode & 0o111 == 0 {
+        return Ok(());
+    }
+    let exec_bits = match umask {
+        Some(umask) if default_permissions => 0o111 & !umask,
+        _ => 0o100,
+    };
+    let mode = fs::metadata(path)?.permissions().mode();
+    if mode & exec_bits != exec_bits {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode | exec_bits))?;
+        debug!("✅ Restored execute bit on {path:?} (source mode {source_mode:o})");
+    }
+    Ok(())
+}
+
 /// Set file permissions based on descriptor or defaults
 #[cfg(unix)]
 fn set_file_permissions(
     path: &Path,
     descriptors: &[SlotDescriptor],
     slot_index: usize,
+    default_permissions: bool,
+    umask: Option<u32>,
 ) -> Result<()> {
     use std::os::unix::fs::PermissionsExt;
 
     // Get permissions from descriptor
     let descriptor = &descriptors[slot_index];
     // Combine both permission bytes (low and high)
-    let perms = u16::from(descriptor.permissions) | (u16::from(descriptor.permissions_high) << 8);
-    let mode = if perms != 0 {
-        u32::from(perms)
+    let perms = u32::from(descriptor.permissions) | (u32::from(descriptor.permissions_high) << 8);
+    // Zero means unset: default to secure file permissions (0600). Only
+    // builder defaults are widened under an extraction umask.
+    let explicit = (perms != 0 && !default_permissions).then_some(perms);
+    let default = if perms == 0 {
+        u32::from(DEFAULT_FILE_PERMS)
     } else {
-        // Default to secure file permissions
-        u32::from(crate::psp::format_2025::defaults::DEFAULT_FILE_PERMS) // 0600
+        perms
     };
+    let mode = extraction_file_mode(explicit, default, umask);
 
     match fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
         Ok(()) => {
@@ -266,7 +692,7 @@ fn set_file_permissions(
         }
         Err(e) => {
             error!("❌ Failed to set permissions on {path:?}: {e}");
-            Err(FlavorError::Generic(format!(
+            Err(FlavorError::ExtractionError(format!(
                 "Failed to set permissions: {e}"
             )))
         }
@@ -278,6 +704,8 @@ fn set_file_permissions(
     _path: &Path,
     _descriptors: &[SlotDescriptor],
     _slot_index: usize,
+    _default_permissions: bool,
+    _umask: Option<u32>,
 ) -> Result<()> {
     // No-op on non-Unix systems
     Ok(())
@@ -303,6 +731,16 @@ fn is_tarball(data: &[u8]) -> bool {
 /// - Tarball extraction fails
 /// - Permission setting fails
 pub fn extract_tarball(data: &[u8], dest_dir: &Path) -> Result<()> {
+    extract_tarball_tracked(data, dest_dir, &mut ExtractionTracker::default(), "")
+}
+
+/// Extract a tarball, claiming each file in `tracker` on behalf of `slot_id`
+fn extract_tarball_tracked(
+    data: &[u8],
+    dest_dir: &Path,
+    tracker: &mut ExtractionTracker,
+    slot_id: &str,
+) -> Result<()> {
     debug!("📦 Extracting tarball to {dest_dir:?}");
 
     // Create destination directory if it doesn't exist
@@ -316,9 +754,14 @@ pub fn extract_tarball(data: &[u8], dest_dir: &Path) -> Result<()> {
     // Extract all files
     for entry_result in tar.entries()? {
         let mut entry = entry_result?;
-        let path = entry.path()?;
+        let path = entry.path()?.into_owned();
         let dest_path = dest_dir.join(&path);
 
+        // Directories may be shared between slots; only files can collide
+        if !entry.header().entry_type().is_dir() && !tracker.claim(&path, slot_id)? {
+            continue;
+        }
+
         trace!("📄 Extracting: {path:?}");
 
         // Create parent directories if needed
@@ -358,3 +801,308 @@ pub fn is_gzipped_tarball(data: &[u8]) -> Result<bool> {
     decoder.read_to_end(&mut decompressed)?;
     Ok(is_tarball(&decompressed))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_applies_collision_policy() {
+        let mut error = ExtractionTracker::new(CollisionPolicy::Error);
+        assert!(matches!(error.claim(Path::new("bin/tool"), "a"), Ok(true)));
+        assert!(error.claim(Path::new("./bin/tool"), "b").is_err());
+
+        let mut skip = ExtractionTracker::new(CollisionPolicy::Skip);
+        assert!(matches!(skip.claim(Path::new("bin/tool"), "a"), Ok(true)));
+        assert!(matches!(skip.claim(Path::new("bin/tool"), "b"), Ok(false)));
+
+        let mut overwrite = ExtractionTracker::new(CollisionPolicy::Overwrite);
+        assert!(matches!(
+            overwrite.claim(Path::new("bin/tool"), "a"),
+            Ok(true)
+        ));
+        assert!(matches!(
+            overwrite.claim(Path::new("bin/tool"), "b"),
+            Ok(true)
+        ));
+    }
+
+    #[test]
+    fn test_decode_slot_stream_undoes_gzip() {
+        use crate::psp::format_2025::constants::{OP_GZIP, OP_TAR};
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        assert!(encoder.write_all(b"archive bytes").is_ok());
+        let compressed = encoder.finish().unwrap_or_default();
+
+        let stream = decode_slot_stream(Box::new(compressed.as_slice()), &[OP_TAR, OP_GZIP], 0);
+        let mut out = Vec::new();
+        assert!(stream.is_ok_and(|mut s| s.read_to_end(&mut out).is_ok()));
+        assert_eq!(out, b"archive bytes");
+    }
+
+    #[test]
+    fn test_decode_slot_stream_undoes_zstd() {
+        use crate::psp::format_2025::constants::OP_ZSTD;
+        use ruzstd::encoding::{CompressionLevel, compress_to_vec};
+
+        let compressed = compress_to_vec(&b"zstd bytes"[..], CompressionLevel::Fastest);
+        let stream = decode_slot_stream(Box::new(compressed.as_slice()), &[OP_ZSTD], 0);
+        let mut out = Vec::new();
+        assert!(stream.is_ok_and(|mut s| s.read_to_end(&mut out).is_ok()));
+        assert_eq!(out, b"zstd bytes");
+
+        let garbage = decode_slot_stream(Box::new(&b"not zstd"[..]), &[OP_ZSTD], 0);
+        assert!(garbage.is_err());
+    }
+
+    #[test]
+    fn test_original_size_mismatch_follows_validation_level() {
+        use crate::psp::format_2025::constants::OP_GZIP;
+
+        let mut descriptor = SlotDescriptor::new(0);
+        descriptor.size = 40;
+        descriptor.original_size = 100;
+
+        let check = |descriptor: &SlotDescriptor, len, level| {
+            check_original_size(descriptor, &[OP_GZIP], len, 0, level)
+        };
+        assert!(check(&descriptor, 100, ValidationLevel::Strict).is_ok());
+        assert!(matches!(
+            check(&descriptor, 99, ValidationLevel::Standard),
+            Err(FlavorError::VerificationFailed(_))
+        ));
+        assert!(check(&descriptor, 99, ValidationLevel::Relaxed).is_ok());
+        assert!(check(&descriptor, 99, ValidationLevel::None).is_ok());
+
+        // Unrecorded, or the stored size written by older builders
+        descriptor.original_size = 0;
+        assert!(check(&descriptor, 99, ValidationLevel::Strict).is_ok());
+        descriptor.original_size = 40;
+        assert!(check(&descriptor, 99, ValidationLevel::Strict).is_ok());
+        assert!(check_original_size(&descriptor, &[], 99, 0, ValidationLevel::Strict).is_err());
+    }
+
+    #[test]
+    fn test_default_decode_limit_is_off_at_validation_none() {
+        use crate::psp::format_2025::constants::OP_GZIP;
+
+        let mut descriptor = SlotDescriptor::new(0);
+        descriptor.size = 40;
+        descriptor.original_size = 100;
+
+        let limit = |level| default_decode_limit(&descriptor, &[OP_GZIP], level);
+        assert_eq!(
+            limit(ValidationLevel::Minimal),
+            Some(100 + DEFAULT_DECOMPRESSION_MARGIN)
+        );
+        assert_eq!(limit(ValidationLevel::None), None);
+    }
+
+    #[test]
+    fn test_read_limited_stops_over_expanding_slots() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        assert!(encoder.write_all(&vec![0u8; 1 << 20]).is_ok());
+        let bomb = encoder.finish().unwrap_or_default();
+        assert!(bomb.len() < 4096);
+
+        let decode = |limit| read_limited(GzDecoder::new(bomb.as_slice()), limit, 0);
+        assert!(decode(None).is_ok_and(|d| d.len() == 1 << 20));
+        assert!(decode(Some(1 << 20)).is_ok());
+        assert!(matches!(
+            decode(Some(4096)),
+            Err(FlavorError::VerificationFailed(ref m)) if m.contains("more than 4096 bytes")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bin_slot_extracts_executable() {
+        use crate::psp::format_2025::slots::default_slot_permissions;
+        use std::os::unix::fs::PermissionsExt;
+
+        let perms = default_slot_permissions("{workenv}/bin/tool", "payload");
+        let mut descriptor = SlotDescriptor::new(0);
+        descriptor.permissions = (perms & 0xFF) as u8;
+        descriptor.permissions_high = ((perms >> 8) & 0xFF) as u8;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let target = dir.path().join("bin/tool");
+        assert!(
+            extract_single_file(
+                b"#!/bin/sh\n",
+                &target,
+                &[descriptor],
+                0,
+                SourceAttributes::default(),
+                None
+            )
+            .is_ok()
+        );
+
+        let mode = fs::metadata(&target).map(|m| m.permissions().mode() & 0o777);
+        assert!(matches!(mode, Ok(0o700)));
+        assert_eq!(
+            default_slot_permissions("data/config.json", "payload"),
+            0o600
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_single_file_restores_source_attributes() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut descriptor = SlotDescriptor::new(0);
+        // 0o600 split into low and high permission bytes
+        descriptor.permissions = 0x80;
+        descriptor.permissions_high = 0x01;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let target = dir.path().join("run.sh");
+        let source = SourceAttributes {
+            mode: Some(0o755),
+            mtime: Some(1_700_000_000),
+            ..SourceAttributes::default()
+        };
+        assert!(
+            extract_single_file(b"#!/bin/sh\n", &target, &[descriptor], 0, source, None).is_ok()
+        );
+
+        let meta = fs::metadata(&target);
+        assert!(
+            meta.as_ref()
+                .is_ok_and(|m| m.permissions().mode() & 0o777 == 0o700)
+        );
+        assert!(meta.is_ok_and(|m| {
+            m.modified()
+                .is_ok_and(|t| t == UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extraction_umask_loosens_defaults() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let mode_of = |path: &Path| fs::metadata(path).map(|m| m.permissions().mode() & 0o7777);
+        let umask = Some(0o027);
+
+        // Unset descriptor permissions fall back to 0600, widened to 0666 & !umask
+        let target = dir.path().join("shared/data.txt");
+        let descriptor = SlotDescriptor {
+            permissions: 0,
+            permissions_high: 0,
+            ..SlotDescriptor::new(0)
+        };
+        let source = SourceAttributes::default();
+        assert!(extract_single_file(b"data", &target, &[descriptor], 0, source, umask).is_ok());
+        assert!(matches!(mode_of(&target), Ok(0o640)));
+        assert!(matches!(mode_of(&dir.path().join("shared")), Ok(0o750)));
+
+        // Explicit permissions (0755) are masked
+        let target = dir.path().join("shared/tool");
+        let descriptor = SlotDescriptor {
+            permissions: 0xED,
+            permissions_high: 0x01,
+            ..SlotDescriptor::new(0)
+        };
+        assert!(
+            extract_single_file(b"#!/bin/sh\n", &target, &[descriptor], 0, source, umask).is_ok()
+        );
+        assert!(matches!(mode_of(&target), Ok(0o750)));
+
+        // An explicit 0600 (SlotDescriptor::new's mode) is never widened
+        let target = dir.path().join("shared/key.pem");
+        let descriptors = [SlotDescriptor::new(0)];
+        assert!(extract_single_file(b"key", &target, &descriptors, 0, source, umask).is_ok());
+        assert!(matches!(mode_of(&target), Ok(0o600)));
+
+        // Restored execute bits follow the umask too, for default permissions
+        let target = dir.path().join("shared/run.sh");
+        let source = SourceAttributes {
+            mode: Some(0o755),
+            mtime: None,
+            default_permissions: true,
+        };
+        assert!(
+            extract_single_file(b"#!/bin/sh\n", &target, &descriptors, 0, source, umask).is_ok()
+        );
+        assert!(matches!(mode_of(&target), Ok(0o750)));
+
+        // ...and only for the owner when the permissions are explicit
+        let target = dir.path().join("shared/private.sh");
+        let source = SourceAttributes {
+            default_permissions: false,
+            ..source
+        };
+        assert!(
+            extract_single_file(b"#!/bin/sh\n", &target, &descriptors, 0, source, umask).is_ok()
+        );
+        assert!(matches!(mode_of(&target), Ok(0o700)));
+
+        assert_eq!(extraction_file_mode(None, 0o600, None), 0o600);
+        assert_eq!(extraction_file_mode(None, 0o600, Some(0o022)), 0o644);
+        assert_eq!(extraction_file_mode(Some(0o600), 0o600, Some(0o022)), 0o600);
+        assert_eq!(extraction_dir_mode(None, None), 0o700);
+        assert_eq!(extraction_dir_mode(Some(0o700), Some(0o022)), 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extraction_umask_keeps_explicit_slot_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("key.pem"), b"secret").is_ok());
+        assert!(fs::write(root.join("data.txt"), b"shared").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "umask-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "key", "source": root.join("key.pem"), "target": "key.pem", "permissions": "0600"},
+                {"id": "data", "source": root.join("data.txt"), "target": "data.txt"}
+            ]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = crate::api::BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("umask".to_string()),
+            ..crate::api::BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let workenv = root.join("workenv");
+        let mut tracker =
+            ExtractionTracker::new(CollisionPolicy::default()).with_umask(Some(0o022));
+        for slot in 0..2 {
+            assert!(extract_slot_tracked(&mut reader, slot, &workenv, &mut tracker).is_ok());
+        }
+        let mode_of =
+            |name: &str| fs::metadata(workenv.join(name)).map(|m| m.permissions().mode() & 0o7777);
+        assert!(matches!(mode_of("key.pem"), Ok(0o600)));
+        assert!(matches!(mode_of("data.txt"), Ok(0o644)));
+    }
+}
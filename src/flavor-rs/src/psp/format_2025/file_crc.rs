@@ -0,0 +1,115 @@
+//! Whole-file CRC32C pre-check
+//!
+//! With `INDEX_FLAG_FILE_CRC` set, the index records a CRC32C of the entire
+//! package file, taken with the CRC field and the index checksum zeroed. The
+//! launcher can then reject a truncated or corrupted download with a single
+//! sequential read, before any signature work. This is triage, not security:
+//! anyone who can modify the file can recompute the CRC, so signature
+//! verification remains authoritative.
+
+use super::constants::{MAGIC_TRAILER_SIZE, PACKAGE_EMOJI_BYTES};
+use super::index::Index;
+use crate::exceptions::{FlavorError, Result};
+use log::debug;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Offset of the CRC within the index (`padding1[0..4]`)
+const FILE_CRC_FIELD: usize = 692;
+/// Offset of `index_checksum` within the index
+const INDEX_CHECKSUM_FIELD: usize = 4;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// CRC32C of the package file at `path`, skipping the fields that depend on it
+pub fn compute_file_crc(path: &Path) -> Result<u32> {
+    let mut file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+    if file_size < MAGIC_TRAILER_SIZE as u64 {
+        return Err(FlavorError::VerificationFailed(format!(
+            "file is {file_size} bytes, smaller than the {MAGIC_TRAILER_SIZE}-byte trailer"
+        )));
+    }
+    let index_start = file_size - MAGIC_TRAILER_SIZE as u64 + PACKAGE_EMOJI_BYTES.len() as u64;
+    let excluded = [
+        index_start + INDEX_CHECKSUM_FIELD as u64,
+        index_start + FILE_CRC_FIELD as u64,
+    ];
+
+    let mut crc = 0u32;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut pos = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let chunk = &mut buf[..n];
+        for &start in &excluded {
+            // Zero whatever part of the 4-byte field falls inside this chunk
+            for offset in start..start + 4 {
+                if let Some(i) = offset.checked_sub(pos).filter(|&i| i < n as u64) {
+                    chunk[i as usize] = 0;
+                }
+            }
+        }
+        crc = crc32c::crc32c_append(crc, chunk);
+        pos += n as u64;
+    }
+    Ok(crc)
+}
+
+/// Check the file against the CRC recorded in `index`
+///
+/// Packages built without a file CRC always pass.
+pub fn check_file_crc(path: &Path, index: &Index) -> Result<()> {
+    let Some(expected) = index.file_crc() else {
+        debug!("⏭️ No file CRC recorded, skipping pre-check");
+        return Ok(());
+    };
+    let actual = compute_file_crc(path)?;
+    if actual != expected {
+        return Err(FlavorError::ChecksumMismatch {
+            subject: "package file CRC32C (file is corrupted or truncated)".to_string(),
+            expected: format!("0x{expected:08x}"),
+            actual: format!("0x{actual:08x}"),
+        });
+    }
+    debug!("✅ File CRC32C 0x{actual:08x} matches");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_crc_ignores_its_own_fields() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let path = dir.path().join("pkg");
+
+        let mut data: Vec<u8> = (0..3 * MAGIC_TRAILER_SIZE).map(|i| i as u8).collect();
+        assert!(std::fs::write(&path, &data).is_ok());
+        let base = compute_file_crc(&path);
+        assert!(base.is_ok());
+        let Ok(base) = base else { return };
+
+        let index_start = data.len() - MAGIC_TRAILER_SIZE + PACKAGE_EMOJI_BYTES.len();
+        data[index_start + INDEX_CHECKSUM_FIELD..][..4].copy_from_slice(&[1, 2, 3, 4]);
+        data[index_start + FILE_CRC_FIELD..][..4].copy_from_slice(&base.to_le_bytes());
+        assert!(std::fs::write(&path, &data).is_ok());
+        assert!(compute_file_crc(&path).is_ok_and(|crc| crc == base));
+
+        let mut index = Index::new();
+        index.set_file_crc(base);
+        assert!(check_file_crc(&path, &index).is_ok());
+
+        data[10] ^= 0xff;
+        assert!(std::fs::write(&path, &data).is_ok());
+        assert!(check_file_crc(&path, &index).is_err_and(|e| e.to_string().contains("corrupted")));
+        assert!(check_file_crc(&path, &Index::new()).is_ok());
+    }
+}
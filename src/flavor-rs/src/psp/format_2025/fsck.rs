@@ -0,0 +1,271 @@
+//! Consistency checks for the workenv cache
+//!
+//! Walks `<cache>/workenv/` and reports leftovers of interrupted or abandoned
+//! launches. Repairs only ever delete markers and scratch data, so the next
+//! launch of an affected package simply extracts again.
+
+use super::defaults::{DEFAULT_PSPF_HIDDEN_PREFIX, DEFAULT_PSPF_SUFFIX};
+use super::execution::single_file_slot_targets;
+use super::locking::{is_extraction_complete, is_process_running};
+use super::metadata::Metadata;
+use super::paths::WorkenvPaths;
+use crate::exceptions::Result;
+use log::{debug, info};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A problem found in one workenv
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum CacheIssue {
+    /// Marked complete, but slot targets are missing from the workenv
+    MissingSlotTargets {
+        workenv: String,
+        missing: Vec<String>,
+    },
+    /// Extraction lock left behind by a process that is gone
+    StaleLock { workenv: String, pid: Option<u32> },
+    /// Temp extraction directory of a process that is gone
+    StaleTempDir { workenv: String, path: PathBuf },
+    /// Checksum record (or completion marker) for a workenv that doesn't exist
+    OrphanedChecksum { workenv: String },
+}
+
+impl fmt::Display for CacheIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingSlotTargets { workenv, missing } => write!(
+                f,
+                "{workenv}: marked complete but missing {}",
+                missing.join(", ")
+            ),
+            Self::StaleLock {
+                workenv,
+                pid: Some(pid),
+            } => write!(f, "{workenv}: stale lock from dead process {pid}"),
+            Self::StaleLock { workenv, pid: None } => {
+                write!(f, "{workenv}: unreadable lock file")
+            }
+            Self::StaleTempDir { workenv, path } => write!(
+                f,
+                "{workenv}: temp extraction from dead process at {}",
+                path.display()
+            ),
+            Self::OrphanedChecksum { workenv } => {
+                write!(f, "{workenv}: checksum record without a workenv")
+            }
+        }
+    }
+}
+
+/// Result of checking a cache directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FsckReport {
+    pub workenvs: usize,
+    pub issues: Vec<CacheIssue>,
+    pub repaired: bool,
+}
+
+impl fmt::Display for FsckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Checked {} workenvs", self.workenvs)?;
+        if self.issues.is_empty() {
+            return writeln!(f, "No problems found");
+        }
+        let marker = if self.repaired { "fixed" } else { "found" };
+        for issue in &self.issues {
+            writeln!(f, "  [{marker}] {issue}")?;
+        }
+        if !self.repaired {
+            writeln!(f, "Run with --repair to remove them")?;
+        }
+        Ok(())
+    }
+}
+
+/// Check every workenv under `cache_dir`, removing what is found if `repair`
+pub fn fsck(cache_dir: &Path, repair: bool) -> Result<FsckReport> {
+    let mut report = FsckReport {
+        repaired: repair,
+        ..FsckReport::default()
+    };
+    for name in workenv_names(&cache_dir.join("workenv"))? {
+        let paths = WorkenvPaths::new(cache_dir.to_path_buf(), Path::new(&name));
+        report.workenvs += 1;
+        let issues = check_workenv(&paths);
+        if repair {
+            for issue in &issues {
+                repair_issue(&paths, issue)?;
+            }
+        }
+        report.issues.extend(issues);
+    }
+    Ok(report)
+}
+
+/// Names of all workenvs with content or metadata directories
+fn workenv_names(root: &Path) -> Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(names),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let name = name
+            .strip_prefix(DEFAULT_PSPF_HIDDEN_PREFIX)
+            .and_then(|n| n.strip_suffix(DEFAULT_PSPF_SUFFIX))
+            .map(str::to_string)
+            .unwrap_or(name);
+        names.insert(name);
+    }
+    Ok(names)
+}
+
+fn check_workenv(paths: &WorkenvPaths) -> Vec<CacheIssue> {
+    let workenv = paths.name().to_string();
+    let mut issues = Vec::new();
+
+    let lock_file = paths.lock_file();
+    if lock_file.exists() {
+        let pid = fs::read_to_string(&lock_file)
+            .ok()
+            .and_then(|contents| contents.trim().parse::<u32>().ok());
+        if pid.is_some_and(is_process_running) {
+            // Extraction in progress; everything else may legitimately be half-done
+            debug!("🔒 {workenv} is being extracted, skipping");
+            return issues;
+        }
+        issues.push(CacheIssue::StaleLock {
+            workenv: workenv.clone(),
+            pid,
+        });
+    }
+
+    for path in paths.list_temp_extractions().unwrap_or_default() {
+        let pid = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.parse::<u32>().ok());
+        if pid.is_some_and(|pid| !is_process_running(pid)) {
+            issues.push(CacheIssue::StaleTempDir {
+                workenv: workenv.clone(),
+                path,
+            });
+        }
+    }
+
+    if !paths.workenv_exists() {
+        if paths.checksum_file().exists() || paths.complete_file().exists() {
+            issues.push(CacheIssue::OrphanedChecksum { workenv });
+        }
+        return issues;
+    }
+
+    if is_extraction_complete(paths) {
+        let missing = missing_slot_targets(paths);
+        if !missing.is_empty() {
+            issues.push(CacheIssue::MissingSlotTargets { workenv, missing });
+        }
+    }
+    issues
+}
+
+/// Single-file slot targets recorded in the workenv's metadata that don't exist
+fn missing_slot_targets(paths: &WorkenvPaths) -> Vec<String> {
+    let Some(metadata) = fs::read_to_string(paths.psp_metadata_file())
+        .ok()
+        .and_then(|json| serde_json::from_str::<Metadata>(&json).ok())
+    else {
+        debug!("⚠️ No readable metadata for {}, skipping", paths.name());
+        return Vec::new();
+    };
+
+    let workenv = paths.workenv();
+    single_file_slot_targets(&metadata)
+        .into_iter()
+        .map(|(_, target)| target)
+        .filter(|target| !workenv.join(target).exists())
+        .collect()
+}
+
+fn repair_issue(paths: &WorkenvPaths, issue: &CacheIssue) -> Result<()> {
+    info!("🧹 Repairing {issue}");
+    match issue {
+        CacheIssue::MissingSlotTargets { .. } => remove_if_present(&paths.complete_file()),
+        CacheIssue::StaleLock { .. } => remove_if_present(&paths.lock_file()),
+        CacheIssue::StaleTempDir { path, .. } => Ok(fs::remove_dir_all(path)?),
+        CacheIssue::OrphanedChecksum { .. } => {
+            remove_if_present(&paths.checksum_file())?;
+            remove_if_present(&paths.complete_file())
+        }
+    }
+}
+
+fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::execution::save_package_checksum;
+    use super::super::locking::mark_extraction_complete;
+    use super::*;
+
+    #[test]
+    fn test_fsck_reports_and_repairs_leftovers() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let cache = dir.path();
+
+        // Complete, but the extracted file is gone
+        let broken = WorkenvPaths::new(cache.to_path_buf(), Path::new("broken"));
+        assert!(fs::create_dir_all(broken.workenv()).is_ok());
+        assert!(fs::create_dir_all(broken.package_metadata()).is_ok());
+        let metadata = serde_json::json!({
+            "format": "PSPF/2025",
+            "package": {"name": "broken", "version": "1.0"},
+            "slots": [{"slot": 0, "id": "app", "source": "app.sh", "target": "app.sh",
+                       "size": 1, "checksum": "", "operations": "", "purpose": "data",
+                       "lifecycle": "runtime"}],
+            "execution": {"primary_slot": 0, "command": "/bin/sh {workenv}/app.sh"}
+        });
+        assert!(fs::write(broken.psp_metadata_file(), metadata.to_string()).is_ok());
+        assert!(mark_extraction_complete(&broken).is_ok());
+
+        // Metadata only: a dead lock, a dead temp dir and a checksum record
+        let orphan = WorkenvPaths::new(cache.to_path_buf(), Path::new("orphan"));
+        assert!(save_package_checksum(&orphan, 0x1234_abcd).is_ok());
+        assert!(fs::create_dir_all(orphan.extract()).is_ok());
+        assert!(fs::write(orphan.lock_file(), format!("{}", u32::MAX - 2)).is_ok());
+        assert!(fs::create_dir_all(orphan.temp_extraction(u32::MAX - 2)).is_ok());
+
+        let report = fsck(cache, false);
+        assert!(
+            report
+                .as_ref()
+                .is_ok_and(|r| r.workenvs == 2 && r.issues.len() == 4)
+        );
+        assert!(broken.complete_file().exists());
+
+        assert!(fsck(cache, true).is_ok_and(|r| r.repaired && r.issues.len() == 4));
+        assert!(!broken.complete_file().exists());
+        assert!(!orphan.lock_file().exists());
+        assert!(!orphan.checksum_file().exists());
+        assert!(!orphan.temp_extraction(u32::MAX - 2).exists());
+        assert!(fsck(cache, false).is_ok_and(|r| r.issues.is_empty()));
+    }
+}
@@ -1,16 +1,26 @@
 // helpers/flavor-rs/src/psp/format_2025/index.rs
-// PSPF 2025 Index Block - Future-proof 4096-byte Header
-
-use super::constants::{HEADER_SIZE, PSPF_VERSION};
+// PSPF 2025 Index Block - Future-proof 8192-byte Header
+
+use super::constants::{
+    HEADER_SIZE, MAGIC_TRAILER_SIZE, MAGIC_WAND_EMOJI_BYTES, PACKAGE_EMOJI_BYTES, PSPF_VERSION,
+};
+use super::defaults::{
+    INDEX_FLAG_ATTESTATION, INDEX_FLAG_CRC32C, INDEX_FLAG_FILE_CRC, INDEX_FLAG_LAUNCHER_HASH,
+    INDEX_FLAG_METADATA_RAW,
+};
 use crate::exceptions::{FlavorError, Result};
 
 /// PSPF/2025 index structure (8192 bytes total)
+///
+/// The on-disk format is canonically little-endian on every host: `pack` and
+/// `unpack` convert each field explicitly and never transmute the struct, so
+/// big-endian targets read and write the same bytes.
 #[repr(C, packed)]
 #[derive(Clone, Debug)]
 pub struct Index {
     // Core identification (8 bytes)
     pub format_version: u32, // 0x20250001
-    pub index_checksum: u32, // Adler-32 of index block (with this field as 0)
+    pub index_checksum: u32, // Adler-32 or CRC32C (see flags) of index block (with this field as 0)
 
     // File structure (48 bytes)
     pub package_size: u64,      // Total file size
@@ -65,6 +75,15 @@ pub struct Index {
     pub reserved: [u8; 6816], // Large buffer for future expansion
 }
 
+// The field layout mirrors the wire layout byte for byte
+const _: () = assert!(std::mem::size_of::<Index>() == HEADER_SIZE);
+
+/// Key a workenv is cached under for a package with this index checksum:
+/// 8 lowercase hex digits, as stored in the workenv's `package.checksum`
+pub fn cache_key(index_checksum: u32) -> String {
+    format!("{index_checksum:08x}")
+}
+
 impl Index {
     /// Create a new index with defaults
     pub fn new() -> Self {
@@ -110,10 +129,86 @@ impl Index {
         }
     }
 
+    /// Locate the index block at the end of a package
+    ///
+    /// `tail` holds the last bytes of the file, at least one MagicTrailer.
+    pub fn locate_in_trailer(tail: &[u8]) -> Option<&[u8]> {
+        Self::parse_trailer(tail).ok()
+    }
+
+    /// Count the bytes following the last complete MagicTrailer in `window`
+    ///
+    /// `window` holds the end of a file that doesn't end with a trailer.
+    /// `None` means no trailer was found in it, so the file is not a package
+    /// with data appended.
+    pub fn bytes_after_trailer(window: &[u8]) -> Option<usize> {
+        let mut end = window.len();
+        while let Some(pos) = window[..end]
+            .windows(MAGIC_WAND_EMOJI_BYTES.len())
+            .rposition(|w| w == MAGIC_WAND_EMOJI_BYTES)
+        {
+            let trailer_end = pos + MAGIC_WAND_EMOJI_BYTES.len();
+            if trailer_end < window.len()
+                && Self::locate_in_trailer(&window[..trailer_end]).is_some()
+            {
+                return Some(window.len() - trailer_end);
+            }
+            end = trailer_end - 1;
+        }
+        None
+    }
+
+    /// Locate the index block like [`locate_in_trailer`](Self::locate_in_trailer),
+    /// failing with a `FormatError` that says which part of the trailer is wrong
+    pub fn parse_trailer(tail: &[u8]) -> Result<&[u8]> {
+        let invalid =
+            |reason: String| FlavorError::FormatError(format!("Invalid MagicTrailer: {reason}"));
+        let Some(start) = tail.len().checked_sub(MAGIC_TRAILER_SIZE) else {
+            return Err(invalid(format!(
+                "{} bytes is smaller than the {MAGIC_TRAILER_SIZE}-byte trailer; the file is truncated or not a package",
+                tail.len()
+            )));
+        };
+        if !tail.ends_with(MAGIC_WAND_EMOJI_BYTES) {
+            return Err(invalid("missing 🪄 at end".to_string()));
+        }
+        if &tail[start..start + 4] != PACKAGE_EMOJI_BYTES {
+            return Err(invalid(format!(
+                "missing 📦 {MAGIC_TRAILER_SIZE} bytes before the end"
+            )));
+        }
+        Ok(&tail[start + 4..start + 4 + HEADER_SIZE])
+    }
+
+    /// Refuse packages that need a newer reader than this one
+    ///
+    /// `compatibility` is the oldest reader format that can read the package;
+    /// anything newer than [`PSPF_VERSION`] may use layout this reader would
+    /// misparse. Packages written in an older format only get a warning.
+    pub fn check_compatibility(&self) -> Result<()> {
+        let (required, format_version) = (self.compatibility, self.format_version);
+        if required > PSPF_VERSION {
+            return Err(FlavorError::IncompatibleFormat {
+                required,
+                supported: PSPF_VERSION,
+            });
+        }
+        if format_version < PSPF_VERSION {
+            log::warn!(
+                "⚠️ Package uses older format 0x{format_version:08x} (current 0x{PSPF_VERSION:08x}); rebuild it to pick up format fixes"
+            );
+        }
+        Ok(())
+    }
+
     /// Unpack index from bytes
+    ///
+    /// Every PSPF/2025 writer (Rust, Go and Python) has only ever produced the
+    /// 8192-byte block described in FEP-0001; there is no smaller legacy
+    /// layout to fall back to, so any other size is a format error.
     pub fn unpack(data: &[u8]) -> Result<Self> {
         if data.len() != HEADER_SIZE {
-            return Err(FlavorError::Generic(format!(
+            return Err(FlavorError::FormatError(format!(
                 "Invalid index size: {} != {}",
                 data.len(),
                 HEADER_SIZE
@@ -128,22 +223,22 @@ impl Index {
         index.format_version = u32::from_le_bytes(
             data[0..4]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid format version bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid format version bytes".into()))?,
         );
         index.index_checksum = u32::from_le_bytes(
             data[4..8]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid index checksum bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid index checksum bytes".into()))?,
         );
         index.package_size = u64::from_le_bytes(
             data[8..16]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid package size bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid package size bytes".into()))?,
         );
         index.launcher_size = u64::from_le_bytes(
             data[16..24]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid launcher size bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid launcher size bytes".into()))?,
         );
 
         // Debug: Log the raw bytes we're parsing for metadata offset and size
@@ -159,12 +254,12 @@ impl Index {
         index.metadata_offset = u64::from_le_bytes(
             data[24..32]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid metadata offset bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid metadata offset bytes".into()))?,
         );
         index.metadata_size = u64::from_le_bytes(
             data[32..40]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid metadata size bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid metadata size bytes".into()))?,
         );
 
         // Copy to locals before logging to avoid alignment issues
@@ -175,22 +270,22 @@ impl Index {
         index.slot_table_offset = u64::from_le_bytes(
             data[40..48]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid slot table offset bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid slot table offset bytes".into()))?,
         );
         index.slot_table_size = u64::from_le_bytes(
             data[48..56]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid slot table size bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid slot table size bytes".into()))?,
         );
         index.slot_count = u32::from_le_bytes(
             data[56..60]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid slot count bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid slot count bytes".into()))?,
         );
         index.flags = u32::from_le_bytes(
             data[60..64]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid flags bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid flags bytes".into()))?,
         );
         index.public_key.copy_from_slice(&data[64..96]);
         index.metadata_checksum.copy_from_slice(&data[96..128]);
@@ -204,37 +299,37 @@ impl Index {
         index.page_size = u32::from_le_bytes(
             data[644..648]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid page size bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid page size bytes".into()))?,
         );
         index.max_memory = u64::from_le_bytes(
             data[648..656]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid max memory bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid max memory bytes".into()))?,
         );
         index.min_memory = u64::from_le_bytes(
             data[656..664]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid min memory bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid min memory bytes".into()))?,
         );
         index.cpu_features = u64::from_le_bytes(
             data[664..672]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid CPU features bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid CPU features bytes".into()))?,
         );
         index.gpu_requirements = u64::from_le_bytes(
             data[672..680]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid GPU requirements bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid GPU requirements bytes".into()))?,
         );
         index.numa_hints = u64::from_le_bytes(
             data[680..688]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid NUMA hints bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid NUMA hints bytes".into()))?,
         );
         index.stream_chunk_size = u32::from_le_bytes(
             data[688..692]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid stream chunk size bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid stream chunk size bytes".into()))?,
         );
         index.padding1.copy_from_slice(&data[692..704]);
 
@@ -242,7 +337,7 @@ impl Index {
         index.build_timestamp = u64::from_le_bytes(
             data[704..712]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid build timestamp bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid build timestamp bytes".into()))?,
         );
         index.build_machine.copy_from_slice(&data[712..744]);
         index.source_hash.copy_from_slice(&data[744..776]);
@@ -254,27 +349,27 @@ impl Index {
         index.capabilities = u64::from_le_bytes(
             data[832..840]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid capabilities bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid capabilities bytes".into()))?,
         );
         index.requirements = u64::from_le_bytes(
             data[840..848]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid requirements bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid requirements bytes".into()))?,
         );
         index.extensions = u64::from_le_bytes(
             data[848..856]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid extensions bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid extensions bytes".into()))?,
         );
         index.compatibility = u32::from_le_bytes(
             data[856..860]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid compatibility bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid compatibility bytes".into()))?,
         );
         index.protocol_version = u32::from_le_bytes(
             data[860..864]
                 .try_into()
-                .map_err(|_| FlavorError::Generic("Invalid protocol version bytes".into()))?,
+                .map_err(|_| FlavorError::FormatError("Invalid protocol version bytes".into()))?,
         );
 
         // Parse future crypto and reserved
@@ -338,19 +433,109 @@ impl Index {
 
         // Calculate and update checksum (with checksum field zeroed)
         bytes[4..8].copy_from_slice(&[0, 0, 0, 0]);
-        let checksum = adler::adler32_slice(&bytes[..]);
+        let checksum = Self::calculate_checksum(self.flags, &bytes[..]);
         bytes[4..8].copy_from_slice(&checksum.to_le_bytes());
 
         bytes
     }
 
+    /// Calculate the index checksum over packed bytes (checksum field zeroed)
+    ///
+    /// Uses CRC32C when `INDEX_FLAG_CRC32C` is set, otherwise Adler-32.
+    pub fn calculate_checksum(flags: u32, bytes: &[u8]) -> u32 {
+        if flags & INDEX_FLAG_CRC32C != 0 {
+            crc32c::crc32c(bytes)
+        } else {
+            adler::adler32_slice(bytes)
+        }
+    }
+
+    /// Key the package's workenv is cached under (see [`cache_key`])
+    pub fn cache_key(&self) -> String {
+        cache_key(self.index_checksum)
+    }
+
+    /// Name of the checksum algorithm used for `index_checksum`
+    pub fn checksum_algorithm(&self) -> &'static str {
+        if self.flags & INDEX_FLAG_CRC32C != 0 {
+            "crc32c"
+        } else {
+            "adler32"
+        }
+    }
+
+    /// SHA-256 of the launcher bytes, if the builder recorded one
+    pub fn launcher_hash(&self) -> Option<[u8; 32]> {
+        if self.flags & INDEX_FLAG_LAUNCHER_HASH == 0 {
+            return None;
+        }
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&self.reserved[..32]);
+        Some(hash)
+    }
+
+    /// Record the launcher's SHA-256 in the first 32 reserved bytes
+    pub fn set_launcher_hash(&mut self, hash: [u8; 32]) {
+        self.reserved[..32].copy_from_slice(&hash);
+        self.flags |= INDEX_FLAG_LAUNCHER_HASH;
+    }
+
+    /// Transparency log entry stored after the launcher hash, if any
+    pub fn attestation(&self) -> Option<Vec<u8>> {
+        if self.flags & INDEX_FLAG_ATTESTATION == 0 {
+            return None;
+        }
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&self.reserved[32..36]);
+        let end = 36usize.saturating_add(u32::from_le_bytes(len) as usize);
+        self.reserved.get(36..end).map(<[u8]>::to_vec)
+    }
+
+    /// Store a transparency log entry (up to 6780 bytes) in the reserved space
+    pub fn set_attestation(&mut self, entry: &[u8]) -> Result<()> {
+        let capacity = self.reserved.len() - 36;
+        if entry.len() > capacity {
+            return Err(FlavorError::BuildError(format!(
+                "Attestation is {} bytes, the index holds at most {capacity}",
+                entry.len()
+            )));
+        }
+        self.reserved[32..36].copy_from_slice(&(entry.len() as u32).to_le_bytes());
+        self.reserved[36..36 + entry.len()].copy_from_slice(entry);
+        self.flags |= INDEX_FLAG_ATTESTATION;
+        Ok(())
+    }
+
+    /// Whole-file CRC32C recorded at build time, if any (see [`super::file_crc`])
+    pub fn file_crc(&self) -> Option<u32> {
+        if self.flags & INDEX_FLAG_FILE_CRC == 0 {
+            return None;
+        }
+        let mut crc = [0u8; 4];
+        crc.copy_from_slice(&self.padding1[..4]);
+        Some(u32::from_le_bytes(crc))
+    }
+
+    /// Record the whole-file CRC32C in the first 4 padding bytes
+    pub fn set_file_crc(&mut self, crc: u32) {
+        self.padding1[..4].copy_from_slice(&crc.to_le_bytes());
+        self.flags |= INDEX_FLAG_FILE_CRC;
+    }
+
+    /// Whether the metadata is gzipped; tiny metadata that gzip can't shrink
+    /// is stored as plain JSON
+    pub fn metadata_compressed(&self) -> bool {
+        self.flags & INDEX_FLAG_METADATA_RAW == 0
+    }
+
     /// Verify index checksum against raw data
     pub fn verify_checksum_raw(&self, raw_data: &[u8]) -> bool {
         use log::debug;
 
-        if raw_data.len() != HEADER_SIZE {
+        let expected_size = HEADER_SIZE;
+        if raw_data.len() != expected_size {
             let size = raw_data.len();
-            debug!("Index size mismatch: {} != {}", size, HEADER_SIZE);
+            debug!("Index size mismatch: {} != {}", size, expected_size);
             return false;
         }
 
@@ -383,11 +568,15 @@ impl Index {
             debug!("{hex_line}");
         }
 
-        let calculated = adler::adler32_slice(&data_copy);
+        let calculated = Self::calculate_checksum(self.flags, &data_copy);
         let expected = self.index_checksum;
         debug!(
-            "Checksum verification - Expected: {} (0x{:08x}), Calculated: {} (0x{:08x})",
-            expected, expected, calculated, calculated
+            "Checksum verification ({}) - Expected: {} (0x{:08x}), Calculated: {} (0x{:08x})",
+            self.checksum_algorithm(),
+            expected,
+            expected,
+            calculated,
+            calculated
         );
         calculated == expected
     }
@@ -399,7 +588,7 @@ impl Index {
         // Zero out checksum field (bytes 4-8)
         bytes[4..8].copy_from_slice(&[0, 0, 0, 0]);
 
-        let calculated = adler::adler32_slice(&bytes[..]);
+        let calculated = Self::calculate_checksum(self.flags, &bytes[..]);
         calculated == self.index_checksum
     }
 }
@@ -410,4 +599,145 @@ impl Default for Index {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_roundtrip_per_algorithm() {
+        for flags in [0, INDEX_FLAG_CRC32C] {
+            let mut index = Index::new();
+            index.flags = flags;
+            let bytes = index.pack();
+
+            let unpacked = Index::unpack(&bytes);
+            assert!(unpacked.is_ok_and(|index| index.verify_checksum_raw(&bytes)));
+        }
+    }
+
+    #[test]
+    fn test_launcher_hash_roundtrip() {
+        let mut index = Index::new();
+        assert!(index.launcher_hash().is_none());
+
+        index.set_launcher_hash([0xab; 32]);
+        let unpacked = Index::unpack(&index.pack());
+        assert!(unpacked.is_ok_and(|index| index.launcher_hash() == Some([0xab; 32])));
+    }
+
+    #[test]
+    fn test_attestation_roundtrip() {
+        let mut index = Index::new();
+        assert!(index.attestation().is_none());
+        assert!(index.set_attestation(&[0; 6781]).is_err());
+
+        index.set_launcher_hash([0xab; 32]);
+        assert!(index.set_attestation(b"{\"logIndex\": 42}").is_ok());
+        let unpacked = Index::unpack(&index.pack());
+        assert!(unpacked.is_ok_and(|index| {
+            index.attestation().as_deref() == Some(&b"{\"logIndex\": 42}"[..])
+                && index.launcher_hash() == Some([0xab; 32])
+        }));
+    }
+
+    #[test]
+    fn test_pack_is_little_endian_at_fixed_offsets() {
+        let mut index = Index::new();
+        index.package_size = 0x0102_0304_0506_0708;
+        index.slot_count = 0x1122_3344;
+        index.page_size = 0x0000_4000;
+        index.capabilities = 0xA1A2_A3A4_A5A6_A7A8;
+        index.protocol_version = 0x0000_0002;
+
+        let bytes = index.pack();
+        assert_eq!(bytes.len(), HEADER_SIZE);
+        assert_eq!(bytes[0..4], [0x01, 0x00, 0x25, 0x20]); // 0x20250001
+        assert_eq!(
+            bytes[8..16],
+            [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+        assert_eq!(bytes[56..60], [0x44, 0x33, 0x22, 0x11]);
+        assert_eq!(bytes[644..648], [0x00, 0x40, 0x00, 0x00]);
+        assert_eq!(
+            bytes[832..840],
+            [0xA8, 0xA7, 0xA6, 0xA5, 0xA4, 0xA3, 0xA2, 0xA1]
+        );
+        assert_eq!(bytes[860..864], [0x02, 0x00, 0x00, 0x00]);
+
+        let unpacked = Index::unpack(&bytes);
+        assert!(unpacked.is_ok_and(|u| {
+            let (size, count, caps) = (u.package_size, u.slot_count, u.capabilities);
+            size == 0x0102_0304_0506_0708 && count == 0x1122_3344 && caps == 0xA1A2_A3A4_A5A6_A7A8
+        }));
+    }
+
+    #[test]
+    fn test_smaller_index_block_is_rejected() {
+        let block = Index::new().pack();
+        assert!(
+            Index::unpack(&block[..4096])
+                .is_err_and(|e| e.to_string().contains("Invalid index size: 4096 != 8192"))
+        );
+    }
+
+    #[test]
+    fn test_check_compatibility() {
+        let mut index = Index::new();
+        assert!(index.check_compatibility().is_ok());
+
+        index.format_version = PSPF_VERSION - 1;
+        index.compatibility = PSPF_VERSION - 1;
+        assert!(index.check_compatibility().is_ok());
+
+        // A newer format that older readers can still read is fine
+        index.format_version = PSPF_VERSION + 1;
+        index.compatibility = PSPF_VERSION;
+        assert!(index.check_compatibility().is_ok());
+
+        index.compatibility = PSPF_VERSION + 1;
+        let error = index.check_compatibility();
+        assert!(error.is_err_and(|e| matches!(
+            e,
+            FlavorError::IncompatibleFormat { required, supported }
+                if required == PSPF_VERSION + 1 && supported == PSPF_VERSION
+        )));
+    }
+
+    #[test]
+    fn test_parse_trailer_reports_structural_problems() {
+        let bytes = Index::new().pack();
+        let mut trailer = PACKAGE_EMOJI_BYTES.to_vec();
+        trailer.extend_from_slice(&bytes);
+        trailer.extend_from_slice(MAGIC_WAND_EMOJI_BYTES);
+        assert!(Index::parse_trailer(&trailer).is_ok_and(|block| block == &bytes[..]));
+
+        let reason = |tail: &[u8]| match Index::parse_trailer(tail) {
+            Err(FlavorError::FormatError(reason)) => reason,
+            _ => String::new(),
+        };
+        assert!(reason(&trailer[trailer.len() - 100..]).contains("smaller than the 8200-byte"));
+        let mut no_wand = trailer.clone();
+        no_wand[MAGIC_TRAILER_SIZE - 1] = b'x';
+        assert!(reason(&no_wand).contains("missing 🪄"));
+
+        let mut no_box = trailer.clone();
+        no_box[0] = b'x';
+        assert!(reason(&no_box).contains("missing 📦"));
+
+        // Data before the trailer is ignored
+        let mut padded = vec![0u8; 16];
+        padded.extend_from_slice(&trailer);
+        assert_eq!(Index::locate_in_trailer(&padded), Some(&bytes[..]));
+    }
+
+    #[test]
+    fn test_crc32c_differs_from_adler32() {
+        let data = [0x5au8; 64];
+        assert_ne!(
+            Index::calculate_checksum(0, &data),
+            Index::calculate_checksum(INDEX_FLAG_CRC32C, &data)
+        );
+    }
+}
+
 // 📦🔧🏗️🪄
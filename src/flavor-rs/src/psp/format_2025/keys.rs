@@ -2,13 +2,62 @@
 
 use crate::api::BuildOptions;
 use crate::exceptions::{FlavorError, Result};
-use ed25519_dalek::{SigningKey, VerifyingKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use log::{debug, info, warn};
 use pem::parse;
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::Path;
 
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo; the raw key follows
+pub(crate) const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Source of the metadata signature for a package build
+///
+/// Implement this to sign with keys held in an HSM or cloud KMS; the builder
+/// only sees the public key and the finished Ed25519 signature, never the
+/// private key bytes.
+pub trait KeyProvider: std::fmt::Debug + Send + Sync {
+    /// Sign `message` (the uncompressed metadata JSON) with Ed25519
+    fn sign(&self, message: &[u8]) -> Result<Signature>;
+
+    /// Public key recorded in the index for signature verification
+    fn public_key(&self) -> VerifyingKey;
+}
+
+/// [`KeyProvider`] backed by a signing key held in process memory
+#[derive(Debug)]
+pub struct LocalKeyProvider {
+    signing_key: SigningKey,
+}
+
+impl LocalKeyProvider {
+    /// Wrap an existing signing key
+    pub fn new(signing_key: SigningKey) -> Self {
+        Self { signing_key }
+    }
+
+    /// Resolve keys from a seed, PEM files or an ephemeral keypair
+    ///
+    /// See [`load_or_generate_keys`] for the order in which options are tried.
+    pub fn from_options(options: &BuildOptions) -> Result<Self> {
+        let (signing_key, _) = load_or_generate_keys(options)?;
+        Ok(Self::new(signing_key))
+    }
+}
+
+impl KeyProvider for LocalKeyProvider {
+    fn sign(&self, message: &[u8]) -> Result<Signature> {
+        Ok(self.signing_key.sign(message))
+    }
+
+    fn public_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+}
+
 /// Load signing keys from PEM files or generate from seed
 pub fn load_or_generate_keys(options: &BuildOptions) -> Result<(SigningKey, VerifyingKey)> {
     // Try key seed first
@@ -85,12 +134,25 @@ fn load_keys_from_files(
             .map_err(|_| FlavorError::BuildError("Invalid private key length".to_string()))?,
     );
 
-    // Load and parse public key
-    let public_pem = fs::read_to_string(public_key_path)
-        .map_err(|e| FlavorError::BuildError(format!("Failed to read public key: {}", e)))?;
+    let verifying_key = load_public_key(public_key_path)?;
+
+    debug!("✅ Loaded keys from files");
+    Ok((signing_key, verifying_key))
+}
+
+/// Load an Ed25519 public key from a PEM file
+///
+/// Accepts SubjectPublicKeyInfo (`PUBLIC KEY`) and raw `ED25519 PUBLIC KEY` PEMs.
+pub fn load_public_key(public_key_path: &Path) -> Result<VerifyingKey> {
+    let public_pem = fs::read_to_string(public_key_path).map_err(|e| {
+        FlavorError::InvalidKey(format!(
+            "Failed to read public key {}: {e}",
+            public_key_path.display()
+        ))
+    })?;
 
     let public_parsed = parse(&public_pem)
-        .map_err(|e| FlavorError::BuildError(format!("Failed to parse public key PEM: {}", e)))?;
+        .map_err(|e| FlavorError::InvalidKey(format!("Failed to parse public key PEM: {e}")))?;
 
     // Extract the key bytes - handle both PKCS#8 and raw Ed25519 formats
     let public_bytes = if public_parsed.tag() == "PUBLIC KEY" {
@@ -103,7 +165,7 @@ fn load_keys_from_files(
             // Raw 32-byte key
             contents
         } else {
-            return Err(FlavorError::BuildError(
+            return Err(FlavorError::InvalidKey(
                 "Invalid public key format".to_string(),
             ));
         }
@@ -111,22 +173,27 @@ fn load_keys_from_files(
         // Raw Ed25519 key
         public_parsed.contents()
     } else {
-        return Err(FlavorError::BuildError(format!(
+        return Err(FlavorError::InvalidKey(format!(
             "Unsupported public key type: {}",
             public_parsed.tag()
         )));
     };
 
-    // Create verifying key from bytes
-    let verifying_key = VerifyingKey::from_bytes(
+    VerifyingKey::from_bytes(
         public_bytes
             .try_into()
-            .map_err(|_| FlavorError::BuildError("Invalid public key length".to_string()))?,
+            .map_err(|_| FlavorError::InvalidKey("Invalid public key length".to_string()))?,
     )
-    .map_err(|e| FlavorError::BuildError(format!("Invalid public key: {}", e)))?;
+    .map_err(|e| FlavorError::InvalidKey(format!("Invalid public key: {e}")))
+}
 
-    debug!("✅ Loaded keys from files");
-    Ok((signing_key, verifying_key))
+/// Encode an Ed25519 public key as a SubjectPublicKeyInfo (`PUBLIC KEY`) PEM
+///
+/// The inverse of [`load_public_key`], and readable by OpenSSL.
+pub fn public_key_pem(public_key: &VerifyingKey) -> String {
+    let mut spki = ED25519_SPKI_PREFIX.to_vec();
+    spki.extend_from_slice(public_key.as_bytes());
+    pem::encode(&pem::Pem::new("PUBLIC KEY", spki))
 }
 
 /// Generate deterministic keys from a seed string
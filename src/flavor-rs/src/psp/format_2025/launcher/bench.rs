@@ -0,0 +1,194 @@
+//! Cold and warm launch timings for `flavor bench`
+//!
+//! Every run prepares the workenv exactly as a real launch would but stops
+//! before executing the package command, so only launcher overhead is measured.
+
+use super::launch_timed;
+use crate::api::LaunchOptions;
+use crate::exceptions::{FlavorError, Result};
+use crate::psp::format_2025::paths::WorkenvPaths;
+use log::info;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Time spent in each phase of a single launch
+#[derive(Debug, Clone, Default)]
+pub struct LaunchTimings {
+    /// Package integrity checks (checksums and signature)
+    pub verification: Duration,
+    /// Workenv cache validation
+    pub cache_check: Duration,
+    /// Slot extraction into the temp directory (zero on a cache hit)
+    pub extraction: Duration,
+    /// Setup commands (zero on a cache hit)
+    pub setup: Duration,
+    /// From launcher start until the command would be prepared
+    pub total: Duration,
+    /// True when the existing workenv was reused
+    pub cache_hit: bool,
+}
+
+/// One measured launch, in milliseconds
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchRun {
+    pub cache_hit: bool,
+    pub verification_ms: f64,
+    pub cache_check_ms: f64,
+    pub extraction_ms: f64,
+    pub setup_ms: f64,
+    /// Everything not covered by the phases above (metadata, locking, moves)
+    pub other_ms: f64,
+    pub total_ms: f64,
+}
+
+impl From<&LaunchTimings> for BenchRun {
+    fn from(t: &LaunchTimings) -> Self {
+        let phases = t.verification + t.cache_check + t.extraction + t.setup;
+        Self {
+            cache_hit: t.cache_hit,
+            verification_ms: millis(t.verification),
+            cache_check_ms: millis(t.cache_check),
+            extraction_ms: millis(t.extraction),
+            setup_ms: millis(t.setup),
+            other_ms: millis(t.total.saturating_sub(phases)),
+            total_ms: millis(t.total),
+        }
+    }
+}
+
+/// Mean, min and max of one phase across runs
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PhaseStats {
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Cold and warm launch timings for a package
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub package: String,
+    pub iterations: usize,
+    pub cold: Vec<BenchRun>,
+    pub warm: Vec<BenchRun>,
+    /// Per-phase statistics over the cold runs
+    pub cold_summary: BTreeMap<&'static str, PhaseStats>,
+    /// Per-phase statistics over the warm runs
+    pub warm_summary: BTreeMap<&'static str, PhaseStats>,
+}
+
+/// Measure `iterations` cold and `iterations` warm launches of a package
+///
+/// Cold runs each use a fresh workenv in a temporary directory, so they pay for
+/// extraction and setup. Warm runs reuse one prepared workenv and only pay for
+/// verification and cache validation. The user's own workenv is never touched.
+pub fn bench(package_path: &Path, iterations: usize) -> Result<BenchReport> {
+    if iterations == 0 {
+        return Err(FlavorError::ConfigError(
+            "Benchmark needs at least one iteration".to_string(),
+        ));
+    }
+    if std::env::var_os("FLAVOR_WORKENV").is_some() {
+        return Err(FlavorError::ConfigError(
+            "FLAVOR_WORKENV is set; unset it so the benchmark can use a fresh workenv".to_string(),
+        ));
+    }
+
+    info!("⏱️ Benchmarking {package_path:?} with {iterations} cold and warm launches");
+    let mut cold = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let scratch = tempfile::tempdir()?;
+        cold.push(timed_launch(package_path, scratch.path())?);
+    }
+
+    let scratch = tempfile::tempdir()?;
+    timed_launch(package_path, scratch.path())?;
+    let mut warm = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let run = timed_launch(package_path, scratch.path())?;
+        if !run.cache_hit {
+            return Err(FlavorError::LaunchError(
+                "Warm launch re-extracted the workenv; is FLAVOR_WORKENV_CACHE disabled?"
+                    .to_string(),
+            ));
+        }
+        warm.push(run);
+    }
+
+    Ok(BenchReport {
+        package: package_path.display().to_string(),
+        iterations,
+        cold_summary: summarize(&cold),
+        warm_summary: summarize(&warm),
+        cold,
+        warm,
+    })
+}
+
+/// Prepare the workenv under `cache_dir` and return the phase timings
+fn timed_launch(package_path: &Path, cache_dir: &Path) -> Result<BenchRun> {
+    let workenv = WorkenvPaths::new(cache_dir.to_path_buf(), package_path).workenv();
+    let options = LaunchOptions {
+        workdir: Some(workenv.display().to_string()),
+        ..LaunchOptions::default()
+    };
+    let mut timings = LaunchTimings::default();
+    launch_timed(package_path, &[], options, &mut timings, true)?;
+    Ok(BenchRun::from(&timings))
+}
+
+/// Phase name and accessor for its duration in a run
+type Phase = (&'static str, fn(&BenchRun) -> f64);
+
+fn summarize(runs: &[BenchRun]) -> BTreeMap<&'static str, PhaseStats> {
+    let phases: [Phase; 6] = [
+        ("verification", |r| r.verification_ms),
+        ("cache_check", |r| r.cache_check_ms),
+        ("extraction", |r| r.extraction_ms),
+        ("setup", |r| r.setup_ms),
+        ("other", |r| r.other_ms),
+        ("total", |r| r.total_ms),
+    ];
+    phases
+        .into_iter()
+        .map(|(name, value)| (name, stats(runs.iter().map(value))))
+        .collect()
+}
+
+fn stats(values: impl Iterator<Item = f64>) -> PhaseStats {
+    let (mut sum, mut count) = (0.0, 0usize);
+    let (mut min_ms, mut max_ms) = (f64::INFINITY, 0.0_f64);
+    for value in values {
+        sum += value;
+        count += 1;
+        min_ms = min_ms.min(value);
+        max_ms = max_ms.max(value);
+    }
+    if count == 0 {
+        min_ms = 0.0;
+    }
+    PhaseStats {
+        mean_ms: sum / count.max(1) as f64,
+        min_ms,
+        max_ms,
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_stats() {
+        let summary = stats([3.0, 1.0, 2.0].into_iter());
+        assert!((summary.mean_ms - 2.0).abs() < f64::EPSILON);
+        assert!((summary.min_ms - 1.0).abs() < f64::EPSILON);
+        assert!((summary.max_ms - 3.0).abs() < f64::EPSILON);
+    }
+}
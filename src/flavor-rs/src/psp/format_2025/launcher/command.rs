@@ -1,20 +1,25 @@
 //! Command preparation and environment setup
 
-use super::super::execution::substitute_placeholders;
-use super::super::metadata::Metadata;
+use super::super::execution::{command_words, substitute_placeholders};
+use super::super::metadata::{ExecutionInfo, Metadata};
 use super::super::runtime::process_runtime_env;
 use crate::exceptions::{FlavorError, Result};
 use log::debug;
 use std::collections::HashMap;
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Resolve executable path using PATH environment variable
 ///
-/// Handles absolute Unix paths (e.g., /usr/bin/python3) by extracting the basename.
+/// Absolute paths that exist (e.g. a hook extracted into the workenv) are kept.
+/// Other absolute Unix paths (e.g., /usr/bin/python3) are resolved by their basename.
 /// On Windows, this handles .exe extension resolution automatically.
 /// Falls back to the basename if resolution fails.
 pub fn resolve_executable(executable: &str) -> String {
+    if Path::new(executable).is_absolute() && Path::new(executable).is_file() {
+        return executable.to_string();
+    }
+
     // If it's an absolute Unix path (starts with /), extract just the basename
     // This handles cases like "/usr/bin/python3" -> "python3"
     let exec_name = if executable.starts_with('/') {
@@ -77,23 +82,75 @@ pub fn resolve_executable(executable: &str) -> String {
     }
 }
 
+/// Choose the command to run: a named entrypoint or `execution.command`
+///
+/// An explicitly requested `entrypoint` must exist. Otherwise the basename of
+/// `argv0` (with any extension dropped) selects an entrypoint of that name, so
+/// a package symlinked as `ls` runs its `ls` entrypoint; anything else falls
+/// back to the default command.
+pub(super) fn select_command<'a>(
+    execution: &'a ExecutionInfo,
+    entrypoint: Option<&str>,
+    argv0: Option<&str>,
+) -> Result<&'a str> {
+    if let Some(name) = entrypoint {
+        return match execution.entrypoints.get(name) {
+            Some(command) => {
+                debug!("🚪 Using requested entrypoint '{name}'");
+                Ok(command)
+            }
+            None if execution.entrypoints.is_empty() => Err(FlavorError::LaunchError(format!(
+                "Entrypoint '{name}' requested, but the package defines no entrypoints"
+            ))),
+            None => Err(FlavorError::LaunchError(format!(
+                "Unknown entrypoint '{name}' (available: {})",
+                execution
+                    .entrypoints
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        };
+    }
+
+    let invoked_as = argv0.map(Path::new).and_then(|argv0| {
+        [argv0.file_name(), argv0.file_stem()]
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.to_str())
+            .find(|name| execution.entrypoints.contains_key(*name))
+    });
+    match invoked_as {
+        Some(name) => {
+            debug!("🚪 Invoked as '{name}', using its entrypoint");
+            Ok(&execution.entrypoints[name])
+        }
+        None => Ok(&execution.command),
+    }
+}
+
 /// Prepare the command to execute
+///
+/// `command` is the one picked by [`select_command`]. When `strict_env` is
+/// set, missing required `runtime.env.pass` variables abort the launch instead
+/// of being logged. `env_allowlist` names are copied from the host after
+/// `runtime.env` is applied (see [`apply_env_allowlist`]).
 pub(super) fn prepare_command(
     metadata: &Metadata,
+    command: &str,
     workenv_path: &Path,
     package_path: &Path,
     args: &[String],
+    strict_env: bool,
+    env_allowlist: &[String],
 ) -> Result<(String, Vec<String>, HashMap<String, String>)> {
-    // Substitute placeholders in command
-    let command =
-        substitute_placeholders(&metadata.execution.command, workenv_path, &metadata.package);
+    // Split command into words, then substitute placeholders in each
+    let mut command_parts = command_words(command, workenv_path, &metadata.package)?;
 
-    debug!("🎯 Final command: {command}");
-
-    // Split command into parts
-    let mut command_parts: Vec<String> = command.split_whitespace().map(String::from).collect();
+    debug!("🎯 Final command: {command_parts:?}");
     if command_parts.is_empty() {
-        return Err(FlavorError::Generic("No command specified".to_string()));
+        return Err(FlavorError::LaunchError("No command specified".to_string()));
     }
 
     let executable = command_parts.remove(0);
@@ -105,6 +162,10 @@ pub(super) fn prepare_command(
 
     // Prepare environment
     let mut env_map: HashMap<String, String> = env::vars().collect();
+    // Meant for this launcher only; a packaged launcher must find its own data
+    // and pick its own entrypoint
+    env_map.remove("FLAVOR_DATA_FILE");
+    env_map.remove("FLAVOR_ENTRYPOINT");
 
     // Set FLAVOR_CACHE to the HOST's cache directory BEFORE workenv env is applied
     // This ensures we use the HOST's HOME, not the workenv's HOME
@@ -125,9 +186,10 @@ pub(super) fn prepare_command(
     if let Some(runtime_info) = &metadata.runtime {
         if let Some(runtime_env) = &runtime_info.env {
             debug!("🔄 Processing runtime.env configuration");
-            process_runtime_env(&mut env_map, runtime_env);
+            process_runtime_env(&mut env_map, runtime_env, strict_env)?;
         }
     }
+    apply_env_allowlist(&mut env_map, env_allowlist, |name| env::var(name).ok());
 
     // Add workenv environment variables (layer 2)
     if let Some(ref workenv_info) = metadata.workenv {
@@ -179,3 +241,195 @@ pub(super) fn prepare_command(
 
     Ok((executable, all_args, env_map))
 }
+
+/// Working directory for the child process
+///
+/// `execution.workdir` is expanded like the command (`{workenv}`, `{home}`,
+/// ...) and resolved against the workenv when relative. Without it the child
+/// inherits the launcher's current directory.
+pub(super) fn resolve_workdir(metadata: &Metadata, workenv_path: &Path) -> Result<PathBuf> {
+    let Some(workdir) = metadata.execution.workdir.as_deref() else {
+        return Ok(env::current_dir()?);
+    };
+
+    let workdir = workenv_path.join(substitute_placeholders(
+        workdir,
+        workenv_path,
+        &metadata.package,
+    ));
+    if !workdir.is_dir() {
+        return Err(FlavorError::LaunchError(format!(
+            "Working directory does not exist: {}",
+            workdir.display()
+        )));
+    }
+    debug!("📂 Using package working directory: {}", workdir.display());
+    Ok(workdir)
+}
+
+/// The packager's `execution.argv0`, if set
+///
+/// When set it replaces the launcher's default (the package name for binaries
+/// in exec mode, untouched otherwise) for scripts and in spawn mode too.
+pub(super) fn explicit_argv0(execution: &ExecutionInfo) -> Result<Option<&str>> {
+    match execution.argv0.as_deref() {
+        Some(argv0) if argv0.is_empty() || argv0.contains('\0') => Err(FlavorError::LaunchError(
+            "execution.argv0 must be non-empty and contain no NUL bytes".to_string(),
+        )),
+        argv0 => Ok(argv0),
+    }
+}
+
+/// Copy allowlisted variables from the host into the child environment
+///
+/// Runs after `runtime.env`, so a host value replaces anything its `unset`,
+/// `map` or `set` operations produced for that name. Variables the host does
+/// not have are left alone. `workenv.env` and `execution.env` are applied
+/// later and still take precedence.
+fn apply_env_allowlist(
+    env_map: &mut HashMap<String, String>,
+    allowlist: &[String],
+    host_var: impl Fn(&str) -> Option<String>,
+) {
+    for name in allowlist {
+        if let Some(value) = host_var(name) {
+            debug!("🔓 Passing allowlisted variable: {name}");
+            env_map.insert(name.clone(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_allowlist_overrides_runtime_env() {
+        let host: HashMap<&str, &str> = [("TERM", "xterm"), ("SSH_AUTH_SOCK", "/tmp/agent")].into();
+        let mut env_map: HashMap<String, String> =
+            [("TERM".to_string(), "dumb".to_string())].into();
+
+        apply_env_allowlist(
+            &mut env_map,
+            &[
+                "TERM".to_string(),
+                "SSH_AUTH_SOCK".to_string(),
+                "MISSING".to_string(),
+            ],
+            |name| host.get(name).map(|v| v.to_string()),
+        );
+
+        assert_eq!(env_map.get("TERM").map(String::as_str), Some("xterm"));
+        assert_eq!(
+            env_map.get("SSH_AUTH_SOCK").map(String::as_str),
+            Some("/tmp/agent")
+        );
+        assert!(!env_map.contains_key("MISSING"));
+    }
+
+    #[test]
+    fn test_resolve_workdir_expands_against_workenv() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let workenv = dir.path();
+        assert!(std::fs::create_dir_all(workenv.join("app/bin")).is_ok());
+
+        let metadata = serde_json::from_value::<Metadata>(serde_json::json!({
+            "format": "PSPF/2025",
+            "package": {"name": "tool", "version": "1.0"},
+            "slots": [],
+            "execution": {"primary_slot": 0, "command": "bin/tool"}
+        }));
+        assert!(metadata.is_ok());
+        let Ok(mut metadata) = metadata else { return };
+
+        // No workdir: inherit the caller's directory
+        assert!(
+            resolve_workdir(&metadata, workenv)
+                .is_ok_and(|d| env::current_dir().is_ok_and(|cwd| d == cwd))
+        );
+
+        metadata.execution.workdir = Some("{workenv}/app".to_string());
+        assert!(resolve_workdir(&metadata, workenv).is_ok_and(|d| d == workenv.join("app")));
+
+        metadata.execution.workdir = Some("app/bin".to_string());
+        assert!(resolve_workdir(&metadata, workenv).is_ok_and(|d| d == workenv.join("app/bin")));
+
+        metadata.execution.workdir = Some("missing".to_string());
+        assert!(resolve_workdir(&metadata, workenv).is_err());
+    }
+
+    #[test]
+    fn test_select_command_by_name_and_argv0() {
+        let execution = serde_json::from_value::<ExecutionInfo>(serde_json::json!({
+            "primary_slot": 0,
+            "command": "{workenv}/bin/box",
+            "entrypoints": {"ls": "{workenv}/bin/box ls", "cat": "{workenv}/bin/box cat"}
+        }));
+        assert!(execution.is_ok());
+        let Ok(execution) = execution else { return };
+
+        assert!(select_command(&execution, Some("cat"), None).is_ok_and(|c| c.ends_with("cat")));
+        let unknown = select_command(&execution, Some("rm"), Some("ls"));
+        assert!(unknown.is_err_and(|e| e.to_string().contains("available: cat, ls")));
+
+        assert!(
+            select_command(&execution, None, Some("/usr/local/bin/ls"))
+                .is_ok_and(|c| c.ends_with("ls"))
+        );
+        assert!(
+            select_command(&execution, None, Some("./ls.psp")).is_ok_and(|c| c.ends_with("ls"))
+        );
+        assert!(
+            select_command(&execution, None, Some("./box.psp"))
+                .is_ok_and(|c| c == "{workenv}/bin/box")
+        );
+        assert!(select_command(&execution, None, None).is_ok_and(|c| c == "{workenv}/bin/box"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_executable_keeps_existing_absolute_paths() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let hook = dir.path().join("on exit.sh");
+        assert!(std::fs::write(&hook, "#!/bin/sh\n").is_ok());
+        let hook = hook.display().to_string();
+        assert_eq!(resolve_executable(&hook), hook);
+
+        // A missing absolute path still falls back to its basename
+        let missing = dir.path().join("no-such-tool-xyz").display().to_string();
+        assert_eq!(resolve_executable(&missing), "no-such-tool-xyz");
+    }
+
+    #[test]
+    fn test_explicit_argv0() {
+        let execution = |argv0: serde_json::Value| {
+            serde_json::from_value::<ExecutionInfo>(serde_json::json!({
+                "primary_slot": 0,
+                "command": "{workenv}/bin/busybox",
+                "argv0": argv0
+            }))
+        };
+        let unset = execution(serde_json::Value::Null);
+        assert!(unset.is_ok());
+        let Ok(unset) = unset else {
+            return;
+        };
+        assert!(explicit_argv0(&unset).is_ok_and(|a| a.is_none()));
+        let set = execution("sh".into());
+        assert!(set.is_ok());
+        let Ok(set) = set else {
+            return;
+        };
+        assert!(explicit_argv0(&set).is_ok_and(|a| a == Some("sh")));
+        let empty = execution("".into());
+        assert!(empty.is_ok());
+        let Ok(empty) = empty else {
+            return;
+        };
+        assert!(explicit_argv0(&empty).is_err());
+    }
+}
@@ -1,29 +1,46 @@
 //! Slot extraction utilities
 
-use super::super::metadata::Metadata;
+use super::super::condition::Condition;
+use super::super::constants::SBOM_SLOT_ID;
+use super::super::execution::resolve_slot_target;
+use super::super::extraction::{ExtractionTracker, extract_slot_tracked};
+use super::super::locking::is_extraction_complete;
+use super::super::metadata::{InheritedSlot, Metadata};
+use super::super::paths::WorkenvPaths;
 use super::super::reader::Reader;
-use crate::exceptions::Result;
+use crate::exceptions::{FlavorError, Result};
 use log::{debug, error, info};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 
 /// Extract slots from the package
+///
+/// Inherited slots of a delta package are copied from the base package's
+/// workenv, looked up next to `paths` in the same cache.
 pub(super) fn extract_slots(
     reader: &mut Reader,
+    paths: &WorkenvPaths,
     workenv_path: &Path,
+    mut tracker: ExtractionTracker,
 ) -> Result<(HashMap<usize, PathBuf>, Vec<PathBuf>)> {
     // Re-read metadata inside this function to avoid borrow issues
     debug!("📖 Reading metadata for slot extraction");
     let metadata = match reader.read_metadata() {
         Ok(m) => m.clone(),
         Err(e) => {
-            error!("🚨 Failed to read metadata: {}", e);
+            error!("🚨 Failed to read metadata: {:#}", e);
             return Err(e);
         }
     };
     let mut slot_paths = HashMap::new();
     let mut init_paths = Vec::new();
 
+    if metadata.slots.is_empty() {
+        info!("📭 Package has no slots, nothing to extract");
+        return Ok((slot_paths, init_paths));
+    }
     info!("📤 Extracting {} slots...", metadata.slots.len());
 
     // Print extraction progress to stderr
@@ -34,6 +51,19 @@ pub(super) fn extract_slots(
     // Extract slots by index
     for i in 0..metadata.slots.len() {
         let slot = &metadata.slots[i];
+        if slot.id == SBOM_SLOT_ID {
+            debug!("📋 Skipping SBOM slot {i} (read on demand, not part of the workenv)");
+            continue;
+        }
+        if let Some(ref condition) = slot.condition {
+            if !Condition::parse(condition)?.is_met() {
+                info!(
+                    "⏭️ Skipping slot {}: condition '{condition}' not met",
+                    slot.id
+                );
+                continue;
+            }
+        }
         debug!(
             "📦 Extracting slot {}: {} ({} bytes)",
             slot.index, slot.id, slot.size
@@ -58,9 +88,14 @@ pub(super) fn extract_slots(
         // The extract_slot function will use the metadata to determine the target path
 
         // Extract the slot to workenv (it will use metadata.target internally)
-        reader.extract_slot(i, workenv_path)?;
+        if let Some(ref inherited) = slot.inherited {
+            let base = paths.sibling(base_workenv_name(inherited, &slot.id)?);
+            copy_inherited_slot(inherited, &base, workenv_path, &mut tracker, &slot.id)?;
+        } else {
+            extract_slot_tracked(reader, i, workenv_path, &mut tracker)?;
+        }
 
-        let extracted_path = workenv_path.join(&slot.target);
+        let extracted_path = workenv_path.join(resolve_slot_target(&slot.target));
         debug!("✅ Extracted to: {extracted_path:?}");
 
         // Track init slots for later cleanup (removed after initialization)
@@ -75,6 +110,122 @@ pub(super) fn extract_slots(
     Ok((slot_paths, init_paths))
 }
 
+/// The base package's workenv name, which must be a single path component
+fn base_workenv_name<'a>(inherited: &'a InheritedSlot, slot_id: &str) -> Result<&'a str> {
+    let name = inherited.package.as_str();
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(FlavorError::ExtractionError(format!(
+            "Slot '{slot_id}' names an invalid base package '{name}'"
+        )));
+    }
+    Ok(name)
+}
+
+/// Copy an inherited slot's files from the base package's workenv
+///
+/// The base workenv must be completely extracted from the exact build the
+/// delta was made against. Files are copied rather than linked, so writes in
+/// either workenv never reach the other, and each copy is checked against the
+/// digest recorded when the delta was built.
+fn copy_inherited_slot(
+    inherited: &InheritedSlot,
+    base: &WorkenvPaths,
+    dest_dir: &Path,
+    tracker: &mut ExtractionTracker,
+    slot_id: &str,
+) -> Result<()> {
+    let cached_checksum = fs::read_to_string(base.checksum_file()).unwrap_or_default();
+    if !is_extraction_complete(base) || cached_checksum.trim() != inherited.package_checksum {
+        return Err(FlavorError::ExtractionError(format!(
+            "Slot '{slot_id}' is inherited from base package '{}' (checksum {}), but {} is missing or holds a different build; run the base package first",
+            inherited.package,
+            inherited.package_checksum,
+            base.workenv().display()
+        )));
+    }
+    if inherited.file_checksums.len() != inherited.files.len() {
+        return Err(FlavorError::ExtractionError(format!(
+            "Slot '{slot_id}' records no checksums for its inherited files; rebuild the delta package"
+        )));
+    }
+
+    debug!(
+        "🧬 Copying {} files for slot '{slot_id}' from base slot '{}'",
+        inherited.files.len(),
+        inherited.slot_id
+    );
+    let base_dir = base.workenv();
+    for (file, digest) in inherited.files.iter().zip(&inherited.file_checksums) {
+        let relative = Path::new(file);
+        if file.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(FlavorError::ExtractionError(format!(
+                "Slot '{slot_id}' inherits a file outside the workenv: {file}"
+            )));
+        }
+        if !tracker.claim(relative, slot_id)? {
+            continue;
+        }
+        let source = base_dir.join(relative);
+        let dest = dest_dir.join(relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Never write through an earlier symlink
+        if dest.symlink_metadata().is_ok() {
+            fs::remove_file(&dest)?;
+        }
+        if let Some(target) = digest.strip_prefix("symlink:") {
+            copy_inherited_symlink(&source, &dest, target)?;
+        } else {
+            copy_inherited_file(&source, &dest, digest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy a regular file from the base workenv, checking it against `digest`
+fn copy_inherited_file(source: &Path, dest: &Path, digest: &str) -> Result<()> {
+    fs::copy(source, dest).map_err(|e| {
+        FlavorError::ExtractionError(format!(
+            "Failed to copy {} from the base workenv: {e}",
+            source.display()
+        ))
+    })?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut fs::File::open(dest)?, &mut hasher)?;
+    let actual = format!("sha256:{}", hex::encode(hasher.finalize()));
+    if actual != digest {
+        let _ = fs::remove_file(dest);
+        return Err(FlavorError::ChecksumMismatch {
+            subject: format!("inherited file {}", source.display()),
+            expected: digest.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Recreate a symlink from the base workenv, checking its target
+fn copy_inherited_symlink(source: &Path, dest: &Path, target: &str) -> Result<()> {
+    let actual = fs::read_link(source)?;
+    if actual != Path::new(target) {
+        return Err(FlavorError::ChecksumMismatch {
+            subject: format!("inherited symlink {}", source.display()),
+            expected: target.to_string(),
+            actual: actual.display().to_string(),
+        });
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&actual, dest)?;
+    #[cfg(not(unix))]
+    fs::copy(source, dest)?;
+    Ok(())
+}
+
 /// Build slot paths without extraction (when cache is valid)
 pub(super) fn build_slot_paths(
     metadata: &Metadata,
@@ -84,9 +235,81 @@ pub(super) fn build_slot_paths(
 
     for slot in &metadata.slots {
         // Target field specifies where to extract (relative to workenv)
-        let slot_path = workenv_path.join(&slot.target);
+        let slot_path = workenv_path.join(resolve_slot_target(&slot.target));
         slot_paths.insert(slot.index, slot_path);
     }
 
     slot_paths
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::execution::save_package_checksum;
+    use super::super::super::locking::mark_extraction_complete;
+    use super::*;
+
+    #[test]
+    fn test_inherited_slot_copies_from_complete_base() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let base = WorkenvPaths::new(dir.path().to_path_buf(), Path::new("base.psp"));
+        let dest = dir.path().join("delta-tmp");
+        let inherited = InheritedSlot {
+            package: "base".to_string(),
+            package_checksum: "0000abcd".to_string(),
+            slot_id: "runtime".to_string(),
+            checksum: String::new(),
+            files: vec!["lib/runtime.bin".to_string()],
+            file_checksums: vec![format!("sha256:{}", hex::encode(Sha256::digest(b"shared")))],
+        };
+        let link = |tracker: &mut ExtractionTracker| {
+            copy_inherited_slot(&inherited, &base, &dest, tracker, "runtime")
+        };
+
+        assert!(fs::create_dir_all(base.workenv().join("lib")).is_ok());
+        assert!(fs::write(base.workenv().join("lib/runtime.bin"), b"shared").is_ok());
+        assert!(link(&mut ExtractionTracker::default()).is_err());
+
+        assert!(mark_extraction_complete(&base).is_ok());
+        assert!(save_package_checksum(&base, 0xabcd).is_ok());
+        assert!(link(&mut ExtractionTracker::default()).is_ok());
+        assert!(fs::read(dest.join("lib/runtime.bin")).is_ok_and(|data| data == b"shared"));
+
+        // The copy is independent of the base's file
+        assert!(fs::write(dest.join("lib/runtime.bin"), b"patched").is_ok());
+        assert!(
+            fs::read(base.workenv().join("lib/runtime.bin")).is_ok_and(|data| data == b"shared")
+        );
+
+        // A modified base file is refused
+        assert!(fs::write(base.workenv().join("lib/runtime.bin"), b"tampered").is_ok());
+        let tampered = link(&mut ExtractionTracker::default());
+        assert!(matches!(
+            tampered.as_ref().map_err(FlavorError::kind),
+            Err(FlavorError::ChecksumMismatch { .. })
+        ));
+        assert!(!dest.join("lib/runtime.bin").exists());
+
+        assert!(fs::write(base.workenv().join("lib/runtime.bin"), b"shared").is_ok());
+        assert!(save_package_checksum(&base, 0x1234).is_ok());
+        assert!(link(&mut ExtractionTracker::default()).is_err());
+    }
+
+    #[test]
+    fn test_inherited_slot_rejects_escaping_names() {
+        let mut inherited = InheritedSlot {
+            package: "base".to_string(),
+            package_checksum: String::new(),
+            slot_id: "runtime".to_string(),
+            checksum: String::new(),
+            files: Vec::new(),
+            file_checksums: Vec::new(),
+        };
+        assert!(base_workenv_name(&inherited, "runtime").is_ok_and(|name| name == "base"));
+        for name in ["", ".", "..", "../base", "cache/base", "..\\base"] {
+            inherited.package = name.to_string();
+            assert!(base_workenv_name(&inherited, "runtime").is_err(), "{name}");
+        }
+    }
+}
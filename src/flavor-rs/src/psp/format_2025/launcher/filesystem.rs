@@ -1,10 +1,15 @@
 //! Filesystem utilities for package extraction
 
 use crate::exceptions::Result;
-use log::debug;
+use log::{debug, warn};
 use std::fs;
-use std::io::{Read, Write};
-use std::path::Path;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Longest shebang line, `#!` included, that every kernel reads in full
+///
+/// Linux before 5.1 silently truncated anything past 127 bytes.
+const MAX_SHEBANG_LEN: usize = 127;
 
 /// Helper function to recursively copy a directory
 pub(super) fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
@@ -23,7 +28,74 @@ pub(super) fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Move `source` to `dest`, copying only when they are on different filesystems
+///
+/// Other rename failures are returned rather than papered over with a copy: on
+/// a network filesystem they are usually transient, and copying a tree that is
+/// only partly visible would hide the problem. A failed copy removes whatever
+/// it wrote to `dest`.
+pub(super) fn move_into_place(source: &Path, dest: &Path) -> Result<()> {
+    match fs::rename(source, dest) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            debug!(
+                "{} is on another filesystem, copying instead",
+                dest.display()
+            );
+            let copied = if source.is_dir() {
+                copy_dir_all(source, dest)
+            } else {
+                fs::copy(source, dest).map(|_| ()).map_err(Into::into)
+            };
+            if let Err(e) = copied {
+                let _ = if dest.is_dir() {
+                    fs::remove_dir_all(dest)
+                } else {
+                    fs::remove_file(dest)
+                };
+                return Err(e);
+            }
+            if source.is_dir() {
+                fs::remove_dir_all(source)?;
+            } else {
+                fs::remove_file(source)?;
+            }
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Move each top-level entry of `source_dir` into `dest_dir`, replacing
+/// entries already there
+pub(super) fn move_contents_into(source_dir: &Path, dest_dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(source_dir)? {
+        let entry = entry?;
+        let source = entry.path();
+        let dest = dest_dir.join(entry.file_name());
+
+        // Remove destination if it exists (for overwrite)
+        if dest.is_dir() {
+            fs::remove_dir_all(&dest)?;
+        } else if dest.exists() {
+            fs::remove_file(&dest)?;
+        }
+
+        debug!("Moving {:?} to {:?}", source, dest);
+        move_into_place(&source, &dest)?;
+    }
+    Ok(())
+}
+
 /// Fix shebangs in scripts after atomic move
+///
+/// Interpreter paths under `old_prefix` are moved to `new_prefix`.
+/// `#!/usr/bin/env NAME` and direct interpreter paths such as
+/// `#!/usr/bin/python3` are pointed at `bin_dir/NAME` when the package bundles
+/// that interpreter; system interpreters the package doesn't bundle are left
+/// alone. A rewritten line longer than [`MAX_SHEBANG_LEN`] becomes an `exec`
+/// trampoline for Python scripts, below any coding line, and an `env` lookup
+/// otherwise, which the launcher's `PATH` resolves to the bundled interpreter.
 pub(super) fn fix_shebangs(bin_dir: &Path, old_prefix: &Path, new_prefix: &Path) -> Result<()> {
     if !bin_dir.exists() {
         return Ok(());
@@ -32,50 +104,298 @@ pub(super) fn fix_shebangs(bin_dir: &Path, old_prefix: &Path, new_prefix: &Path)
     for entry in fs::read_dir(bin_dir)? {
         let entry = entry?;
         let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
 
-        if path.is_file() {
-            // Read first few bytes to check for shebang
-            let mut file = fs::File::open(&path)?;
-            let mut header = [0u8; 2];
-            if file.read_exact(&mut header).is_ok() && &header == b"#!" {
-                // Read entire file
-                file = fs::File::open(&path)?;
-                let mut content = Vec::new();
-                file.read_to_end(&mut content)?;
-
-                // Find end of first line
-                if let Some(newline_pos) = content.iter().position(|&b| b == b'\n') {
-                    let first_line = &content[0..newline_pos];
-                    let old_prefix_str = old_prefix.to_string_lossy();
-                    let old_prefix_bytes = old_prefix_str.as_bytes();
-
-                    // Check if the shebang contains the old prefix
-                    if first_line
-                        .windows(old_prefix_bytes.len())
-                        .any(|window| window == old_prefix_bytes)
-                    {
-                        // Replace old prefix with new prefix in first line
-                        let mut new_content = Vec::new();
-                        let first_line_str = String::from_utf8_lossy(first_line);
-                        let new_prefix_str = new_prefix.to_string_lossy();
-                        let new_first_line = first_line_str
-                            .replace(old_prefix_str.as_ref(), new_prefix_str.as_ref());
-                        new_content.extend_from_slice(new_first_line.as_bytes());
-                        new_content.extend_from_slice(&content[newline_pos..]);
-
-                        // Write back the modified content
-                        let mut file = fs::File::create(&path)?;
-                        file.write_all(&new_content)?;
-
-                        debug!(
-                            "Fixed shebang in {:?}",
-                            path.file_name().unwrap_or_default()
-                        );
-                    }
-                }
-            }
+        // Read first few bytes to check for shebang
+        let mut file = fs::File::open(&path)?;
+        let mut header = [0u8; 2];
+        if file.read_exact(&mut header).is_err() || &header != b"#!" {
+            continue;
         }
+        let mut content = Vec::new();
+        fs::File::open(&path)?.read_to_end(&mut content)?;
+
+        let line_end = content
+            .iter()
+            .position(|&b| b == b'\n')
+            .unwrap_or(content.len());
+        let Ok(first_line) = std::str::from_utf8(&content[..line_end]) else {
+            continue;
+        };
+        let bundled = |name: &str| {
+            let candidate = bin_dir.join(name);
+            (candidate != path && candidate.is_file()).then_some(candidate)
+        };
+        let Some(new_header) = rewrite_shebang(first_line, old_prefix, new_prefix, bundled) else {
+            continue;
+        };
+        let (new_header, rest) = keep_coding_line(new_header, &content[line_end..]);
+
+        let mut new_content = new_header.into_bytes();
+        new_content.extend_from_slice(rest);
+        let mut file = fs::File::create(&path)?;
+        file.write_all(&new_content)?;
+
+        debug!(
+            "Fixed shebang in {:?}",
+            path.file_name().unwrap_or_default()
+        );
     }
 
     Ok(())
 }
+
+/// Work out the replacement for a script's shebang line, if it needs one
+///
+/// `bundled` maps an interpreter name to the package's own copy of it. The
+/// returned header has no trailing newline and may span several lines.
+fn rewrite_shebang(
+    line: &str,
+    old_prefix: &Path,
+    new_prefix: &Path,
+    bundled: impl Fn(&str) -> Option<PathBuf>,
+) -> Option<String> {
+    let body = line.strip_prefix("#!")?.trim();
+    let old_prefix = old_prefix.to_string_lossy();
+    let moved = if old_prefix.is_empty() {
+        body.to_string()
+    } else {
+        body.replace(old_prefix.as_ref(), &new_prefix.to_string_lossy())
+    };
+    let (interpreter, args) = split_first_word(&moved);
+
+    let (target, args) = if Path::new(interpreter).starts_with(new_prefix) {
+        if moved == body {
+            return None;
+        }
+        (PathBuf::from(interpreter), args)
+    } else if Path::new(interpreter).file_name()? == "env" {
+        let (name, args) = split_first_word(args);
+        // Options such as `env -S` are left to env
+        if name.is_empty() || name.starts_with('-') {
+            return None;
+        }
+        (bundled(name)?, args)
+    } else {
+        (
+            bundled(Path::new(interpreter).file_name()?.to_str()?)?,
+            args,
+        )
+    };
+
+    let target = target.to_string_lossy();
+    let suffix = if args.is_empty() {
+        String::new()
+    } else {
+        format!(" {args}")
+    };
+    let direct = format!("#!{target}{suffix}");
+    if direct.len() <= MAX_SHEBANG_LEN {
+        return Some(direct);
+    }
+
+    let name = Path::new(target.as_ref()).file_name()?.to_str()?;
+    let command: Option<Vec<String>> = std::iter::once(target.as_ref())
+        .chain(args.split_whitespace())
+        .map(shell_quote)
+        .collect();
+    match command {
+        Some(command) if name.starts_with("python") => {
+            // sh runs the second line and execs the interpreter; Python sees a string literal
+            warn!("⚠️ Shebang for {target} is too long, using an exec trampoline");
+            Some(format!(
+                "#!/bin/sh\n'''exec' {} \"$0\" \"$@\"\n' '''",
+                command.join(" ")
+            ))
+        }
+        _ => {
+            warn!("⚠️ Shebang for {target} is too long, looking up {name} on PATH instead");
+            Some(format!("#!/usr/bin/env {name}{suffix}"))
+        }
+    }
+}
+
+/// Single-quote `word` for sh, unless it contains a quote itself
+///
+/// A quote could also end the Python string the trampoline hides in, so such
+/// words get no trampoline at all.
+fn shell_quote(word: &str) -> Option<String> {
+    (!word.contains('\'')).then(|| format!("'{word}'"))
+}
+
+/// Move a PEP 263 coding line at the start of `rest` into a trampoline `header`
+///
+/// Python only honours the declaration on the first two lines, and the
+/// trampoline would push it further down. `rest` is the script after its
+/// shebang line; returns the new header and what follows it.
+fn keep_coding_line(header: String, rest: &[u8]) -> (String, &[u8]) {
+    let Some((shebang, trampoline)) = header.split_once('\n') else {
+        return (header, rest);
+    };
+    let Some(after) = rest.strip_prefix(b"\n") else {
+        return (header, rest);
+    };
+    let end = after
+        .iter()
+        .position(|&b| b == b'\n')
+        .unwrap_or(after.len());
+    let Ok(line) = std::str::from_utf8(&after[..end]) else {
+        return (header, rest);
+    };
+    let coding = regex::Regex::new(r"^[ \t\x0c]*#.*?coding[:=][ \t]*[-_.a-zA-Z0-9]+")
+        .is_ok_and(|re| re.is_match(line));
+    if !coding {
+        return (header, rest);
+    }
+    (format!("{shebang}\n{line}\n{trampoline}"), &after[end..])
+}
+
+/// Split off the first whitespace-separated word
+fn split_first_word(s: &str) -> (&str, &str) {
+    s.split_once(char::is_whitespace)
+        .map_or((s, ""), |(first, rest)| (first, rest.trim_start()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_script(path: &Path, content: &str) {
+        assert!(fs::write(path, content).is_ok());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert!(fs::set_permissions(path, fs::Permissions::from_mode(0o755)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_move_into_place() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let source = dir.path().join("src");
+        assert!(fs::create_dir_all(source.join("lib")).is_ok());
+        assert!(fs::write(source.join("lib/mod.py"), b"x = 1").is_ok());
+
+        let dest = dir.path().join("dest");
+        assert!(move_into_place(&source, &dest).is_ok());
+        assert!(!source.exists());
+        assert!(fs::read(dest.join("lib/mod.py")).is_ok_and(|d| d == b"x = 1"));
+
+        // A missing source is an error, not a copy attempt
+        let moved = move_into_place(&source, &dir.path().join("other"));
+        assert!(moved.is_err());
+        assert!(!dir.path().join("other").exists());
+    }
+
+    #[test]
+    fn test_rewrite_shebang() {
+        let old = Path::new("/tmp/.pkg.tmp");
+        let new = Path::new("/cache/pkg");
+        let python = |name: &str| (name == "python3").then(|| new.join("bin/python3"));
+        let none = |_: &str| None;
+
+        assert_eq!(
+            rewrite_shebang("#!/tmp/.pkg.tmp/bin/python3 -u", old, new, none).as_deref(),
+            Some("#!/cache/pkg/bin/python3 -u")
+        );
+        assert_eq!(
+            rewrite_shebang("#!/usr/bin/env python3", old, new, python).as_deref(),
+            Some("#!/cache/pkg/bin/python3")
+        );
+        assert_eq!(
+            rewrite_shebang("#!/usr/local/bin/python3 -E", old, new, python).as_deref(),
+            Some("#!/cache/pkg/bin/python3 -E")
+        );
+        // Not bundled, already correct, or left to env
+        assert_eq!(
+            rewrite_shebang("#!/usr/bin/env python3", old, new, none),
+            None
+        );
+        assert_eq!(rewrite_shebang("#!/bin/sh", old, new, python), None);
+        assert_eq!(
+            rewrite_shebang("#!/cache/pkg/bin/python3", old, new, python),
+            None
+        );
+        assert_eq!(
+            rewrite_shebang("#!/usr/bin/env -S python3 -u", old, new, python),
+            None
+        );
+    }
+
+    #[test]
+    fn test_long_shebangs_fall_back() {
+        let long = PathBuf::from(format!("/{}", "d".repeat(MAX_SHEBANG_LEN)));
+        let bundled = |name: &str| Some(long.join("bin").join(name));
+        let old = Path::new("/old");
+
+        let python = rewrite_shebang("#!/usr/bin/env python3 -u", old, &long, bundled);
+        assert!(
+            python
+                .as_deref()
+                .is_some_and(|h| h.starts_with("#!/bin/sh\n'''exec' '/ddd")
+                    && h.ends_with("/bin/python3' '-u' \"$0\" \"$@\"\n' '''"))
+        );
+
+        let node = rewrite_shebang("#!/usr/bin/node", old, &long, bundled);
+        assert_eq!(node.as_deref(), Some("#!/usr/bin/env node"));
+
+        // A quote can't be put in the trampoline safely
+        let quoted = long.join("it's");
+        let bundled = |name: &str| Some(quoted.join(name));
+        let python = rewrite_shebang("#!/usr/bin/env python3", old, &long, bundled);
+        assert_eq!(python.as_deref(), Some("#!/usr/bin/env python3"));
+    }
+
+    #[test]
+    fn test_fix_shebangs_rewrites_bin_dir() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        // Deep enough that workenv/bin/python3 doesn't fit in a shebang
+        let workenv = dir.path().join("w".repeat(MAX_SHEBANG_LEN));
+        let bin = workenv.join("bin");
+        assert!(fs::create_dir_all(&bin).is_ok());
+
+        write_script(&bin.join("python3"), "#!/bin/sh\necho \"bundled $*\"\n");
+        write_script(&bin.join("tool"), "#!/usr/bin/env python3\nprint('tool')\n");
+        write_script(
+            &bin.join("coded"),
+            "#!/usr/bin/env python3\n# -*- coding: latin-1 -*-\nprint('coded')\n",
+        );
+        write_script(&bin.join("shell"), "#!/bin/sh\necho shell\n");
+        assert!(fix_shebangs(&bin, Path::new("/old"), &workenv).is_ok());
+
+        let tool = fs::read_to_string(bin.join("tool"));
+        assert!(tool.is_ok());
+        let Ok(tool) = tool else { return };
+        assert!(tool.starts_with("#!/bin/sh\n'''exec' "));
+        assert!(tool.ends_with("' '''\nprint('tool')\n"));
+        // The coding line stays on line 2, ahead of the trampoline
+        let coded = fs::read_to_string(bin.join("coded"));
+        assert!(coded.is_ok_and(|coded| {
+            coded.starts_with("#!/bin/sh\n# -*- coding: latin-1 -*-\n'''exec' ")
+                && coded.ends_with("' '''\nprint('coded')\n")
+        }));
+        assert!(
+            fs::read_to_string(bin.join("shell")).is_ok_and(|s| s == "#!/bin/sh\necho shell\n")
+        );
+        assert!(
+            fs::read_to_string(bin.join("python3")).is_ok_and(|s| s.starts_with("#!/bin/sh\n"))
+        );
+
+        // The trampoline hands the script to the bundled interpreter
+        #[cfg(unix)]
+        for script in ["tool", "coded"] {
+            let output = std::process::Command::new(bin.join(script)).output();
+            assert!(output.is_ok_and(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .trim_end()
+                    .ends_with(&format!("/bin/{script}"))
+            }));
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Persistent installation of a package's workenv
+//!
+//! Runs the same pipeline as a launch (verification, slot extraction, setup
+//! commands, init slot cleanup and shebang fixing) but into a directory the
+//! caller chooses, and stops before the entrypoint. None of the cache
+//! machinery is involved: no lock, no completion marker, no checksum record.
+
+use super::extraction::extract_slots;
+use super::filesystem::{fix_shebangs, move_into_place};
+use super::workenv::{create_workenv_symlinks, get_workenv_paths, setup_workenv_directories};
+use super::{apply_decryption_key, verify_before_extraction};
+use crate::api::InstallOptions;
+use crate::exceptions::{FlavorError, Result};
+use log::{debug, info, warn};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::super::defaults::get_validation_level;
+use super::super::execution::execute_setup_commands;
+use super::super::extraction::ExtractionTracker;
+use super::super::reader::Reader;
+
+/// Install the package at `package_path` into `dest`
+///
+/// `dest` must not exist or be an empty directory. The package is prepared in
+/// a sibling temp directory and renamed into place, so a failed install leaves
+/// `dest` as it was. Returns the installed directory.
+pub fn install(package_path: &Path, dest: &Path, options: InstallOptions) -> Result<PathBuf> {
+    if dest.exists() && fs::read_dir(dest)?.next().is_some() {
+        return Err(FlavorError::ExtractionError(format!(
+            "Install directory {} is not empty",
+            dest.display()
+        )));
+    }
+    let dest = std::path::absolute(dest)?;
+    let parent = dest.parent().ok_or_else(|| {
+        FlavorError::ExtractionError(format!("Cannot install to {}", dest.display()))
+    })?;
+    fs::create_dir_all(parent)?;
+
+    let data_path = super::super::detached::resolve_data_path(package_path)?;
+    let mut reader = Reader::new(&data_path)?;
+    let index = reader.read_index()?.clone();
+    apply_decryption_key(&mut reader, &index, options.decryption_key.as_ref())?;
+    verify_before_extraction(
+        &data_path,
+        &index,
+        get_validation_level(),
+        options.skip_file_crc,
+    )?;
+
+    let metadata = reader.read_metadata()?.clone();
+    info!(
+        "📦 Installing {} v{} to {}",
+        metadata.package.name,
+        metadata.package.version,
+        dest.display()
+    );
+
+    let file_name = dest.file_name().unwrap_or_default().to_string_lossy();
+    let temp_dir = parent.join(format!(".{file_name}.install-{}", std::process::id()));
+    let result = prepare(&mut reader, package_path, &metadata, &temp_dir, &options);
+    if let Err(e) = result {
+        if let Err(cleanup) = fs::remove_dir_all(&temp_dir) {
+            debug!("⚠️ Failed to remove {}: {cleanup}", temp_dir.display());
+        }
+        return Err(e);
+    }
+
+    // An empty destination is replaced by the prepared tree
+    if dest.exists() {
+        fs::remove_dir(&dest)?;
+    }
+    move_into_place(&temp_dir, &dest)?;
+
+    let bin_dir = dest.join("bin");
+    if bin_dir.exists() {
+        info!("🔧 Fixing shebangs in scripts...");
+        if let Err(e) = fix_shebangs(&bin_dir, &temp_dir, &dest) {
+            warn!("⚠️ Failed to fix some shebangs: {:#}", e);
+        }
+    }
+    if let Some(ref workenv_info) = metadata.workenv {
+        create_workenv_symlinks(&dest, workenv_info)?;
+    }
+
+    info!("✅ Installed to {}", dest.display());
+    Ok(dest)
+}
+
+/// Extract the slots into `temp_dir` and run the setup commands there
+fn prepare(
+    reader: &mut Reader,
+    package_path: &Path,
+    metadata: &super::super::metadata::Metadata,
+    temp_dir: &Path,
+    options: &InstallOptions,
+) -> Result<()> {
+    fs::create_dir_all(temp_dir)?;
+    if let Some(ref workenv_info) = metadata.workenv {
+        setup_workenv_directories(temp_dir, workenv_info, options.extraction_umask)?;
+    }
+
+    // Inherited slots of a delta package still come from the base's cached workenv
+    let paths = get_workenv_paths(package_path);
+    let tracker = ExtractionTracker::new(options.collision_policy)
+        .with_umask(options.extraction_umask)
+        .with_max_decompressed_size(options.max_decompressed_slot_size);
+    let (_, init_slots) = extract_slots(reader, &paths, temp_dir, tracker)?;
+
+    if !metadata.setup_commands.is_empty() {
+        info!(
+            "🔧 Running {} setup commands...",
+            metadata.setup_commands.len()
+        );
+        execute_setup_commands(
+            &metadata.setup_commands,
+            temp_dir,
+            &metadata.package,
+            &env::current_dir()?,
+            &metadata.execution.env,
+        )?;
+    }
+
+    for init_path in init_slots.iter().filter(|p| p.exists()) {
+        debug!("🗑️ Removing init path: {init_path:?}");
+        if init_path.is_dir() {
+            fs::remove_dir_all(init_path)?;
+        } else {
+            fs::remove_file(init_path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BuildOptions;
+
+    #[test]
+    fn test_install_runs_setup_and_refuses_non_empty_dest() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("tool.sh"), b"#!/bin/sh\necho tool\n").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "installable", "version": "1.0"},
+            "execution": {"command": "{workenv}/bin/tool.sh"},
+            "setup_commands": [
+                {"type": "write_file", "path": "{workenv}/installed.txt", "content": "setup ran"}
+            ],
+            "slots": [{"id": "tool", "source": root.join("tool.sh"), "target": "bin/tool.sh"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("install".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let dest = root.join("opt/installable");
+        let installed = install(&package, &dest, InstallOptions::default());
+        assert!(
+            installed
+                .as_ref()
+                .is_ok_and(|p| p.ends_with("opt/installable"))
+        );
+        assert!(fs::read(dest.join("bin/tool.sh")).is_ok_and(|d| d.starts_with(b"#!/bin/sh")));
+        assert!(fs::read_to_string(dest.join("installed.txt")).is_ok_and(|s| s == "setup ran"));
+        let leftovers = fs::read_dir(root.join("opt")).map(|entries| entries.count());
+        assert!(leftovers.is_ok_and(|n| n == 1));
+
+        assert!(install(&package, &dest, InstallOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_install_skips_slots_whose_condition_fails() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(fs::write(root.join("gpu.bin"), b"gpu").is_ok());
+        assert!(fs::write(root.join("cpu.bin"), b"cpu").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "conditional", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "gpu", "source": root.join("gpu.bin"), "target": "gpu.bin",
+                 "condition": "env:FLAVOR_TEST_UNSET_GPU"},
+                {"id": "cpu", "source": root.join("cpu.bin"), "target": "cpu.bin",
+                 "condition": "!env:FLAVOR_TEST_UNSET_GPU && platform:" },
+            ]
+        });
+        let build = |manifest: &serde_json::Value| {
+            let options = BuildOptions {
+                launcher_bin: Some(root.join("launcher")),
+                key_seed: Some("conditional".to_string()),
+                ..BuildOptions::default()
+            };
+            super::super::super::builder::build_from_str(
+                &manifest.to_string(),
+                &root.join("pkg.psp"),
+                options,
+            )
+        };
+        // Malformed conditions are rejected at build time
+        assert!(build(&manifest).is_err_and(|e| matches!(e, FlavorError::ConfigError(_))));
+
+        let mut manifest = manifest;
+        manifest["slots"][1]["condition"] = serde_json::json!(format!(
+            "!env:FLAVOR_TEST_UNSET_GPU && platform:{}",
+            crate::get_platform_string()
+        ));
+        assert!(build(&manifest).is_ok());
+
+        let dest = root.join("out");
+        assert!(install(&root.join("pkg.psp"), &dest, InstallOptions::default()).is_ok());
+        assert!(!dest.join("gpu.bin").exists());
+        assert!(fs::read(dest.join("cpu.bin")).is_ok_and(|d| d == b"cpu"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Resource limits for the packaged process (Unix only)
+
+use crate::api::ResourceLimits;
+#[cfg(unix)]
+use crate::exceptions::{FlavorError, Result};
+#[cfg(unix)]
+use log::debug;
+
+/// Fill unset limits from the package's index hints
+pub(super) fn with_index_defaults(limits: ResourceLimits, index_max_memory: u64) -> ResourceLimits {
+    ResourceLimits {
+        max_memory: limits
+            .max_memory
+            .or((index_max_memory > 0).then_some(index_max_memory)),
+        ..limits
+    }
+}
+
+/// Apply limits to the current process (used right before exec)
+#[cfg(unix)]
+pub(super) fn apply_to_current_process(limits: &ResourceLimits) -> Result<()> {
+    for (resource, value) in limit_pairs(limits) {
+        set_limit(resource, value)
+            .map_err(|e| FlavorError::LaunchError(format!("Failed to set resource limit: {e}")))?;
+    }
+    debug!("🔒 Applied resource limits: {limits:?}");
+    Ok(())
+}
+
+/// Apply limits to a command's child process only (used in spawn mode)
+#[cfg(unix)]
+pub(super) fn apply_to_command(cmd: &mut std::process::Command, limits: ResourceLimits) {
+    use std::os::unix::process::CommandExt;
+
+    let pairs = limit_pairs(&limits);
+    debug!("🔒 Child resource limits: {limits:?}");
+
+    // SAFETY: the closure runs between fork and exec and only calls
+    // getrlimit/setrlimit, which are async-signal-safe, without allocating.
+    #[allow(unsafe_code)]
+    unsafe {
+        cmd.pre_exec(move || {
+            for &(resource, value) in &pairs {
+                set_limit(resource, value)?;
+            }
+            Ok(())
+        });
+    }
+}
+
+// glibc and uClibc type the resource argument; musl, bionic and the BSDs take an int
+#[cfg(all(target_os = "linux", any(target_env = "gnu", target_env = "uclibc")))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(
+    unix,
+    not(all(target_os = "linux", any(target_env = "gnu", target_env = "uclibc")))
+))]
+type Resource = libc::c_int;
+
+#[cfg(unix)]
+fn limit_pairs(limits: &ResourceLimits) -> Vec<(Resource, u64)> {
+    [
+        (libc::RLIMIT_AS as Resource, limits.max_memory),
+        (libc::RLIMIT_CPU as Resource, limits.max_cpu_seconds),
+        (libc::RLIMIT_NOFILE as Resource, limits.max_open_files),
+    ]
+    .into_iter()
+    .filter_map(|(resource, value)| value.map(|v| (resource, v)))
+    .collect()
+}
+
+/// Lower both soft and hard limit to `value`, clamped to the current hard limit
+#[cfg(unix)]
+#[allow(unsafe_code)]
+fn set_limit(resource: Resource, value: u64) -> std::io::Result<()> {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: `current` is a valid, writable rlimit struct.
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let value = (value as libc::rlim_t).min(current.rlim_max);
+    let limit = libc::rlimit {
+        rlim_cur: value,
+        rlim_max: value,
+    };
+    // SAFETY: `limit` is a valid rlimit struct that outlives the call.
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_open_files_limit() {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "ulimit -n"]);
+        apply_to_command(
+            &mut cmd,
+            ResourceLimits {
+                max_open_files: Some(64),
+                ..ResourceLimits::default()
+            },
+        );
+
+        let output = cmd.output();
+        assert!(output.is_ok_and(|o| String::from_utf8_lossy(&o.stdout).trim() == "64"));
+    }
+
+    #[test]
+    fn test_index_hint_fills_missing_memory_limit() {
+        let limits = with_index_defaults(ResourceLimits::default(), 1 << 30);
+        assert_eq!(limits.max_memory, Some(1 << 30));
+
+        let explicit = ResourceLimits {
+            max_memory: Some(1 << 20),
+            ..ResourceLimits::default()
+        };
+        assert_eq!(
+            with_index_defaults(explicit, 1 << 30).max_memory,
+            Some(1 << 20)
+        );
+    }
+}
@@ -1,18 +1,29 @@
 //! PSPF/2025 package launcher
 
+mod bench;
 pub mod command;
 mod extraction;
 mod filesystem;
+mod install;
+mod limits;
+mod path_security;
+#[cfg(unix)]
+mod signals;
 mod workenv;
 
-use command::prepare_command;
+pub use bench::{BenchReport, BenchRun, LaunchTimings, bench};
+use command::{explicit_argv0, prepare_command, resolve_workdir, select_command};
 use extraction::{build_slot_paths, extract_slots};
-use filesystem::{copy_dir_all, fix_shebangs};
-use workenv::{check_disk_space, get_workenv_paths, setup_workenv_directories};
+use filesystem::{fix_shebangs, move_contents_into};
+pub use install::install;
+use workenv::{
+    check_disk_space, create_workenv_symlinks, get_workenv_paths, parse_workenv_output,
+    parse_workenv_verification, print_workenv, setup_workenv_directories,
+};
 
-use crate::api::LaunchOptions;
+use crate::api::{LaunchOptions, PreExecContext, WorkenvVerification};
 use crate::exceptions::{FlavorError, Result};
-use crate::utils::get_cache_dir;
+use crate::utils::{get_cache_dir, is_env_true};
 use log::{debug, error, info, trace, warn};
 use std::env;
 use std::fs;
@@ -21,21 +32,99 @@ use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
+use super::defaults::{
+    DEFAULT_EXTRACTION_RETRIES, DEFAULT_EXTRACTION_WAIT_TIMEOUT_SECS, EXTRACTION_RETRY_BACKOFF_MS,
+};
+use super::encryption::{ENCRYPTION_NONE, EncryptionKey, resolve_decryption_key};
 use super::execution::{
     check_workenv_validity_full, execute_setup_commands, save_index_metadata, save_package_checksum,
 };
+use super::extraction::ExtractionTracker;
 use super::locking::{
-    cleanup_stale_extractions, mark_extraction_complete, release_lock, try_acquire_lock,
-    wait_for_extraction,
+    WaitOutcome, cleanup_stale_extractions, mark_extraction_complete, mark_extraction_incomplete,
+    release_lock, try_acquire_lock, wait_for_extraction,
 };
 use super::paths::WorkenvPaths;
 use super::reader::Reader;
+use super::verification_cache::VerifiedSlots;
 
 // Use CHILD_PID from lib.rs
 use crate::CHILD_PID;
 static EXTRACTING: AtomicBool = AtomicBool::new(false);
 
+/// Run the full integrity check without extracting or executing
+///
+/// Structural and signature checks are enforced at every validation level; the
+/// level only controls whether slot data checksums are verified as well. With
+/// `incremental`, slots that workenv's verification record lists are not
+/// hashed again, and newly verified slots are added to it.
+fn verify_package_only(
+    package_path: &Path,
+    validation_level: super::defaults::ValidationLevel,
+    incremental: Option<&WorkenvPaths>,
+) -> i32 {
+    use super::defaults::ValidationLevel;
+    use crate::exit_codes::{EXIT_SUCCESS, EXIT_VERIFICATION_FAILED};
+
+    info!("🔍 Verify-only mode (level: {:?})", validation_level);
+
+    match super::verifier::verify(package_path) {
+        Ok(result) if result.signature_valid => {
+            debug!("✅ Package integrity verified");
+        }
+        Ok(_) => {
+            eprintln!("❌ Package integrity verification failed");
+            return EXIT_VERIFICATION_FAILED;
+        }
+        Err(e) => {
+            eprintln!("❌ Package integrity verification failed: {e:#}");
+            return EXIT_VERIFICATION_FAILED;
+        }
+    }
+
+    if matches!(
+        validation_level,
+        ValidationLevel::Minimal | ValidationLevel::None
+    ) {
+        debug!("⏭️ Skipping slot checksums (level: {:?})", validation_level);
+    } else {
+        let mismatched = Reader::new(package_path).and_then(|mut r| match incremental {
+            Some(paths) => {
+                let mut verified = VerifiedSlots::load(paths);
+                debug!("🔍 {} slot(s) verified previously", verified.len());
+                let mismatched =
+                    super::verifier::verify_changed_slot_checksums(&mut r, &mut verified)?;
+                if let Err(e) = verified.save(paths) {
+                    warn!("⚠️ Failed to save slot verification record: {e:#}");
+                }
+                Ok(mismatched)
+            }
+            None => super::verifier::verify_slot_checksums(&mut r),
+        });
+        match mismatched {
+            Ok(mismatched) if mismatched.is_empty() => {
+                debug!("✅ All slot checksums verified");
+            }
+            Ok(mismatched) => {
+                eprintln!("❌ Slot checksum mismatch for slots: {mismatched:?}");
+                return EXIT_VERIFICATION_FAILED;
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to verify slot checksums: {e:#}");
+                return EXIT_VERIFICATION_FAILED;
+            }
+        }
+    }
+
+    info!("✅ Package verified, exiting without launch");
+    EXIT_SUCCESS
+}
+
+/// How many times to retry extraction after a lock holder dies mid-extraction
+const MAX_TAKEOVER_ATTEMPTS: u32 = 3;
+
 // Type alias for extraction result to reduce complexity
 type SlotPaths = std::collections::HashMap<usize, PathBuf>;
 type ExtractionResult = ((SlotPaths, Vec<PathBuf>), PathBuf);
@@ -49,8 +138,31 @@ type ExtractionResult = ((SlotPaths, Vec<PathBuf>), PathBuf);
 /// - Signature verification fails (in strict mode)
 /// - Extraction fails
 /// - Command execution fails
-#[allow(clippy::cognitive_complexity)]
+///
+/// Shutdown hooks (slots with `shutdown` lifecycle and `shutdown_commands`) only
+/// run in spawn mode; in exec mode the launcher process is replaced and cannot
+/// regain control after the child exits.
 pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> Result<i32> {
+    launch_timed(
+        package_path,
+        args,
+        options,
+        &mut LaunchTimings::default(),
+        false,
+    )
+}
+
+/// Launch, recording phase timings; with `prepare_only`, stop once the
+/// workenv is ready instead of executing the command
+#[allow(clippy::cognitive_complexity)]
+fn launch_timed(
+    package_path: &Path,
+    args: &[String],
+    options: LaunchOptions,
+    timings: &mut LaunchTimings,
+    prepare_only: bool,
+) -> Result<i32> {
+    let launch_timer = Instant::now();
     info!("🦀🦀🦀 Hello from Flavor's Rust Launcher 🦀🦀🦀");
     info!("PSPF Rust Launcher starting...");
     debug!("📖 Reading PSPF bundle");
@@ -66,56 +178,78 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
         }
     }
 
+    // A split package's data lives beside the launcher stub
+    let data_path = super::detached::resolve_data_path(package_path)?;
+    let data_path = data_path.as_path();
+
+    let verify_only = options.verify_only || is_env_true("FLAVOR_VERIFY_ONLY");
+    if verify_only {
+        let incremental = options.incremental_verify || is_env_true("FLAVOR_VERIFY_INCREMENTAL");
+        let paths = resolve_workenv_paths(package_path, &options);
+        return Ok(verify_package_only(
+            data_path,
+            super::defaults::get_validation_level(),
+            incremental.then_some(&paths),
+        ));
+    }
+
+    let strict = matches!(
+        super::defaults::get_validation_level(),
+        super::defaults::ValidationLevel::Strict
+    );
+    if path_security::guard_enabled(options.refuse_insecure_path, strict) {
+        path_security::check_package_location(package_path)?;
+        if data_path != package_path {
+            path_security::check_package_location(data_path)?;
+        }
+    }
+
+    let restore_mtime = options.restore_mtime || is_env_true("FLAVOR_RESTORE_MTIME");
+    let heal_on_mismatch = options.heal_on_mismatch || is_env_true("FLAVOR_HEAL_ON_MISMATCH");
+    let verify_workenv = match options.verify_workenv {
+        WorkenvVerification::Off => env::var("FLAVOR_VERIFY_WORKENV")
+            .ok()
+            .and_then(|value| {
+                let verification = parse_workenv_verification(&value);
+                if verification.is_none() {
+                    warn!("⚠️ Ignoring invalid FLAVOR_VERIFY_WORKENV '{value}' (expected off, sample or full)");
+                }
+                verification
+            })
+            .unwrap_or_default(),
+        verification => verification,
+    };
+    let extraction_umask = options.extraction_umask.or_else(|| {
+        let value = env::var("FLAVOR_EXTRACTION_UMASK").ok()?;
+        let umask = super::defaults::parse_umask(&value);
+        if umask.is_none() {
+            warn!(
+                "⚠️ Ignoring invalid FLAVOR_EXTRACTION_UMASK '{value}' (expected octal, e.g. 027)"
+            );
+        }
+        umask
+    });
+    if let Some(umask) = extraction_umask {
+        debug!("🔓 Extraction umask {umask:03o}");
+    }
+    #[cfg(unix)]
+    let dir_mode = super::defaults::extraction_dir_mode(None, extraction_umask);
+
     // Create reader for the bundle
-    let mut reader = Reader::new(package_path)?;
+    let mut reader = Reader::new(data_path)?;
 
     // Read index for checksum validation
     let index = reader.read_index()?.clone();
+    apply_decryption_key(&mut reader, &index, options.decryption_key.as_ref())?;
 
     // Verify integrity based on validation level
     use crate::psp::format_2025::defaults::{ValidationLevel, get_validation_level};
 
     let validation_level = get_validation_level();
-    if matches!(validation_level, ValidationLevel::None) {
-        eprintln!(
-            "⚠️ SECURITY WARNING: Skipping all integrity verification (FLAVOR_VALIDATION=none)"
-        );
-        eprintln!("⚠️ This is NOT RECOMMENDED for production use");
-        warn!("⚠️ VALIDATION DISABLED: Skipping integrity verification");
-    } else {
-        debug!(
-            "🔍 Verifying package integrity (level: {:?})",
-            validation_level
-        );
-        // Call verifier
-        let verify_result = super::verifier::verify(package_path)?;
-        if verify_result.signature_valid {
-            debug!("✅ Package integrity verified");
-        } else if matches!(
-            validation_level,
-            ValidationLevel::Minimal | ValidationLevel::Relaxed
-        ) {
-            eprintln!("⚠️ SECURITY WARNING: Package signature verification failed");
-            eprintln!("⚠️ Package may be corrupted or tampered with");
-            eprintln!(
-                "⚠️ Continuing due to validation level: {:?}",
-                validation_level
-            );
-            warn!("⚠️ Package signature verification failed, continuing");
-        } else if matches!(validation_level, ValidationLevel::Standard) {
-            eprintln!("🚨 SECURITY WARNING: Package signature verification failed");
-            eprintln!("🚨 Package may be corrupted or tampered with");
-            eprintln!(
-                "🚨 Continuing with standard validation (use FLAVOR_VALIDATION=strict to enforce)"
-            );
-            warn!("⚠️ Package signature verification failed, continuing with standard validation");
-        } else if matches!(validation_level, ValidationLevel::Strict) {
-            error!("❌ Package signature verification failed");
-            return Err(FlavorError::Generic(
-                "Package signature verification failed".to_string(),
-            ));
-        }
-    }
+
+    let verify_timer = Instant::now();
+    verify_before_extraction(data_path, &index, validation_level, options.skip_file_crc)?;
+    timings.verification = verify_timer.elapsed();
 
     // Read metadata and clone to avoid borrow issues
     let metadata = reader.read_metadata()?.clone();
@@ -136,28 +270,7 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
     debug!("🔧 Command: {}", metadata.execution.command);
 
     // Get work environment paths
-    let paths = if let Ok(custom_workenv) = env::var("FLAVOR_WORKENV") {
-        // Use custom workenv path from environment variable
-        info!(
-            "📁 Using custom work environment from FLAVOR_WORKENV: {}",
-            custom_workenv
-        );
-        let cache_dir = PathBuf::from(custom_workenv)
-            .parent()
-            .and_then(|p| p.parent())
-            .map(|p| p.to_path_buf())
-            .unwrap_or_else(get_cache_dir);
-        WorkenvPaths::new(cache_dir, package_path)
-    } else if let Some(ref workdir) = options.workdir {
-        let cache_dir = PathBuf::from(workdir)
-            .parent()
-            .and_then(|p| p.parent())
-            .map(|p| p.to_path_buf())
-            .unwrap_or_else(get_cache_dir);
-        WorkenvPaths::new(cache_dir, package_path)
-    } else {
-        get_workenv_paths(package_path)
-    };
+    let paths = resolve_workenv_paths(package_path, &options);
 
     let workenv_path = paths.workenv();
 
@@ -167,13 +280,12 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
     // Set secure permissions on workenv directory
     #[cfg(unix)]
     {
-        use crate::psp::format_2025::defaults::DEFAULT_DIR_PERMS;
         use std::os::unix::fs::PermissionsExt;
-        let permissions = fs::Permissions::from_mode(DEFAULT_DIR_PERMS as u32);
+        let permissions = fs::Permissions::from_mode(dir_mode);
         fs::set_permissions(&workenv_path, permissions)?;
         debug!(
-            "🔒 Set secure permissions {} on workenv directory",
-            DEFAULT_DIR_PERMS
+            "🔒 Set secure permissions {:o} on workenv directory",
+            dir_mode
         );
     }
 
@@ -181,12 +293,12 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
 
     // Setup workenv directories if specified
     if let Some(ref workenv_info) = metadata.workenv {
-        setup_workenv_directories(&workenv_path, workenv_info)?;
+        setup_workenv_directories(&workenv_path, workenv_info, extraction_umask)?;
     }
 
     // Clean up any stale extraction directories from dead processes
     if let Err(e) = cleanup_stale_extractions(&paths) {
-        debug!("⚠️ Failed to clean up stale extractions: {}", e);
+        debug!("⚠️ Failed to clean up stale extractions: {:#}", e);
     }
 
     // Check work environment validity
@@ -195,12 +307,19 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
         .map(|v| v.to_lowercase() != "false" && v != "0")
         .unwrap_or(true);
 
+    let cache_timer = Instant::now();
     let workenv_valid = if use_cache {
         debug!("🔍 Checking cache validity");
         trace!("📂 Checking workenv at: {:?}", workenv_path);
         let checksum = index.index_checksum;
         trace!("📊 Package checksum: {:08x}", checksum);
-        match check_workenv_validity_full(&paths, &index, &metadata) {
+        match check_workenv_validity_full(
+            &paths,
+            &index,
+            &metadata,
+            heal_on_mismatch,
+            verify_workenv,
+        ) {
             Ok(valid) => {
                 if valid {
                     info!("✅ Cache is valid, skipping extraction");
@@ -218,6 +337,8 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
         info!("📦 FLAVOR_WORKENV_CACHE=false, forcing fresh extraction");
         false
     };
+    timings.cache_check = cache_timer.elapsed();
+    timings.cache_hit = workenv_valid;
 
     let (_slot_paths, _init_paths) = if workenv_valid {
         info!("✅ Work environment is valid, skipping extraction and setup");
@@ -226,197 +347,82 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
         // Check disk space before extraction
         check_disk_space(&paths, &metadata)?;
 
-        // Try to acquire lock for extraction
-        let acquired_lock = try_acquire_lock(&paths)?;
-
-        if acquired_lock {
-            EXTRACTING.store(true, Ordering::SeqCst);
-
-            // Create temporary extraction directory
-            let temp_extract_dir = paths.temp_extraction(std::process::id());
-            fs::create_dir_all(&temp_extract_dir)?;
-
-            // Set secure permissions on temp extraction directory
-            #[cfg(unix)]
-            {
-                use crate::psp::format_2025::defaults::DEFAULT_DIR_PERMS;
-                use std::os::unix::fs::PermissionsExt;
-                let permissions = fs::Permissions::from_mode(DEFAULT_DIR_PERMS as u32);
-                fs::set_permissions(&temp_extract_dir, permissions)?;
-                debug!("🔒 Set secure permissions on temp extraction directory");
-            }
-
-            info!(
-                "📁 Created temporary extraction directory: {:?}",
-                temp_extract_dir
-            );
-            trace!("🗂️ Extracting to temp before atomic move");
-
-            // Extract slots to temporary directory
-            let extraction_result = (|| -> Result<ExtractionResult> {
-                let (slot_path_map, init_slots) = extract_slots(&mut reader, &temp_extract_dir)?;
-                Ok(((slot_path_map, init_slots), temp_extract_dir.clone()))
-            })();
-
-            let ((slot_path_map, init_slots), temp_dir) = match extraction_result {
-                Ok(result) => result,
-                Err(e) => {
-                    // Clean up temporary directory on extraction failure
-                    error!("❌ Extraction failed, cleaning up temporary directory");
-                    if let Err(cleanup_err) = fs::remove_dir_all(&temp_extract_dir) {
-                        warn!("⚠️ Failed to clean up temp directory: {}", cleanup_err);
-                    }
-                    EXTRACTING.store(false, Ordering::SeqCst);
-                    release_lock(&paths);
-                    return Err(e);
-                }
-            };
-
-            // Write metadata to package metadata directory directly in cache (not in temp)
-            // Use hidden .{workenv}.pspf/package/ structure as a sibling to workenv
-            let package_metadata_dir = paths.metadata().join("package");
-            fs::create_dir_all(&package_metadata_dir)?;
-
-            // Set secure permissions on metadata directory and its parent
-            #[cfg(unix)]
-            {
-                use crate::psp::format_2025::defaults::DEFAULT_DIR_PERMS;
-                use std::os::unix::fs::PermissionsExt;
-                let permissions = fs::Permissions::from_mode(DEFAULT_DIR_PERMS as u32);
-                // Set permissions on both the metadata parent directory and package subdirectory
-                let metadata_parent = paths.metadata();
-                fs::set_permissions(&metadata_parent, permissions.clone())?;
-                fs::set_permissions(&package_metadata_dir, permissions)?;
-                debug!("🔒 Set secure permissions on metadata directories");
-            }
-            let metadata_file = package_metadata_dir.join("psp.json");
-            let metadata_json = serde_json::to_string_pretty(&metadata)?;
-            fs::write(&metadata_file, metadata_json)?;
-            debug!("📝 Wrote metadata to {metadata_file:?}");
-
-            // Run setup commands in temp directory
-            if !metadata.setup_commands.is_empty() {
-                info!(
-                    "🔧 Running {} setup commands...",
-                    metadata.setup_commands.len()
-                );
-                let user_cwd = env::current_dir()?;
-                if let Err(e) = execute_setup_commands(
-                    &metadata.setup_commands,
-                    &temp_dir,
-                    &metadata.package,
-                    &user_cwd,
-                    &metadata.execution.env,
-                ) {
-                    // Clean up temporary directory on setup failure
-                    error!("❌ Setup commands failed, cleaning up temporary directory");
-                    if let Err(cleanup_err) = fs::remove_dir_all(&temp_extract_dir) {
-                        warn!("⚠️ Failed to clean up temp directory: {}", cleanup_err);
-                    }
-                    EXTRACTING.store(false, Ordering::SeqCst);
-                    release_lock(&paths);
-                    return Err(e);
-                }
-            }
-
-            // Remove init files after setup (in temp directory)
-            if !init_slots.is_empty() {
-                info!("🧹 Cleaning up {} init slot(s)...", init_slots.len());
-                for init_path in &init_slots {
-                    if init_path.exists() {
-                        debug!("🗑️ Removing init path: {init_path:?}");
-                        if init_path.is_dir() {
-                            if let Err(e) = fs::remove_dir_all(init_path) {
-                                warn!("Failed to remove init directory {init_path:?}: {e}");
-                            }
-                        } else if let Err(e) = fs::remove_file(init_path) {
-                            warn!("Failed to remove init file {init_path:?}: {e}");
-                        }
-                    }
-                }
+        // Try to acquire lock for extraction, otherwise wait for the holder.
+        // If the holder dies mid-extraction, retry acquiring the lock ourselves.
+        let wait_timeout = options
+            .extraction_wait_timeout
+            .unwrap_or(Duration::from_secs(DEFAULT_EXTRACTION_WAIT_TIMEOUT_SECS));
+        let mut takeover_attempts = 0;
+        let acquired_lock = loop {
+            if try_acquire_lock(&paths)? {
+                break true;
             }
 
-            // Atomically move extracted content from temp to final location
-            info!("🔄 Moving extracted content to final location...");
-
-            // List all top-level items in temp directory
-            let entries = fs::read_dir(&temp_dir)?;
-            for entry in entries {
-                let entry = entry?;
-                let file_name = entry.file_name();
-                let source = entry.path();
-                let dest = workenv_path.join(&file_name);
-
-                // Remove destination if it exists (for overwrite)
-                if dest.exists() {
-                    if dest.is_dir() {
-                        fs::remove_dir_all(&dest)?;
-                    } else {
-                        fs::remove_file(&dest)?;
-                    }
+            info!("⏳ Another process is extracting, waiting...");
+            match wait_for_extraction(&paths, wait_timeout)? {
+                WaitOutcome::Released => break false,
+                WaitOutcome::HolderDied if takeover_attempts < MAX_TAKEOVER_ATTEMPTS => {
+                    takeover_attempts += 1;
                 }
-
-                // Move from temp to final location
-                debug!("Moving {:?} to {:?}", source, dest);
-                if let Err(e) = fs::rename(&source, &dest) {
-                    // If rename fails (e.g., cross-filesystem), fall back to copy
-                    warn!("Rename failed, falling back to copy: {}", e);
-                    if source.is_dir() {
-                        // Recursive copy for directories
-                        copy_dir_all(&source, &dest)?;
-                        fs::remove_dir_all(&source)?;
-                    } else {
-                        fs::copy(&source, &dest)?;
-                        fs::remove_file(&source)?;
-                    }
+                WaitOutcome::HolderDied => {
+                    return Err(FlavorError::ExtractionError(
+                        "Extraction lock holders repeatedly died; giving up".to_string(),
+                    ));
                 }
             }
+        };
 
-            // Fix shebangs in bin directory
-            let bin_dir = workenv_path.join("bin");
-            if bin_dir.exists() {
-                info!("🔧 Fixing shebangs in scripts...");
-                if let Err(e) = fix_shebangs(&bin_dir, &temp_extract_dir, &workenv_path) {
-                    warn!("⚠️ Failed to fix some shebangs: {}", e);
+        if acquired_lock {
+            EXTRACTING.store(true, Ordering::SeqCst);
+            let keep_temp = options.keep_temp || is_env_true("FLAVOR_KEEP_TEMP");
+
+            let retries = options.extraction_retries.or_else(|| {
+                let value = env::var("FLAVOR_EXTRACTION_RETRIES").ok()?;
+                let retries = value.trim().parse().ok();
+                if retries.is_none() {
+                    warn!(
+                        "⚠️ Ignoring invalid FLAVOR_EXTRACTION_RETRIES '{value}' (expected a count)"
+                    );
                 }
-            }
-
-            // Remove the now-empty temp directory
-            if let Err(e) = fs::remove_dir_all(&temp_extract_dir) {
-                debug!("⚠️ Failed to remove temp directory: {}", e);
-            }
-
-            // Save index metadata for inspection
-            if let Err(e) = save_index_metadata(&paths, &index) {
-                debug!("⚠️ Failed to save index metadata: {}", e);
-            }
-
-            // Mark extraction as complete
-            mark_extraction_complete(&paths)?;
+                retries
+            });
+            let ctx = CacheExtraction {
+                paths: &paths,
+                index: &index,
+                metadata: &metadata,
+                options: &options,
+                restore_mtime,
+                extraction_umask,
+                keep_temp,
+                #[cfg(unix)]
+                dir_mode,
+            };
+            let extracted = extract_with_retries(
+                &ctx,
+                &mut reader,
+                timings,
+                retries.unwrap_or(DEFAULT_EXTRACTION_RETRIES),
+                verify_workenv,
+                heal_on_mismatch,
+            );
             EXTRACTING.store(false, Ordering::SeqCst);
-
-            // Save package checksum for future cache validation
-            if let Err(e) = save_package_checksum(&paths, index.index_checksum) {
-                debug!("⚠️ Failed to save package checksum: {}", e);
-            }
-
-            // Release the lock
             release_lock(&paths);
-
-            (slot_path_map, init_slots)
+            extracted?
         } else {
-            // Another process is extracting, wait for it
-            info!("⏳ Another process is extracting, waiting...");
-            wait_for_extraction(&paths, 60)?;
-
-            // Re-check validity
-            match check_workenv_validity_full(&paths, &index, &metadata) {
+            // Another process finished extracting; re-check validity
+            match check_workenv_validity_full(
+                &paths,
+                &index,
+                &metadata,
+                heal_on_mismatch,
+                verify_workenv,
+            ) {
                 Ok(valid_after_wait) => {
                     if valid_after_wait {
                         info!("✅ Cache extraction completed by another process");
                         (build_slot_paths(&metadata, &workenv_path), Vec::new())
                     } else {
-                        return Err(FlavorError::Generic(
+                        return Err(FlavorError::ExtractionError(
                             "Cache extraction by another process failed validation".to_string(),
                         ));
                     }
@@ -429,9 +435,55 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
         }
     };
 
+    timings.total = launch_timer.elapsed();
+    if prepare_only {
+        debug!("⏱️ Workenv prepared in {:?}", timings.total);
+        return Ok(crate::exit_codes::EXIT_SUCCESS);
+    }
+
+    let print_target = options.print_workenv.or_else(|| {
+        let value = env::var("FLAVOR_PRINT_WORKENV").ok()?;
+        let target = parse_workenv_output(&value);
+        if target.is_none() {
+            warn!("⚠️ Ignoring invalid FLAVOR_PRINT_WORKENV '{value}' (expected stderr, stdout or fd:N)");
+        }
+        target
+    });
+    if let Some(target) = print_target {
+        print_workenv(target, &workenv_path)?;
+    }
+
     // Prepare command
-    let (executable, cmd_args, env_map) =
-        prepare_command(&metadata, &workenv_path, package_path, args)?;
+    let strict_env = options.strict_env || matches!(validation_level, ValidationLevel::Strict);
+    let entrypoint = options
+        .entrypoint
+        .clone()
+        .or_else(|| env::var("FLAVOR_ENTRYPOINT").ok().filter(|e| !e.is_empty()));
+    let command = select_command(
+        &metadata.execution,
+        entrypoint.as_deref(),
+        options.argv0.as_deref(),
+    )?;
+    let (executable, cmd_args, env_map) = prepare_command(
+        &metadata,
+        command,
+        &workenv_path,
+        package_path,
+        args,
+        strict_env,
+        &options.env_allowlist,
+    )?;
+    let workdir = resolve_workdir(&metadata, &workenv_path)?;
+    let argv0 = explicit_argv0(&metadata.execution)?;
+
+    // Resource limits, with the index memory hint as a default
+    let resource_limits = options
+        .resource_limits
+        .map(|l| limits::with_index_defaults(l, index.max_memory));
+    #[cfg(not(unix))]
+    if resource_limits.is_some() {
+        warn!("⚠️ Resource limits are only supported on Unix; ignoring");
+    }
 
     // Get execution mode
     let exec_mode = env::var("FLAVOR_EXEC_MODE").unwrap_or_else(|_| "exec".to_string());
@@ -440,6 +492,17 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
     if use_exec {
         debug!("🔄 Using exec mode - process will be replaced");
 
+        #[cfg(unix)]
+        if options.child_pre_exec.is_some() {
+            warn!("⚠️ child_pre_exec is only run in spawn mode; ignoring");
+        }
+        #[cfg(unix)]
+        if !shutdown_hooks(&metadata).is_empty() {
+            warn!(
+                "⚠️ Shutdown hooks are skipped in exec mode; set FLAVOR_EXEC_MODE=spawn to run them"
+            );
+        }
+
         // On Unix, we can replace the current process
         #[cfg(unix)]
         {
@@ -447,7 +510,7 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
             cmd.args(&cmd_args);
             cmd.env_clear(); // Clear inherited environment first
             cmd.envs(&env_map);
-            cmd.current_dir(env::current_dir()?);
+            cmd.current_dir(&workdir);
 
             // Check if the executable is a script (has a shebang)
             let is_script = if let Ok(file) = fs::File::open(&executable) {
@@ -474,16 +537,20 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
                 false
             };
 
-            // Only set argv[0] for binary executables, not scripts
-            // Scripts with shebangs can fail with permission denied when argv[0] is changed
-            if is_script {
+            // Only set argv[0] for binary executables, not scripts, unless the
+            // package asks for one. Scripts with shebangs can fail with
+            // permission denied when argv[0] is changed
+            if let Some(argv0) = argv0 {
+                cmd.arg0(argv0);
+                info!("🚀 Executing: {executable} with argv[0]={argv0} (from package)");
+            } else if is_script {
                 info!("🚀 Executing script: {executable}");
             } else {
                 // Get the binary name for argv[0]
                 let binary_name = package_path
                     .file_name()
                     .and_then(|n| n.to_str())
-                    .ok_or_else(|| FlavorError::Generic("Invalid package path".to_string()))?;
+                    .ok_or_else(|| FlavorError::LaunchError("Invalid package path".to_string()))?;
                 // Set argv[0] to the binary name
                 cmd.arg0(binary_name);
                 info!("🚀 Executing binary: {executable} with argv[0]={binary_name}");
@@ -496,9 +563,23 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
             trace!("  Env vars count computed");
             info!("🔄 Replacing process via exec()");
 
+            run_pre_exec_hook(
+                options.pre_exec_hook.as_ref(),
+                &executable,
+                &cmd_args,
+                &env_map,
+                &workdir,
+                false,
+            )?;
+
+            // Limits set on this process persist into the exec'd replacement
+            if let Some(ref resource_limits) = resource_limits {
+                limits::apply_to_current_process(resource_limits)?;
+            }
+
             // This replaces the current process and never returns on success
             let error = cmd.exec();
-            return Err(FlavorError::Generic(format!("Failed to exec: {error}")));
+            return Err(FlavorError::LaunchError(format!("Failed to exec: {error}")));
         }
 
         #[cfg(not(unix))]
@@ -515,18 +596,799 @@ pub fn launch(package_path: &Path, args: &[String], options: LaunchOptions) -> R
     cmd.args(&cmd_args);
     cmd.env_clear(); // Clear inherited environment first
     cmd.envs(&env_map);
-    cmd.current_dir(env::current_dir()?);
+    cmd.current_dir(&workdir);
+    #[cfg(unix)]
+    if let Some(resource_limits) = resource_limits {
+        limits::apply_to_command(&mut cmd, resource_limits);
+    }
+    #[cfg(unix)]
+    if let Some(argv0) = argv0 {
+        cmd.arg0(argv0);
+    }
+    #[cfg(not(unix))]
+    if argv0.is_some() {
+        warn!("⚠️ execution.argv0 is only supported on Unix; ignoring");
+    }
+    #[cfg(unix)]
+    if let Some(hook) = options.child_pre_exec {
+        let hook = hook.into_inner();
+        // SAFETY: `ChildPreExec::new` is unsafe; its caller vouched that the
+        // callback is sound to run between fork and exec.
+        #[allow(unsafe_code)]
+        unsafe {
+            cmd.pre_exec(hook);
+        }
+    }
+    #[cfg(not(unix))]
+    if options.child_pre_exec.is_some() {
+        warn!("⚠️ child_pre_exec is only supported on Unix; ignoring");
+    }
 
+    run_pre_exec_hook(
+        options.pre_exec_hook.as_ref(),
+        &executable,
+        &cmd_args,
+        &env_map,
+        &workdir,
+        true,
+    )?;
     info!("🚀 Spawning: {executable}");
 
+    // Installed before spawning so a signal in between is held for the child
+    #[cfg(unix)]
+    let forwarder = if options.forward_signals {
+        Some(signals::SignalForwarder::install()?)
+    } else {
+        None
+    };
+    #[cfg(not(unix))]
+    if options.forward_signals {
+        warn!("⚠️ Signal forwarding is only supported on Unix; ignoring");
+    }
+
     let mut child = cmd.spawn()?;
 
     // Store child PID for signal handling (if needed by binary)
     CHILD_PID.store(child.id(), Ordering::SeqCst);
 
+    #[cfg(unix)]
+    if let Some(forwarder) = &forwarder {
+        if let Err(e) = forwarder.forward_to(child.id()) {
+            // Don't leave the child running unwaited
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    }
+
     // Wait for child to exit
     let status = child.wait()?;
+    #[cfg(unix)]
+    drop(forwarder);
+
+    // Run shutdown hooks; failures are logged but never replace the child's exit code
+    run_shutdown_hooks(&metadata, &workenv_path);
 
     // Return exit code
     Ok(status.code().unwrap_or(1))
 }
+
+/// Run the embedder's [`LaunchOptions::pre_exec_hook`], if any
+fn run_pre_exec_hook(
+    hook: Option<&crate::api::PreExecHook>,
+    executable: &str,
+    args: &[String],
+    env: &std::collections::HashMap<String, String>,
+    workdir: &Path,
+    spawn: bool,
+) -> Result<()> {
+    let Some(hook) = hook else {
+        return Ok(());
+    };
+    debug!("🪝 Running pre-exec hook");
+    hook.call(&PreExecContext {
+        executable,
+        args,
+        env,
+        workdir,
+        spawn,
+    })
+    .inspect_err(|e| error!("❌ Pre-exec hook aborted the launch: {e:#}"))
+}
+
+/// What [`extract_to_cache`] needs from the launch
+struct CacheExtraction<'a> {
+    paths: &'a WorkenvPaths,
+    index: &'a super::index::Index,
+    metadata: &'a super::metadata::Metadata,
+    options: &'a LaunchOptions,
+    restore_mtime: bool,
+    extraction_umask: Option<u32>,
+    keep_temp: bool,
+    #[cfg(unix)]
+    dir_mode: u32,
+}
+
+/// Extract into a temp directory, run setup, move the result into the cache
+/// workenv and mark it complete
+///
+/// Must be called with the extraction lock held; the caller releases it.
+fn extract_to_cache(
+    ctx: &CacheExtraction<'_>,
+    reader: &mut Reader,
+    timings: &mut LaunchTimings,
+) -> Result<(SlotPaths, Vec<PathBuf>)> {
+    let &CacheExtraction {
+        paths,
+        index,
+        metadata,
+        options,
+        restore_mtime,
+        extraction_umask,
+        keep_temp,
+        #[cfg(unix)]
+        dir_mode,
+    } = ctx;
+    let workenv_path = paths.workenv();
+
+    // Create temporary extraction directory
+    let temp_extract_dir = paths.temp_extraction(std::process::id());
+    // A directory kept from an earlier attempt would be moved in with this one
+    if temp_extract_dir.exists() {
+        fs::remove_dir_all(&temp_extract_dir)?;
+    }
+    fs::create_dir_all(&temp_extract_dir)?;
+
+    // Set secure permissions on temp extraction directory
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = fs::Permissions::from_mode(dir_mode);
+        fs::set_permissions(&temp_extract_dir, permissions)?;
+        debug!("🔒 Set secure permissions on temp extraction directory");
+    }
+
+    info!(
+        "📁 Created temporary extraction directory: {:?}",
+        temp_extract_dir
+    );
+    trace!("🗂️ Extracting to temp before atomic move");
+
+    // Extract slots to temporary directory
+    let extraction_timer = Instant::now();
+    let extraction_result = (|| -> Result<ExtractionResult> {
+        let tracker = ExtractionTracker::new(options.collision_policy)
+            .with_restore_mtime(restore_mtime)
+            .with_umask(extraction_umask)
+            .with_max_decompressed_size(options.max_decompressed_slot_size);
+        let (slot_path_map, init_slots) = extract_slots(reader, paths, &temp_extract_dir, tracker)?;
+        Ok(((slot_path_map, init_slots), temp_extract_dir.clone()))
+    })();
+
+    timings.extraction = extraction_timer.elapsed();
+    let ((slot_path_map, init_slots), temp_dir) = match extraction_result {
+        Ok(result) => result,
+        Err(e) => {
+            // Clean up temporary directory on extraction failure
+            error!("❌ Extraction failed");
+            discard_temp_dir(&temp_extract_dir, keep_temp);
+            // Integrity and format errors keep their kind
+            return Err(match e {
+                FlavorError::IoError(e) => FlavorError::ExtractionError(e.to_string()),
+                e => e,
+            });
+        }
+    };
+
+    // Write metadata to package metadata directory directly in cache (not in temp)
+    // Use hidden .{workenv}.pspf/package/ structure as a sibling to workenv
+    let package_metadata_dir = paths.metadata().join("package");
+    fs::create_dir_all(&package_metadata_dir)?;
+
+    // Set secure permissions on metadata directory and its parent
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = fs::Permissions::from_mode(dir_mode);
+        // Set permissions on both the metadata parent directory and package subdirectory
+        let metadata_parent = paths.metadata();
+        fs::set_permissions(&metadata_parent, permissions.clone())?;
+        fs::set_permissions(&package_metadata_dir, permissions)?;
+        debug!("🔒 Set secure permissions on metadata directories");
+    }
+    let metadata_file = package_metadata_dir.join("psp.json");
+    let metadata_json = serde_json::to_string_pretty(metadata)?;
+    fs::write(&metadata_file, metadata_json)?;
+    debug!("📝 Wrote metadata to {metadata_file:?}");
+
+    // Run setup commands in temp directory
+    let setup_timer = Instant::now();
+    if !metadata.setup_commands.is_empty() {
+        info!(
+            "🔧 Running {} setup commands...",
+            metadata.setup_commands.len()
+        );
+        let user_cwd = env::current_dir()?;
+        if let Err(e) = execute_setup_commands(
+            &metadata.setup_commands,
+            &temp_dir,
+            &metadata.package,
+            &user_cwd,
+            &metadata.execution.env,
+        ) {
+            // Clean up temporary directory on setup failure
+            error!("❌ Setup commands failed");
+            discard_temp_dir(&temp_extract_dir, keep_temp);
+            return Err(e);
+        }
+    }
+
+    timings.setup = setup_timer.elapsed();
+
+    // Remove init files after setup (in temp directory)
+    remove_init_paths(&init_slots);
+
+    // Atomically move extracted content from temp to final location
+    info!("🔄 Moving extracted content to final location...");
+
+    move_contents_into(&temp_dir, &workenv_path)?;
+
+    // Fix shebangs in bin directory
+    let bin_dir = workenv_path.join("bin");
+    if bin_dir.exists() {
+        info!("🔧 Fixing shebangs in scripts...");
+        if let Err(e) = fix_shebangs(&bin_dir, &temp_extract_dir, &workenv_path) {
+            warn!("⚠️ Failed to fix some shebangs: {:#}", e);
+        }
+    }
+
+    if let Some(ref workenv_info) = metadata.workenv {
+        create_workenv_symlinks(&workenv_path, workenv_info)?;
+    }
+
+    // Remove the now-empty temp directory
+    if let Err(e) = fs::remove_dir_all(&temp_extract_dir) {
+        debug!("⚠️ Failed to remove temp directory: {}", e);
+    }
+
+    // Save index metadata for inspection
+    if let Err(e) = save_index_metadata(paths, index) {
+        debug!("⚠️ Failed to save index metadata: {:#}", e);
+    }
+
+    // Mark extraction as complete
+    mark_extraction_complete(paths)?;
+
+    // Save package checksum for future cache validation
+    if let Err(e) = save_package_checksum(paths, index.index_checksum) {
+        debug!("⚠️ Failed to save package checksum: {:#}", e);
+    }
+
+    Ok((slot_path_map, init_slots))
+}
+
+/// Remove init slots once setup has used them
+fn remove_init_paths(init_slots: &[PathBuf]) {
+    if init_slots.is_empty() {
+        return;
+    }
+    info!("🧹 Cleaning up {} init slot(s)...", init_slots.len());
+    for init_path in init_slots.iter().filter(|p| p.exists()) {
+        debug!("🗑️ Removing init path: {init_path:?}");
+        if init_path.is_dir() {
+            if let Err(e) = fs::remove_dir_all(init_path) {
+                warn!("Failed to remove init directory {init_path:?}: {e}");
+            }
+        } else if let Err(e) = fs::remove_file(init_path) {
+            warn!("Failed to remove init file {init_path:?}: {e}");
+        }
+    }
+}
+
+/// Run [`extract_to_cache`], re-checking the result and retrying on failure
+///
+/// On a network filesystem a rename or read can fail transiently, or the
+/// moved files can show up late. After each extraction the workenv is checked
+/// again, with extracted files compared as `verification` says; an I/O or
+/// extraction error or a failed check is retried up to `retries` times with
+/// exponential backoff.
+fn extract_with_retries(
+    ctx: &CacheExtraction<'_>,
+    reader: &mut Reader,
+    timings: &mut LaunchTimings,
+    retries: u32,
+    verification: WorkenvVerification,
+    heal_on_mismatch: bool,
+) -> Result<(SlotPaths, Vec<PathBuf>)> {
+    let mut attempt = 0;
+    loop {
+        let result = extract_to_cache(ctx, reader, timings).and_then(|extracted| {
+            if check_workenv_validity_full(
+                ctx.paths,
+                ctx.index,
+                ctx.metadata,
+                heal_on_mismatch,
+                verification,
+            )? {
+                Ok(extracted)
+            } else {
+                Err(FlavorError::ExtractionError(
+                    "Extracted workenv failed validation".to_string(),
+                ))
+            }
+        });
+        match result {
+            Err(e) if attempt < retries && is_retryable(&e) => {
+                let delay = retry_delay(attempt);
+                attempt += 1;
+                warn!("⚠️ Extraction attempt {attempt} failed, retrying in {delay:?}: {e:#}");
+                mark_extraction_incomplete(ctx.paths);
+                std::thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether an extraction failure may be transient: I/O and extraction errors,
+/// including those wrapping a decoder's I/O error
+fn is_retryable(error: &FlavorError) -> bool {
+    matches!(
+        error.kind(),
+        FlavorError::IoError(_) | FlavorError::ExtractionError(_)
+    )
+}
+
+/// Backoff before retry number `attempt` (counting from zero)
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_millis(EXTRACTION_RETRY_BACKOFF_MS << attempt.min(6))
+}
+
+/// Dispose of the temp extraction directory after a failed extraction
+///
+/// With `keep` set, the directory is renamed to `<pid>-<unix secs>.failed`, which
+/// the PID-keyed stale-extraction cleanup only removes once it is a week old,
+/// and its path is printed to stderr. The timestamp keeps a reused PID from
+/// colliding with an earlier kept directory.
+fn discard_temp_dir(temp_extract_dir: &Path, keep: bool) {
+    if !keep {
+        debug!("🧹 Cleaning up temporary directory");
+        if let Err(e) = fs::remove_dir_all(temp_extract_dir) {
+            warn!("⚠️ Failed to clean up temp directory: {}", e);
+        }
+        return;
+    }
+
+    let now_secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let kept_dir =
+        temp_extract_dir.with_file_name(format!("{}-{}.failed", std::process::id(), now_secs));
+    let kept_dir = match fs::rename(temp_extract_dir, &kept_dir) {
+        Ok(()) => kept_dir,
+        Err(e) => {
+            warn!(
+                "⚠️ Failed to rename temp directory, keeping it in place: {}",
+                e
+            );
+            temp_extract_dir.to_path_buf()
+        }
+    };
+    eprintln!(
+        "Kept temporary extraction directory: {}",
+        kept_dir.display()
+    );
+}
+
+/// Collect shutdown hooks: slots with `shutdown` lifecycle followed by `shutdown_commands`
+fn shutdown_hooks(metadata: &super::metadata::Metadata) -> Vec<serde_json::Value> {
+    metadata
+        .slots
+        .iter()
+        .filter(|slot| slot.lifecycle == "shutdown")
+        .map(|slot| {
+            // Quoted so targets with spaces stay one word
+            let target = format!("{{workenv}}/{}", slot.target);
+            let command = shlex::try_quote(&target).map_or(target.clone(), |q| q.into_owned());
+            serde_json::json!({
+                "type": "execute",
+                "command": command,
+            })
+        })
+        .chain(metadata.shutdown_commands.iter().cloned())
+        .collect()
+}
+
+/// Work environment paths for a launch, honouring `FLAVOR_WORKENV` and
+/// `LaunchOptions::workdir`
+fn resolve_workenv_paths(package_path: &Path, options: &LaunchOptions) -> WorkenvPaths {
+    if let Ok(custom_workenv) = env::var("FLAVOR_WORKENV") {
+        // Use custom workenv path from environment variable
+        info!(
+            "📁 Using custom work environment from FLAVOR_WORKENV: {}",
+            custom_workenv
+        );
+        let cache_dir = PathBuf::from(custom_workenv)
+            .parent()
+            .and_then(|p| p.parent())
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(get_cache_dir);
+        WorkenvPaths::new(cache_dir, package_path)
+    } else if let Some(ref workdir) = options.workdir {
+        let cache_dir = PathBuf::from(workdir)
+            .parent()
+            .and_then(|p| p.parent())
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(get_cache_dir);
+        WorkenvPaths::new(cache_dir, package_path)
+    } else {
+        get_workenv_paths(package_path)
+    }
+}
+
+/// Give `reader` the slot decryption key if the package is encrypted
+///
+/// `explicit` wins over `FLAVOR_DECRYPTION_KEY`. A missing key only warns: a
+/// valid cached workenv needs none, and extraction fails without one.
+fn apply_decryption_key(
+    reader: &mut Reader,
+    index: &super::index::Index,
+    explicit: Option<&EncryptionKey>,
+) -> Result<()> {
+    if index.encryption_type == ENCRYPTION_NONE {
+        return Ok(());
+    }
+    if let Some(key) = resolve_decryption_key(explicit)? {
+        return reader.set_decryption_key(&key);
+    }
+    warn!("⚠️ Package slots are encrypted and FLAVOR_DECRYPTION_KEY is not set");
+    Ok(())
+}
+
+/// Check the file CRC and signature before anything is extracted
+///
+/// How a bad signature is handled depends on `validation_level`: only strict
+/// validation refuses the package, the other levels warn and continue.
+fn verify_before_extraction(
+    data_path: &Path,
+    index: &super::index::Index,
+    validation_level: super::defaults::ValidationLevel,
+    skip_file_crc: bool,
+) -> Result<()> {
+    use super::defaults::ValidationLevel;
+
+    if matches!(validation_level, ValidationLevel::None) {
+        eprintln!(
+            "⚠️ SECURITY WARNING: Skipping all integrity verification (FLAVOR_VALIDATION=none)"
+        );
+        eprintln!("⚠️ This is NOT RECOMMENDED for production use");
+        warn!("⚠️ VALIDATION DISABLED: Skipping integrity verification");
+    } else {
+        debug!(
+            "🔍 Verifying package integrity (level: {:?})",
+            validation_level
+        );
+        // Cheap corruption check before the signature work; not a security check
+        let skip_file_crc = skip_file_crc || is_env_true("FLAVOR_SKIP_FILE_CRC");
+        if skip_file_crc {
+            debug!("⏭️ Skipping file CRC pre-check");
+        } else {
+            super::file_crc::check_file_crc(data_path, index)?;
+        }
+
+        // Call verifier
+        let verify_result = super::verifier::verify(data_path)?;
+        if !verify_result.index_checksum_valid {
+            error!("❌ Index block does not match its checksum");
+        }
+        if verify_result.launcher_hash_valid == Some(false) {
+            error!("❌ Launcher bytes do not match the hash recorded at build time");
+        }
+        if verify_result.signature_valid {
+            debug!("✅ Package integrity verified");
+        } else if matches!(
+            validation_level,
+            ValidationLevel::Minimal | ValidationLevel::Relaxed
+        ) {
+            eprintln!("⚠️ SECURITY WARNING: Package signature verification failed");
+            eprintln!("⚠️ Package may be corrupted or tampered with");
+            eprintln!(
+                "⚠️ Continuing due to validation level: {:?}",
+                validation_level
+            );
+            warn!("⚠️ Package signature verification failed, continuing");
+        } else if matches!(validation_level, ValidationLevel::Standard) {
+            eprintln!("🚨 SECURITY WARNING: Package signature verification failed");
+            eprintln!("🚨 Package may be corrupted or tampered with");
+            eprintln!(
+                "🚨 Continuing with standard validation (use FLAVOR_VALIDATION=strict to enforce)"
+            );
+            warn!("⚠️ Package signature verification failed, continuing with standard validation");
+        } else if matches!(validation_level, ValidationLevel::Strict) {
+            error!("❌ Package signature verification failed");
+            return Err(FlavorError::SignatureVerificationFailed(
+                "package may be corrupted or tampered with".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Run shutdown hooks after the child process has exited
+fn run_shutdown_hooks(metadata: &super::metadata::Metadata, workenv_path: &Path) {
+    let hooks = shutdown_hooks(metadata);
+    if hooks.is_empty() {
+        return;
+    }
+
+    info!("🔚 Running {} shutdown hook(s)...", hooks.len());
+    let user_cwd = match env::current_dir() {
+        Ok(cwd) => cwd,
+        Err(e) => {
+            warn!("⚠️ Skipping shutdown hooks, cannot determine working directory: {e}");
+            return;
+        }
+    };
+
+    if let Err(e) = execute_setup_commands(
+        &hooks,
+        workenv_path,
+        &metadata.package,
+        &user_cwd,
+        &metadata.execution.env,
+    ) {
+        warn!("⚠️ Shutdown hook failed: {e:#}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a package with no slots that runs `/bin/echo hello`
+    #[cfg(unix)]
+    fn build_echo_package(root: &Path) -> PathBuf {
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "no-slots", "version": "1.0"},
+            "execution": {"command": "/bin/echo hello"},
+            "slots": []
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = crate::api::BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("no-slots".to_string()),
+            ..crate::api::BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+        package
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_zero_slot_package_runs_a_system_command() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        let package = build_echo_package(root);
+
+        // The first launch has nothing to extract; the second reuses the workenv
+        let workenv = WorkenvPaths::new(root.join("cache"), &package).workenv();
+        let prepare = |timings: &mut LaunchTimings| {
+            let options = LaunchOptions {
+                workdir: Some(workenv.display().to_string()),
+                ..LaunchOptions::default()
+            };
+            launch_timed(&package, &[], options, timings, true)
+        };
+        let mut timings = LaunchTimings::default();
+        assert!(prepare(&mut timings).is_ok_and(|code| code == 0));
+        assert!(!timings.cache_hit);
+        let mut timings = LaunchTimings::default();
+        assert!(prepare(&mut timings).is_ok_and(|code| code == 0));
+        assert!(timings.cache_hit);
+
+        assert!(fs::read_dir(&workenv).is_ok_and(|mut entries| entries.next().is_none()));
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let metadata = reader.read_metadata().cloned();
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else { return };
+        assert!(metadata.slots.is_empty());
+        let prepared = prepare_command(
+            &metadata,
+            &metadata.execution.command,
+            &workenv,
+            &package,
+            &[],
+            false,
+            &[],
+        );
+        assert!(prepared.is_ok());
+        let Ok((executable, args, _env)) = prepared else {
+            return;
+        };
+        let output = Command::new(executable).args(args).output();
+        assert!(output.is_ok_and(|o| o.status.success() && o.stdout == b"hello\n"));
+    }
+
+    #[test]
+    fn test_wrapped_io_errors_are_retried() {
+        let decode = FlavorError::ExtractionError("Failed to decompress slot 0".into())
+            .with_source(std::io::Error::other("connection reset"));
+        assert!(is_retryable(&decode));
+        assert!(is_retryable(&FlavorError::IoError(std::io::Error::other(
+            "stale handle"
+        ))));
+        let tampered = FlavorError::VerificationFailed("bad signature".into())
+            .with_source(std::io::Error::other("ignored"));
+        assert!(!is_retryable(&tampered));
+    }
+
+    /// Set in the environment of the test binary re-run by
+    /// [`test_shutdown_hooks_run_after_the_child`], to the package's directory
+    #[cfg(unix)]
+    const SHUTDOWN_CHILD_ENV: &str = "FLAVOR_TEST_SHUTDOWN_CHILD";
+
+    /// The launch half of [`test_shutdown_hooks_run_after_the_child`]; does
+    /// nothing unless run as that test's child
+    #[cfg(unix)]
+    #[test]
+    fn shutdown_hooks_child() {
+        let Some(root) = env::var_os(SHUTDOWN_CHILD_ENV).map(PathBuf::from) else {
+            return;
+        };
+        let options = LaunchOptions {
+            workdir: Some(root.join("workenv/pkg").display().to_string()),
+            ..LaunchOptions::default()
+        };
+        assert!(launch(&root.join("pkg.psp"), &[], options).is_ok_and(|code| code == 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shutdown_hooks_run_after_the_child() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        let log = root.join("order.log");
+        let log_arg = shlex::try_quote(&log.display().to_string())
+            .map(|q| q.into_owned())
+            .unwrap_or_default();
+
+        // A shutdown slot whose target has a space, then a shutdown command
+        let hook = root.join("on exit.sh");
+        assert!(fs::write(&hook, format!("#!/bin/sh\necho slot >> {log_arg}\n")).is_ok());
+        assert!(fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "shutdown-hooks", "version": "1.0"},
+            "execution": {"command": format!("/bin/sh -c 'echo child >> {log_arg}'")},
+            "slots": [{"id": "hook", "source": hook, "target": "hooks/on exit.sh",
+                       "lifecycle": "shutdown"}],
+            "shutdown_commands": [
+                {"type": "execute", "command": format!("/bin/sh -c 'echo command >> {log_arg}'")}
+            ]
+        })
+        .to_string();
+        let options = crate::api::BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("shutdown-hooks".to_string()),
+            ..crate::api::BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest, &root.join("pkg.psp"), options)
+                .is_ok()
+        );
+
+        let exe = env::current_exe();
+        assert!(exe.is_ok());
+        let Ok(exe) = exe else { return };
+        let output = Command::new(exe)
+            .args([
+                "--exact",
+                "psp::format_2025::launcher::tests::shutdown_hooks_child",
+                "--test-threads=1",
+            ])
+            .env(SHUTDOWN_CHILD_ENV, root)
+            .env("FLAVOR_EXEC_MODE", "spawn")
+            .output();
+        assert!(output.is_ok());
+        let Ok(output) = output else { return };
+        assert!(
+            output.status.success(),
+            "child output: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        // Hooks run in order, after the child has exited
+        assert!(fs::read_to_string(&log).is_ok_and(|order| order == "child\nslot\ncommand\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compressed_file_slot_launches() {
+        use std::io::Write as _;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        assert!(encoder.write_all(b"key = 1\n").is_ok());
+        let compressed = encoder.finish();
+        assert!(compressed.is_ok_and(|data| fs::write(root.join("conf.gz"), data).is_ok()));
+        let manifest = serde_json::json!({
+            "package": {"name": "gzip-slot", "version": "1.0"},
+            "execution": {"command": "/bin/echo hello"},
+            "slots": [{"id": "conf", "source": root.join("conf.gz"), "target": "conf.toml",
+                       "operations": "gzip"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = crate::api::BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("gzip-slot".to_string()),
+            ..crate::api::BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        // The stored checksum describes the gzip bytes, not the extracted file
+        let workenv = WorkenvPaths::new(root.join("cache"), &package).workenv();
+        for verify_workenv in [WorkenvVerification::Off, WorkenvVerification::Full] {
+            let options = LaunchOptions {
+                workdir: Some(workenv.display().to_string()),
+                verify_workenv,
+                ..LaunchOptions::default()
+            };
+            let mut timings = LaunchTimings::default();
+            let code = launch_timed(&package, &[], options, &mut timings, true);
+            assert!(code.is_ok_and(|code| code == 0));
+        }
+        assert!(fs::read(workenv.join("conf.toml")).is_ok_and(|data| data == b"key = 1\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pre_exec_hook_error_aborts_the_launch() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        let package = build_echo_package(root);
+
+        let seen = Arc::new(Mutex::new(None));
+        let record = Arc::clone(&seen);
+        let hook = crate::api::PreExecHook::new(move |context| {
+            if let Ok(mut seen) = record.lock() {
+                *seen = Some((context.executable.to_string(), context.args.to_vec()));
+            }
+            Err(FlavorError::LaunchError("refused by hook".to_string()))
+        });
+        let options = LaunchOptions {
+            workdir: Some(root.join("cache").display().to_string()),
+            pre_exec_hook: Some(hook),
+            ..LaunchOptions::default()
+        };
+        let launched = launch(&package, &["world".to_string()], options);
+        assert!(launched.is_err_and(|e| e.to_string().contains("refused by hook")));
+        let seen = seen.lock();
+        assert!(seen.is_ok());
+        let Ok(seen) = seen else { return };
+        assert!(seen.is_some());
+        let Some((executable, args)) = seen.as_ref() else {
+            return;
+        };
+        assert!(executable.ends_with("/echo"));
+        assert_eq!(args, &["hello", "world"]);
+    }
+}
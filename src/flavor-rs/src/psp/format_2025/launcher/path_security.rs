@@ -0,0 +1,117 @@
+//! Refuse to run packages from locations other users can tamper with (Unix only)
+
+use crate::exceptions::Result;
+use std::path::Path;
+
+/// Whether the insecure-location guard applies
+///
+/// `FLAVOR_REFUSE_INSECURE_PATH` wins when set (a true value such as `1` or
+/// `true` enables, anything else disables); otherwise the guard follows
+/// strict validation.
+pub(super) fn guard_enabled(requested: bool, strict: bool) -> bool {
+    if requested {
+        return true;
+    }
+    if std::env::var_os("FLAVOR_REFUSE_INSECURE_PATH").is_some() {
+        crate::utils::is_env_true("FLAVOR_REFUSE_INSECURE_PATH")
+    } else {
+        strict
+    }
+}
+
+/// Fail if the package file or its directory is writable by other users
+///
+/// A package is refused when the file is world-writable or owned by another
+/// non-root user, or when its directory is world-writable without the sticky
+/// bit or owned by another non-root user.
+#[cfg(unix)]
+pub(super) fn check_package_location(package_path: &Path) -> Result<()> {
+    use crate::exceptions::FlavorError;
+    use log::debug;
+    use std::os::unix::fs::MetadataExt;
+
+    let package_path = package_path.canonicalize()?;
+    let uid = current_uid();
+    let owned_by_other = |owner: u32| owner != uid && owner != 0;
+
+    let file = std::fs::metadata(&package_path)?;
+    let mut problems = Vec::new();
+    if file.mode() & 0o002 != 0 {
+        problems.push("the package is world-writable".to_string());
+    }
+    if owned_by_other(file.uid()) {
+        problems.push(format!("the package is owned by uid {}", file.uid()));
+    }
+
+    if let Some(dir) = package_path.parent() {
+        let dir_meta = std::fs::metadata(dir)?;
+        if dir_meta.mode() & 0o002 != 0 && dir_meta.mode() & 0o1000 == 0 {
+            problems.push(format!("{} is world-writable", dir.display()));
+        }
+        if owned_by_other(dir_meta.uid()) {
+            problems.push(format!(
+                "{} is owned by uid {}",
+                dir.display(),
+                dir_meta.uid()
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        debug!("🔒 Package location is not writable by other users: {package_path:?}");
+        return Ok(());
+    }
+
+    Err(FlavorError::LaunchError(format!(
+        "Refusing to run {} from an insecure location: {}. \
+         Move it somewhere only you (or root) can write, or set \
+         FLAVOR_REFUSE_INSECURE_PATH=0 to override",
+        package_path.display(),
+        problems.join("; ")
+    )))
+}
+
+#[cfg(not(unix))]
+pub(super) fn check_package_location(_package_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+#[allow(unsafe_code)]
+fn current_uid() -> u32 {
+    // SAFETY: geteuid has no preconditions and cannot fail.
+    unsafe { libc::geteuid() }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_world_writable_locations_are_refused() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let package = dir.path().join("app.psp");
+        assert!(fs::write(&package, b"psp").is_ok());
+        let chmod = |path: &Path, mode| fs::set_permissions(path, fs::Permissions::from_mode(mode));
+
+        assert!(chmod(dir.path(), 0o700).is_ok());
+        assert!(chmod(&package, 0o755).is_ok());
+        assert!(check_package_location(&package).is_ok());
+
+        assert!(chmod(&package, 0o757).is_ok());
+        assert!(check_package_location(&package).is_err());
+
+        assert!(chmod(&package, 0o755).is_ok());
+        assert!(chmod(dir.path(), 0o777).is_ok());
+        assert!(check_package_location(&package).is_err());
+
+        // Sticky directories such as /tmp keep others from replacing the file
+        assert!(chmod(dir.path(), 0o1777).is_ok());
+        assert!(check_package_location(&package).is_ok());
+        assert!(chmod(dir.path(), 0o700).is_ok());
+    }
+}
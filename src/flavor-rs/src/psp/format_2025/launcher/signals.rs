@@ -0,0 +1,211 @@
+//! Forward termination signals to the spawned child (Unix only)
+//!
+//! Exec mode needs none of this: the package replaces the launcher process and
+//! receives signals directly.
+//!
+//! The handlers are registered once per process and stay registered: while no
+//! [`SignalForwarder`] is active they emulate the default action, so the
+//! signals terminate the launcher again as if no handler had been installed.
+//!
+//! A forwarder is installed before the child is spawned, so no signal can
+//! slip through between spawn and install; signals arriving before
+//! [`SignalForwarder::forward_to`] names the child are held and relayed then.
+
+use crate::exceptions::{FlavorError, Result};
+use log::debug;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::low_level::emulate_default_handler;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Signals relayed to the child
+const FORWARDED_SIGNALS: [i32; 3] = [SIGINT, SIGTERM, SIGHUP];
+
+/// Pid the handlers relay to, 0 while no forwarder is active
+static FORWARD_PID: AtomicI32 = AtomicI32::new(0);
+
+/// [`FORWARD_PID`] while the active forwarder's child is not spawned yet
+const PENDING: i32 = -1;
+
+/// Last signal received while [`PENDING`], 0 if none
+static HELD_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Outcome of registering the handlers, the first time a forwarder is installed
+static HANDLERS: OnceLock<std::result::Result<(), String>> = OnceLock::new();
+
+/// Relays SIGINT/SIGTERM/SIGHUP to a child process until dropped
+///
+/// While installed, these signals no longer terminate the launcher itself, so
+/// it keeps waiting for the child and can run shutdown hooks afterwards. Once
+/// dropped, the signals are relayed to whatever the previous forwarder relayed
+/// to, or take their default action again; a signal still held because no
+/// child was named is handled that way too.
+#[derive(Debug)]
+pub(super) struct SignalForwarder {
+    previous: libc::pid_t,
+}
+
+impl SignalForwarder {
+    /// Register the handlers and hold the signals until a child is named
+    ///
+    /// Call before spawning the child, then [`Self::forward_to`] with its pid.
+    pub(super) fn install() -> Result<Self> {
+        HANDLERS
+            .get_or_init(|| register_handlers().map_err(|e| e.to_string()))
+            .clone()
+            .map_err(|e| {
+                FlavorError::LaunchError(format!("Failed to install signal handlers: {e}"))
+            })?;
+
+        let previous = FORWARD_PID.swap(PENDING, Ordering::SeqCst);
+        Ok(Self { previous })
+    }
+
+    /// Relay the signals to `child_pid`, starting with any held since install
+    pub(super) fn forward_to(&self, child_pid: u32) -> Result<()> {
+        let pid = libc::pid_t::try_from(child_pid)
+            .ok()
+            .filter(|&pid| pid > 0)
+            .ok_or_else(|| FlavorError::LaunchError(format!("Invalid child pid {child_pid}")))?;
+        FORWARD_PID.store(pid, Ordering::SeqCst);
+        debug!("📡 Forwarding SIGINT/SIGTERM/SIGHUP to child {child_pid}");
+        match HELD_SIGNAL.swap(0, Ordering::SeqCst) {
+            0 => {}
+            signal => {
+                debug!("📡 Relaying signal {signal} received before the child started");
+                // A child that already exited has nothing left to signal
+                if let Err(e) = send_signal(pid, signal) {
+                    debug!("Could not relay signal {signal} to child {child_pid}: {e}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SignalForwarder {
+    fn drop(&mut self) {
+        FORWARD_PID.store(self.previous, Ordering::SeqCst);
+        match HELD_SIGNAL.swap(0, Ordering::SeqCst) {
+            0 => {}
+            signal => relay(signal),
+        }
+    }
+}
+
+/// Register [`relay`] for every forwarded signal
+#[allow(unsafe_code)]
+fn register_handlers() -> std::io::Result<()> {
+    for signal in FORWARDED_SIGNALS {
+        // SAFETY: the action only loads an atomic and calls kill(2) or
+        // emulate_default_handler, which are async-signal-safe.
+        unsafe { signal_hook::low_level::register(signal, move || relay(signal)) }?;
+    }
+    Ok(())
+}
+
+/// Signal handler action: relay to the child, hold the signal until there is
+/// one, or act as the default handler
+///
+/// Runs in signal context, so nothing here may allocate, lock or log.
+fn relay(signal: i32) {
+    match FORWARD_PID.load(Ordering::SeqCst) {
+        0 => {
+            let _ = emulate_default_handler(signal);
+        }
+        PENDING => HELD_SIGNAL.store(signal, Ordering::SeqCst),
+        pid => {
+            let _ = send_signal(pid, signal);
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+fn send_signal(pid: libc::pid_t, signal: i32) -> std::io::Result<()> {
+    // SAFETY: kill has no memory-safety preconditions; failures are reported via errno.
+    if unsafe { libc::kill(pid, signal) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Command, Stdio};
+
+    /// Set in the environment of the test binary re-run by
+    /// [`test_signals_are_forwarded_then_restored`]
+    const CHILD_ENV: &str = "FLAVOR_TEST_SIGNAL_CHILD";
+
+    /// The part of [`test_signals_are_forwarded_then_restored`] that signals
+    /// its own process; does nothing unless run as that test's child
+    #[test]
+    fn signal_forwarding_child() {
+        if std::env::var_os(CHILD_ENV).is_none() {
+            return;
+        }
+        let forwarder = SignalForwarder::install();
+        assert!(forwarder.is_ok());
+        let Ok(forwarder) = forwarder else { return };
+        // Before the child exists the signal is held, not fatal
+        assert!(send_signal(std::process::id() as libc::pid_t, SIGTERM).is_ok());
+
+        let child = Command::new("sh")
+            .args(["-c", "trap 'exit 42' TERM; while :; do sleep 0.05; done"])
+            .spawn();
+        assert!(child.is_ok());
+        let Ok(mut child) = child else { return };
+        // Give the shell time to install its trap
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(forwarder.forward_to(child.id()).is_ok());
+        assert!(child.wait().is_ok_and(|s| s.code() == Some(42)));
+
+        // Later signals go straight to the (next) child
+        let child = Command::new("sh")
+            .args(["-c", "trap 'exit 43' TERM; while :; do sleep 0.05; done"])
+            .spawn();
+        assert!(child.is_ok());
+        let Ok(mut child) = child else { return };
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert!(forwarder.forward_to(child.id()).is_ok());
+        assert!(send_signal(std::process::id() as libc::pid_t, SIGTERM).is_ok());
+        assert!(child.wait().is_ok_and(|s| s.code() == Some(43)));
+        let mut stdout = std::io::stdout();
+        assert!(
+            writeln!(stdout, "forwarded")
+                .and_then(|()| stdout.flush())
+                .is_ok()
+        );
+
+        // With the forwarder gone, SIGTERM terminates this process again
+        drop(forwarder);
+        assert!(send_signal(std::process::id() as libc::pid_t, SIGTERM).is_ok());
+        std::thread::sleep(std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_signals_are_forwarded_then_restored() {
+        let exe = std::env::current_exe();
+        assert!(exe.is_ok());
+        let Ok(exe) = exe else { return };
+        let output = Command::new(exe)
+            .args([
+                "--exact",
+                "psp::format_2025::launcher::signals::tests::signal_forwarding_child",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(CHILD_ENV, "1")
+            .stdin(Stdio::null())
+            .output();
+        assert!(output.is_ok());
+        let Ok(output) = output else { return };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("forwarded"), "child output: {stdout}");
+        assert_eq!(output.status.signal(), Some(SIGTERM));
+    }
+}
@@ -1,13 +1,15 @@
 //! Work environment management
 
 use super::super::defaults::DEFAULT_DISK_SPACE_MULTIPLIER;
-use super::super::metadata::{Metadata, WorkenvInfo};
+use super::super::metadata::{Metadata, SymlinkSpec, WorkenvInfo};
 use super::super::paths::WorkenvPaths;
-use crate::exceptions::Result;
+use crate::api::{WorkenvOutput, WorkenvVerification};
+use crate::exceptions::{FlavorError, Result};
 use crate::utils::get_cache_dir;
 use log::{debug, warn};
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
 
 /// Calculate a deterministic cache path for a package
 pub(super) fn get_workenv_paths(package_path: &Path) -> WorkenvPaths {
@@ -15,8 +17,64 @@ pub(super) fn get_workenv_paths(package_path: &Path) -> WorkenvPaths {
     WorkenvPaths::new(cache_base, package_path)
 }
 
+/// Parse a `FLAVOR_PRINT_WORKENV` value: `1`/`true`/`stderr`, `stdout` or `fd:N`
+pub(super) fn parse_workenv_output(value: &str) -> Option<WorkenvOutput> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "stderr" => Some(WorkenvOutput::Stderr),
+        "stdout" => Some(WorkenvOutput::Stdout),
+        other => other
+            .strip_prefix("fd:")
+            .and_then(|fd| fd.parse().ok())
+            .map(WorkenvOutput::Fd),
+    }
+}
+
+/// Parse a `FLAVOR_VERIFY_WORKENV` value: `off`/`0`, `sample` or `full`/`1`
+pub(super) fn parse_workenv_verification(value: &str) -> Option<WorkenvVerification> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "0" | "false" | "off" => Some(WorkenvVerification::Off),
+        "sample" => Some(WorkenvVerification::Sample),
+        "1" | "true" | "full" => Some(WorkenvVerification::Full),
+        _ => None,
+    }
+}
+
+/// Write the workenv path, followed by a newline, to `output`
+pub(super) fn print_workenv(output: WorkenvOutput, workenv_path: &Path) -> Result<()> {
+    let line = format!("{}\n", workenv_path.display());
+    match output {
+        WorkenvOutput::Stderr => std::io::stderr().write_all(line.as_bytes())?,
+        WorkenvOutput::Stdout => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(line.as_bytes())?;
+            stdout.flush()?;
+        }
+        // Reopened by path: wrapping a raw descriptor would need unsafe code
+        #[cfg(unix)]
+        WorkenvOutput::Fd(fd) => fs::OpenOptions::new()
+            .append(true)
+            .open(format!("/dev/fd/{fd}"))
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| {
+                FlavorError::LaunchError(format!("Cannot print workenv to fd {fd}: {e}"))
+            })?,
+        #[cfg(not(unix))]
+        WorkenvOutput::Fd(fd) => {
+            return Err(FlavorError::LaunchError(format!(
+                "Printing the workenv to fd {fd} is only supported on Unix"
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Check if there's enough disk space for extraction
 pub(super) fn check_disk_space(_paths: &WorkenvPaths, metadata: &Metadata) -> Result<()> {
+    if metadata.slots.is_empty() {
+        debug!("✅ No slots to extract, skipping disk space check");
+        return Ok(());
+    }
+
     // Calculate total size needed (compressed size * DISK_SPACE_MULTIPLIER for safety)
     let _total_size_needed: u64 = metadata
         .slots
@@ -50,7 +108,7 @@ pub(super) fn check_disk_space(_paths: &WorkenvPaths, metadata: &Metadata) -> Re
             }
             Err(e) => {
                 warn!("⚠️ Could not create workenv directory: {}", e);
-                return Err(FlavorError::Generic(format!(
+                return Err(FlavorError::ExtractionError(format!(
                     "Cannot create workenv directory: {}",
                     e
                 )));
@@ -70,7 +128,10 @@ pub(super) fn check_disk_space(_paths: &WorkenvPaths, metadata: &Metadata) -> Re
 pub(super) fn setup_workenv_directories(
     workenv_path: &Path,
     workenv_info: &WorkenvInfo,
+    umask: Option<u32>,
 ) -> Result<()> {
+    #[cfg(not(unix))]
+    let _ = umask;
     if let Some(ref directories) = workenv_info.directories {
         for dir_spec in directories {
             // Substitute {workenv} placeholder in the path
@@ -93,28 +154,251 @@ pub(super) fn setup_workenv_directories(
             // Set permissions on Unix systems
             #[cfg(unix)]
             {
-                use super::super::defaults::DEFAULT_DIR_PERMS;
+                use super::super::defaults::extraction_dir_mode;
                 use std::os::unix::fs::PermissionsExt;
 
-                // Use specified mode or default to 0700 (user-only access)
-                let mode_str = dir_spec.mode.as_deref().unwrap_or("0700");
-
-                // Parse octal mode string (e.g., "0700")
-                if let Ok(mode) = u32::from_str_radix(mode_str.trim_start_matches('0'), 8) {
-                    let permissions = fs::Permissions::from_mode(mode);
-                    fs::set_permissions(&dir_path, permissions)?;
-                    debug!("🔒 Set permissions {} on {:?}", mode_str, dir_path);
-                } else {
-                    // Fallback to default dir permissions if parsing fails
-                    let permissions = fs::Permissions::from_mode(DEFAULT_DIR_PERMS as u32);
-                    fs::set_permissions(&dir_path, permissions)?;
-                    debug!(
-                        "🔒 Set default permissions {} on {:?}",
-                        DEFAULT_DIR_PERMS, dir_path
-                    );
-                }
+                // Use specified mode or default to 0700 (user-only access);
+                // unparseable modes fall back to the default as well
+                let explicit = dir_spec
+                    .mode
+                    .as_deref()
+                    .and_then(|m| u32::from_str_radix(m.trim_start_matches('0'), 8).ok());
+                let mode = extraction_dir_mode(explicit, umask);
+                fs::set_permissions(&dir_path, fs::Permissions::from_mode(mode))?;
+                debug!("🔒 Set permissions {:o} on {:?}", mode, dir_path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Create the symlinks listed in the workenv configuration
+///
+/// Runs once extracted content is in its final place, so `{workenv}` in a
+/// target is the workenv's real path. Links and targets that would leave the
+/// workenv are rejected. Without symlink support each link is a copy of its
+/// target instead.
+pub(super) fn create_workenv_symlinks(
+    workenv_path: &Path,
+    workenv_info: &WorkenvInfo,
+) -> Result<()> {
+    let Some(ref symlinks) = workenv_info.symlinks else {
+        return Ok(());
+    };
+    for spec in symlinks {
+        let (link, target, resolved) = resolve_symlink(workenv_path, spec)?;
+        let escape = || {
+            FlavorError::ExtractionError(format!(
+                "Symlink {} -> {} leads outside the workenv",
+                spec.link, spec.target
+            ))
+        };
+        let parent = link.parent().unwrap_or(workenv_path);
+        fs::create_dir_all(parent)?;
+        // Extracted symlinks along either path could still lead elsewhere
+        let root = workenv_path.canonicalize()?;
+        let outside = |path: &Path| {
+            path.canonicalize()
+                .is_ok_and(|real| !real.starts_with(&root))
+        };
+        if outside(parent) || outside(&resolved) {
+            return Err(escape());
+        }
+
+        if let Ok(existing) = fs::symlink_metadata(&link) {
+            if !existing.file_type().is_symlink() {
+                return Err(FlavorError::ExtractionError(format!(
+                    "Cannot create symlink {}: an extracted file is already there",
+                    spec.link
+                )));
+            }
+            fs::remove_file(&link)?;
+        }
+
+        #[cfg(unix)]
+        {
+            let _ = resolved;
+            std::os::unix::fs::symlink(&target, &link)?;
+            debug!("🔗 Linked {link:?} -> {target:?}");
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = target;
+            if resolved.is_dir() {
+                warn!("⚠️ Symlinks aren't supported here, copying {resolved:?} to {link:?}");
+                super::filesystem::copy_dir_all(&resolved, &link)?;
+            } else if resolved.is_file() {
+                warn!("⚠️ Symlinks aren't supported here, copying {resolved:?} to {link:?}");
+                fs::copy(&resolved, &link)?;
+            } else {
+                warn!("⚠️ Skipping symlink {link:?}: {resolved:?} doesn't exist to copy");
             }
         }
     }
     Ok(())
 }
+
+/// Resolve a symlink spec to the link's path, the target to write into it and
+/// the path that target points at
+///
+/// A `{workenv}` target becomes absolute; other targets are kept relative to
+/// the link's directory. Absolute paths and `..` climbing out of the workenv
+/// are errors.
+fn resolve_symlink(workenv_path: &Path, spec: &SymlinkSpec) -> Result<(PathBuf, PathBuf, PathBuf)> {
+    let escape = || {
+        FlavorError::ExtractionError(format!(
+            "Symlink {} -> {} leads outside the workenv",
+            spec.link, spec.target
+        ))
+    };
+    let link = join_within(workenv_path, workenv_path, strip_workenv(&spec.link))
+        .filter(|link| link != workenv_path)
+        .ok_or_else(escape)?;
+
+    if spec.target.starts_with("{workenv}") {
+        let resolved = join_within(workenv_path, workenv_path, strip_workenv(&spec.target))
+            .ok_or_else(escape)?;
+        return Ok((link, resolved.clone(), resolved));
+    }
+    let parent = link.parent().unwrap_or(workenv_path);
+    let resolved = join_within(workenv_path, parent, &spec.target).ok_or_else(escape)?;
+    Ok((link, PathBuf::from(&spec.target), resolved))
+}
+
+/// Drop a leading `{workenv}` placeholder, leaving a path relative to the workenv
+fn strip_workenv(path: &str) -> &str {
+    path.strip_prefix("{workenv}")
+        .map_or(path, |rest| rest.trim_start_matches('/'))
+}
+
+/// Join `path` onto `base` without touching the filesystem, or `None` if the
+/// result is outside `root`
+fn join_within(root: &Path, base: &Path, path: impl AsRef<Path>) -> Option<PathBuf> {
+    let mut joined = base.to_path_buf();
+    for component in path.as_ref().components() {
+        match component {
+            Component::Normal(part) => joined.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                joined.pop();
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+        if !joined.starts_with(root) {
+            return None;
+        }
+    }
+    Some(joined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_workenv_output() {
+        assert_eq!(parse_workenv_output("1"), Some(WorkenvOutput::Stderr));
+        assert_eq!(parse_workenv_output("STDERR"), Some(WorkenvOutput::Stderr));
+        assert_eq!(parse_workenv_output("stdout"), Some(WorkenvOutput::Stdout));
+        assert_eq!(parse_workenv_output("fd:3"), Some(WorkenvOutput::Fd(3)));
+        assert_eq!(parse_workenv_output("fd:x"), None);
+        assert_eq!(parse_workenv_output("0"), None);
+    }
+
+    #[test]
+    fn test_parse_workenv_verification() {
+        assert_eq!(
+            parse_workenv_verification("Sample"),
+            Some(WorkenvVerification::Sample)
+        );
+        assert_eq!(
+            parse_workenv_verification("1"),
+            Some(WorkenvVerification::Full)
+        );
+        assert_eq!(
+            parse_workenv_verification("off"),
+            Some(WorkenvVerification::Off)
+        );
+        assert_eq!(parse_workenv_verification("some"), None);
+    }
+
+    fn symlinks(pairs: &[(&str, &str)]) -> WorkenvInfo {
+        WorkenvInfo {
+            directories: None,
+            env: None,
+            symlinks: Some(
+                pairs
+                    .iter()
+                    .map(|(link, target)| SymlinkSpec {
+                        link: link.to_string(),
+                        target: target.to_string(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_workenv_symlinks() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let workenv = dir.path();
+        assert!(fs::create_dir_all(workenv.join("bin")).is_ok());
+        assert!(fs::write(workenv.join("bin/python3.11"), b"py").is_ok());
+
+        let info = symlinks(&[
+            ("bin/python", "python3.11"),
+            ("{workenv}/current", "{workenv}/bin"),
+            ("lib/python", "../bin/python"),
+        ]);
+        assert!(create_workenv_symlinks(workenv, &info).is_ok());
+        assert!(
+            fs::read_link(workenv.join("bin/python")).is_ok_and(|t| t == Path::new("python3.11"))
+        );
+        assert!(fs::read_link(workenv.join("current")).is_ok_and(|t| t == workenv.join("bin")));
+        assert!(fs::read(workenv.join("lib/python")).is_ok_and(|d| d == b"py"));
+
+        // Re-running replaces the links it made
+        assert!(create_workenv_symlinks(workenv, &info).is_ok());
+        // but not extracted files
+        let clash = symlinks(&[("bin/python3.11", "python")]);
+        assert!(create_workenv_symlinks(workenv, &clash).is_err());
+    }
+
+    #[test]
+    fn test_symlinks_leaving_the_workenv_are_rejected() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let workenv = dir.path().join("workenv");
+        assert!(fs::create_dir_all(&workenv).is_ok());
+
+        for (link, target) in [
+            ("bin/python", "/usr/bin/python3"),
+            ("bin/passwd", "../../etc/passwd"),
+            ("{workenv}/../python", "bin/python3"),
+            ("/tmp/python", "bin/python3"),
+            ("{workenv}", "bin"),
+            ("bin/up", "{workenv}/.."),
+        ] {
+            let result = create_workenv_symlinks(&workenv, &symlinks(&[(link, target)]));
+            assert!(
+                result.is_err_and(|e| matches!(e, FlavorError::ExtractionError(_))),
+                "{link} -> {target}"
+            );
+        }
+        assert!(!workenv.join("bin/python").exists());
+        assert!(!dir.path().join("python").exists());
+
+        // Nor can an extracted symlink carry the link out
+        #[cfg(unix)]
+        {
+            assert!(std::os::unix::fs::symlink(dir.path(), workenv.join("escape")).is_ok());
+            let through = symlinks(&[("escape/python", "python3")]);
+            assert!(create_workenv_symlinks(&workenv, &through).is_err());
+            assert!(!dir.path().join("python").exists());
+        }
+    }
+}
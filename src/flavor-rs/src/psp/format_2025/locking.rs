@@ -5,7 +5,12 @@ use log::{debug, info};
 use std::fs;
 use std::io::Write;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use super::defaults::{
+    EXTRACTION_WAIT_INITIAL_BACKOFF_MS, EXTRACTION_WAIT_MAX_BACKOFF_MS, KEPT_TEMP_RETENTION_SECS,
+};
 use super::paths::WorkenvPaths;
 
 /// Global flag for lock acquisition status
@@ -19,10 +24,33 @@ pub fn is_process_running(pid: u32) -> bool {
     std::path::Path::new(&proc_path).exists()
 }
 
-#[cfg(not(unix))]
+#[cfg(windows)]
+#[allow(unsafe_code)] // Required for Windows API FFI calls
+pub fn is_process_running(pid: u32) -> bool {
+    use windows::Win32::Foundation::{CloseHandle, E_ACCESSDENIED, STILL_ACTIVE};
+    use windows::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // SAFETY: the handle is only used while open and is closed before returning
+    unsafe {
+        let handle = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+            Ok(handle) => handle,
+            // The process exists but belongs to someone we can't query
+            Err(e) => return e.code() == E_ACCESSDENIED,
+        };
+
+        let mut exit_code = 0u32;
+        let running = GetExitCodeProcess(handle, &mut exit_code).is_ok()
+            && exit_code == STILL_ACTIVE.0 as u32;
+        let _ = CloseHandle(handle);
+        running
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
 pub fn is_process_running(_pid: u32) -> bool {
-    // On non-Unix systems, assume process is not running
-    // This is conservative but safe
+    // No process inspection available; assume process is not running
     false
 }
 
@@ -96,36 +124,58 @@ pub fn release_lock(paths: &WorkenvPaths) {
     LOCK_ACQUIRED.store(false, Ordering::SeqCst);
 }
 
-/// Wait for another process to finish extraction
-pub fn wait_for_extraction(paths: &WorkenvPaths, timeout_secs: u64) -> Result<()> {
-    use std::thread;
-    use std::time::Duration;
+/// Outcome of waiting on another process's extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitOutcome {
+    /// The lock was released; the cache should be ready
+    Released,
+    /// The lock holder died mid-extraction; the caller should extract itself
+    HolderDied,
+}
 
+/// Wait for another process to finish extraction
+///
+/// Polls with exponential backoff (10ms doubling up to 500ms) until the lock is
+/// released, the lock-holder process dies, or `timeout` elapses.
+pub fn wait_for_extraction(paths: &WorkenvPaths, timeout: Duration) -> Result<WaitOutcome> {
     let lock_path = paths.lock_file();
-    let max_attempts = timeout_secs * 10; // Check every 100ms
+    let start = Instant::now();
+    let mut backoff = Duration::from_millis(EXTRACTION_WAIT_INITIAL_BACKOFF_MS);
+    let max_backoff = Duration::from_millis(EXTRACTION_WAIT_MAX_BACKOFF_MS);
 
-    for attempt in 0..max_attempts {
+    loop {
+        // The completion marker is written before the lock is released
         if !lock_path.exists() {
             debug!("✅ Extraction lock released, cache should be ready");
-            // Give a bit more time for files to be fully written
-            thread::sleep(Duration::from_millis(100));
-            return Ok(());
+            return Ok(WaitOutcome::Released);
         }
 
-        if attempt % 10 == 0 {
-            debug!(
-                "⏳ Waiting for extraction to complete... ({}/{}s)",
-                attempt / 10,
-                timeout_secs
-            );
+        let holder = fs::read_to_string(&lock_path)
+            .ok()
+            .and_then(|contents| contents.trim().parse::<u32>().ok());
+        if let Some(pid) = holder {
+            if !is_process_running(pid) {
+                info!("💀 Extraction lock holder (PID: {pid}) died, taking over");
+                return Ok(WaitOutcome::HolderDied);
+            }
         }
 
-        thread::sleep(Duration::from_millis(100));
-    }
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            return Err(FlavorError::ExtractionError(format!(
+                "Timeout waiting for cache extraction to complete ({}s)",
+                timeout.as_secs()
+            )));
+        }
 
-    Err(FlavorError::Generic(
-        "Timeout waiting for cache extraction to complete".to_string(),
-    ))
+        debug!(
+            "⏳ Waiting for extraction to complete... ({:.1}/{}s)",
+            elapsed.as_secs_f64(),
+            timeout.as_secs()
+        );
+        thread::sleep(backoff.min(timeout - elapsed));
+        backoff = (backoff * 2).min(max_backoff);
+    }
 }
 
 /// Mark cache extraction as complete
@@ -133,15 +183,18 @@ pub fn mark_extraction_complete(paths: &WorkenvPaths) -> Result<()> {
     let extract_dir = paths.extract();
     fs::create_dir_all(&extract_dir)?;
     let marker_path = paths.complete_file();
-    let mut file = fs::File::create(&marker_path)?;
-    writeln!(file, "{}", std::process::id())?;
+    crate::utils::write_atomic(&marker_path, format!("{}\n", std::process::id()).as_bytes())?;
     debug!("✅ Marked extraction as complete");
     Ok(())
 }
 
 /// Check if cache extraction is complete
+///
+/// The marker holds the PID of the extracting process; an empty or garbled
+/// marker (e.g. from a launcher that wrote it non-atomically) counts as absent.
 pub fn is_extraction_complete(paths: &WorkenvPaths) -> bool {
-    paths.complete_file().exists()
+    fs::read_to_string(paths.complete_file())
+        .is_ok_and(|contents| contents.trim().parse::<u32>().is_ok())
 }
 
 /// Mark cache as incomplete (used during signal handling)
@@ -160,9 +213,24 @@ pub fn is_lock_acquired() -> bool {
     LOCK_ACQUIRED.load(Ordering::SeqCst)
 }
 
+/// Whether `name` is a failed extraction kept by `FLAVOR_KEEP_TEMP`
+/// (`<pid>-<unix secs>.failed`) older than [`KEPT_TEMP_RETENTION_SECS`]
+fn is_expired_kept_dir(name: &str, now_secs: u64) -> bool {
+    name.strip_suffix(".failed")
+        .and_then(|stem| stem.split_once('-'))
+        .and_then(|(_, secs)| secs.parse::<u64>().ok())
+        .is_some_and(|secs| now_secs.saturating_sub(secs) > KEPT_TEMP_RETENTION_SECS)
+}
+
 /// Clean up stale extraction directories from dead processes
+///
+/// Failed extractions kept by `FLAVOR_KEEP_TEMP` are removed once they are
+/// older than [`KEPT_TEMP_RETENTION_SECS`].
 pub fn cleanup_stale_extractions(paths: &WorkenvPaths) -> Result<()> {
     let tmp_dir = paths.tmp();
+    let now_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
 
     // If the directory doesn't exist, nothing to clean
     if !tmp_dir.exists() {
@@ -186,6 +254,15 @@ pub fn cleanup_stale_extractions(paths: &WorkenvPaths) -> Result<()> {
                             debug!("⚠️ Failed to remove stale directory {:?}: {}", stale_dir, e);
                         }
                     }
+                } else if is_expired_kept_dir(&file_name, now_secs) {
+                    let kept_dir = entry.path();
+                    info!(
+                        "🧹 Removing expired kept extraction directory {:?}",
+                        kept_dir
+                    );
+                    if let Err(e) = fs::remove_dir_all(&kept_dir) {
+                        debug!("⚠️ Failed to remove kept directory {:?}: {}", kept_dir, e);
+                    }
                 }
             }
         }
@@ -193,3 +270,46 @@ pub fn cleanup_stale_extractions(paths: &WorkenvPaths) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(all(test, any(unix, windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_process_is_running() {
+        assert!(is_process_running(std::process::id()));
+    }
+
+    #[test]
+    fn test_bogus_pid_is_not_running() {
+        // Windows PIDs are multiples of 4, Linux caps them well below this
+        assert!(!is_process_running(u32::MAX - 2));
+    }
+
+    #[test]
+    fn test_cleanup_removes_expired_kept_dirs() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let paths = WorkenvPaths::new(dir.path().to_path_buf(), &dir.path().join("app.psp"));
+        let tmp = paths.tmp();
+
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let expired = tmp.join(format!(
+            "42-{}.failed",
+            now_secs - KEPT_TEMP_RETENTION_SECS - 1
+        ));
+        let recent = tmp.join(format!("42-{now_secs}.failed"));
+        let unrelated = tmp.join("notes.failed");
+        for kept in [&expired, &recent, &unrelated] {
+            assert!(fs::create_dir_all(kept).is_ok());
+        }
+
+        assert!(cleanup_stale_extractions(&paths).is_ok());
+        assert!(!expired.exists());
+        assert!(recent.exists());
+        assert!(unrelated.exists());
+    }
+}
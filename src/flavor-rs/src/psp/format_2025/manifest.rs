@@ -1,7 +1,13 @@
 //! Build manifest structures for PSPF/2025
 
+use crate::exceptions::{FlavorError, Result};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Build manifest structure - matches PSPF/2025 spec
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,6 +23,15 @@ pub struct BuildManifest {
     pub workenv: Option<serde_json::Value>,
     #[serde(default)]
     pub setup_commands: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shutdown_commands: Vec<serde_json::Value>,
+    /// Manifests merged in beneath this one (see [`resolve_includes`]); always
+    /// empty once read
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<String>,
+    /// Expected launcher `--version` output; a trailing `*` matches by prefix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launcher_version: Option<String>,
 }
 
 /// Package information
@@ -34,14 +49,23 @@ pub struct ExecutionInfo {
     pub command: String,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<String>, // Child cwd; defaults to the caller's cwd
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub entrypoints: BTreeMap<String, String>, // Name -> command, e.g. busybox-style tools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argv0: Option<String>, // argv[0] for the launched process, for multi-call binaries
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManifestSlot {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub slot: Option<i32>, // Optional: position validator
-    pub id: String,     // Arbitrary identifier for the slot
+    pub id: String, // Arbitrary identifier for the slot
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub source: String, // Source path within the package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<InlineContent>, // Slot data given inline, instead of `source`
     pub target: String, // Destination path in workenv
     #[serde(default)]
     pub operations: String, // Operations chain (e.g., "gzip", "tar.gz")
@@ -53,6 +77,75 @@ pub struct ManifestSlot {
     pub permissions: Option<String>, // Unix permissions as octal string (e.g., "0755")
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resolution: Option<String>, // When to resolve: build|runtime|lazy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_level: Option<CompressionLevel>, // For inline `content`: 0-9 or "fast"/"default"/"best"
+    #[serde(default = "default_required")]
+    pub required: bool, // false: skip the slot when its source is missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>, // Extract only when this holds at launch (see `condition`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>, // Free-form notes for tooling, e.g. license
+}
+
+impl ManifestSlot {
+    /// The slot's inline data, once checked that exactly one of `source` and
+    /// `content` is set
+    pub fn inline_data(&self) -> Result<Option<Vec<u8>>> {
+        let error =
+            |message: String| FlavorError::ConfigError(format!("Slot '{}': {message}", self.id));
+        match (&self.content, self.source.is_empty()) {
+            (None, false) => Ok(None),
+            (Some(content), true) => content.bytes().map(Some).map_err(error),
+            (None, true) => Err(error("needs a 'source' or inline 'content'".into())),
+            (Some(_), false) => Err(error(
+                "has both 'source' and 'content', set only one".into(),
+            )),
+        }
+    }
+}
+
+/// Slot data written in the manifest: a string, or `{"base64": "..."}` for bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum InlineContent {
+    Text(String),
+    Base64 { base64: String },
+}
+
+impl InlineContent {
+    /// Decode to the slot's bytes
+    pub fn bytes(&self) -> std::result::Result<Vec<u8>, String> {
+        match self {
+            Self::Text(text) => Ok(text.as_bytes().to_vec()),
+            Self::Base64 { base64 } => STANDARD
+                .decode(base64)
+                .map_err(|e| format!("inline content is not valid base64: {e}")),
+        }
+    }
+}
+
+/// Compression level for a slot, as a number (0-9) or a named preset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CompressionLevel {
+    Numeric(u32),
+    Named(String),
+}
+
+impl CompressionLevel {
+    /// Resolve to a numeric level (0-9)
+    pub fn level(&self) -> std::result::Result<u32, String> {
+        match self {
+            Self::Numeric(level) if *level <= 9 => Ok(*level),
+            Self::Numeric(level) => Err(format!("Compression level {level} out of range 0-9")),
+            Self::Named(name) => match name.as_str() {
+                "fast" => Ok(1),
+                "default" => Ok(6),
+                "best" => Ok(9),
+                other => Err(format!("Unknown compression level '{other}'")),
+            },
+        }
+    }
 }
 
 fn default_purpose() -> String {
@@ -62,3 +155,270 @@ fn default_purpose() -> String {
 fn default_lifecycle() -> String {
     "runtime".to_string()
 }
+
+fn default_required() -> bool {
+    true
+}
+
+/// Read a manifest file, merging in the manifests it includes
+pub fn read_manifest(path: &Path) -> Result<BuildManifest> {
+    let data = fs::read_to_string(path)?;
+    let value = serde_json::from_str(&data)
+        .map_err(|e| FlavorError::ConfigError(format!("Failed to parse manifest: {e}")))?;
+    let path = fs::canonicalize(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let merged = merge_includes(value, base_dir, &mut vec![path.clone()])?;
+    serde_json::from_value(Value::Object(merged))
+        .map_err(|e| FlavorError::ConfigError(format!("Failed to parse manifest: {e}")))
+}
+
+/// Merge the manifests listed in a manifest's top-level `includes`
+///
+/// Includes are paths relative to `base_dir` and are merged in order, each
+/// with its own includes resolved first. Slots are concatenated, included
+/// slots first; every other top-level key is taken from the last manifest
+/// that sets it, the including one last. Slot positions declared with `slot`
+/// are checked against each file on its own and renumbered for the merged
+/// list. Relative slot sources of an included manifest are rebased onto its
+/// directory. A manifest without includes is returned unchanged.
+pub fn resolve_includes(manifest: Value, base_dir: &Path) -> Result<Value> {
+    merge_includes(manifest, base_dir, &mut Vec::new()).map(Value::Object)
+}
+
+/// [`resolve_includes`], with `chain` holding the files being merged to detect cycles
+fn merge_includes(
+    manifest: Value,
+    base_dir: &Path,
+    chain: &mut Vec<PathBuf>,
+) -> Result<Map<String, Value>> {
+    let Value::Object(mut own) = manifest else {
+        return Err(FlavorError::ConfigError(
+            "Manifest must be a JSON object".to_string(),
+        ));
+    };
+    let includes: Vec<String> = match own.remove("includes") {
+        Some(includes) => serde_json::from_value(includes).map_err(|e| {
+            FlavorError::ConfigError(format!("'includes' must be a list of paths: {e}"))
+        })?,
+        None => Vec::new(),
+    };
+    if includes.is_empty() {
+        return Ok(own);
+    }
+
+    let mut merged = Map::new();
+    let mut slots = Vec::new();
+    for include in &includes {
+        let path = base_dir.join(include);
+        let path = fs::canonicalize(&path).map_err(|e| {
+            FlavorError::ConfigError(format!(
+                "Cannot read included manifest {}: {e}",
+                path.display()
+            ))
+        })?;
+        if chain.contains(&path) {
+            let cycle: Vec<_> = chain
+                .iter()
+                .chain([&path])
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(FlavorError::ConfigError(format!(
+                "Manifest include cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+        let value = serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+            FlavorError::ConfigError(format!(
+                "Failed to parse included manifest {}: {e}",
+                path.display()
+            ))
+        })?;
+
+        chain.push(path.clone());
+        let included = merge_includes(value, path.parent().unwrap_or(base_dir), chain)?;
+        chain.pop();
+        let include_dir = path.parent().unwrap_or(base_dir);
+        for (key, value) in included {
+            if key == "slots" {
+                let mut included_slots = file_slots(value, &path.display().to_string())?;
+                for slot in &mut included_slots {
+                    rebase_source(slot, include_dir);
+                }
+                slots.extend(included_slots);
+            } else {
+                merged.insert(key, value);
+            }
+        }
+    }
+    for (key, value) in own {
+        if key == "slots" {
+            slots.extend(file_slots(value, "the including manifest")?);
+        } else {
+            merged.insert(key, value);
+        }
+    }
+
+    for (position, slot) in slots.iter_mut().enumerate() {
+        if let Some(declared) = slot.get_mut("slot") {
+            *declared = Value::from(position);
+        }
+    }
+    merged.insert("slots".to_string(), Value::Array(slots));
+    Ok(merged)
+}
+
+/// Make a relative slot `source` relative to `dir` instead of the build directory
+///
+/// Sources that start with `$` (build variables, `$SELF`) or use `{workenv}`
+/// are resolved later and left as they are.
+fn rebase_source(slot: &mut Value, dir: &Path) {
+    let Some(Value::String(source)) = slot.get_mut("source") else {
+        return;
+    };
+    if source.starts_with('$') || source.contains("{workenv}") || Path::new(source).is_absolute() {
+        return;
+    }
+    *source = dir.join(&*source).to_string_lossy().into_owned();
+}
+
+/// One file's slots, after checking that any declared positions are contiguous
+fn file_slots(slots: Value, origin: &str) -> Result<Vec<Value>> {
+    let Value::Array(slots) = slots else {
+        return Err(FlavorError::ConfigError(format!(
+            "'slots' in {origin} must be a list"
+        )));
+    };
+    for (position, slot) in slots.iter().enumerate() {
+        let declared = slot.get("slot").filter(|d| !d.is_null());
+        if declared.is_some_and(|d| d.as_u64() != Some(position as u64)) {
+            return Err(FlavorError::ConfigError(format!(
+                "Slot {} in {origin} declares position {} but is at position {position}",
+                slot.get("id").unwrap_or(&Value::Null),
+                declared.unwrap_or(&Value::Null)
+            )));
+        }
+    }
+    Ok(slots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_json(path: &Path, value: &Value) {
+        assert!(fs::write(path, value.to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_read_manifest_merges_includes() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        assert!(fs::create_dir_all(root.join("common")).is_ok());
+
+        write_json(
+            &root.join("common/runtime.json"),
+            &json!({"slots": [{"slot": 0, "id": "runtime", "source": "rt.tgz", "target": "rt"}]}),
+        );
+        write_json(
+            &root.join("common/base.json"),
+            &json!({
+                "includes": ["runtime.json"],
+                "package": {"name": "base", "version": "1.0"},
+                "execution": {"command": "/bin/base"},
+                "slots": [{"slot": 0, "id": "lib", "source": "lib.tgz", "target": "lib"}]
+            }),
+        );
+        write_json(
+            &root.join("gpu.json"),
+            &json!({
+                "includes": ["common/base.json"],
+                "package": {"name": "app-gpu", "version": "2.0"},
+                "slots": [
+                    {"slot": 0, "id": "cuda", "source": "cuda.tgz", "target": "cuda"},
+                    {"id": "app", "source": "app.tgz", "target": "app"}
+                ]
+            }),
+        );
+
+        let manifest = read_manifest(&root.join("gpu.json"));
+        assert!(manifest.is_ok());
+        let Ok(manifest) = manifest else { return };
+        assert_eq!(manifest.package.name, "app-gpu");
+        assert_eq!(manifest.execution.command, "/bin/base");
+        assert!(manifest.includes.is_empty());
+        let slots: Vec<_> = manifest
+            .slots
+            .iter()
+            .map(|s| (s.id.as_str(), s.slot))
+            .collect();
+        assert_eq!(
+            slots,
+            [
+                ("runtime", Some(0)),
+                ("lib", Some(1)),
+                ("cuda", Some(2)),
+                ("app", None)
+            ]
+        );
+
+        // Included sources resolve next to the manifest that declares them
+        let root = fs::canonicalize(root);
+        assert!(root.is_ok());
+        let Ok(root) = root else { return };
+        let sources: Vec<_> = manifest.slots.iter().map(|s| s.source.as_str()).collect();
+        assert_eq!(
+            sources,
+            [
+                root.join("common/rt.tgz").to_string_lossy(),
+                root.join("common/lib.tgz").to_string_lossy(),
+                "cuda.tgz".into(),
+                "app.tgz".into()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include_errors() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+        let config_error = |path: &str| {
+            read_manifest(&root.join(path)).is_err_and(|e| matches!(e, FlavorError::ConfigError(_)))
+        };
+
+        write_json(&root.join("a.json"), &json!({"includes": ["b.json"]}));
+        write_json(&root.join("b.json"), &json!({"includes": ["./a.json"]}));
+        assert!(config_error("a.json"));
+        assert!(
+            read_manifest(&root.join("a.json")).is_err_and(|e| e.to_string().contains("cycle"))
+        );
+
+        write_json(
+            &root.join("missing.json"),
+            &json!({"includes": ["nope.json"]}),
+        );
+        assert!(config_error("missing.json"));
+
+        write_json(
+            &root.join("gap.json"),
+            &json!({
+                "includes": [],
+                "package": {"name": "gap", "version": "1.0"},
+                "execution": {"command": "/bin/true"},
+                "slots": []
+            }),
+        );
+        write_json(
+            &root.join("bad-slot.json"),
+            &json!({
+                "includes": ["gap.json"],
+                "slots": [{"slot": 1, "id": "x", "source": "x", "target": "x"}]
+            }),
+        );
+        assert!(config_error("bad-slot.json"));
+    }
+}
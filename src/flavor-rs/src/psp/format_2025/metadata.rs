@@ -1,8 +1,16 @@
 //! PSPF/2025 metadata structures and types
 
+use crate::exceptions::Result;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Metadata schema version written by this builder
+///
+/// Metadata without `metadata_schema_version` is treated as version 0 and
+/// upgraded by [`migrate_metadata`] before parsing.
+pub const METADATA_SCHEMA_VERSION: u32 = 1;
 
 /// Main metadata structure for a PSPF package
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -10,6 +18,8 @@ pub struct Metadata {
     pub format: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_schema_version: Option<u32>,
     pub package: PackageInfo,
     pub slots: Vec<SlotMetadata>,
     pub execution: ExecutionInfo,
@@ -29,6 +39,79 @@ pub struct Metadata {
     pub workenv: Option<WorkenvInfo>,
     #[serde(default)]
     pub setup_commands: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shutdown_commands: Vec<Value>, // Run after the child exits (spawn mode only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sbom: Option<SbomInfo>, // Embedded SBOM slot, if any
+    /// Fields this reader doesn't model, kept so re-signing and repacking are lossless
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Metadata {
+    /// Parse metadata JSON, upgrading older schema versions first
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        Ok(serde_json::from_value(migrate_metadata(value))?)
+    }
+}
+
+/// Upgrade metadata JSON from older schema versions to [`METADATA_SCHEMA_VERSION`]
+///
+/// Version 0 packages may omit a slot's `slot` position and its `operations`,
+/// `purpose`, `lifecycle` or `checksum` fields, and `execution.primary_slot`.
+/// Newer versions are passed through unchanged; their unknown fields land in
+/// `extra`.
+pub fn migrate_metadata(mut value: Value) -> Value {
+    let version = value
+        .get("metadata_schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version > u64::from(METADATA_SCHEMA_VERSION) {
+        warn!(
+            "⚠️ Metadata schema version {version} is newer than supported ({METADATA_SCHEMA_VERSION}); reading best-effort"
+        );
+        return value;
+    }
+    if version == u64::from(METADATA_SCHEMA_VERSION) {
+        return value;
+    }
+
+    debug!("🔄 Migrating metadata schema v{version} -> v{METADATA_SCHEMA_VERSION}");
+    let Some(root) = value.as_object_mut() else {
+        return value;
+    };
+    if let Some(slots) = root.get_mut("slots").and_then(Value::as_array_mut) {
+        for (i, slot) in slots.iter_mut().enumerate() {
+            let Some(slot) = slot.as_object_mut() else {
+                continue;
+            };
+            slot.entry("slot").or_insert_with(|| Value::from(i));
+            slot.entry("operations").or_insert_with(|| Value::from(""));
+            slot.entry("purpose")
+                .or_insert_with(|| Value::from("payload"));
+            slot.entry("lifecycle")
+                .or_insert_with(|| Value::from("runtime"));
+            slot.entry("checksum").or_insert_with(|| Value::from(""));
+        }
+    }
+    if let Some(execution) = root.get_mut("execution").and_then(Value::as_object_mut) {
+        execution
+            .entry("primary_slot")
+            .or_insert_with(|| Value::from(0));
+    }
+    root.insert(
+        "metadata_schema_version".to_string(),
+        Value::from(METADATA_SCHEMA_VERSION),
+    );
+    value
+}
+
+/// Embedded software bill of materials
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SbomInfo {
+    pub slot: usize,
+    pub format: String, // "cyclonedx", "spdx" or "unknown"
 }
 
 /// Package information
@@ -53,10 +136,42 @@ pub struct SlotMetadata {
     pub lifecycle: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub permissions: Option<String>, // Unix permissions as octal string (e.g., "0755")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_permissions: Option<bool>, // `permissions` is the builder default, not from the manifest
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resolution: Option<String>, // When to resolve: build|runtime|lazy
     #[serde(skip_serializing_if = "Option::is_none")]
     pub self_ref: Option<bool>, // Self-referential slot (references launcher itself)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression_level: Option<u32>, // Gzip level used when compressing (0-9)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_mode: Option<String>, // Mode of an unencoded source at build time, as octal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_mtime: Option<i64>, // Mtime of an unencoded source at build time (unix seconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inherited: Option<InheritedSlot>, // Contents come from a base package's workenv
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>, // Extract only when this holds at launch (see `condition`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>, // Copied from the manifest; never read at launch
+    #[serde(flatten)]
+    pub extra: Map<String, Value>, // Fields this reader doesn't model
+}
+
+/// Where a delta package finds the contents of a slot it doesn't store
+///
+/// The slot's bytes live in a base package; the launcher copies `files` from
+/// the base's workenv once it is extracted and matches `package_checksum`,
+/// checking each copy against the matching entry of `file_checksums`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InheritedSlot {
+    pub package: String,          // Base workenv name
+    pub package_checksum: String, // Base index checksum (8 hex digits)
+    pub slot_id: String,          // Slot id in the base package
+    pub checksum: String,         // "sha256:..." of the base slot's stored bytes
+    pub files: Vec<String>,       // Files the slot extracts, relative to the workenv
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_checksums: Vec<String>, // Per file: "sha256:..." of its contents, or "symlink:<target>"
 }
 
 /// Execution configuration
@@ -66,6 +181,15 @@ pub struct ExecutionInfo {
     pub command: String,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Working directory for the child; `{workenv}`-expandable, relative to the workenv
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<String>,
+    /// Named alternatives to `command`, chosen at launch by name or `argv[0]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub entrypoints: BTreeMap<String, String>,
+    /// `argv[0]` for the launched process, overriding the launcher's default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argv0: Option<String>,
 }
 
 /// Verification information
@@ -165,6 +289,8 @@ pub struct RuntimeEnv {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub map: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy: Option<HashMap<String, String>>, // Like map, but keeps the original variable
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub set: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pass: Option<Vec<String>>,
@@ -177,6 +303,15 @@ pub struct WorkenvInfo {
     pub directories: Option<Vec<DirectorySpec>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub env: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symlinks: Option<Vec<SymlinkSpec>>, // Created after extraction
+}
+
+/// Symlink to create in the workenv after extraction
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SymlinkSpec {
+    pub link: String,   // Path of the link, relative to the workenv
+    pub target: String, // Relative to the link's directory, or under {workenv}
 }
 
 /// Directory specification for workenv
@@ -186,3 +321,62 @@ pub struct DirectorySpec {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mode: Option<String>, // Unix permission mode like "0700"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let json = r#"{
+            "format": "PSPF/2025",
+            "metadata_schema_version": 7,
+            "package": {"name": "future", "version": "9.0"},
+            "slots": [{
+                "slot": 0, "id": "app", "source": "app", "target": "app", "size": 1,
+                "checksum": "", "operations": "", "purpose": "payload", "lifecycle": "runtime",
+                "content_type": "application/wasm"
+            }],
+            "execution": {"primary_slot": 0, "command": "app"},
+            "attestations": [{"kind": "slsa", "uri": "https://example.test/p"}]
+        }"#;
+
+        let metadata = Metadata::from_json(json);
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else { return };
+        assert_eq!(metadata.metadata_schema_version, Some(7));
+        assert!(metadata.extra.contains_key("attestations"));
+        assert!(metadata.slots[0].extra.contains_key("content_type"));
+
+        let reserialized = serde_json::to_value(&metadata);
+        assert!(reserialized.is_ok_and(|v| {
+            v["attestations"][0]["kind"] == "slsa"
+                && v["slots"][0]["content_type"] == "application/wasm"
+        }));
+    }
+
+    #[test]
+    fn test_legacy_metadata_is_migrated() {
+        let json = r#"{
+            "format": "PSPF/2025",
+            "package": {"name": "legacy", "version": "0.1"},
+            "slots": [
+                {"id": "a", "source": "a", "target": "a", "size": 1},
+                {"id": "b", "source": "b", "target": "b", "size": 2, "lifecycle": "init"}
+            ],
+            "execution": {"command": "a"}
+        }"#;
+
+        let metadata = Metadata::from_json(json);
+        assert!(metadata.is_ok());
+        let Ok(metadata) = metadata else { return };
+        assert_eq!(
+            metadata.metadata_schema_version,
+            Some(METADATA_SCHEMA_VERSION)
+        );
+        assert_eq!(metadata.slots[1].index, 1);
+        assert_eq!(metadata.slots[0].lifecycle, "runtime");
+        assert_eq!(metadata.slots[1].lifecycle, "init");
+        assert_eq!(metadata.execution.primary_slot, 0);
+    }
+}
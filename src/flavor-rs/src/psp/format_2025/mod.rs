@@ -1,29 +1,43 @@
 //! PSPF/2025 format implementation
 
+pub mod attestation;
 pub mod backends;
 pub mod builder;
 pub mod checksums;
 pub mod cli;
+pub mod condition;
 pub mod constants;
 pub mod crypto;
 pub mod debug;
 pub mod defaults;
+pub mod detached;
+pub mod diagnostics;
+pub mod diff;
+pub mod encryption;
 pub mod execution;
 pub mod extraction;
+pub mod file_crc;
+pub mod fsck;
 pub mod index;
 pub mod keys;
 pub mod launcher;
 pub mod locking;
 pub mod manifest;
 pub mod metadata;
+#[cfg(all(unix, feature = "fuse"))]
+pub mod mount;
 pub mod operations;
 pub mod packaging;
 pub mod paths;
 pub mod pe_resources;
 pub mod pe_utils;
 pub mod reader;
+pub mod recovery;
 pub mod runtime;
+pub mod slot_reader;
+pub mod slot_tree;
 pub mod slots;
+pub mod verification_cache;
 pub mod verifier;
 
 // Re-export main functions
@@ -34,5 +48,30 @@ pub use verifier::verify;
 // Re-export types for advanced usage
 pub use index::Index;
 pub use metadata::Metadata;
-pub use reader::Reader;
+pub use reader::{Reader, VerificationConfig};
+pub use slot_reader::SlotReader;
 pub use slots::SlotDescriptor;
+
+/// Whether the file ends with a MagicTrailer (📦 + index + 🪄),
+/// possibly followed by appended bytes
+pub fn detect(file: &mut std::fs::File, file_size: u64) -> crate::exceptions::Result<bool> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    if file_size < constants::MAGIC_TRAILER_SIZE as u64 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-(constants::MAGIC_TRAILER_SIZE as i64)))?;
+    let mut tail = vec![0u8; constants::MAGIC_TRAILER_SIZE];
+    file.read_exact(&mut tail)?;
+    if Index::locate_in_trailer(&tail).is_some() {
+        return Ok(true);
+    }
+
+    // A package with bytes appended is still recognized, so that reading it
+    // reports what is wrong instead of "not a package"
+    let window_size = file_size.min(defaults::DEFAULT_TRAILER_SEARCH_WINDOW);
+    file.seek(SeekFrom::End(-(window_size as i64)))?;
+    let mut window = vec![0u8; window_size as usize];
+    file.read_exact(&mut window)?;
+    Ok(Index::bytes_after_trailer(&window).is_some())
+}
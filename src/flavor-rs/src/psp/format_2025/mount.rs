@@ -0,0 +1,273 @@
+//! Read-only FUSE mount of a package's slots
+//!
+//! Available on Unix with the `fuse` feature. [`mount`] serves a
+//! [`SlotTree`] through the `fuser` crate: the root holds one entry per slot,
+//! tar slots are directories of their entries, and file contents are decoded
+//! lazily as they are read. Nothing is extracted or written to the cache.
+//!
+//! `fuser` is built without its `libfuse` feature, so no C library is linked;
+//! mounting goes through the `fusermount3` (or `fusermount`) helper on Linux,
+//! and needs macFUSE on macOS.
+
+use super::reader::Reader;
+use super::slot_tree::{Node, NodeKind, ReadCursor, SlotTree};
+use crate::exceptions::{FlavorError, Result};
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request,
+};
+use log::{debug, info};
+use std::ffi::OsStr;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// How long the kernel may cache attributes and entries; the tree never changes
+const TTL: Duration = Duration::from_secs(3600);
+
+/// Block size reported for every node
+const BLOCK_SIZE: u32 = 4096;
+
+/// A package's [`SlotTree`] served as a read-only FUSE filesystem
+#[derive(Debug)]
+pub struct SlotFs {
+    reader: Reader,
+    tree: SlotTree,
+    cursor: ReadCursor,
+    uid: u32,
+    gid: u32,
+}
+
+impl SlotFs {
+    /// Lay out the slots of the package at `package_path`
+    ///
+    /// Nodes are owned by the owner of the package file.
+    pub fn open(package_path: &Path) -> Result<Self> {
+        let owner = std::fs::metadata(package_path)?;
+        let mut reader = Reader::new(package_path)?;
+        let tree = SlotTree::build(&mut reader)?;
+        Ok(Self {
+            reader,
+            tree,
+            cursor: ReadCursor::default(),
+            uid: owner.uid(),
+            gid: owner.gid(),
+        })
+    }
+
+    /// Attributes of node `ino`
+    fn attr(&self, ino: u64, node: &Node) -> FileAttr {
+        let (kind, nlink) = match node.kind {
+            NodeKind::Directory(_) => (FileType::Directory, 2),
+            NodeKind::File { .. } => (FileType::RegularFile, 1),
+            NodeKind::Symlink(_) => (FileType::Symlink, 1),
+        };
+        let size = node.size();
+        let mtime = UNIX_EPOCH + Duration::from_secs(node.mtime);
+        FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm: (node.mode & 0o7777) as u16,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        }
+    }
+
+    /// Attributes of node `ino`, if it exists
+    fn attr_of(&self, ino: u64) -> Option<FileAttr> {
+        self.tree.get(ino).map(|node| self.attr(ino, node))
+    }
+}
+
+/// The errno reported for a failed read
+fn errno(error: &FlavorError) -> i32 {
+    match error.kind() {
+        FlavorError::IoError(e) => match e.kind() {
+            io::ErrorKind::NotFound => libc::ENOENT,
+            io::ErrorKind::IsADirectory => libc::EISDIR,
+            io::ErrorKind::InvalidInput => libc::EINVAL,
+            _ => libc::EIO,
+        },
+        _ => libc::EIO,
+    }
+}
+
+impl Filesystem for SlotFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let found = name
+            .to_str()
+            .and_then(|name| self.tree.lookup(parent, name))
+            .and_then(|ino| self.attr_of(ino));
+        match found {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.attr_of(ino) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        match self.tree.get(ino).map(|node| &node.kind) {
+            Some(NodeKind::Symlink(target)) => reply.data(target.as_os_str().as_bytes()),
+            Some(_) => reply.error(libc::EINVAL),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Ok(offset) = u64::try_from(offset) else {
+            reply.error(libc::EINVAL);
+            return;
+        };
+        let read = self.tree.read(
+            &mut self.reader,
+            &mut self.cursor,
+            ino,
+            offset,
+            size as usize,
+        );
+        match read {
+            Ok(data) => reply.data(&data),
+            Err(e) => {
+                debug!("Read of inode {ino} at {offset} failed: {e:#}");
+                reply.error(errno(&e));
+            }
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(node) = self.tree.get(ino) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let NodeKind::Directory(entries) = &node.kind else {
+            reply.error(libc::ENOTDIR);
+            return;
+        };
+        let dots = [
+            (ino, FileType::Directory, "."),
+            (node.parent, FileType::Directory, ".."),
+        ];
+        let children = entries.iter().filter_map(|(name, &child)| {
+            let kind = match self.tree.get(child)?.kind {
+                NodeKind::Directory(_) => FileType::Directory,
+                NodeKind::File { .. } => FileType::RegularFile,
+                NodeKind::Symlink(_) => FileType::Symlink,
+            };
+            Some((child, kind, name.as_str()))
+        });
+        let skip = usize::try_from(offset).unwrap_or(0);
+        for (position, (child, kind, name)) in
+            dots.into_iter().chain(children).enumerate().skip(skip)
+        {
+            // The offset handed back is where the next readdir resumes
+            if reply.add(child, position as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Mount the slots of the package at `package_path` read-only on `mountpoint`
+///
+/// Blocks until the filesystem is unmounted, e.g. with `fusermount -u` or
+/// `umount`.
+pub fn mount(package_path: &Path, mountpoint: &Path) -> Result<()> {
+    let fs = SlotFs::open(package_path)?;
+    let options = [
+        MountOption::RO,
+        MountOption::NoExec,
+        MountOption::FSName(package_path.display().to_string()),
+        MountOption::Subtype("pspf".to_string()),
+    ];
+    info!(
+        "🗂️ Mounting {} read-only on {}",
+        package_path.display(),
+        mountpoint.display()
+    );
+    fuser::mount2(fs, mountpoint, &options)?;
+    debug!("Unmounted {}", mountpoint.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::slot_tree::ROOT_INODE;
+    use super::*;
+    use crate::api::BuildOptions;
+
+    #[test]
+    fn test_slot_fs_attributes() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("a.txt"), b"mounted").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "mount-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt", "permissions": "0640"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("mount".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let fs = SlotFs::open(&package);
+        assert!(fs.is_ok());
+        let Ok(fs) = fs else { return };
+        assert!(
+            fs.attr_of(ROOT_INODE)
+                .is_some_and(|attr| attr.kind == FileType::Directory && attr.nlink == 2)
+        );
+        let file = fs
+            .tree
+            .lookup(ROOT_INODE, "a")
+            .and_then(|ino| fs.attr_of(ino));
+        assert!(file.is_some_and(|attr| {
+            attr.kind == FileType::RegularFile && attr.size == 7 && attr.perm == 0o640
+        }));
+        assert!(fs.attr_of(99).is_none());
+    }
+}
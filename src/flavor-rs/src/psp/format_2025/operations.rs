@@ -1,8 +1,102 @@
 // helpers/flavor-rs/src/psp/format_2025/operations.rs
 // PSPF 2025 Operations - Protobuf-based operation chains
 
+use super::constants::{OP_BZIP2, OP_GZIP, OP_LZ4, OP_TAR, OP_XZ, OP_ZSTD};
+use crate::exceptions::{FlavorError, Result};
 use log::{debug, trace};
 
+/// Operation codes and the names manifests, metadata and tools use for them
+const OPERATION_NAMES: &[(u8, &str)] = &[
+    (OP_TAR, "tar"),
+    (OP_GZIP, "gzip"),
+    (OP_BZIP2, "bzip2"),
+    (OP_XZ, "xz"),
+    (OP_ZSTD, "zstd"),
+    (OP_LZ4, "lz4"),
+];
+
+/// Shorthands accepted for whole chains
+const CHAIN_ALIASES: &[(&str, &[u8])] = &[
+    ("none", &[]),
+    ("raw", &[]),
+    ("tgz", &[OP_TAR, OP_GZIP]),
+    ("tar.gz", &[OP_TAR, OP_GZIP]),
+    ("tbz2", &[OP_TAR, OP_BZIP2]),
+    ("tar.bz2", &[OP_TAR, OP_BZIP2]),
+    ("txz", &[OP_TAR, OP_XZ]),
+    ("tar.xz", &[OP_TAR, OP_XZ]),
+    ("tar.zst", &[OP_TAR, OP_ZSTD]),
+    ("tar.lz4", &[OP_TAR, OP_LZ4]),
+];
+
+/// Human-readable name for an operation code
+pub fn operation_name(op: u8) -> &'static str {
+    OPERATION_NAMES
+        .iter()
+        .find(|&&(code, _)| code == op)
+        .map_or("unknown", |&(_, name)| name)
+}
+
+/// Render a packed operation chain as comma-separated names (e.g. "tar,lz4")
+///
+/// An empty chain renders as an empty string. [`string_to_operations`] parses
+/// the result back to the same chain.
+pub fn operations_to_string(packed: u64) -> String {
+    unpack_operations(packed)
+        .into_iter()
+        .map(operation_name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parse an operation chain as written in a manifest
+///
+/// Accepts operation names separated by `,` or `|` (e.g. "tar,gzip"), a
+/// shorthand such as "tgz" or "tar.zst", and "", "none" or "raw" for no
+/// operations, all case-insensitively. Unknown names and chains longer than
+/// eight operations fail with `ConfigError`.
+pub fn string_to_operations(chain: &str) -> Result<u64> {
+    let normalized = chain.trim().to_ascii_lowercase();
+    if normalized.is_empty() {
+        return Ok(0);
+    }
+    if let Some(&(_, ops)) = CHAIN_ALIASES
+        .iter()
+        .find(|&&(alias, _)| alias == normalized)
+    {
+        return Ok(pack_operations(ops));
+    }
+
+    let operations = normalized
+        .split([',', '|'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            OPERATION_NAMES
+                .iter()
+                .find(|&&(_, known)| known == name)
+                .map(|&(code, _)| code)
+                .ok_or_else(|| {
+                    FlavorError::ConfigError(format!("Unknown operation '{name}' in '{chain}'"))
+                })
+        })
+        .collect::<Result<Vec<u8>>>()?;
+    if operations.len() > 8 {
+        return Err(FlavorError::ConfigError(format!(
+            "Operation chain '{chain}' has {} operations, at most 8 are allowed",
+            operations.len()
+        )));
+    }
+    Ok(pack_operations(&operations))
+}
+
+/// Whether a chain contains a codec the reader decodes (gzip, LZ4 or zstd)
+pub fn is_compressed(operations: &[u8]) -> bool {
+    operations
+        .iter()
+        .any(|&op| matches!(op, OP_GZIP | OP_LZ4 | OP_ZSTD))
+}
+
 /// Pack operation chain into 64-bit integer
 /// Operations are packed as 8-bit values in little-endian order
 /// Up to 8 operations can be packed (8 bytes × 8 operations = 64 bits)
@@ -57,16 +151,20 @@ pub fn unpack_operations(packed: u64) -> Vec<u8> {
     }
 
     debug!(
-        "✅ Operations unpacked: count={} operations={:?}",
+        "✅ Operations unpacked: count={} operations={:?} ({})",
         operations.len(),
+        operations,
         operations
+            .iter()
+            .map(|&op| operation_name(op))
+            .collect::<Vec<_>>()
+            .join(",")
     );
     operations
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::constants::{OP_GZIP, OP_TAR};
     use super::*;
 
     #[test]
@@ -105,6 +203,69 @@ mod tests {
         assert_eq!(original, unpacked);
     }
 
+    #[test]
+    fn test_describe_lz4_chain() {
+        let packed = pack_operations(&[OP_TAR, OP_LZ4]);
+        assert_eq!(operations_to_string(packed), "tar,lz4");
+    }
+
+    #[test]
+    fn test_every_supported_chain_round_trips() {
+        let codes: Vec<u8> = OPERATION_NAMES.iter().map(|&(code, _)| code).collect();
+        let mut chains: Vec<Vec<u8>> = vec![vec![]];
+        chains.extend(codes.iter().map(|&op| vec![op]));
+        chains.extend(codes.iter().map(|&op| vec![OP_TAR, op]));
+        chains.extend(
+            codes
+                .iter()
+                .flat_map(|&a| codes.iter().map(move |&b| vec![a, b])),
+        );
+        chains.push(vec![
+            OP_TAR, OP_ZSTD, OP_GZIP, OP_LZ4, OP_XZ, OP_BZIP2, OP_TAR, OP_GZIP,
+        ]);
+
+        for chain in chains {
+            let packed = pack_operations(&chain);
+            let rendered = operations_to_string(packed);
+            assert!(
+                string_to_operations(&rendered).is_ok_and(|p| p == packed),
+                "{chain:?} -> {rendered}"
+            );
+        }
+        for &(alias, ops) in CHAIN_ALIASES {
+            let packed = pack_operations(ops);
+            assert!(
+                string_to_operations(alias).is_ok_and(|p| p == packed),
+                "{alias}"
+            );
+            let canonical = operations_to_string(packed);
+            assert!(
+                string_to_operations(&canonical).is_ok_and(|p| p == packed),
+                "{alias}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_to_operations_forms_and_errors() {
+        let tar_gzip = pack_operations(&[OP_TAR, OP_GZIP]);
+        for chain in ["tar,gzip", "TAR|GZIP", " tar , gzip ", "tgz", "Tar.Gz"] {
+            assert!(
+                string_to_operations(chain).is_ok_and(|p| p == tar_gzip),
+                "{chain}"
+            );
+        }
+        assert!(string_to_operations("none").is_ok_and(|p| p == 0));
+        assert!(string_to_operations(" ").is_ok_and(|p| p == 0));
+        for chain in ["tar,brotli", "gz", "tar,tar,tar,tar,tar,tar,tar,tar,tar"] {
+            assert!(
+                string_to_operations(chain)
+                    .is_err_and(|e| matches!(e, FlavorError::ConfigError(_))),
+                "{chain}"
+            );
+        }
+    }
+
     #[test]
     fn test_empty_operations() {
         let ops: Vec<u8> = vec![];
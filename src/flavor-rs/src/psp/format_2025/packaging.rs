@@ -9,8 +9,7 @@
 #![allow(clippy::module_name_repetitions)]
 #![allow(clippy::cast_possible_truncation)]
 
-use crate::psp::format_2025::defaults::DEFAULT_FILE_PERMS;
-use crate::psp::format_2025::operations::pack_operations;
+use crate::psp::format_2025::operations::{string_to_operations, unpack_operations};
 use std::fs::File;
 use std::io::{Seek, SeekFrom, Write};
 use std::path::Path;
@@ -20,7 +19,7 @@ use flate2::write::GzEncoder;
 use log::{debug, info, trace};
 use sha2::{Digest, Sha256};
 
-use super::constants::{HEADER_SIZE, OP_GZIP, OP_TAR};
+use super::constants::{HEADER_SIZE, OP_GZIP, OP_LZ4, OP_TAR};
 
 /// Compute SHA-256 checksum truncated to first 8 bytes (as u64 little-endian)
 fn compute_slot_checksum(data: &[u8]) -> u64 {
@@ -33,11 +32,15 @@ fn compute_slot_checksum(data: &[u8]) -> u64 {
 }
 use super::index::Index;
 use super::metadata::{Metadata, SlotMetadata};
-use super::slots::SlotDescriptor;
-use crate::exceptions::Result;
+use super::slots::{SlotDescriptor, lifecycle_from_str, purpose_from_str};
+use crate::exceptions::{FlavorError, Result};
 
 /// Write a slot to the package file
 ///
+/// The slot file is compressed as `slot_info.operations` says, at
+/// `slot_info.compression_level` (default best). The builder itself stores
+/// slot sources as they are and only compresses inline content.
+///
 /// # Errors
 ///
 /// Returns an error if:
@@ -60,8 +63,19 @@ pub fn write_slot(
         slot_path.display()
     );
 
-    // Determine operations and compress if needed
-    let (processed_data, operations_str) = process_slot_data(&slot_data, &slot_info.operations)?;
+    // Permissions from slot metadata if provided; executables in bin/ and
+    // tool/code slots default to 0700
+    let permissions = super::slots::slot_permissions(
+        slot_info.permissions.as_deref(),
+        &slot_info.target,
+        &slot_info.purpose,
+    )?;
+
+    // Determine operations and compress if needed (best compression unless the slot says otherwise)
+    let level = slot_info
+        .compression_level
+        .map_or_else(Compression::best, Compression::new);
+    let (processed_data, operations) = process_slot_data(&slot_data, &slot_info.operations, level)?;
 
     // Get current position (this will be the slot offset)
     let offset = out.stream_position()?;
@@ -69,15 +83,6 @@ pub fn write_slot(
     // Write slot data
     out.write_all(&processed_data)?;
 
-    // Parse permissions from slot metadata if provided, otherwise use default
-    let permissions = if let Some(perm_str) = &slot_info.permissions {
-        // Parse octal string (e.g., "0755" or "755")
-        u16::from_str_radix(perm_str.trim_start_matches("0o").trim_start_matches('0'), 8)
-            .unwrap_or(DEFAULT_FILE_PERMS)
-    } else {
-        DEFAULT_FILE_PERMS // Default file permissions
-    };
-
     // Hash the slot's ID for fast lookup
     let name_hash = {
         use std::collections::hash_map::DefaultHasher;
@@ -88,14 +93,6 @@ pub fn write_slot(
         hasher.finish()
     };
 
-    // Parse operation string (e.g., "0" for raw, "1" for tar, "16" for gzip, "3" for tgz)
-    let operations = match operations_str {
-        3 => pack_operations(&[OP_TAR, OP_GZIP]), // TGZ
-        1 => pack_operations(&[OP_TAR]),          // TAR only
-        16 => pack_operations(&[OP_GZIP]),        // GZIP only
-        _ => pack_operations(&[]),                // Raw or unknown
-    };
-
     let checksum = compute_slot_checksum(&processed_data);
     debug!(
         "🦀 Rust builder computed slot {} checksum: {:016x} (data length: {} bytes)",
@@ -112,8 +109,8 @@ pub fn write_slot(
         original_size: slot_data.len() as u64,
         operations,
         checksum,
-        purpose: get_purpose_byte(&slot_info.purpose),
-        lifecycle: get_lifecycle_byte(&slot_info.lifecycle),
+        purpose: purpose_from_str(&slot_info.purpose),
+        lifecycle: lifecycle_from_str(&slot_info.lifecycle),
         priority: 0,
         platform: 0,
         reserved1: 0,
@@ -133,12 +130,30 @@ pub fn write_slot(
     Ok(descriptor)
 }
 
-/// Process slot data based on operations
-fn process_slot_data(data: &[u8], operations_str: &str) -> Result<(Vec<u8>, u8)> {
-    match operations_str {
-        "gzip" => {
-            // Single file, gzipped
-            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+/// Compress slot data as its operation chain says, returning the packed chain
+///
+/// Tar data is taken as already archived, so only a final gzip or LZ4 step is
+/// applied here; other compression operations are rejected.
+pub(crate) fn process_slot_data(
+    data: &[u8],
+    operations_str: &str,
+    level: Compression,
+) -> Result<(Vec<u8>, u64)> {
+    let packed = string_to_operations(operations_str)?;
+    let operations = unpack_operations(packed);
+    let compression = match operations[..] {
+        [] | [OP_TAR] => None,
+        [op] | [OP_TAR, op] => Some(op),
+        _ => return Err(unsupported(operations_str)),
+    };
+
+    let processed = match compression {
+        None => {
+            trace!("  📄 Using data as-is ({} bytes)", data.len());
+            data.to_vec()
+        }
+        Some(OP_GZIP) => {
+            let mut encoder = GzEncoder::new(Vec::new(), level);
             encoder.write_all(data)?;
             let compressed = encoder.finish()?;
             trace!(
@@ -146,59 +161,31 @@ fn process_slot_data(data: &[u8], operations_str: &str) -> Result<(Vec<u8>, u8)>
                 data.len(),
                 compressed.len()
             );
-            Ok((compressed, 16)) // OP_GZIP
+            compressed
         }
-        "tgz" | "tar.gz" => {
-            // Tar archive, then gzipped - assume data is already a tar
-            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        Some(OP_LZ4) => {
+            // LZ4 frame - much faster to decompress than gzip, at some size cost
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
             encoder.write_all(data)?;
-            let compressed = encoder.finish()?;
+            let compressed = encoder
+                .finish()
+                .map_err(|e| FlavorError::BuildError(format!("LZ4 error: {e}")))?;
             trace!(
-                "  📦 Compressed tar {} -> {} bytes",
+                "  ⚡ LZ4 compressed {} -> {} bytes",
                 data.len(),
                 compressed.len()
             );
-            Ok((compressed, 3)) // Legacy indicator for TAR+GZIP
-        }
-        "tar" => {
-            // Uncompressed tar
-            trace!("  📦 Using uncompressed tar ({} bytes)", data.len());
-            Ok((data.to_vec(), 1)) // OP_TAR
-        }
-        _ => {
-            // Raw/uncompressed
-            trace!("  📄 Using raw data ({} bytes)", data.len());
-            Ok((data.to_vec(), 0)) // No operations
+            compressed
         }
-    }
-}
-
-/// Get purpose byte from string
-fn get_purpose_byte(purpose: &str) -> u8 {
-    match purpose {
-        "payload" => 1,
-        "runtime" => 2,
-        "tool" => 3,
-        "config" => 4,
-        _ => 0,
-    }
+        Some(_) => return Err(unsupported(operations_str)),
+    };
+    Ok((processed, packed))
 }
 
-/// Get lifecycle byte from string
-fn get_lifecycle_byte(lifecycle: &str) -> u8 {
-    match lifecycle {
-        "init" => 0,
-        "startup" => 1,
-        "shutdown" => 3,
-        "cache" => 4,
-        "temporary" => 5,
-        "lazy" => 6,
-        "eager" => 7,
-        "dev" => 8,
-        "config" => 9,
-        "platform" => 10,
-        _ => 2, // default to runtime
-    }
+fn unsupported(operations_str: &str) -> FlavorError {
+    FlavorError::BuildError(format!(
+        "Cannot apply operations '{operations_str}': only gzip or lz4, optionally after tar, are supported"
+    ))
 }
 
 /// Write the index block to the file
@@ -4,6 +4,7 @@ use super::defaults::{
     DEFAULT_COMPLETE_FILE, DEFAULT_EXTRACT_DIR, DEFAULT_INDEX_METADATA_FILE, DEFAULT_INSTANCE_DIR,
     DEFAULT_LOCK_FILE, DEFAULT_LOG_DIR, DEFAULT_PACKAGE_CHECKSUM_FILE, DEFAULT_PACKAGE_DIR,
     DEFAULT_PSP_METADATA_FILE, DEFAULT_PSPF_HIDDEN_PREFIX, DEFAULT_PSPF_SUFFIX, DEFAULT_TMP_DIR,
+    DEFAULT_VERIFIED_SLOTS_FILE, DEFAULT_VERIFY_KEY_FILE,
 };
 use std::path::{Path, PathBuf};
 
@@ -105,6 +106,16 @@ impl WorkenvPaths {
         self.instance().join(DEFAULT_INDEX_METADATA_FILE)
     }
 
+    /// Get the record of slots already verified by incremental verification
+    pub fn verified_slots_file(&self) -> PathBuf {
+        self.instance().join(DEFAULT_VERIFIED_SLOTS_FILE)
+    }
+
+    /// Get the key that signs verification records, shared by the whole cache
+    pub fn verify_key_file(&self) -> PathBuf {
+        self.cache_dir.join(DEFAULT_VERIFY_KEY_FILE)
+    }
+
     // ==================== Package Metadata Paths ====================
 
     /// Get the PSP metadata JSON file path
@@ -119,6 +130,14 @@ impl WorkenvPaths {
         &self.workenv_name
     }
 
+    /// Paths for another workenv in the same cache, e.g. a delta package's base
+    pub fn sibling(&self, workenv_name: &str) -> Self {
+        Self {
+            cache_dir: self.cache_dir.clone(),
+            workenv_name: workenv_name.to_string(),
+        }
+    }
+
     /// Check if the workenv exists
     pub fn workenv_exists(&self) -> bool {
         self.workenv().exists()
@@ -1,29 +1,78 @@
 // helpers/flavor-rs/src/psp/format_2025/reader.rs
 // PSPF 2025 Bundle Reader - Uses backend system for flexible access
 
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use std::path::Path;
 use std::time::Instant;
 
-use super::backends::{Backend, MMapBackend, create_backend};
-use super::constants::{
-    HEADER_SIZE, MAGIC_TRAILER_SIZE, MAGIC_WAND_EMOJI_BYTES, PACKAGE_EMOJI_BYTES,
-    SLOT_DESCRIPTOR_SIZE,
+use super::backends::{
+    AccessPattern, Backend, BackendStats, MMapBackend, MemoryBackend, create_backend,
+    select_backend_mode,
 };
+use super::constants::{MAGIC_TRAILER_SIZE, SLOT_DESCRIPTOR_SIZE};
 use super::debug::debug_dump;
-use super::defaults::ACCESS_AUTO;
-use super::extraction::extract_slot;
+use super::defaults::{ACCESS_AUTO, ACCESS_FILE, DEFAULT_TRAILER_SEARCH_WINDOW};
+use super::diagnostics::{CheckStatus, DescriptorIssue, PackageDiagnostics, decode_metadata};
+use super::encryption::{ENCRYPTION_NONE, EncryptionAlgorithm, EncryptionKey, Encryptor, open};
+use super::extraction::{decode_slot_stream, extract_slot};
 use super::index::Index;
 use super::metadata::Metadata;
+use super::operations::unpack_operations;
+use super::slot_reader::SlotReader;
 use super::slots::SlotDescriptor;
 use crate::exceptions::{FlavorError, Result};
 
+/// Which integrity checks a [`Reader`] performs while reading
+///
+/// Everything is on by default. Forensic and recovery tools can switch
+/// individual checks off to read through known corruption deliberately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationConfig {
+    /// Check the index checksum (a mismatch is logged, never fatal)
+    pub index_checksum: bool,
+    /// Reject metadata whose SHA-256 does not match the index
+    pub metadata_checksum: bool,
+    /// Reject slot data whose checksum does not match its descriptor
+    pub slot_checksums: bool,
+    /// Reject packages whose index asks for a newer reader format
+    pub format_compatibility: bool,
+    /// Reject files whose size differs from the package size in the index
+    pub package_size: bool,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            index_checksum: true,
+            metadata_checksum: true,
+            slot_checksums: true,
+            format_compatibility: true,
+            package_size: true,
+        }
+    }
+}
+
+impl VerificationConfig {
+    /// Skip every check
+    pub fn none() -> Self {
+        Self {
+            index_checksum: false,
+            metadata_checksum: false,
+            slot_checksums: false,
+            format_compatibility: false,
+            package_size: false,
+        }
+    }
+}
+
 /// Reader for PSPF/2025 bundles with backend support
 pub struct Reader {
     backend: Box<dyn Backend>,
     path: std::path::PathBuf,
     index: Option<Index>,
     metadata: Option<Metadata>,
+    verification: VerificationConfig,
+    decryptor: Option<Box<dyn Encryptor>>,
 }
 
 impl std::fmt::Debug for Reader {
@@ -33,15 +82,58 @@ impl std::fmt::Debug for Reader {
             .field("path", &self.path)
             .field("index", &self.index.as_ref().map(|_| "<Index>"))
             .field("metadata", &self.metadata.as_ref().map(|_| "<Metadata>"))
+            .field("verification", &self.verification)
+            .field("decryptor", &self.decryptor)
             .finish()
     }
 }
 
 impl Reader {
-    /// Create a new reader with automatic backend selection
+    /// Create a new reader, using the backend the package's index asks for
+    ///
+    /// The index is read first through a plain file backend. Its
+    /// `access_mode` hint (mmap, file or stream) then picks the backend; with
+    /// no hint the backend is chosen by file size. A file whose index can't be
+    /// read gets the size-based choice, and the error surfaces on first use.
+    /// Use [`Reader::with_backend`] to choose the backend explicitly.
     pub fn new(path: &Path) -> Result<Self> {
         trace!("Creating reader for: {:?}", path);
-        Self::with_backend(path, ACCESS_AUTO)
+        let mut reader = Self::with_backend(path, ACCESS_FILE)?;
+        let access_mode = match reader.read_index() {
+            Ok(index) => index.access_mode,
+            Err(e) => {
+                trace!("Index unreadable ({e}), selecting backend by size");
+                return Self::with_backend(path, ACCESS_AUTO);
+            }
+        };
+
+        let file_size = std::fs::metadata(path)?.len();
+        let mode = select_backend_mode(file_size, AccessPattern::Auto, access_mode, 0);
+        reader.switch_backend(mode)?;
+        debug!(
+            "🎛️ Index access mode {access_mode} selected the {} backend",
+            reader.backend.name()
+        );
+        Ok(reader)
+    }
+
+    /// Detect the package format, then open a reader for it
+    ///
+    /// Fails with `UnsupportedFormat` for files no known format recognizes,
+    /// where [`Reader::new`] would only fail once the index is read.
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with_format(path, crate::psp::detect_format(path)?)
+    }
+
+    /// Open a reader for `format` without detecting it first
+    ///
+    /// Useful to skip detection when the format is already known, or to read
+    /// a package whose trailer a detector doesn't (yet) recognize.
+    pub fn open_with_format(path: &Path, format: crate::psp::PackageFormat) -> Result<Self> {
+        trace!("Opening {:?} as {format}", path);
+        match format {
+            crate::psp::PackageFormat::PSPF2025 => Self::new(path),
+        }
     }
 
     /// Create a reader with specified backend mode
@@ -57,6 +149,69 @@ impl Reader {
             path: path.to_path_buf(),
             index: None,
             metadata: None,
+            verification: VerificationConfig::default(),
+            decryptor: None,
+        })
+    }
+
+    /// Create a reader whose backend is tuned for the intended access pattern
+    ///
+    /// The index is read first through a plain file backend so its
+    /// `access_mode`/`cache_strategy` hints can inform the final choice.
+    pub fn with_access_pattern(path: &Path, pattern: AccessPattern) -> Result<Self> {
+        let mut reader = Self::with_backend(path, ACCESS_FILE)?;
+        let (access_mode, cache_strategy) = {
+            let index = reader.read_index()?;
+            (index.access_mode, index.cache_strategy)
+        };
+
+        let file_size = std::fs::metadata(path)?.len();
+        let mode = select_backend_mode(file_size, pattern, access_mode, cache_strategy);
+        debug!(
+            "🎛️ Access pattern {:?} (hints: mode={}, cache={}) selected backend mode {}",
+            pattern, access_mode, cache_strategy, mode
+        );
+
+        reader.switch_backend(mode)?;
+        Ok(reader)
+    }
+
+    /// Replace the plain file backend a reader was opened with by `mode`'s
+    fn switch_backend(&mut self, mode: u8) -> Result<()> {
+        if mode != ACCESS_FILE {
+            let mut backend = create_backend(mode, Some(&self.path));
+            backend.open(&self.path)?;
+            self.backend = backend;
+        }
+        Ok(())
+    }
+
+    /// Create a reader over a package piped through stdin
+    ///
+    /// The index lives at the end of the package, so the whole stream is
+    /// buffered in memory before anything can be read.
+    pub fn from_stdin() -> Result<Self> {
+        Self::from_reader(std::io::stdin().lock())
+    }
+
+    /// Create a reader by fully buffering a non-seekable stream into memory
+    pub fn from_reader(mut source: impl std::io::Read) -> Result<Self> {
+        let timer = Instant::now();
+        let mut data = Vec::new();
+        source.read_to_end(&mut data)?;
+        debug!(
+            "🧠 Buffered {} bytes from stream in {:?}",
+            data.len(),
+            timer.elapsed()
+        );
+
+        Ok(Self {
+            backend: Box::new(MemoryBackend::new(data)),
+            path: std::path::PathBuf::from("-"),
+            index: None,
+            metadata: None,
+            verification: VerificationConfig::default(),
+            decryptor: None,
         })
     }
 
@@ -73,9 +228,62 @@ impl Reader {
             path: path.to_path_buf(),
             index: None,
             metadata: None,
+            verification: VerificationConfig::default(),
+            decryptor: None,
         })
     }
 
+    /// Choose which checksums are enforced on subsequent reads
+    ///
+    /// Already-cached index and metadata are not re-checked.
+    pub fn set_verification(&mut self, verification: VerificationConfig) {
+        if verification != VerificationConfig::default() {
+            warn!("⚠️ Reader verification relaxed: {verification:?}");
+        }
+        self.verification = verification;
+    }
+
+    /// Checks currently performed by this reader
+    pub fn verification(&self) -> VerificationConfig {
+        self.verification
+    }
+
+    /// Decrypt slots of an encrypted package with `key` on subsequent reads
+    ///
+    /// The algorithm comes from the index. Setting a key for an unencrypted
+    /// package is harmless; it is never used.
+    pub fn set_decryption_key(&mut self, key: &EncryptionKey) -> Result<()> {
+        let encryption_type = self.read_index()?.encryption_type;
+        if encryption_type == ENCRYPTION_NONE {
+            return Ok(());
+        }
+        let algorithm = EncryptionAlgorithm::from_type(encryption_type).ok_or_else(|| {
+            FlavorError::UnsupportedFormat(format!("Unknown encryption type {encryption_type}"))
+        })?;
+        self.decryptor = Some(algorithm.encryptor(key));
+        Ok(())
+    }
+
+    /// Decrypt stored slot bytes if the package is encrypted
+    fn decrypt_slot(&mut self, descriptor: &SlotDescriptor, data: Vec<u8>) -> Result<Vec<u8>> {
+        if self.read_index()?.encryption_type == ENCRYPTION_NONE || data.is_empty() {
+            return Ok(data);
+        }
+        let decryptor = self.decryptor.as_deref().ok_or_else(|| {
+            FlavorError::VerificationFailed(
+                "Package slots are encrypted; a decryption key is required".to_string(),
+            )
+        })?;
+        open(decryptor, descriptor.id, &data)
+    }
+
+    /// I/O counters of the current backend
+    ///
+    /// The counters restart when [`Reader::with_access_pattern`] switches backends.
+    pub fn backend_stats(&self) -> BackendStats {
+        self.backend.stats()
+    }
+
     /// Read the PSPF index
     pub fn read_index(&mut self) -> Result<&Index> {
         if self.index.is_none() {
@@ -110,21 +318,36 @@ impl Reader {
             // Skip checksum verification for now - Go launcher doesn't verify it either
             // TODO: Fix checksum calculation to match Python builder
             // if !index.verify_checksum_raw(&index_data) {
-            //     return Err(FlavorError::Generic("Index checksum mismatch".into()));
+            //     return Err(FlavorError::FormatError("Index checksum mismatch".into()));
             // }
 
             // Log a warning if checksum doesn't match
-            if !index.verify_checksum_raw(&index_data) {
+            if !self.verification.index_checksum {
+                trace!("⏭️ Index checksum check skipped");
+            } else if !index.verify_checksum_raw(&index_data) {
                 debug!("Warning: Index checksum mismatch (verification disabled)");
             }
 
+            if self.verification.format_compatibility {
+                index.check_compatibility()?;
+            }
+            if self.verification.package_size {
+                check_package_size(&index, self.package_len()?)?;
+            }
+
             self.index = Some(index);
             debug!("Index loaded in {:?}", timer.elapsed());
         }
 
         self.index
             .as_ref()
-            .ok_or_else(|| FlavorError::Generic("Failed to read index".into()))
+            .ok_or_else(|| FlavorError::FormatError("failed to read index".into()))
+    }
+
+    /// Key the package's workenv is cached under, for correlating cache
+    /// directories with packages (see [`super::index::cache_key`])
+    pub fn package_cache_key(&mut self) -> Result<String> {
+        Ok(self.read_index()?.cache_key())
     }
 
     /// Read and parse metadata
@@ -139,7 +362,7 @@ impl Reader {
             let index = self
                 .index
                 .as_ref()
-                .ok_or_else(|| FlavorError::Generic("Index not loaded".into()))?;
+                .ok_or_else(|| FlavorError::FormatError("index not loaded".into()))?;
 
             let meta_offset = index.metadata_offset;
             let meta_size = index.metadata_size;
@@ -190,20 +413,20 @@ impl Reader {
             use sha2::{Digest, Sha256};
             let actual_hash = Sha256::digest(&metadata_data);
             let actual_checksum: [u8; 32] = actual_hash.into();
-            if actual_checksum != index.metadata_checksum {
-                debug!(
-                    "❌ Metadata checksum mismatch: expected {:02x?}, got {:02x?}",
-                    &index.metadata_checksum[..8],
-                    &actual_checksum[..8]
-                );
-                return Err(FlavorError::Generic("Metadata checksum mismatch".into()));
+            if actual_checksum == index.metadata_checksum {
+                trace!("✅ Metadata checksum verified (SHA-256)");
+            } else if self.verification.metadata_checksum {
+                return Err(FlavorError::ChecksumMismatch {
+                    subject: "metadata".into(),
+                    expected: hex::encode(index.metadata_checksum),
+                    actual: hex::encode(actual_checksum),
+                });
+            } else {
+                warn!("⚠️ Metadata checksum mismatch ignored (verification disabled)");
             }
-            trace!("✅ Metadata checksum verified (SHA-256)");
 
-            // Parse metadata - always gzip compressed for now
-            let metadata: Metadata = if true {
-                // Always gzip for now
-                // Decompress first
+            // Parse metadata - gzip unless the index flags it as plain JSON
+            let metadata: Metadata = if index.metadata_compressed() {
                 use flate2::read::GzDecoder;
                 use std::io::Read;
 
@@ -235,13 +458,14 @@ impl Reader {
                     }
                 }
 
-                serde_json::from_str(&json_data)?
+                Metadata::from_json(&json_data)?
             } else {
                 // Direct JSON
                 trace!("📝 Parsing uncompressed JSON metadata");
-                let json_str = std::str::from_utf8(&metadata_data)
-                    .map_err(|e| FlavorError::Generic(format!("Invalid UTF-8: {}", e)))?;
-                serde_json::from_str(json_str)?
+                let json_str = std::str::from_utf8(&metadata_data).map_err(|e| {
+                    FlavorError::FormatError("metadata is not UTF-8".into()).with_source(e)
+                })?;
+                Metadata::from_json(json_str)?
             };
 
             debug!(
@@ -254,35 +478,252 @@ impl Reader {
 
         self.metadata
             .as_ref()
-            .ok_or_else(|| FlavorError::Generic("Failed to read metadata".into()))
+            .ok_or_else(|| FlavorError::FormatError("failed to read metadata".into()))
+    }
+
+    /// Stream the decompressed metadata JSON into `sink` without parsing it
+    ///
+    /// The compressed bytes are hashed as they are read, so the metadata
+    /// checksum is still enforced (unless disabled with
+    /// [`Reader::set_verification`]). On a mismatch the error is returned after
+    /// `sink` has already received the output.
+    pub fn stream_metadata_to(&mut self, sink: &mut impl std::io::Write) -> Result<u64> {
+        use flate2::read::GzDecoder;
+        use sha2::{Digest, Sha256};
+        use std::io::{Read, Seek, SeekFrom};
+
+        let (meta_offset, meta_size, expected, compressed) = {
+            let index = self.read_index()?;
+            (
+                index.metadata_offset,
+                index.metadata_size,
+                index.metadata_checksum,
+                index.metadata_compressed(),
+            )
+        };
+        debug!(
+            "📖 Streaming metadata from offset {:#x}, size {} bytes",
+            meta_offset, meta_size
+        );
+
+        let source: Box<dyn Read + '_> =
+            if let Ok(view) = self.backend.view_at(meta_offset, meta_size as usize) {
+                Box::new(view)
+            } else if self.path.as_os_str() != "-" {
+                let mut file = std::fs::File::open(&self.path)?;
+                file.seek(SeekFrom::Start(meta_offset))?;
+                Box::new(file.take(meta_size))
+            } else {
+                Box::new(std::io::Cursor::new(
+                    self.backend.read_at(meta_offset, meta_size as usize)?,
+                ))
+            };
+
+        let mut hashing = HashingReader {
+            inner: source,
+            hasher: Sha256::new(),
+        };
+        let written = if compressed {
+            let mut decoder = GzDecoder::new(hashing);
+            let written = std::io::copy(&mut decoder, sink)?;
+            // Hash anything the decoder left unread so the whole region is covered
+            hashing = decoder.into_inner();
+            std::io::copy(&mut hashing, &mut std::io::sink())?;
+            written
+        } else {
+            std::io::copy(&mut hashing, sink)?
+        };
+        let actual: [u8; 32] = hashing.hasher.finalize().into();
+        if actual == expected {
+            trace!("✅ Metadata checksum verified while streaming (SHA-256)");
+        } else if self.verification.metadata_checksum {
+            return Err(FlavorError::ChecksumMismatch {
+                subject: "metadata".into(),
+                expected: hex::encode(expected),
+                actual: hex::encode(actual),
+            });
+        } else {
+            warn!("⚠️ Metadata checksum mismatch ignored (verification disabled)");
+        }
+
+        trace!("📤 Streamed {} bytes of metadata JSON", written);
+        Ok(written)
+    }
+
+    /// Run every integrity check and collect all problems instead of stopping at
+    /// the first one
+    ///
+    /// Each stage runs independently where possible: a metadata checksum
+    /// mismatch doesn't stop the metadata from being parsed, and a bad descriptor
+    /// doesn't hide the ones after it. Only an unreadable index skips the rest.
+    pub fn collect_diagnostics(&mut self) -> PackageDiagnostics {
+        let file_size = match self.backend.data_size() {
+            Some(size) => Ok(size),
+            None => self.path.metadata().map(|m| m.len()),
+        };
+        let file_size = match file_size {
+            Ok(size) if size >= MAGIC_TRAILER_SIZE as u64 => size,
+            Ok(size) => {
+                return PackageDiagnostics::index_unavailable(format!(
+                    "file is {size} bytes, smaller than the {MAGIC_TRAILER_SIZE}-byte trailer"
+                ));
+            }
+            Err(e) => return PackageDiagnostics::index_unavailable(e.to_string()),
+        };
+
+        let index_data = match self.read_magic_trailer() {
+            Ok(data) => data,
+            Err(e) => return PackageDiagnostics::index_unavailable(e.to_string()),
+        };
+        let index = match Index::unpack(&index_data) {
+            Ok(index) => index,
+            Err(e) => return PackageDiagnostics::index_unavailable(e.to_string()),
+        };
+
+        let index_checksum = if index.verify_checksum_raw(&index_data) {
+            CheckStatus::Passed
+        } else {
+            CheckStatus::Failed(format!("{} mismatch", index.checksum_algorithm()))
+        };
+
+        // Metadata: checksum and parse are reported separately
+        let (meta_offset, meta_size) = (index.metadata_offset, index.metadata_size);
+        let mut parsed_metadata = None;
+        let (metadata_checksum, metadata) = if meta_offset.saturating_add(meta_size) > file_size {
+            let reason = "metadata extends past end of file".to_string();
+            (
+                CheckStatus::Skipped(reason.clone()),
+                CheckStatus::Failed(reason),
+            )
+        } else {
+            match self.backend.read_at(meta_offset, meta_size as usize) {
+                Ok(data) => {
+                    use sha2::{Digest, Sha256};
+                    let actual: [u8; 32] = Sha256::digest(&data).into();
+                    let checksum = if actual == index.metadata_checksum {
+                        CheckStatus::Passed
+                    } else {
+                        CheckStatus::Failed("SHA-256 mismatch".to_string())
+                    };
+                    let parsed = match decode_metadata(&data) {
+                        Ok(metadata) => {
+                            parsed_metadata = Some(metadata);
+                            CheckStatus::Passed
+                        }
+                        Err(e) => CheckStatus::Failed(e.to_string()),
+                    };
+                    (checksum, parsed)
+                }
+                Err(e) => (
+                    CheckStatus::Skipped("metadata unreadable".to_string()),
+                    CheckStatus::Failed(e.to_string()),
+                ),
+            }
+        };
+
+        // Descriptors: check each one independently. A corrupt index can
+        // declare any number of slots, so only look at what fits before the
+        // trailer when its checksum fails.
+        let slot_count = index.slot_count as usize;
+        let table_offset = index.slot_table_offset;
+        let data_end = file_size - MAGIC_TRAILER_SIZE as u64;
+        let checked_slots = if index_checksum.is_failed() {
+            let fits = data_end.saturating_sub(table_offset) / SLOT_DESCRIPTOR_SIZE as u64;
+            slot_count.min(usize::try_from(fits).unwrap_or(usize::MAX))
+        } else {
+            slot_count
+        };
+        let mut descriptors = Vec::new();
+        let mut parsed_descriptors = 0;
+        for i in 0..checked_slots {
+            let offset = table_offset + (i * SLOT_DESCRIPTOR_SIZE) as u64;
+            let mut issue = |problem: String| {
+                descriptors.push(DescriptorIssue {
+                    slot: i,
+                    offset,
+                    problem,
+                });
+            };
+
+            let data = match self.backend.read_at(offset, SLOT_DESCRIPTOR_SIZE) {
+                Ok(data) => data,
+                Err(e) => {
+                    issue(format!("unreadable: {e}"));
+                    continue;
+                }
+            };
+            if data.starts_with(b"\x1f\x8b") {
+                issue("contains gzip data instead of a descriptor".to_string());
+                continue;
+            }
+            if data.starts_with(b"{") {
+                issue("contains JSON instead of a descriptor".to_string());
+                continue;
+            }
+            let Some(descriptor) = SlotDescriptor::unpack(&data) else {
+                issue("could not be parsed".to_string());
+                continue;
+            };
+            parsed_descriptors += 1;
+
+            if let Some(problem) = slot_bounds_problem(&descriptor, data_end) {
+                issue(problem);
+            }
+        }
+
+        let slot_count_status = match &parsed_metadata {
+            _ if checked_slots < slot_count => CheckStatus::Failed(format!(
+                "index declares {slot_count} slots, only {checked_slots} descriptors fit before the trailer"
+            )),
+            Some(metadata) if metadata.slots.len() != slot_count => CheckStatus::Failed(format!(
+                "index declares {slot_count} slots, metadata lists {}",
+                metadata.slots.len()
+            )),
+            _ if parsed_descriptors != slot_count => CheckStatus::Failed(format!(
+                "index declares {slot_count} slots, {parsed_descriptors} descriptors parsed"
+            )),
+            Some(_) => CheckStatus::Passed,
+            None => CheckStatus::Skipped("metadata unavailable".to_string()),
+        };
+
+        PackageDiagnostics {
+            trailer: CheckStatus::Passed,
+            format_version: Some(index.format_version),
+            index_checksum,
+            metadata_checksum,
+            metadata,
+            descriptors,
+            slot_count: slot_count_status,
+        }
+    }
+
+    /// Total package length (in-memory backends know it without a path)
+    fn package_len(&self) -> Result<u64> {
+        match self.backend.data_size() {
+            Some(size) => Ok(size),
+            None => Ok(self.path.metadata()?.len()),
+        }
     }
 
     /// Read MagicTrailer and return index data
     fn read_magic_trailer(&mut self) -> Result<Vec<u8>> {
         use log::trace;
 
-        // Get file size
-        let file_size = self.path.metadata()?.len();
+        let file_size = self.package_len()?;
 
         // Read MagicTrailer (last 8200 bytes)
-        let trailer = self
+        if file_size < MAGIC_TRAILER_SIZE as u64 {
+            return Err(FlavorError::FormatError(format!(
+                "Invalid MagicTrailer: file is {file_size} bytes, smaller than the {MAGIC_TRAILER_SIZE}-byte trailer; it is truncated or not a package"
+            )));
+        }
+        let tail = self
             .backend
             .read_at(file_size - MAGIC_TRAILER_SIZE as u64, MAGIC_TRAILER_SIZE)?;
-
-        // Verify emoji bookends
-        if &trailer[..4] != PACKAGE_EMOJI_BYTES {
-            return Err(FlavorError::Generic(
-                "Invalid MagicTrailer: missing 📦 at start".into(),
-            ));
-        }
-        if &trailer[MAGIC_TRAILER_SIZE - 4..] != MAGIC_WAND_EMOJI_BYTES {
-            return Err(FlavorError::Generic(
-                "Invalid MagicTrailer: missing 🪄 at end".into(),
-            ));
-        }
-
-        // Extract index from between emojis
-        let index_data = trailer[4..4 + HEADER_SIZE].to_vec();
+        let index_data = match Index::parse_trailer(&tail) {
+            Ok(index_data) => index_data.to_vec(),
+            Err(e) => return Err(self.appended_data_error(file_size).unwrap_or(e)),
+        };
 
         trace!("Found index in MagicTrailer");
         debug!(
@@ -293,6 +734,19 @@ impl Reader {
         Ok(index_data)
     }
 
+    /// Explain a missing trailer when one sits a little before the end of the file
+    fn appended_data_error(&mut self, file_size: u64) -> Option<FlavorError> {
+        let window_size = file_size.min(DEFAULT_TRAILER_SEARCH_WINDOW);
+        let window = self
+            .backend
+            .read_at(file_size - window_size, window_size as usize)
+            .ok()?;
+        let extra = Index::bytes_after_trailer(&window)?;
+        Some(FlavorError::FormatError(format!(
+            "Invalid MagicTrailer: {extra} bytes follow the package trailer; data was appended to the package after it was built"
+        )))
+    }
+
     /// Read slot descriptors
     pub fn read_slot_descriptors(&mut self) -> Result<Vec<SlotDescriptor>> {
         // Ensure index is loaded
@@ -303,11 +757,21 @@ impl Reader {
         let index = self
             .index
             .as_ref()
-            .ok_or_else(|| FlavorError::Generic("Index not loaded".into()))?;
+            .ok_or_else(|| FlavorError::FormatError("index not loaded".into()))?;
         let desc_count = index.slot_count;
         let desc_offset = index.slot_table_offset;
+        let data_end = self
+            .package_len()?
+            .saturating_sub(MAGIC_TRAILER_SIZE as u64);
         let mut descriptors = Vec::new();
 
+        let table_end = desc_offset.checked_add(desc_count as u64 * SLOT_DESCRIPTOR_SIZE as u64);
+        if table_end.is_none_or(|end| end > data_end) {
+            return Err(FlavorError::VerificationFailed(format!(
+                "slot table at {desc_offset:#x} with {desc_count} descriptors extends past the data section ending at {data_end:#x}"
+            )));
+        }
+
         debug!(
             "📊 Reading {} slot descriptors from offset {:#x}",
             desc_count, desc_offset
@@ -325,21 +789,26 @@ impl Reader {
                     "  This package was built with a buggy builder that doesn't write descriptors"
                 );
                 error!("  Descriptor #{} at offset {:#x}", i, offset);
-                return Err(FlavorError::Generic(
-                    "Package format error: slot descriptors contain data instead of descriptors"
-                        .into(),
+                return Err(FlavorError::FormatError(
+                    "slot descriptors contain data instead of descriptors".into(),
                 ));
             }
 
             if data.starts_with(b"{") {
                 error!("🚨 CRITICAL: Found JSON where slot descriptor expected!");
                 error!("  Descriptor #{} at offset {:#x}", i, offset);
-                return Err(FlavorError::Generic(
-                    "Package format error: slot descriptors contain JSON".into(),
+                return Err(FlavorError::FormatError(
+                    "slot descriptors contain JSON".into(),
                 ));
             }
 
             if let Some(descriptor) = SlotDescriptor::unpack(&data) {
+                if let Some(problem) = slot_bounds_problem(&descriptor, data_end) {
+                    return Err(FlavorError::DescriptorOutOfBounds {
+                        slot: i as usize,
+                        reason: problem,
+                    });
+                }
                 let desc_offset = descriptor.offset;
                 let desc_size = descriptor.size;
                 let desc_checksum = descriptor.checksum;
@@ -356,16 +825,99 @@ impl Reader {
         Ok(descriptors)
     }
 
+    /// Find a slot by its id using the descriptor `name_hash`
+    ///
+    /// Hash matches are confirmed against the metadata `id` to guard against
+    /// collisions; if no descriptor hash matches (e.g. older packages without
+    /// name hashes), the metadata is scanned by id instead.
+    pub fn find_slot_by_name(&mut self, name: &str) -> Result<Option<(usize, SlotDescriptor)>> {
+        let descriptors = self.read_slot_descriptors()?;
+        let ids: Vec<String> = self
+            .read_metadata()?
+            .slots
+            .iter()
+            .map(|slot| slot.id.clone())
+            .collect();
+        let name_hash = SlotDescriptor::hash_name(name);
+
+        let by_hash = descriptors.iter().enumerate().find(|(i, descriptor)| {
+            descriptor.name_hash == name_hash && ids.get(*i).is_none_or(|id| id == name)
+        });
+        if let Some((i, descriptor)) = by_hash {
+            trace!("🔎 Found slot '{}' at {} via name hash", name, i);
+            return Ok(Some((i, *descriptor)));
+        }
+
+        let by_id = ids
+            .iter()
+            .position(|id| id == name)
+            .and_then(|i| descriptors.get(i).map(|descriptor| (i, *descriptor)));
+        if by_id.is_some() {
+            debug!(
+                "🔎 Found slot '{}' via metadata id (no name hash match)",
+                name
+            );
+        }
+        Ok(by_id)
+    }
+
+    /// Read the launcher binary embedded at the start of the package
+    ///
+    /// Returns bytes `[0, launcher_size)` as stored, which is empty for the data
+    /// file of a split package. Check them against [`Index::launcher_hash`].
+    pub fn read_launcher(&mut self) -> Result<Vec<u8>> {
+        let launcher_size = self.read_index()?.launcher_size;
+        let size = usize::try_from(launcher_size).map_err(|_| {
+            FlavorError::FormatError(format!("Launcher size {launcher_size} is too large"))
+        })?;
+        let mut launcher = Vec::with_capacity(size);
+        // Some backends cap a single read at their chunk size
+        while launcher.len() < size {
+            let chunk = self
+                .backend
+                .read_at(launcher.len() as u64, size - launcher.len())?;
+            if chunk.is_empty() {
+                return Err(FlavorError::FormatError(format!(
+                    "Launcher region ({size} bytes) extends past end of file"
+                )));
+            }
+            launcher.extend_from_slice(&chunk);
+        }
+        Ok(launcher)
+    }
+
     /// Read slot data by descriptor
+    ///
+    /// Fails on a descriptor checksum mismatch unless slot checksum
+    /// verification is disabled. Descriptors with a zero checksum are not checked.
+    /// Slots of an encrypted package are checked as stored, then decrypted.
     pub fn read_slot(&mut self, descriptor: &SlotDescriptor) -> Result<Vec<u8>> {
         let desc_offset = descriptor.offset;
         let desc_size = descriptor.size;
+        let expected = descriptor.checksum;
         trace!(
             "🔍 Reading slot from descriptor: offset={:#x}, size={}",
             desc_offset, desc_size
         );
         let data = self.backend.read_slot(descriptor)?;
 
+        if self.verification.slot_checksums && expected != 0 {
+            use sha2::{Digest, Sha256};
+            let hash = Sha256::digest(&data);
+            let mut first = [0u8; 8];
+            first.copy_from_slice(&hash[..8]);
+            let actual = u64::from_le_bytes(first);
+            if actual != expected {
+                return Err(FlavorError::ChecksumMismatch {
+                    subject: format!("slot at offset {desc_offset:#x}"),
+                    expected: format!("{expected:016x}"),
+                    actual: format!("{actual:016x}"),
+                });
+            }
+        }
+
+        let data = self.decrypt_slot(descriptor, data)?;
+
         // Debug check: warn if we got JSON instead of expected data
         if data.starts_with(b"{") || data.starts_with(b"[") {
             error!("🚨 WARNING: Read JSON data from slot descriptor!");
@@ -382,6 +934,83 @@ impl Reader {
         Ok(data)
     }
 
+    /// Stream a slot's decoded bytes into `sink`, returning the bytes written
+    ///
+    /// The operation chain is decoded on the fly; slot data is read straight from
+    /// the package file (or a zero-copy view) instead of being buffered whole.
+    /// Tar slots are written as the archive itself, e.g. for piping to `tar -x`.
+    pub fn copy_slot_to(
+        &mut self,
+        slot_index: usize,
+        sink: &mut impl std::io::Write,
+    ) -> Result<u64> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let descriptors = self.read_slot_descriptors()?;
+        let descriptor =
+            descriptors
+                .get(slot_index)
+                .copied()
+                .ok_or(FlavorError::SlotOutOfRange {
+                    index: slot_index,
+                    count: descriptors.len(),
+                })?;
+        let operations = unpack_operations(descriptor.operations);
+        let (offset, size) = (descriptor.offset, descriptor.size);
+        let encrypted = self.read_index()?.encryption_type != ENCRYPTION_NONE;
+
+        // Sealed slots are opened in memory, so they can't be streamed
+        let source: Box<dyn Read + '_> = if encrypted {
+            Box::new(std::io::Cursor::new(self.read_slot(&descriptor)?))
+        } else if let Ok(view) = self.backend.view_at(offset, size as usize) {
+            Box::new(view)
+        } else if self.path.as_os_str() != "-" {
+            let mut file = std::fs::File::open(&self.path)?;
+            file.seek(SeekFrom::Start(offset))?;
+            Box::new(file.take(size))
+        } else {
+            Box::new(std::io::Cursor::new(self.backend.read_slot(&descriptor)?))
+        };
+
+        let mut decoded = decode_slot_stream(source, &operations, slot_index)?;
+        let written = std::io::copy(&mut decoded, sink)?;
+        trace!("📤 Copied slot {} to sink: {} bytes", slot_index, written);
+        Ok(written)
+    }
+
+    /// Open a slot as a [`Read`](std::io::Read) stream of its decoded bytes
+    ///
+    /// Unlike [`copy_slot_to`](Self::copy_slot_to), the stream does not borrow
+    /// the reader: it reads the package file itself through a
+    /// [`StreamBackend`](super::backends::StreamBackend). Tar slots yield the
+    /// archive, so `tar::Archive::new(reader.slot_reader(i)?)` unpacks them.
+    pub fn slot_reader(&mut self, slot_index: usize) -> Result<SlotReader> {
+        let descriptors = self.read_slot_descriptors()?;
+        let descriptor =
+            descriptors
+                .get(slot_index)
+                .copied()
+                .ok_or(FlavorError::SlotOutOfRange {
+                    index: slot_index,
+                    count: descriptors.len(),
+                })?;
+        let operations = unpack_operations(descriptor.operations);
+        let encrypted = self.read_index()?.encryption_type != ENCRYPTION_NONE;
+
+        // Sealed slots are opened in memory, so they can't be streamed
+        if encrypted || self.path.as_os_str() == "-" {
+            let data = self.read_slot(&descriptor)?;
+            return SlotReader::from_bytes(data, &operations, slot_index);
+        }
+        SlotReader::open(
+            &self.path,
+            &descriptor,
+            &operations,
+            slot_index,
+            self.verification.slot_checksums,
+        )
+    }
+
     /// Extract a slot to a directory
     pub fn extract_slot(&mut self, slot_index: usize, dest_dir: &Path) -> Result<()> {
         extract_slot(self, slot_index, dest_dir)
@@ -403,6 +1032,60 @@ impl Reader {
     }
 }
 
+/// Feeds every byte read from `inner` into `hasher`
+struct HashingReader<R> {
+    inner: R,
+    hasher: sha2::Sha256,
+}
+
+impl<R: std::io::Read> std::io::Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        use sha2::Digest;
+
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Describe how a descriptor's data falls outside the slot data section
+///
+/// `data_end` is where the MagicTrailer starts; slot data must end at or
+/// before it.
+pub(super) fn slot_bounds_problem(descriptor: &SlotDescriptor, data_end: u64) -> Option<String> {
+    let (offset, size) = (descriptor.offset, descriptor.size);
+    match offset.checked_add(size) {
+        None => Some(format!("slot data {offset:#x}+{size} overflows")),
+        Some(end) if end > data_end => Some(format!(
+            "slot data {offset:#x}+{size} extends past the data section ending at {data_end:#x}"
+        )),
+        Some(_) => None,
+    }
+}
+
+/// Fail unless the file is exactly as long as the package its index describes
+///
+/// A longer file usually holds something, often another package, in front of
+/// this one, which shifts every offset the index records. A shorter one has
+/// lost data between its start and the trailer.
+pub(super) fn check_package_size(index: &Index, file_size: u64) -> Result<()> {
+    let package_size = index.package_size;
+    let problem = match file_size.cmp(&package_size) {
+        std::cmp::Ordering::Equal => return Ok(()),
+        std::cmp::Ordering::Greater => format!(
+            "{} unexpected bytes precede the package; it may have been concatenated with another file",
+            file_size - package_size
+        ),
+        std::cmp::Ordering::Less => format!(
+            "{} bytes are missing; it was truncated or damaged in transfer",
+            package_size - file_size
+        ),
+    };
+    Err(FlavorError::FormatError(format!(
+        "Package size mismatch: the index records {package_size} bytes but the file is {file_size} bytes; {problem}"
+    )))
+}
+
 impl Drop for Reader {
     fn drop(&mut self) {
         // Ensure backend is closed
@@ -410,4 +1093,421 @@ impl Drop for Reader {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BuildOptions;
+
+    #[test]
+    fn test_verification_can_be_relaxed_per_check() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("config.json"), b"{\"debug\": false}").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "verification-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "config", "source": root.join("config.json"), "target": "config.json"}]
+        });
+        let package = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("verification".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest.to_string(), &package, options).is_ok()
+        );
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        let mut streamed = Vec::new();
+        assert!(reader.stream_metadata_to(&mut streamed).is_ok());
+        assert!(
+            serde_json::from_slice::<serde_json::Value>(&streamed)
+                .is_ok_and(|v| v["package"]["name"] == "verification-test")
+        );
+        let Ok((metadata_offset, descriptors)) = reader
+            .read_index()
+            .map(|i| i.metadata_offset)
+            .and_then(|offset| Ok((offset, reader.read_slot_descriptors()?)))
+        else {
+            return;
+        };
+        let slot_offset = descriptors[0].offset;
+        drop(reader);
+
+        // Corrupt the gzip MTIME field (still decodes) and the first slot byte
+        let bytes = std::fs::read(&package);
+        assert!(bytes.is_ok());
+        let Ok(mut bytes) = bytes else {
+            return;
+        };
+        bytes[metadata_offset as usize + 4] ^= 0xFF;
+        bytes[slot_offset as usize] ^= 0xFF;
+        assert!(std::fs::write(&package, &bytes).is_ok());
+
+        let strict = Reader::new(&package);
+        assert!(strict.is_ok());
+        let Ok(mut strict) = strict else {
+            return;
+        };
+        assert!(strict.read_metadata().is_err());
+        assert!(strict.stream_metadata_to(&mut std::io::sink()).is_err());
+        assert!(strict.read_slot(&descriptors[0]).is_err());
+
+        let relaxed = Reader::new(&package);
+        assert!(relaxed.is_ok());
+        let Ok(mut relaxed) = relaxed else {
+            return;
+        };
+        relaxed.set_verification(VerificationConfig::none());
+        assert!(
+            relaxed
+                .read_metadata()
+                .is_ok_and(|m| m.package.name == "verification-test")
+        );
+        assert!(
+            relaxed
+                .read_slot(&descriptors[0])
+                .is_ok_and(|d| d.len() == 16)
+        );
+    }
+
+    #[test]
+    fn test_index_access_mode_selects_backend() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("data.txt"), b"streamed").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "access-mode", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "data", "source": root.join("data.txt"), "target": "data.txt"}]
+        })
+        .to_string();
+        let build = |access_mode| {
+            let package = root.join(format!("mode-{access_mode}.psp"));
+            let options = BuildOptions {
+                launcher_bin: Some(root.join("launcher")),
+                key_seed: Some("access-mode".to_string()),
+                access_mode,
+                ..BuildOptions::default()
+            };
+            super::super::builder::build_from_str(&manifest, &package, options).map(|_| package)
+        };
+
+        let stream = build(3);
+        assert!(stream.is_ok());
+        let Ok(stream) = stream else { return };
+        let reader = Reader::new(&stream);
+        assert!(
+            reader
+                .as_ref()
+                .is_ok_and(|r| r.backend().name() == "stream")
+        );
+        let Ok(mut reader) = reader else { return };
+        assert!(
+            reader
+                .read_metadata()
+                .is_ok_and(|m| m.package.name == "access-mode")
+        );
+        assert!(
+            reader
+                .copy_slot_to(0, &mut Vec::new())
+                .is_ok_and(|n| n == 8)
+        );
+
+        // An explicit backend still wins, and small packages default to file I/O
+        let explicit = Reader::with_backend(&stream, super::super::defaults::ACCESS_MMAP);
+        assert!(explicit.is_ok_and(|r| r.backend().name() == "mmap"));
+        let auto = build(0);
+        assert!(auto.is_ok());
+        let Ok(auto) = auto else { return };
+        assert!(Reader::new(&auto).is_ok_and(|r| r.backend().name() == "file"));
+        assert!(build(4).is_err_and(|e| matches!(e, FlavorError::ConfigError(_))));
+
+        // Files without an index still open, and fail when read
+        assert!(std::fs::write(root.join("junk"), b"not a package").is_ok());
+        let junk = Reader::new(&root.join("junk"));
+        assert!(junk.is_ok_and(|mut r| r.read_index().is_err()));
+    }
+
+    #[test]
+    fn test_copy_slot_to_decodes_from_every_source() {
+        use super::super::defaults::{ACCESS_FILE, ACCESS_MMAP};
+        use std::io::Write;
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let text = b"copied out of the package\n".repeat(40);
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        assert!(gz.write_all(&text).is_ok());
+        let gzipped = gz.finish();
+        assert!(gzipped.is_ok());
+        let Ok(gzipped) = gzipped else { return };
+        assert!(std::fs::write(root.join("text.gz"), gzipped).is_ok());
+        assert!(std::fs::write(root.join("raw.bin"), [0u8, 1, 2, 255]).is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "copy-slot", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "text", "source": root.join("text.gz"), "target": "text.txt", "operations": "gzip"},
+                {"id": "raw", "source": root.join("raw.bin"), "target": "raw.bin"}
+            ]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("copy-slot".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        // A mapped view, the package file itself, and a buffered stream
+        let file = std::fs::File::open(&package);
+        assert!(file.is_ok());
+        let Ok(file) = file else { return };
+        let readers = [
+            Reader::with_backend(&package, ACCESS_MMAP),
+            Reader::with_backend(&package, ACCESS_FILE),
+            Reader::from_reader(file),
+        ];
+        for reader in readers {
+            assert!(reader.is_ok());
+            let Ok(mut reader) = reader else { return };
+            let mut copied = Vec::new();
+            assert!(
+                reader
+                    .copy_slot_to(0, &mut copied)
+                    .is_ok_and(|n| n == text.len() as u64)
+            );
+            assert_eq!(copied, text);
+            copied.clear();
+            assert!(reader.copy_slot_to(1, &mut copied).is_ok_and(|n| n == 4));
+            assert_eq!(copied, [0, 1, 2, 255]);
+            assert!(
+                reader
+                    .copy_slot_to(2, &mut copied)
+                    .is_err_and(|e| matches!(e, FlavorError::SlotOutOfRange { .. }))
+            );
+        }
+    }
+
+    #[test]
+    fn test_out_of_bounds_descriptor_is_rejected() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("app.sh"), b"echo hi\n").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "bounds-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        });
+        let package = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("bounds".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest.to_string(), &package, options).is_ok()
+        );
+
+        let Ok(table_offset) =
+            Reader::new(&package).and_then(|mut reader| Ok(reader.read_index()?.slot_table_offset))
+        else {
+            return;
+        };
+        let bytes = std::fs::read(&package);
+        assert!(bytes.is_ok());
+        let Ok(mut bytes) = bytes else {
+            return;
+        };
+        let size_field = table_offset as usize + 24;
+        let oversized = (bytes.len() as u64).to_le_bytes();
+        bytes[size_field..size_field + 8].copy_from_slice(&oversized);
+        assert!(std::fs::write(&package, &bytes).is_ok());
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        assert!(matches!(
+            reader.read_slot_descriptors(),
+            Err(FlavorError::DescriptorOutOfBounds { slot: 0, .. })
+        ));
+        assert!(super::super::verifier::verify(&package).is_err());
+        assert_eq!(reader.collect_diagnostics().descriptors.len(), 1);
+    }
+
+    #[test]
+    fn test_diagnostics_cap_slot_count_of_corrupt_index() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("app.sh"), b"echo hi\n").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "corrupt-count", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        });
+        let package = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("corrupt-count".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest.to_string(), &package, options).is_ok()
+        );
+
+        // slot_count sits 56 bytes into the index block, after 📦
+        let bytes = std::fs::read(&package);
+        assert!(bytes.is_ok());
+        let Ok(mut bytes) = bytes else { return };
+        let slot_count = bytes.len() - MAGIC_TRAILER_SIZE + 4 + 56;
+        bytes[slot_count..slot_count + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(std::fs::write(&package, &bytes).is_ok());
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let diagnostics = reader.collect_diagnostics();
+        assert!(diagnostics.index_checksum.is_failed());
+        assert!(diagnostics.descriptors.len() < 64);
+        assert!(matches!(
+            diagnostics.slot_count,
+            CheckStatus::Failed(ref reason) if reason.contains("fit before the trailer")
+        ));
+        assert!(diagnostics.to_string().contains("Format version: 20250001"));
+    }
+
+    #[test]
+    fn test_mangled_package_sizes_are_reported() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("app.sh"), b"echo hi\n").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "mangled", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        });
+        let package = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("mangled".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest.to_string(), &package, options).is_ok()
+        );
+        let bytes = std::fs::read(&package);
+        assert!(bytes.is_ok());
+        let Ok(bytes) = bytes else {
+            return;
+        };
+        let read_index_error = |name: &str, data: &[u8]| {
+            let path = root.join(name);
+            assert!(std::fs::write(&path, data).is_ok());
+            // Still recognized as a package, so the reader can explain
+            assert!(crate::psp::detect_format(&path).is_ok());
+            Reader::open(&path)
+                .and_then(|mut reader| reader.read_index().map(|_| ()))
+                .err()
+                .map(|e| e.to_string())
+        };
+
+        let doubled = [bytes.as_slice(), bytes.as_slice()].concat();
+        let error = read_index_error("doubled.psp", &doubled);
+        assert!(
+            error.is_some_and(|e| e.contains(&format!("{} unexpected bytes precede", bytes.len())))
+        );
+
+        let appended = [bytes.as_slice(), b"trailing junk"].concat();
+        let error = read_index_error("appended.psp", &appended);
+        assert!(error.is_some_and(|e| e.contains("13 bytes follow the package trailer")));
+
+        let truncated = [&bytes[..512], &bytes[1024..]].concat();
+        let error = read_index_error("truncated.psp", &truncated);
+        assert!(error.is_some_and(|e| e.contains("512 bytes are missing")));
+
+        // Recovery tools can still read the last package's index
+        let relaxed = Reader::new(&root.join("doubled.psp"));
+        assert!(relaxed.is_ok());
+        let Ok(mut relaxed) = relaxed else {
+            return;
+        };
+        relaxed.set_verification(VerificationConfig::none());
+        assert!(relaxed.read_index().is_ok());
+    }
+
+    #[test]
+    fn test_read_launcher_matches_recorded_hash() {
+        use sha2::{Digest, Sha256};
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let launcher: Vec<u8> = (0..70_000u32).map(|i| (i % 251) as u8).collect();
+        assert!(std::fs::write(root.join("app.sh"), b"echo hi\n").is_ok());
+        assert!(std::fs::write(root.join("launcher"), &launcher).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "launcher-test", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "app", "source": root.join("app.sh"), "target": "app.sh"}]
+        });
+        let package = root.join("out.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("launcher".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest.to_string(), &package, options).is_ok()
+        );
+
+        // The streaming backend returns at most one chunk per read
+        let reader = Reader::with_backend(&package, super::super::defaults::ACCESS_STREAM);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let embedded = reader.read_launcher();
+        assert!(embedded.as_ref().is_ok_and(|bytes| *bytes == launcher));
+        let hash: [u8; 32] = Sha256::digest(&launcher).into();
+        assert!(
+            reader
+                .read_index()
+                .is_ok_and(|index| index.launcher_hash() == Some(hash))
+        );
+    }
+}
+
 // 📦📖🗺️🪄
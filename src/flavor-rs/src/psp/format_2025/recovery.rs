@@ -0,0 +1,503 @@
+//! Best-effort salvage of slot data from packages with an unreadable index
+//!
+//! Everything here is heuristic. Descriptors are only trusted when their
+//! checksum matches the bytes they point at; gzip streams and tar archives are
+//! found by their magic bytes and kept only if they decode cleanly, and plain
+//! JSON metadata by its leading `"format"` key. Recovered slots are written as
+//! stored (still compressed) for the user to inspect.
+
+use super::checksums::{ChecksumAlgorithm, calculate_checksum};
+use super::constants::{
+    OP_BZIP2, OP_GZIP, OP_LZ4, OP_TAR, OP_XZ, OP_ZSTD, SLOT_ALIGNMENT, SLOT_DESCRIPTOR_SIZE,
+};
+use super::debug::{SlotContent, detect_slot_content};
+use super::metadata::Metadata;
+use super::operations::unpack_operations;
+use super::slots::{SlotDescriptor, lifecycle_to_str, purpose_to_str};
+use crate::exceptions::Result;
+use flate2::bufread::GzDecoder;
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde::de::IgnoredAny;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Bytes of decoded content kept to recognise what a gzip stream contains
+const PREVIEW_SIZE: usize = 512;
+
+/// Descriptor ids are slot indices, so anything larger is not a descriptor
+const MAX_SLOT_ID: u64 = 0xFFFF;
+
+/// How a recovered slot was located
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecoverySource {
+    /// A slot descriptor whose checksum matches the data it points at
+    Descriptor,
+    /// Gzip or tar magic bytes that decoded cleanly, or a plain JSON object
+    Magic,
+}
+
+/// A slot salvaged from a damaged package
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveredSlot {
+    pub offset: u64,
+    pub size: u64,
+    pub content: SlotContent,
+    pub source: RecoverySource,
+    /// Slot id, when recovered metadata lists a slot with this checksum
+    pub id: Option<String>,
+    /// File the stored bytes were written to
+    pub path: PathBuf,
+}
+
+/// What [`recover`] could and couldn't salvage
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecoverResult {
+    pub slots: Vec<RecoveredSlot>,
+    /// Decoded metadata JSON, when a gzip stream or JSON object parsed as
+    /// package metadata
+    pub metadata: Option<PathBuf>,
+    /// Data known to be missing, or that could not be identified
+    pub unrecovered: Vec<String>,
+}
+
+/// A candidate slot found in the package bytes
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    offset: usize,
+    size: usize,
+    content: SlotContent,
+    source: RecoverySource,
+}
+
+impl Region {
+    fn end(&self) -> usize {
+        self.offset + self.size
+    }
+}
+
+/// Scan a package without relying on its index and salvage what looks like slot data
+///
+/// Slots are written to `output_dir` as stored. When the package metadata is
+/// found it is written as `metadata.json` and used to name the slots and to
+/// report the ones that could not be found. Results are heuristic: slots may be
+/// missed, and unrelated bytes in the launcher may be reported as slots.
+pub fn recover(package_path: &Path, output_dir: &Path) -> Result<RecoverResult> {
+    warn!("🩹 Recovering {package_path:?}: results are heuristic and may be incomplete");
+    let data = std::fs::read(package_path)?;
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut regions = find_descriptor_regions(&data);
+    debug!("🔍 Found {} slots via descriptors", regions.len());
+    let magic = find_magic_regions(&data, &regions);
+    debug!("🔍 Found {} candidate streams via magic bytes", magic.len());
+    regions.extend(magic);
+    regions.sort_by_key(|r| r.offset);
+
+    let mut result = RecoverResult::default();
+
+    // The metadata is JSON, gzipped unless that didn't shrink it; the first
+    // candidate that parses wins
+    let mut metadata = None;
+    regions.retain(|region| {
+        if metadata.is_some() || region.source != RecoverySource::Magic {
+            return true;
+        }
+        match parse_metadata(&data[region.offset..region.end()]) {
+            Some((json, parsed)) => {
+                let path = output_dir.join("metadata.json");
+                match std::fs::write(&path, json) {
+                    Ok(()) => result.metadata = Some(path),
+                    Err(e) => result
+                        .unrecovered
+                        .push(format!("metadata found but could not be written: {e}")),
+                }
+                metadata = Some(parsed);
+                false
+            }
+            None => true,
+        }
+    });
+
+    for (n, region) in regions.iter().enumerate() {
+        let bytes = &data[region.offset..region.end()];
+        let id = metadata.as_ref().and_then(|m| {
+            let checksum = calculate_checksum(bytes, ChecksumAlgorithm::Sha256).ok()?;
+            m.slots
+                .iter()
+                .find(|s| s.checksum == checksum)
+                .map(|s| s.id.clone())
+        });
+
+        let name = id.as_deref().unwrap_or("unknown").replace(['/', '\\'], "_");
+        let path = output_dir.join(format!("{n:03}-{name}{}", extension(region.content, bytes)));
+        std::fs::write(&path, bytes)?;
+        debug!(
+            "🩹 Recovered {:?} slot at {:#x} ({} bytes) -> {path:?}",
+            region.content, region.offset, region.size
+        );
+
+        result.slots.push(RecoveredSlot {
+            offset: region.offset as u64,
+            size: region.size as u64,
+            content: region.content,
+            source: region.source,
+            id,
+            path,
+        });
+    }
+
+    match &metadata {
+        Some(metadata) => {
+            for (i, slot) in metadata.slots.iter().enumerate() {
+                if !result
+                    .slots
+                    .iter()
+                    .any(|r| r.id.as_deref() == Some(&slot.id))
+                {
+                    result
+                        .unrecovered
+                        .push(format!("slot {i} '{}' was not found", slot.id));
+                }
+            }
+        }
+        None => result
+            .unrecovered
+            .push("package metadata was not found; slot names are unknown".to_string()),
+    }
+    if result.slots.is_empty() {
+        result
+            .unrecovered
+            .push("no slot data could be identified".to_string());
+    }
+
+    info!(
+        "🩹 Recovered {} slots ({} problems) into {output_dir:?}",
+        result.slots.len(),
+        result.unrecovered.len()
+    );
+    Ok(result)
+}
+
+/// Find descriptors whose checksum matches the data they point at
+fn find_descriptor_regions(data: &[u8]) -> Vec<Region> {
+    const KNOWN_OPERATIONS: [u8; 6] = [OP_TAR, OP_GZIP, OP_BZIP2, OP_XZ, OP_ZSTD, OP_LZ4];
+
+    let mut regions: Vec<Region> = Vec::new();
+    let mut hashed = HashSet::new();
+    let step = SLOT_ALIGNMENT as usize;
+    let mut pos = 0;
+    while pos + SLOT_DESCRIPTOR_SIZE <= data.len() {
+        let candidate = SlotDescriptor::unpack(&data[pos..pos + SLOT_DESCRIPTOR_SIZE]);
+        pos += step;
+        let Some(descriptor) = candidate else {
+            continue;
+        };
+
+        // Cheap structural checks first; only plausible descriptors get hashed
+        if descriptor.reserved1 != 0
+            || descriptor.reserved2 != 0
+            || purpose_to_str(descriptor.purpose).is_none()
+            || lifecycle_to_str(descriptor.lifecycle).is_none()
+            || descriptor.permissions_high > 0x0F
+            || descriptor.id > MAX_SLOT_ID
+        {
+            continue;
+        }
+        let (offset, size, checksum) = (descriptor.offset, descriptor.size, descriptor.checksum);
+        let (Ok(offset), Ok(size)) = (usize::try_from(offset), usize::try_from(size)) else {
+            continue;
+        };
+        if checksum == 0
+            || size == 0
+            || offset % step != 0
+            || offset.checked_add(size).is_none_or(|end| end > data.len())
+            || regions.iter().any(|r| r.offset == offset)
+            || !hashed.insert((offset, size))
+        {
+            continue;
+        }
+        if !unpack_operations(descriptor.operations)
+            .iter()
+            .all(|op| KNOWN_OPERATIONS.contains(op))
+        {
+            continue;
+        }
+
+        let bytes = &data[offset..offset + size];
+        let hash = Sha256::digest(bytes);
+        let mut first = [0u8; 8];
+        first.copy_from_slice(&hash[..8]);
+        if u64::from_le_bytes(first) != checksum {
+            continue;
+        }
+
+        regions.push(Region {
+            offset,
+            size,
+            content: detect_slot_content(&bytes[..PREVIEW_SIZE.min(size)]),
+            source: RecoverySource::Descriptor,
+        });
+    }
+    regions
+}
+
+/// Find gzip streams, tar archives and metadata JSON outside the already-known regions
+fn find_magic_regions(data: &[u8], known: &[Region]) -> Vec<Region> {
+    let mut regions = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        if let Some(region) = known.iter().find(|r| r.offset <= pos && pos < r.end()) {
+            pos = region.end();
+            continue;
+        }
+
+        let rest = &data[pos..];
+        let found = if rest.starts_with(b"\x1f\x8b\x08") {
+            gzip_extent(rest).map(|size| (size, SlotContent::Gzip))
+        } else if rest.get(257..262) == Some(b"ustar") {
+            tar_extent(rest).map(|size| (size, SlotContent::Tar))
+        } else if rest.starts_with(b"{") {
+            metadata_json_extent(rest).map(|size| (size, SlotContent::Json))
+        } else {
+            None
+        };
+
+        match found {
+            Some((size, content)) => {
+                regions.push(Region {
+                    offset: pos,
+                    size,
+                    content,
+                    source: RecoverySource::Magic,
+                });
+                pos += size;
+            }
+            None => pos += 1,
+        }
+    }
+    regions
+}
+
+/// Length of the gzip stream at the start of `data`, if it decodes cleanly
+fn gzip_extent(data: &[u8]) -> Option<usize> {
+    let mut decoder = GzDecoder::new(data);
+    std::io::copy(&mut decoder, &mut std::io::sink()).ok()?;
+    Some(data.len() - decoder.get_ref().len())
+}
+
+/// Length of the tar archive at the start of `data`, up to its end-of-archive marker
+fn tar_extent(data: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    loop {
+        let block = data.get(pos..pos + 512)?;
+        if block.iter().all(|&b| b == 0) {
+            // Two zero blocks end the archive; tolerate a truncated marker at EOF
+            return (pos > 0).then(|| (pos + 1024).min(data.len()));
+        }
+        if block.get(257..262) != Some(b"ustar") {
+            return None;
+        }
+        let header = tar::Header::from_byte_slice(block);
+        let entry_size = usize::try_from(header.entry_size().ok()?).ok()?;
+        pos = pos.checked_add(512 + entry_size.div_ceil(512) * 512)?;
+    }
+}
+
+/// Length of the JSON object at the start of `data`, if it leads with the
+/// `"format"` key that package metadata starts with
+fn metadata_json_extent(data: &[u8]) -> Option<usize> {
+    let body = data.strip_prefix(b"{")?;
+    let key = body.iter().position(|b| !b.is_ascii_whitespace())?;
+    if !body[key..].starts_with(b"\"format\"") {
+        return None;
+    }
+    let mut objects = serde_json::Deserializer::from_slice(data).into_iter::<IgnoredAny>();
+    objects.next()?.ok()?;
+    Some(objects.byte_offset())
+}
+
+/// Decode stored package metadata, gzipped or plain JSON
+fn parse_metadata(stored: &[u8]) -> Option<(String, Metadata)> {
+    let json = if stored.starts_with(b"\x1f\x8b") {
+        let mut json = String::new();
+        GzDecoder::new(stored).read_to_string(&mut json).ok()?;
+        json
+    } else {
+        std::str::from_utf8(stored).ok()?.to_string()
+    };
+    let metadata = Metadata::from_json(&json).ok()?;
+    Some((json, metadata))
+}
+
+/// File extension for stored slot bytes, looking inside gzip for tar
+fn extension(content: SlotContent, stored: &[u8]) -> &'static str {
+    match content {
+        SlotContent::Gzip => {
+            let mut preview = Vec::with_capacity(PREVIEW_SIZE);
+            let _ = GzDecoder::new(stored)
+                .take(PREVIEW_SIZE as u64)
+                .read_to_end(&mut preview);
+            if detect_slot_content(&preview) == SlotContent::Tar {
+                ".tar.gz"
+            } else {
+                ".gz"
+            }
+        }
+        SlotContent::Tar => ".tar",
+        SlotContent::Zip => ".zip",
+        SlotContent::Json => ".json",
+        SlotContent::Unknown => ".bin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BuildOptions;
+    use crate::psp::format_2025::reader::Reader;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    #[test]
+    fn test_recover_after_trailer_and_descriptor_damage() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let mut tar_builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        let mut header = tar::Header::new_gnu();
+        let body = b"print('hello')\n";
+        header.set_size(body.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        assert!(
+            tar_builder
+                .append_data(&mut header, "lib/app.py", &body[..])
+                .is_ok()
+        );
+        let tgz = tar_builder.into_inner().and_then(|gz| gz.finish());
+        assert!(tgz.is_ok());
+        let Ok(tgz) = tgz else { return };
+        assert!(std::fs::write(root.join("lib.tgz"), &tgz).is_ok());
+        assert!(std::fs::write(root.join("config.json"), b"{\"debug\": false}").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+
+        let manifest = serde_json::json!({
+            "package": {"name": "recover-test", "version": "1.0"},
+            "execution": {"command": "{workenv}/lib/app.py"},
+            "slots": [
+                {"id": "lib", "source": root.join("lib.tgz"), "target": "lib", "operations": "tar,gzip"},
+                {"id": "config", "source": root.join("config.json"), "target": "config.json"}
+            ]
+        });
+        let package = root.join("app.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("recover".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest.to_string(), &package, options).is_ok()
+        );
+        let table_offset = Reader::new(&package)
+            .and_then(|mut r| r.read_index().map(|i| i.slot_table_offset as usize));
+        assert!(table_offset.is_ok());
+        let Ok(table_offset) = table_offset else {
+            return;
+        };
+
+        // Chop the trailer: the index is gone, descriptors still identify both slots
+        let bytes = std::fs::read(&package);
+        assert!(bytes.is_ok());
+        let Ok(mut bytes) = bytes else {
+            return;
+        };
+        bytes.truncate(bytes.len() - 100);
+        assert!(std::fs::write(&package, &bytes).is_ok());
+        assert!(
+            Reader::new(&package)
+                .and_then(|mut r| r.read_index().map(|_| ()))
+                .is_err()
+        );
+
+        let result = recover(&package, &root.join("a"));
+        assert!(result.as_ref().is_ok_and(|r| {
+            r.metadata.is_some()
+                && r.unrecovered.is_empty()
+                && r.slots.len() == 2
+                && r.slots
+                    .iter()
+                    .all(|s| s.source == RecoverySource::Descriptor)
+        }));
+        let Ok(result) = result else { return };
+        assert_eq!(result.slots[0].id.as_deref(), Some("lib"));
+        assert!(std::fs::read(&result.slots[0].path).is_ok_and(|d| d == tgz));
+
+        // Without descriptors only the gzip slot can be found by its magic
+        bytes[table_offset..table_offset + 2 * SLOT_DESCRIPTOR_SIZE].fill(0);
+        assert!(std::fs::write(&package, &bytes).is_ok());
+        let result = recover(&package, &root.join("b"));
+        assert!(result.is_ok_and(|r| {
+            r.slots.len() == 1
+                && r.slots[0].id.as_deref() == Some("lib")
+                && r.slots[0].path.to_string_lossy().ends_with(".tar.gz")
+                && r.unrecovered == vec!["slot 1 'config' was not found".to_string()]
+        }));
+    }
+
+    #[test]
+    fn test_recover_plain_json_metadata() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("config.json"), b"{\"debug\": false}").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "recover-json", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "config", "source": root.join("config.json"), "target": "config.json"}]
+        });
+        let package = root.join("app.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("recover".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(
+            super::super::builder::build_from_str(&manifest.to_string(), &package, options).is_ok()
+        );
+        let json = Reader::new(&package)
+            .and_then(|mut r| Ok(serde_json::to_vec_pretty(r.read_metadata()?)?));
+        assert!(json.is_ok());
+        let Ok(json) = json else { return };
+
+        // Metadata stored uncompressed, surrounded by bytes that are not slots
+        let mut bytes = b"{not metadata}".to_vec();
+        bytes.extend_from_slice(&[0u8; 64]);
+        bytes.extend_from_slice(&json);
+        bytes.extend_from_slice(&[0u8; 64]);
+        let damaged = root.join("damaged.psp");
+        assert!(std::fs::write(&damaged, &bytes).is_ok());
+
+        let result = recover(&damaged, &root.join("out"));
+        assert!(result.as_ref().is_ok_and(|r| {
+            r.slots.is_empty()
+                && r.unrecovered
+                    .contains(&"slot 0 'config' was not found".to_string())
+        }));
+        let Ok(result) = result else { return };
+        assert!(
+            result
+                .metadata
+                .and_then(|path| std::fs::read(path).ok())
+                .is_some_and(|written| written == json)
+        );
+    }
+}
@@ -8,18 +8,47 @@
 //! maintainability and reduced cognitive complexity.
 
 // Use RuntimeEnv from metadata module
+use super::defaults::{ValidationLevel, get_validation_level};
 use super::metadata::RuntimeEnv;
+use super::reader::Reader;
+use crate::exceptions::Result;
+use log::debug;
+use std::collections::HashMap;
+use std::path::Path;
 
 // Re-export the refactored runtime module components
 pub use runtime_impl::process_runtime_env;
 
+/// Resolve the environment a package's process would see, without launching it
+///
+/// Loads the package's `runtime.env` configuration and applies the same ordered
+/// operations used at launch to `base_env`. Packages without a `runtime.env`
+/// section return `base_env` unchanged.
+pub fn resolve_runtime_env(
+    package_path: &Path,
+    base_env: HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+    let mut reader = Reader::new(package_path)?;
+    let metadata = reader.read_metadata()?;
+
+    let strict = matches!(get_validation_level(), ValidationLevel::Strict);
+    let mut env_map = base_env;
+    match metadata.runtime.as_ref().and_then(|r| r.env.as_ref()) {
+        Some(runtime_env) => process_runtime_env(&mut env_map, runtime_env, strict)?,
+        None => debug!("📭 Package has no runtime.env configuration"),
+    }
+
+    Ok(env_map)
+}
+
 // Implementation modules
 mod runtime_impl {
     use super::RuntimeEnv;
     use operations::{MapOperation, SetOperation, UnsetOperation};
     use patterns::PatternProcessor;
 
-    use log::debug;
+    use crate::exceptions::Result;
+    use log::{debug, error};
     use std::collections::HashMap;
 
     /// Process runtime environment configuration
@@ -27,7 +56,8 @@ mod runtime_impl {
     /// Operations are processed in this order:
     /// 1. Analyze pass patterns - Build list of variables to preserve
     /// 2. unset - Remove specified variables (skipping those marked to preserve)
-    /// 3. map - Rename variables
+    /// 3. map - Rename variables (`copy` entries are applied first and keep
+    ///    the original variable)
     /// 4. set - Set specific values
     /// 5. pass verification - Check that required variables/patterns exist
     ///
@@ -35,7 +65,16 @@ mod runtime_impl {
     ///
     /// * `env_map` - Mutable reference to environment variables
     /// * `runtime_env` - Runtime environment configuration
-    pub fn process_runtime_env(env_map: &mut HashMap<String, String>, runtime_env: &RuntimeEnv) {
+    /// * `strict` - Fail when a required `pass` variable is missing instead of logging it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in strict mode if required `pass` variables are missing.
+    pub fn process_runtime_env(
+        env_map: &mut HashMap<String, String>,
+        runtime_env: &RuntimeEnv,
+        strict: bool,
+    ) -> Result<()> {
         debug!("🔧 Processing runtime environment configuration");
 
         // Build pattern processor for pass/preserve operations
@@ -75,24 +114,26 @@ mod runtime_impl {
                 debug!("📋 Unset patterns found: {:?}", unset_patterns);
                 match UnsetOperation::new(unset_patterns, &pattern_processor).execute(env_map) {
                     Ok(_) => debug!("✅ Unset operations completed successfully"),
-                    Err(e) => debug!("⚠️ Error during unset operations: {}", e),
+                    Err(e) => debug!("⚠️ Error during unset operations: {:#}", e),
                 }
             }
         } else {
             debug!("📭 No unset patterns configured");
         }
 
-        // Process map operations (variable renaming)
-        if let Some(map_ops) = &runtime_env.map {
-            if !map_ops.is_empty() {
-                // Convert HashMap to Vec of key=value strings
-                let map_strings: Vec<String> = map_ops
-                    .iter()
-                    .map(|(k, v)| format!("{}={}", k, v))
-                    .collect();
-                if let Err(e) = MapOperation::new(&map_strings, &pattern_processor).execute(env_map)
-                {
-                    debug!("⚠️ Error during map operations: {}", e);
+        // Process map operations (copies first, then renames)
+        for (map_ops, copy) in [(&runtime_env.copy, true), (&runtime_env.map, false)] {
+            if let Some(map_ops) = map_ops {
+                if !map_ops.is_empty() {
+                    // Convert HashMap to Vec of key=value strings
+                    let map_strings: Vec<String> = map_ops
+                        .iter()
+                        .map(|(k, v)| format!("{}={}", k, v))
+                        .collect();
+                    let operation = MapOperation::new(&map_strings, copy, &pattern_processor);
+                    if let Err(e) = operation.execute(env_map) {
+                        debug!("⚠️ Error during map operations: {:#}", e);
+                    }
                 }
             }
         }
@@ -106,25 +147,34 @@ mod runtime_impl {
                     .map(|(k, v)| format!("{}={}", k, v))
                     .collect();
                 if let Err(e) = SetOperation::new(&set_strings).execute(env_map) {
-                    debug!("⚠️ Error during set operations: {}", e);
+                    debug!("⚠️ Error during set operations: {:#}", e);
                 }
             }
         }
 
         // Verify all required pass patterns are satisfied
         if let Err(e) = pattern_processor.verify_requirements(env_map) {
-            debug!("⚠️ Pass pattern verification failed: {}", e);
+            if strict {
+                error!("❌ Pass pattern verification failed: {:#}", e);
+                return Err(e);
+            }
+            debug!("⚠️ Pass pattern verification failed: {:#}", e);
         }
 
         debug!("✅ Runtime environment processing complete");
+        Ok(())
     }
 
     mod patterns {
         use crate::exceptions::{FlavorError, Result};
         use glob::Pattern;
-        use log::{debug, trace};
+        use log::{debug, trace, warn};
+        use regex::Regex;
         use std::collections::{HashMap, HashSet};
 
+        /// Prefix marking a pattern as a regular expression (e.g., `regex:^AWS_.*`)
+        pub(super) const REGEX_PREFIX: &str = "regex:";
+
         /// Handles pattern matching for environment variable preservation
         pub(super) struct PatternProcessor {
             patterns: Vec<CompiledPattern>,
@@ -134,6 +184,7 @@ mod runtime_impl {
         enum CompiledPattern {
             Exact(String),
             Glob(Pattern),
+            Regex(Regex),
         }
 
         impl PatternProcessor {
@@ -142,7 +193,12 @@ mod runtime_impl {
                 let mut exact_matches = HashSet::new();
 
                 for pattern in pass_patterns {
-                    if pattern.contains('*') || pattern.contains('?') {
+                    if let Some(expr) = pattern.strip_prefix(REGEX_PREFIX) {
+                        match Regex::new(expr) {
+                            Ok(re) => patterns.push(CompiledPattern::Regex(re)),
+                            Err(e) => warn!("⚠️ Ignoring invalid regex pattern '{}': {}", expr, e),
+                        }
+                    } else if pattern.contains('*') || pattern.contains('?') {
                         if let Ok(p) = Pattern::new(pattern) {
                             patterns.push(CompiledPattern::Glob(p));
                         } else {
@@ -174,11 +230,16 @@ mod runtime_impl {
                 }
 
                 for pattern in &self.patterns {
-                    if let CompiledPattern::Glob(glob) = pattern {
-                        if glob.matches(key) {
+                    match pattern {
+                        CompiledPattern::Glob(glob) if glob.matches(key) => {
                             trace!("✅ Variable '{}' matches glob pattern: {}", key, glob);
                             return true;
                         }
+                        CompiledPattern::Regex(re) if re.is_match(key) => {
+                            trace!("✅ Variable '{}' matches regex pattern: {}", key, re);
+                            return true;
+                        }
+                        _ => {}
                     }
                 }
 
@@ -213,10 +274,11 @@ mod runtime_impl {
     }
 
     mod operations {
-        use super::patterns::PatternProcessor;
+        use super::patterns::{PatternProcessor, REGEX_PREFIX};
         use crate::exceptions::{FlavorError, Result};
         use glob::Pattern;
         use log::{debug, trace, warn};
+        use regex::Regex;
         use std::collections::HashMap;
 
         /// Handles unset operations on environment variables
@@ -241,6 +303,9 @@ mod runtime_impl {
                     if pattern == "*" {
                         debug!("  Match: unset all except preserved");
                         self.unset_all_except_preserved(env_map)?;
+                    } else if let Some(expr) = pattern.strip_prefix(REGEX_PREFIX) {
+                        debug!("  Match: regex pattern");
+                        self.unset_regex_pattern(expr, env_map)?;
                     } else if pattern.contains('*') || pattern.contains('?') {
                         debug!("  Match: glob pattern");
                         self.unset_glob_pattern(pattern, env_map)?;
@@ -286,7 +351,7 @@ mod runtime_impl {
                 env_map: &mut HashMap<String, String>,
             ) -> Result<()> {
                 let glob_pattern = Pattern::new(pattern).map_err(|e| {
-                    FlavorError::Generic(format!("Invalid glob pattern '{}': {}", pattern, e))
+                    FlavorError::ConfigError(format!("Invalid glob pattern '{}': {}", pattern, e))
                 })?;
 
                 let matching_keys: Vec<String> = env_map
@@ -305,6 +370,28 @@ mod runtime_impl {
                 Ok(())
             }
 
+            fn unset_regex_pattern(
+                &self,
+                expr: &str,
+                env_map: &mut HashMap<String, String>,
+            ) -> Result<()> {
+                let re = Regex::new(expr).map_err(|e| {
+                    FlavorError::ConfigError(format!("Invalid regex pattern '{}': {}", expr, e))
+                })?;
+
+                let matching_keys: Vec<String> =
+                    env_map.keys().filter(|k| re.is_match(k)).cloned().collect();
+
+                for key in matching_keys {
+                    if !self.processor.should_preserve(&key) {
+                        env_map.remove(&key);
+                        trace!("  🗑️ Unset (regex): {}", key);
+                    }
+                }
+
+                Ok(())
+            }
+
             fn unset_exact_match(
                 &self,
                 key: &str,
@@ -318,15 +405,25 @@ mod runtime_impl {
         }
 
         /// Handles map operations on environment variables
+        ///
+        /// Each `OLD=NEW` mapping renames a variable, or with `copy` sets `NEW`
+        /// and keeps `OLD`. Whether a mapping copies is up to the caller, so no
+        /// variable name is ever read as an operator.
         pub(super) struct MapOperation<'a> {
             mappings: &'a [String],
+            copy: bool,
             processor: &'a PatternProcessor,
         }
 
         impl<'a> MapOperation<'a> {
-            pub(super) fn new(mappings: &'a [String], processor: &'a PatternProcessor) -> Self {
+            pub(super) fn new(
+                mappings: &'a [String],
+                copy: bool,
+                processor: &'a PatternProcessor,
+            ) -> Self {
                 Self {
                     mappings,
+                    copy,
                     processor,
                 }
             }
@@ -344,7 +441,12 @@ mod runtime_impl {
 
                     let (old_key, new_key) = (parts[0], parts[1]);
 
-                    if !self.processor.should_preserve(old_key) {
+                    if self.copy {
+                        if let Some(value) = env_map.get(old_key).cloned() {
+                            debug!("📋 Copied: {} -> {}", old_key, new_key);
+                            env_map.insert(new_key.to_string(), value);
+                        }
+                    } else if !self.processor.should_preserve(old_key) {
                         if let Some(value) = env_map.remove(old_key) {
                             debug!("🔄 Mapped: {} -> {}", old_key, new_key);
                             env_map.insert(new_key.to_string(), value);
@@ -387,3 +489,90 @@ mod runtime_impl {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RuntimeEnv, process_runtime_env};
+    use std::collections::HashMap;
+
+    fn env_of(keys: &[&str]) -> HashMap<String, String> {
+        keys.iter()
+            .map(|k| (k.to_string(), "value".to_string()))
+            .collect()
+    }
+
+    fn runtime_env(unset: &[&str], pass: &[&str]) -> RuntimeEnv {
+        RuntimeEnv {
+            unset: Some(unset.iter().map(|s| s.to_string()).collect()),
+            map: None,
+            copy: None,
+            set: None,
+            pass: Some(pass.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn test_unset_mixed_patterns() {
+        let mut env = env_of(&[
+            "HOME",
+            "AWS_KEY_SECRET",
+            "GCP_TOKEN_SECRET",
+            "AWS_REGION",
+            "LC_ALL",
+            "LANG",
+        ]);
+        let config = runtime_env(&["regex:^(AWS|GCP)_.*_SECRET$", "LC_*", "LANG"], &["HOME"]);
+
+        assert!(process_runtime_env(&mut env, &config, true).is_ok());
+
+        let mut keys: Vec<_> = env.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["AWS_REGION", "HOME"]);
+    }
+
+    #[test]
+    fn test_pass_mixed_patterns() {
+        let mut env = env_of(&["HOME", "PATH", "LC_ALL", "AWS_REGION", "GCP_PROJECT"]);
+        let config = runtime_env(&["*"], &["HOME", "LC_*", "regex:^AWS_"]);
+
+        assert!(process_runtime_env(&mut env, &config, true).is_ok());
+
+        let mut keys: Vec<_> = env.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["AWS_REGION", "HOME", "LC_ALL"]);
+    }
+
+    #[test]
+    fn test_copy_keeps_original_and_map_renames() {
+        let mut env = env_of(&["HOME", "LICENSE_KEY", "OLD_NAME", "ODD"]);
+        let mut config = runtime_env(&[], &[]);
+        config.copy = Some(HashMap::from([(
+            "LICENSE_KEY".to_string(),
+            "TOOL_LICENSE".to_string(),
+        )]));
+        // A target starting with '>' is still a rename
+        config.map = Some(HashMap::from([
+            ("OLD_NAME".to_string(), "NEW_NAME".to_string()),
+            ("ODD".to_string(), ">ODD".to_string()),
+        ]));
+
+        assert!(process_runtime_env(&mut env, &config, true).is_ok());
+
+        let mut keys: Vec<_> = env.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![">ODD", "HOME", "LICENSE_KEY", "NEW_NAME", "TOOL_LICENSE"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_regex_unset_is_ignored() {
+        let mut env = env_of(&["HOME", "PATH"]);
+        let config = runtime_env(&["regex:(unclosed"], &[]);
+
+        assert!(process_runtime_env(&mut env, &config, true).is_ok());
+
+        assert_eq!(env.len(), 2);
+    }
+}
@@ -0,0 +1,319 @@
+//! Slots as `std::io::Read` streams
+//!
+//! A [`SlotReader`] yields a slot's decoded bytes, undoing its operation chain
+//! as it goes, so slots compose with other readers:
+//! `tar::Archive::new(reader.slot_reader(0)?)`. Stored bytes are read in
+//! chunks through a [`StreamBackend`] and hashed on the way; the descriptor
+//! checksum is checked once the stream is exhausted.
+
+use super::backends::{Backend, StreamBackend};
+use super::extraction::decode_slot_stream;
+use super::slots::SlotDescriptor;
+use crate::exceptions::{FlavorError, Result};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Decoded slot bytes as a [`Read`] stream
+///
+/// Reads return `Ok(0)` only after the stored bytes have been checked against
+/// the descriptor checksum; a mismatch is returned instead as an
+/// [`io::ErrorKind::InvalidData`] error wrapping a
+/// [`FlavorError::ChecksumMismatch`]. Consumers that stop early, such as a tar
+/// reader ignoring the archive padding, can call [`SlotReader::finish`].
+pub struct SlotReader {
+    raw: Rc<RefCell<RawSlot>>,
+    decoded: Box<dyn Read>,
+}
+
+impl std::fmt::Debug for SlotReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlotReader")
+            .field("raw", &self.raw.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SlotReader {
+    /// Stream a slot straight from the package file at `path`
+    ///
+    /// With `verify`, the stored bytes are checked against the descriptor
+    /// checksum (descriptors with a zero checksum are never checked).
+    pub(crate) fn open(
+        path: &Path,
+        descriptor: &SlotDescriptor,
+        operations: &[u8],
+        slot_index: usize,
+        verify: bool,
+    ) -> Result<Self> {
+        let mut backend = StreamBackend::with_default_chunk_size();
+        backend.open(path)?;
+        let raw = RawSlot {
+            backend: Some(backend),
+            start: descriptor.offset,
+            offset: descriptor.offset,
+            remaining: descriptor.size,
+            chunk: Vec::new(),
+            pos: 0,
+            expected: descriptor.checksum,
+            hasher: (verify && descriptor.checksum != 0).then(Sha256::new),
+        };
+        Self::decode(raw, operations, slot_index)
+    }
+
+    /// Stream a slot whose stored bytes are already in memory
+    ///
+    /// Used for sealed slots, which are opened in memory, and for
+    /// packages read from stdin. `data` is expected to be checked already.
+    pub(crate) fn from_bytes(data: Vec<u8>, operations: &[u8], slot_index: usize) -> Result<Self> {
+        let raw = RawSlot {
+            backend: None,
+            start: 0,
+            offset: 0,
+            remaining: 0,
+            chunk: data,
+            pos: 0,
+            expected: 0,
+            hasher: None,
+        };
+        Self::decode(raw, operations, slot_index)
+    }
+
+    fn decode(raw: RawSlot, operations: &[u8], slot_index: usize) -> Result<Self> {
+        let raw = Rc::new(RefCell::new(raw));
+        let decoded =
+            decode_slot_stream(Box::new(SharedRaw(Rc::clone(&raw))), operations, slot_index)?;
+        Ok(SlotReader { raw, decoded })
+    }
+
+    /// Read any stored bytes the consumer left unread and check the checksum
+    pub fn finish(self) -> Result<()> {
+        self.raw.borrow_mut().drain()
+    }
+}
+
+impl Read for SlotReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.decoded.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            // The decoders may stop short of the stored end (e.g. gzip trailers)
+            self.raw.borrow_mut().drain().map_err(into_io_error)?;
+        }
+        Ok(n)
+    }
+}
+
+/// Stored slot bytes, fetched a chunk at a time and hashed as they are read
+struct RawSlot {
+    backend: Option<StreamBackend>,
+    start: u64,
+    offset: u64,
+    remaining: u64,
+    chunk: Vec<u8>,
+    pos: usize,
+    expected: u64,
+    hasher: Option<Sha256>,
+}
+
+impl std::fmt::Debug for RawSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawSlot")
+            .field("start", &self.start)
+            .field("offset", &self.offset)
+            .field("remaining", &self.remaining)
+            .field("verify", &self.hasher.is_some())
+            .finish()
+    }
+}
+
+impl RawSlot {
+    /// Fetch and hash the next chunk of stored bytes
+    ///
+    /// Returns `false` once every stored byte has been fetched.
+    fn next_chunk(&mut self) -> Result<bool> {
+        let Some(backend) = self.backend.as_mut().filter(|_| self.remaining > 0) else {
+            return Ok(false);
+        };
+        // StreamBackend caps each read at its chunk size
+        let want = usize::try_from(self.remaining).unwrap_or(usize::MAX);
+        self.chunk = backend.read_at(self.offset, want)?;
+        self.pos = 0;
+        self.offset += self.chunk.len() as u64;
+        self.remaining -= self.chunk.len() as u64;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&self.chunk);
+        }
+        Ok(true)
+    }
+
+    /// Fetch whatever is left unread, then check the descriptor checksum
+    fn drain(&mut self) -> Result<()> {
+        while self.next_chunk()? {}
+        self.pos = self.chunk.len();
+
+        let Some(hasher) = &self.hasher else {
+            return Ok(());
+        };
+        let hash = hasher.clone().finalize();
+        let mut first = [0u8; 8];
+        first.copy_from_slice(&hash[..8]);
+        let actual = u64::from_le_bytes(first);
+        if actual == self.expected {
+            return Ok(());
+        }
+        Err(FlavorError::ChecksumMismatch {
+            subject: format!("slot at offset {:#x}", self.start),
+            expected: format!("{:016x}", self.expected),
+            actual: format!("{actual:016x}"),
+        })
+    }
+}
+
+impl Read for RawSlot {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.chunk.len() && !self.next_chunk().map_err(into_io_error)? {
+            self.drain().map_err(into_io_error)?;
+            return Ok(0);
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Surface a [`FlavorError`] through [`Read`], keeping IO errors as they are
+fn into_io_error(error: FlavorError) -> io::Error {
+    match error {
+        FlavorError::IoError(e) => e,
+        other => io::Error::new(io::ErrorKind::InvalidData, other),
+    }
+}
+
+/// Lets the decoders read the raw slot while [`SlotReader`] keeps a handle to it
+struct SharedRaw(Rc<RefCell<RawSlot>>);
+
+impl Read for SharedRaw {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.borrow_mut().read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::reader::Reader;
+    use crate::api::BuildOptions;
+    use std::io::Read;
+
+    #[test]
+    fn test_slot_reader_streams_and_verifies() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        let contents: Vec<u8> = (0..200_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        let mut tarball = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        assert!(
+            tarball
+                .append_data(&mut header, "sub/data.bin", &contents[..])
+                .is_ok()
+        );
+        let tarball = tarball.into_inner();
+        assert!(tarball.is_ok());
+        let Ok(tarball) = tarball else {
+            return;
+        };
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        assert!(std::io::Write::write_all(&mut gz, &tarball).is_ok());
+        let tgz = gz.finish();
+        assert!(tgz.is_ok());
+        let Ok(tgz) = tgz else { return };
+        assert!(std::fs::write(root.join("tree.tgz"), tgz).is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "slot-reader", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "tree", "source": root.join("tree.tgz"), "target": "tree", "operations": "tgz"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("slot-reader".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        let mut expected = Vec::new();
+        assert!(reader.copy_slot_to(0, &mut expected).is_ok());
+
+        // Odd-sized partial reads reassemble the same stream
+        let slot = reader.slot_reader(0);
+        assert!(slot.is_ok());
+        let Ok(mut slot) = slot else {
+            return;
+        };
+        let mut streamed = Vec::new();
+        let mut buf = [0u8; 7];
+        while let Ok(n @ 1..) = slot.read(&mut buf) {
+            streamed.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(streamed, expected);
+        assert!(slot.read(&mut buf).is_ok_and(|n| n == 0));
+
+        let slot = reader.slot_reader(0);
+        assert!(slot.is_ok());
+        let Ok(slot) = slot else {
+            return;
+        };
+        let mut archive = tar::Archive::new(slot);
+        assert!(archive.unpack(root.join("out")).is_ok());
+        assert!(std::fs::read(root.join("out/sub/data.bin")).is_ok_and(|d| d == contents));
+        assert!(archive.into_inner().finish().is_ok());
+        assert!(reader.slot_reader(1).is_err());
+
+        // Flip the last stored byte (the gzip trailer) so only the checksum notices
+        let descriptors = reader.read_slot_descriptors();
+        assert!(descriptors.is_ok());
+        let Ok(descriptors) = descriptors else {
+            return;
+        };
+        let end = (descriptors[0].offset + descriptors[0].size - 1) as usize;
+        drop(reader);
+        let bytes = std::fs::read(&package);
+        assert!(bytes.is_ok());
+        let Ok(mut bytes) = bytes else {
+            return;
+        };
+        bytes[end] ^= 0xFF;
+        assert!(std::fs::write(&package, &bytes).is_ok());
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        let slot = reader.slot_reader(0);
+        assert!(slot.is_ok());
+        let Ok(slot) = slot else {
+            return;
+        };
+        assert!(
+            slot.finish().is_err_and(|e| matches!(
+                e,
+                crate::exceptions::FlavorError::ChecksumMismatch { .. }
+            ))
+        );
+    }
+}
@@ -0,0 +1,584 @@
+//! Read-only directory view of a package's slots
+//!
+//! A [`SlotTree`] lays a package's slots out as a filesystem without extracting
+//! them: the root holds one entry per slot, named by slot id. A single-file slot
+//! is a file holding its decoded bytes; a tar slot is a directory of the
+//! archive's entries. Nodes are numbered like inodes, starting at
+//! [`ROOT_INODE`], and file contents are read on demand by streaming the slot
+//! through [`Reader::slot_reader`], so the tree serves the lookups, reads and
+//! directory listings a read-only filesystem such as a FUSE mount needs (the
+//! `mount` module serves it with the `fuse` feature). A [`ReadCursor`] keeps the
+//! stream open between reads, so reading a file front to back decodes its
+//! slot once.
+//!
+//! Building the tree streams each tar slot once to list its entries, which
+//! checks it against its descriptor checksum. Single-file slots are sized from
+//! their descriptor's `original_size` and not read at all. Reads stop at the
+//! requested range and are not checked. Slots inherited from a base package
+//! are left out: their contents live in the base's workenv.
+//!
+//! [`SlotTree::build_workenv`] lays the same nodes out as extraction would
+//! write them instead: tar entries at the root and single-file slots at their
+//! targets, which previews the workenv without extracting.
+
+use super::constants::OP_TAR;
+use super::execution::resolve_slot_target;
+use super::operations::unpack_operations;
+use super::reader::Reader;
+use super::slot_reader::SlotReader;
+use crate::exceptions::{FlavorError, Result};
+use log::{debug, warn};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Inode of the tree's root directory
+pub const ROOT_INODE: u64 = 1;
+
+/// Mode of single-file slots that don't record their permissions
+const DEFAULT_FILE_MODE: u32 = 0o644;
+
+/// Mode of the root and of directories implied by tar entry paths
+const DEFAULT_DIR_MODE: u32 = 0o755;
+
+/// A file, directory or symlink in a [`SlotTree`]
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// Entry name within its parent (empty for the root)
+    pub name: String,
+    /// Inode of the parent directory (the root is its own parent)
+    pub parent: u64,
+    /// Permission bits
+    pub mode: u32,
+    /// Modification time in unix seconds, 0 when unknown
+    pub mtime: u64,
+    /// What the node is
+    pub kind: NodeKind,
+}
+
+/// The kind of a [`Node`]
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+    /// A directory, mapping entry names to inodes
+    Directory(BTreeMap<String, u64>),
+    /// A file whose bytes are `size` bytes at `start` in slot `slot`'s decoded stream
+    File { slot: usize, start: u64, size: u64 },
+    /// A symbolic link
+    Symlink(PathBuf),
+}
+
+impl Node {
+    fn directory(name: &str, parent: u64, mode: u32, mtime: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            parent,
+            mode,
+            mtime,
+            kind: NodeKind::Directory(BTreeMap::new()),
+        }
+    }
+
+    /// Size in bytes: the data size for files, the target length for symlinks
+    pub fn size(&self) -> u64 {
+        match &self.kind {
+            NodeKind::Directory(_) => 0,
+            NodeKind::File { size, .. } => *size,
+            NodeKind::Symlink(target) => target.as_os_str().len() as u64,
+        }
+    }
+}
+
+/// A package's slots as a read-only tree of [`Node`]s
+#[derive(Debug, Clone)]
+pub struct SlotTree {
+    /// Nodes indexed by inode minus one
+    nodes: Vec<Node>,
+    /// Indexes of the slots left out of the tree
+    omitted: Vec<usize>,
+}
+
+/// The open stream of the file a [`SlotTree::read`] last read, and how far
+#[derive(Debug, Default)]
+pub struct ReadCursor {
+    open: Option<(u64, u64, io::Take<SlotReader>)>,
+}
+
+/// Where [`SlotTree`] places each slot
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// One root entry per slot, named by slot id
+    BySlot,
+    /// Where extraction writes the slot in the workenv
+    Workenv,
+}
+
+impl SlotTree {
+    /// Lay out the slots of the package `reader` is open on
+    pub fn build(reader: &mut Reader) -> Result<Self> {
+        Self::build_with(reader, Layout::BySlot)
+    }
+
+    /// Lay out the slots as extraction writes them into the workenv
+    ///
+    /// Tar entries land at the root and single-file slots at their targets.
+    /// Slots whose target resolves outside the workenv (`{home}`, `{cache}`,
+    /// `{config}`) are left out, like inherited slots.
+    pub fn build_workenv(reader: &mut Reader) -> Result<Self> {
+        Self::build_with(reader, Layout::Workenv)
+    }
+
+    fn build_with(reader: &mut Reader, layout: Layout) -> Result<Self> {
+        let metadata = reader.read_metadata()?.clone();
+        let descriptors = reader.read_slot_descriptors()?;
+        let mut tree = Self {
+            nodes: vec![Node::directory("", ROOT_INODE, DEFAULT_DIR_MODE, 0)],
+            omitted: Vec::new(),
+        };
+
+        for (index, slot) in metadata.slots.iter().enumerate() {
+            if slot.inherited.is_some() {
+                debug!("Leaving inherited slot {} out of the tree", slot.id);
+                tree.omitted.push(index);
+                continue;
+            }
+            let descriptor = descriptors.get(index).ok_or(FlavorError::SlotOutOfRange {
+                index,
+                count: descriptors.len(),
+            })?;
+            let is_tar = unpack_operations(descriptor.operations).contains(&OP_TAR);
+            let (parent, name) = match layout {
+                Layout::BySlot => {
+                    let name = match Path::new(&slot.id).components().collect::<Vec<_>>()[..] {
+                        [Component::Normal(name)] => name.to_string_lossy().into_owned(),
+                        _ => format!("slot-{index}"),
+                    };
+                    (ROOT_INODE, name)
+                }
+                // Tar slots unpack into the workenv root whatever their target
+                Layout::Workenv if is_tar => (ROOT_INODE, String::new()),
+                Layout::Workenv => {
+                    let target = resolve_slot_target(&slot.target);
+                    let names = normal_names(Path::new(&target));
+                    let Some((leaf, dirs)) = names.as_deref().and_then(<[String]>::split_last)
+                    else {
+                        debug!(
+                            "Leaving slot {} with target {target:?} out of the tree",
+                            slot.id
+                        );
+                        tree.omitted.push(index);
+                        continue;
+                    };
+                    (tree.directories(ROOT_INODE, dirs), leaf.clone())
+                }
+            };
+
+            if is_tar {
+                let slot_dir = if layout == Layout::BySlot {
+                    tree.insert(
+                        parent,
+                        &name,
+                        DEFAULT_DIR_MODE,
+                        0,
+                        NodeKind::Directory(BTreeMap::new()),
+                    )
+                } else {
+                    ROOT_INODE
+                };
+                tree.add_tar_slot(reader, index, slot_dir)?;
+            } else {
+                let size = descriptor.original_size; // Copy to avoid unaligned access
+                let mode = slot
+                    .permissions
+                    .as_deref()
+                    .and_then(|p| u32::from_str_radix(p, 8).ok())
+                    .unwrap_or(DEFAULT_FILE_MODE);
+                let mtime = slot.source_mtime.map_or(0, |t| t.max(0) as u64);
+                let kind = NodeKind::File {
+                    slot: index,
+                    start: 0,
+                    size,
+                };
+                tree.insert(parent, &name, mode, mtime, kind);
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Indexes of the slots the tree leaves out: inherited slots, and in the
+    /// workenv layout, slots extracted outside the workenv
+    pub fn omitted(&self) -> &[usize] {
+        &self.omitted
+    }
+
+    /// The node with inode `ino`
+    pub fn get(&self, ino: u64) -> Option<&Node> {
+        let index = usize::try_from(ino.checked_sub(1)?).ok()?;
+        self.nodes.get(index)
+    }
+
+    /// The inode of `name` in directory `parent`
+    pub fn lookup(&self, parent: u64, name: &str) -> Option<u64> {
+        match &self.get(parent)?.kind {
+            NodeKind::Directory(entries) => entries.get(name).copied(),
+            _ => None,
+        }
+    }
+
+    /// The inode at `path`, relative to the root; symlinks are not followed
+    pub fn resolve(&self, path: &Path) -> Option<u64> {
+        path.components()
+            .try_fold(ROOT_INODE, |ino, component| match component {
+                Component::Normal(name) => self.lookup(ino, name.to_str()?),
+                Component::ParentDir => Some(self.get(ino)?.parent),
+                Component::RootDir | Component::CurDir => Some(ino),
+                Component::Prefix(_) => None,
+            })
+    }
+
+    /// Read up to `len` bytes of file `ino` starting at `offset`
+    ///
+    /// Returns fewer bytes, or none, when the range runs past the end of the
+    /// file. A read that starts at or after where `cursor`'s last read of the
+    /// same file stopped continues its stream; any other read reopens the slot.
+    pub fn read(
+        &self,
+        reader: &mut Reader,
+        cursor: &mut ReadCursor,
+        ino: u64,
+        offset: u64,
+        len: usize,
+    ) -> Result<Vec<u8>> {
+        let mut stream = match cursor.open.take() {
+            Some((open_ino, position, mut stream)) if open_ino == ino && position <= offset => {
+                io::copy(&mut (&mut stream).take(offset - position), &mut io::sink())?;
+                stream
+            }
+            _ => self.open(reader, ino, offset)?,
+        };
+        let mut data = Vec::new();
+        (&mut stream).take(len as u64).read_to_end(&mut data)?;
+        cursor.open = Some((ino, offset + data.len() as u64, stream));
+        Ok(data)
+    }
+
+    /// Stream all of file `ino` into `sink`, returning the bytes written
+    pub fn copy_to(&self, reader: &mut Reader, ino: u64, sink: &mut impl Write) -> Result<u64> {
+        Ok(io::copy(&mut self.open(reader, ino, 0)?, sink)?)
+    }
+
+    /// A stream of file `ino`'s bytes from `offset` on
+    fn open(&self, reader: &mut Reader, ino: u64, offset: u64) -> Result<io::Take<SlotReader>> {
+        let node = self.get(ino).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("No node with inode {ino}"))
+        })?;
+        let NodeKind::File { slot, start, size } = node.kind else {
+            let kind = match node.kind {
+                NodeKind::Directory(_) => io::ErrorKind::IsADirectory,
+                _ => io::ErrorKind::InvalidInput,
+            };
+            return Err(
+                io::Error::new(kind, format!("{} is not a regular file", node.name)).into(),
+            );
+        };
+        let offset = offset.min(size);
+        let mut stream = reader.slot_reader(slot)?;
+        io::copy(&mut (&mut stream).take(start + offset), &mut io::sink())?;
+        Ok(stream.take(size - offset))
+    }
+
+    /// Add a tar slot's entries below directory `slot_dir`
+    fn add_tar_slot(&mut self, reader: &mut Reader, slot: usize, slot_dir: u64) -> Result<()> {
+        let mut archive = tar::Archive::new(reader.slot_reader(slot)?);
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let path = entry.path()?.into_owned();
+            let Some(names) = normal_names(&path) else {
+                warn!("⚠️ Skipping tar entry with unsafe path {path:?} in slot {slot}");
+                continue;
+            };
+            let Some((leaf, dirs)) = names.split_last() else {
+                continue;
+            };
+            let header = entry.header();
+            let (mode, mtime) = (
+                header.mode().unwrap_or(DEFAULT_FILE_MODE) & 0o7777,
+                header.mtime().unwrap_or(0),
+            );
+            let kind = match header.entry_type() {
+                tar::EntryType::Regular | tar::EntryType::Continuous => NodeKind::File {
+                    slot,
+                    start: entry.raw_file_position(),
+                    size: entry.size(),
+                },
+                tar::EntryType::Directory => NodeKind::Directory(BTreeMap::new()),
+                tar::EntryType::Symlink => match entry.link_name()? {
+                    Some(target) => NodeKind::Symlink(target.into_owned()),
+                    None => continue,
+                },
+                tar::EntryType::Link => {
+                    let target = entry.link_name()?.and_then(|t| normal_names(&t));
+                    let linked = target.and_then(|t| self.lookup_from(slot_dir, &t));
+                    match linked.and_then(|ino| self.get(ino)) {
+                        Some(node) => node.kind.clone(),
+                        None => continue,
+                    }
+                }
+                other => {
+                    debug!("Skipping {other:?} tar entry {path:?}");
+                    continue;
+                }
+            };
+            let parent = self.directories(slot_dir, dirs);
+            self.insert(parent, leaf, mode, mtime, kind);
+        }
+        // Read the archive padding too, so the slot checksum is checked
+        archive.into_inner().finish()
+    }
+
+    /// The inode of `names` below `dir`, without following symlinks
+    fn lookup_from(&self, dir: u64, names: &[String]) -> Option<u64> {
+        names
+            .iter()
+            .try_fold(dir, |ino, name| self.lookup(ino, name))
+    }
+
+    /// The directory `names` below `dir`, creating any that are missing
+    fn directories(&mut self, dir: u64, names: &[String]) -> u64 {
+        names
+            .iter()
+            .fold(dir, |ino, name| match self.lookup(ino, name) {
+                Some(child)
+                    if self
+                        .get(child)
+                        .is_some_and(|n| matches!(n.kind, NodeKind::Directory(_))) =>
+                {
+                    child
+                }
+                _ => self.insert(
+                    ino,
+                    name,
+                    DEFAULT_DIR_MODE,
+                    0,
+                    NodeKind::Directory(BTreeMap::new()),
+                ),
+            })
+    }
+
+    /// Add a node to directory `parent`, returning its inode
+    ///
+    /// A later entry with the same name replaces the earlier one, as it would
+    /// on extraction; a directory entry for an existing directory only updates
+    /// its mode and mtime.
+    fn insert(&mut self, parent: u64, name: &str, mode: u32, mtime: u64, kind: NodeKind) -> u64 {
+        let existing = self.lookup(parent, name);
+        let node = existing.and_then(|ino| self.nodes.get_mut(ino as usize - 1));
+        if let (Some(existing), Some(node)) = (existing, node) {
+            if let (NodeKind::Directory(_), NodeKind::Directory(_)) = (&node.kind, &kind) {
+                node.mode = mode;
+                node.mtime = mtime;
+                return existing;
+            }
+        }
+        let ino = self.nodes.len() as u64 + 1;
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent,
+            mode,
+            mtime,
+            kind,
+        });
+        if let Some(NodeKind::Directory(entries)) = self
+            .nodes
+            .get_mut(parent as usize - 1)
+            .map(|node| &mut node.kind)
+        {
+            entries.insert(name.to_string(), ino);
+        }
+        ino
+    }
+}
+
+/// The names in a relative tar path, or `None` if it leaves its directory
+fn normal_names(path: &Path) -> Option<Vec<String>> {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BuildOptions;
+    use std::fs;
+
+    /// Build a package with a tar slot, a file slot and a slot targeting
+    /// outside the workenv
+    fn build_browsable_package(root: &Path) -> PathBuf {
+        // No directory entries: pkg/ and pkg/sub/ are implied by the file paths
+        let mut tarball = tar::Builder::new(Vec::new());
+        for (path, contents) in [
+            ("pkg/__init__.py", &b""[..]),
+            ("./pkg/sub/data.txt", b"0123456789"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o640);
+            assert!(tarball.append_data(&mut header, path, contents).is_ok());
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        assert!(
+            tarball
+                .append_link(&mut header, "pkg/link", "sub/data.txt")
+                .is_ok()
+        );
+        let tarball = tarball.into_inner();
+        assert!(tarball.is_ok());
+        let Ok(tarball) = tarball else {
+            return PathBuf::new();
+        };
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        assert!(gz.write_all(&tarball).is_ok());
+        let tgz = gz.finish();
+        assert!(tgz.is_ok());
+        let Ok(tgz) = tgz else {
+            return PathBuf::new();
+        };
+        assert!(fs::write(root.join("app.tgz"), tgz).is_ok());
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        assert!(gz.write_all(&b"note\n".repeat(30)).is_ok());
+        assert!(
+            gz.finish()
+                .is_ok_and(|notes| fs::write(root.join("notes.gz"), notes).is_ok())
+        );
+        assert!(fs::write(root.join("config.json"), b"{\"debug\": true}").is_ok());
+        assert!(fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "browsable", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "app", "source": root.join("app.tgz"), "target": "app", "operations": "tgz"},
+                {"id": "config", "source": root.join("config.json"), "target": "{workenv}/etc/config.json"},
+                {"id": "outside", "source": root.join("config.json"), "target": "../outside.json"},
+                {"id": "notes", "source": root.join("notes.gz"), "target": "notes.txt", "operations": "gzip"},
+            ]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("slot-tree".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+        package
+    }
+
+    #[test]
+    fn test_slot_tree_maps_slots_and_tar_entries() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let package = build_browsable_package(dir.path());
+
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let tree = SlotTree::build(&mut reader);
+        assert!(tree.is_ok());
+        let Ok(tree) = tree else { return };
+        let mut cursor = ReadCursor::default();
+
+        let config = tree.resolve(Path::new("config"));
+        assert!(config.is_some_and(|ino| tree.get(ino).is_some_and(|n| n.size() == 15)));
+        let Some(config) = config else { return };
+        assert!(
+            tree.read(&mut reader, &mut cursor, config, 1, 5)
+                .is_ok_and(|d| d == b"\"debu")
+        );
+        // Sequential reads continue the open stream
+        assert!(
+            tree.read(&mut reader, &mut cursor, config, 6, 4)
+                .is_ok_and(|d| d == b"g\": ")
+        );
+
+        // Compressed single-file slots report their decoded size
+        let notes = tree.resolve(Path::new("notes"));
+        assert!(notes.is_some_and(|ino| tree.get(ino).is_some_and(|n| n.size() == 150)));
+
+        let data = tree.resolve(Path::new("/app/pkg/sub/data.txt"));
+        assert!(data.is_some_and(|ino| tree.get(ino).is_some_and(|n| n.size() == 10)));
+        let Some(data) = data else { return };
+        assert!(
+            tree.read(&mut reader, &mut cursor, data, 7, 100)
+                .is_ok_and(|d| d == b"789")
+        );
+        assert!(
+            tree.read(&mut reader, &mut cursor, data, 2, 3)
+                .is_ok_and(|d| d == b"234")
+        );
+        assert!(
+            tree.read(&mut reader, &mut cursor, data, 20, 5)
+                .is_ok_and(|d| d.is_empty())
+        );
+        let mut copied = Vec::new();
+        assert!(
+            tree.copy_to(&mut reader, data, &mut copied)
+                .is_ok_and(|n| n == 10)
+        );
+        assert_eq!(copied, b"0123456789");
+
+        let link = tree
+            .resolve(Path::new("app/pkg/link"))
+            .and_then(|ino| tree.get(ino));
+        assert!(link.is_some_and(
+            |n| matches!(&n.kind, NodeKind::Symlink(t) if t == Path::new("sub/data.txt"))
+        ));
+        let init = tree
+            .resolve(Path::new("app/pkg/__init__.py"))
+            .and_then(|ino| tree.get(ino));
+        assert!(init.is_some_and(|n| n.mode == 0o640 && n.size() == 0));
+
+        let sub = tree.resolve(Path::new("app/pkg/sub"));
+        assert!(sub.is_some_and(|ino| tree.lookup(ino, "data.txt") == Some(data)));
+        assert!(sub.is_some_and(|ino| tree.read(&mut reader, &mut cursor, ino, 0, 1).is_err()));
+        assert_eq!(
+            tree.resolve(Path::new("app/pkg/sub/..")),
+            tree.resolve(Path::new("app/pkg"))
+        );
+        assert!(tree.resolve(Path::new("app/missing")).is_none());
+        assert!(tree.omitted().is_empty());
+    }
+
+    #[test]
+    fn test_workenv_layout_places_slots_at_targets() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let package = build_browsable_package(dir.path());
+
+        // Tar entries land at the root and files at their targets
+        let reader = Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let workenv = SlotTree::build_workenv(&mut reader);
+        assert!(workenv.is_ok());
+        let Ok(workenv) = workenv else { return };
+        let size_at = |path: &str| {
+            workenv
+                .resolve(Path::new(path))
+                .and_then(|ino| workenv.get(ino))
+                .map(Node::size)
+        };
+        assert_eq!(size_at("pkg/sub/data.txt"), Some(10));
+        assert_eq!(size_at("etc/config.json"), Some(15));
+        assert_eq!(size_at("app"), None);
+        assert_eq!(size_at("config"), None);
+        assert_eq!(workenv.omitted(), [2]);
+    }
+}
@@ -2,11 +2,19 @@
 // PSPF 2025 Slot Management - Enhanced 64-byte descriptors
 
 use super::constants::{LifecycleCache, PurposeData, SLOT_DESCRIPTOR_SIZE};
-use super::defaults::{CACHE_NORMAL, DEFAULT_FILE_PERMS, DEFAULT_PAGE_SIZE};
+use super::defaults::{
+    CACHE_NORMAL, DEFAULT_EXECUTABLE_PERMS, DEFAULT_FILE_PERMS, DEFAULT_LIFECYCLE_CACHE,
+    DEFAULT_LIFECYCLE_CONFIG, DEFAULT_LIFECYCLE_DEV, DEFAULT_LIFECYCLE_EAGER,
+    DEFAULT_LIFECYCLE_INIT, DEFAULT_LIFECYCLE_LAZY, DEFAULT_LIFECYCLE_PLATFORM,
+    DEFAULT_LIFECYCLE_RUNTIME, DEFAULT_LIFECYCLE_SHUTDOWN, DEFAULT_LIFECYCLE_STARTUP,
+    DEFAULT_LIFECYCLE_TEMPORARY, DEFAULT_PAGE_SIZE, DEFAULT_PURPOSE_CODE, DEFAULT_PURPOSE_CONFIG,
+    DEFAULT_PURPOSE_DATA, DEFAULT_PURPOSE_MEDIA,
+};
+use crate::exceptions::{FlavorError, Result};
 use log::trace;
 use std::path::PathBuf;
 
-/// Slot descriptor - 64 bytes total
+/// Slot descriptor - 64 bytes total, little-endian on disk (see [`super::index::Index`])
 #[repr(C, packed)]
 #[derive(Clone, Copy, Debug)]
 pub struct SlotDescriptor {
@@ -30,6 +38,8 @@ pub struct SlotDescriptor {
     pub permissions_high: u8, // Unix-style permissions (high byte)
 }
 
+const _: () = assert!(std::mem::size_of::<SlotDescriptor>() == SLOT_DESCRIPTOR_SIZE);
+
 impl SlotDescriptor {
     /// Create a new slot descriptor
     pub fn new(id: u64) -> Self {
@@ -197,6 +207,110 @@ impl SlotMetadata {
     }
 }
 
+/// Default permissions for a slot that doesn't set `permissions` explicitly
+///
+/// Slots extracting under `bin/`, or whose purpose is `tool` or `code`, default
+/// to executable so packaged tools can launch without a per-slot `0755`.
+pub fn default_slot_permissions(target: &str, purpose: &str) -> u16 {
+    let target = target
+        .trim_start_matches("{workenv}")
+        .trim_start_matches('/')
+        .trim_start_matches("./");
+    let in_bin = target == "bin" || target.starts_with("bin/");
+
+    if in_bin || matches!(purpose, "tool" | "code") {
+        DEFAULT_EXECUTABLE_PERMS
+    } else {
+        DEFAULT_FILE_PERMS
+    }
+}
+
+/// Mode for a slot: its `permissions` octal string, or the default for its target and purpose
+///
+/// Accepts `"0755"`, `"755"` and `"0o755"`. Anything else is an error, and so is
+/// zero: a zero descriptor mode means "use the default", so `"000"` would
+/// otherwise extract as 0600.
+pub fn slot_permissions(permissions: Option<&str>, target: &str, purpose: &str) -> Result<u16> {
+    let Some(value) = permissions else {
+        return Ok(default_slot_permissions(target, purpose));
+    };
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    let mode = if !digits.is_empty() && digits.bytes().all(|b| matches!(b, b'0'..=b'7')) {
+        u16::from_str_radix(digits, 8).ok()
+    } else {
+        None
+    };
+    match mode {
+        Some(mode) if mode != 0 && mode <= 0o7777 => Ok(mode),
+        _ => Err(FlavorError::BuildError(format!(
+            "Invalid permissions '{value}': expected a non-zero octal mode such as 0755"
+        ))),
+    }
+}
+
+/// Descriptor purpose byte for a manifest purpose string
+///
+/// `payload` is an alias for `data`, and `tool` and `runtime` for `code`.
+/// Unknown purposes are stored as data.
+pub fn purpose_from_str(purpose: &str) -> u8 {
+    match purpose {
+        "data" | "payload" => DEFAULT_PURPOSE_DATA,
+        "code" | "tool" | "runtime" => DEFAULT_PURPOSE_CODE,
+        "config" => DEFAULT_PURPOSE_CONFIG,
+        "media" => DEFAULT_PURPOSE_MEDIA,
+        _ => DEFAULT_PURPOSE_DATA,
+    }
+}
+
+/// Canonical name of a descriptor purpose byte, if it is defined
+pub fn purpose_to_str(purpose: u8) -> Option<&'static str> {
+    match purpose {
+        DEFAULT_PURPOSE_DATA => Some("data"),
+        DEFAULT_PURPOSE_CODE => Some("code"),
+        DEFAULT_PURPOSE_CONFIG => Some("config"),
+        DEFAULT_PURPOSE_MEDIA => Some("media"),
+        _ => None,
+    }
+}
+
+/// Descriptor lifecycle byte for a manifest lifecycle string
+///
+/// `temp` is an alias for `temporary`. Unknown lifecycles are stored as runtime.
+pub fn lifecycle_from_str(lifecycle: &str) -> u8 {
+    match lifecycle {
+        "init" => DEFAULT_LIFECYCLE_INIT,
+        "startup" => DEFAULT_LIFECYCLE_STARTUP,
+        "runtime" => DEFAULT_LIFECYCLE_RUNTIME,
+        "shutdown" => DEFAULT_LIFECYCLE_SHUTDOWN,
+        "cache" => DEFAULT_LIFECYCLE_CACHE,
+        "temporary" | "temp" => DEFAULT_LIFECYCLE_TEMPORARY,
+        "lazy" => DEFAULT_LIFECYCLE_LAZY,
+        "eager" => DEFAULT_LIFECYCLE_EAGER,
+        "dev" => DEFAULT_LIFECYCLE_DEV,
+        "config" => DEFAULT_LIFECYCLE_CONFIG,
+        "platform" => DEFAULT_LIFECYCLE_PLATFORM,
+        _ => DEFAULT_LIFECYCLE_RUNTIME,
+    }
+}
+
+/// Canonical name of a descriptor lifecycle byte, if it is defined
+pub fn lifecycle_to_str(lifecycle: u8) -> Option<&'static str> {
+    match lifecycle {
+        DEFAULT_LIFECYCLE_INIT => Some("init"),
+        DEFAULT_LIFECYCLE_STARTUP => Some("startup"),
+        DEFAULT_LIFECYCLE_RUNTIME => Some("runtime"),
+        DEFAULT_LIFECYCLE_SHUTDOWN => Some("shutdown"),
+        DEFAULT_LIFECYCLE_CACHE => Some("cache"),
+        DEFAULT_LIFECYCLE_TEMPORARY => Some("temporary"),
+        DEFAULT_LIFECYCLE_LAZY => Some("lazy"),
+        DEFAULT_LIFECYCLE_EAGER => Some("eager"),
+        DEFAULT_LIFECYCLE_DEV => Some("dev"),
+        DEFAULT_LIFECYCLE_CONFIG => Some("config"),
+        DEFAULT_LIFECYCLE_PLATFORM => Some("platform"),
+        _ => None,
+    }
+}
+
 /// Align offset to boundary
 pub fn align_offset(offset: u64, alignment: u64) -> u64 {
     (offset + alignment - 1) & !(alignment - 1)
@@ -207,4 +321,90 @@ pub fn align_to_page(offset: u64) -> u64 {
     align_offset(offset, DEFAULT_PAGE_SIZE as u64)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purpose_and_lifecycle_mappings() {
+        let purposes = [("data", 0), ("code", 1), ("config", 2), ("media", 3)];
+        for (name, byte) in purposes {
+            assert_eq!(purpose_from_str(name), byte, "{name}");
+            assert_eq!(purpose_to_str(byte), Some(name));
+        }
+        for (alias, byte) in [("payload", 0), ("tool", 1), ("runtime", 1), ("other", 0)] {
+            assert_eq!(purpose_from_str(alias), byte, "{alias}");
+        }
+        assert_eq!(purpose_to_str(4), None);
+
+        let lifecycles = [
+            ("init", 0),
+            ("startup", 1),
+            ("runtime", 2),
+            ("shutdown", 3),
+            ("cache", 4),
+            ("temporary", 5),
+            ("lazy", 6),
+            ("eager", 7),
+            ("dev", 8),
+            ("config", 9),
+            ("platform", 10),
+        ];
+        for (name, byte) in lifecycles {
+            assert_eq!(lifecycle_from_str(name), byte, "{name}");
+            assert_eq!(lifecycle_to_str(byte), Some(name));
+        }
+        assert_eq!(lifecycle_from_str("temp"), 5);
+        assert_eq!(lifecycle_from_str("other"), 2);
+        assert_eq!(lifecycle_to_str(11), None);
+    }
+
+    #[test]
+    fn test_slot_permissions() {
+        for value in ["0755", "755", "0o755", "00755"] {
+            assert!(slot_permissions(Some(value), "app", "data").is_ok_and(|m| m == 0o755));
+        }
+        assert!(slot_permissions(Some("4755"), "app", "data").is_ok_and(|m| m == 0o4755));
+        assert!(
+            slot_permissions(None, "bin/tool", "data").is_ok_and(|m| m == DEFAULT_EXECUTABLE_PERMS)
+        );
+        assert!(slot_permissions(None, "data.txt", "data").is_ok_and(|m| m == DEFAULT_FILE_PERMS));
+
+        for value in [
+            "0", "000", "0o", "", "rwx", "0o0o755", "+755", "0888", "17777",
+        ] {
+            assert!(
+                slot_permissions(Some(value), "app", "data").is_err(),
+                "{value:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_descriptor_pack_is_little_endian() {
+        let mut descriptor = SlotDescriptor::new(0x0102_0304_0506_0708);
+        descriptor.offset = 0x1000;
+        descriptor.operations = 0x0110; // tar, gzip
+        descriptor.checksum = 0xDEAD_BEEF_0000_0001;
+        descriptor.permissions = 0xED;
+        descriptor.permissions_high = 0x01;
+
+        let bytes = descriptor.pack();
+        assert_eq!(
+            bytes[0..8],
+            [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+        assert_eq!(bytes[16..24], [0x00, 0x10, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes[40..42], [0x10, 0x01]);
+        assert_eq!(
+            bytes[48..56],
+            [0x01, 0x00, 0x00, 0x00, 0xEF, 0xBE, 0xAD, 0xDE]
+        );
+        assert_eq!(bytes[62..64], [0xED, 0x01]);
+
+        let unpacked = SlotDescriptor::unpack(&bytes);
+        assert!(unpacked.is_some_and(|u| u.pack() == bytes));
+    }
+}
+
 // 📦🎰🗂️🪄
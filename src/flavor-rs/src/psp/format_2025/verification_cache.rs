@@ -0,0 +1,211 @@
+//! Record of slots already verified, for incremental verify-only runs
+//!
+//! A verify-only launch normally hashes every slot. With incremental
+//! verification the launcher keeps a record in the workenv's instance metadata
+//! of the slots that passed, keyed by descriptor checksum and size, and later
+//! runs hash only the slots the record doesn't list: those added or changed
+//! since. Extraction still checks every slot it reads, so a skipped slot is
+//! never used unverified.
+//!
+//! The record is signed with HMAC-SHA256 under a random key kept in the cache
+//! root, readable only by its owner. A record that fails its MAC, or can't be
+//! read, counts as empty, so a forged record can't skip verification.
+
+use super::paths::WorkenvPaths;
+use super::slots::SlotDescriptor;
+use crate::exceptions::{FlavorError, Result};
+use hmac::{Hmac, Mac};
+use log::{debug, warn};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Write};
+
+/// Length of the record signing key
+const KEY_LEN: usize = 32;
+
+/// Slots whose data has been checked against their descriptor checksum
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VerifiedSlots {
+    slots: BTreeSet<String>,
+}
+
+/// On-disk form of [`VerifiedSlots`]
+#[derive(Serialize, Deserialize)]
+struct SignedRecord {
+    slots: BTreeSet<String>,
+    mac: String,
+}
+
+impl VerifiedSlots {
+    /// Load the record for a workenv; missing, unreadable or forged records
+    /// are empty
+    pub fn load(paths: &WorkenvPaths) -> Self {
+        let Ok(data) = fs::read(paths.verified_slots_file()) else {
+            return Self::default();
+        };
+        let Ok(record) = serde_json::from_slice::<SignedRecord>(&data) else {
+            warn!("⚠️ Ignoring unreadable slot verification record");
+            return Self::default();
+        };
+        let mac = hex::decode(&record.mac).ok();
+        let verified = read_key(paths).zip(mac).is_some_and(|(key, mac)| {
+            record_mac(&key, &record.slots).is_ok_and(|m| m.verify_slice(&mac).is_ok())
+        });
+        if !verified {
+            warn!("⚠️ Slot verification record failed its integrity check, verifying all slots");
+            return Self::default();
+        }
+        Self {
+            slots: record.slots,
+        }
+    }
+
+    /// Sign and write the record, creating the cache's key if needed
+    pub fn save(&self, paths: &WorkenvPaths) -> Result<()> {
+        let key = load_or_create_key(paths)?;
+        let record = SignedRecord {
+            slots: self.slots.clone(),
+            mac: hex::encode(record_mac(&key, &self.slots)?.finalize().into_bytes()),
+        };
+        let path = paths.verified_slots_file();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        crate::utils::write_atomic(&path, &serde_json::to_vec_pretty(&record)?)?;
+        debug!("💾 Saved {} verified slot(s) to {path:?}", self.slots.len());
+        Ok(())
+    }
+
+    /// Whether a slot with this descriptor has already been verified
+    pub fn contains(&self, descriptor: &SlotDescriptor) -> bool {
+        self.slots.contains(&slot_key(descriptor))
+    }
+
+    /// Record a slot whose data matched its descriptor checksum
+    pub fn insert(&mut self, descriptor: &SlotDescriptor) {
+        self.slots.insert(slot_key(descriptor));
+    }
+
+    /// Number of slots recorded
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether no slot is recorded
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+}
+
+fn slot_key(descriptor: &SlotDescriptor) -> String {
+    let (checksum, size) = (descriptor.checksum, descriptor.size);
+    format!("{checksum:016x}:{size}")
+}
+
+fn record_mac(key: &[u8], slots: &BTreeSet<String>) -> Result<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)
+        .map_err(|e| FlavorError::VerificationFailed(format!("Bad record key: {e}")))?;
+    for slot in slots {
+        mac.update(slot.as_bytes());
+        mac.update(b"\n");
+    }
+    Ok(mac)
+}
+
+fn read_key(paths: &WorkenvPaths) -> Option<Vec<u8>> {
+    fs::read(paths.verify_key_file())
+        .ok()
+        .filter(|key| key.len() == KEY_LEN)
+}
+
+/// Read the cache's signing key, generating it (owner-only) on first use
+fn load_or_create_key(paths: &WorkenvPaths) -> Result<Vec<u8>> {
+    let path = paths.verify_key_file();
+    if let Some(key) = read_key(paths) {
+        return Ok(key);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut key = vec![0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    match options.open(&path) {
+        Ok(mut file) => {
+            file.write_all(&key)?;
+            file.sync_all()?;
+            debug!("🔑 Created verification record key {path:?}");
+            Ok(key)
+        }
+        // Another launcher created it first
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => read_key(paths).ok_or_else(|| {
+            FlavorError::VerificationFailed(format!(
+                "Verification record key {} is malformed",
+                path.display()
+            ))
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn descriptor(checksum: u64, size: u64) -> SlotDescriptor {
+        let mut descriptor = SlotDescriptor::new(0);
+        descriptor.checksum = checksum;
+        descriptor.size = size;
+        descriptor
+    }
+
+    #[test]
+    fn test_verified_slots_round_trip_and_reject_forgery() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let paths = WorkenvPaths::new(dir.path().to_path_buf(), Path::new("app.psp"));
+
+        assert!(VerifiedSlots::load(&paths).is_empty());
+        let mut verified = VerifiedSlots::default();
+        verified.insert(&descriptor(0xabc, 10));
+        verified.insert(&descriptor(0xdef, 20));
+        assert!(verified.save(&paths).is_ok());
+
+        let loaded = VerifiedSlots::load(&paths);
+        assert_eq!(loaded, verified);
+        assert!(loaded.contains(&descriptor(0xabc, 10)));
+        assert!(!loaded.contains(&descriptor(0xabc, 11)));
+
+        // Adding a slot without the key invalidates the whole record
+        let data = fs::read_to_string(paths.verified_slots_file());
+        assert!(data.is_ok());
+        let Ok(data) = data else {
+            return;
+        };
+        let forged = data.replacen(
+            "\"slots\": [",
+            "\"slots\": [\n    \"0000000000000bad:5\",",
+            1,
+        );
+        assert!(fs::write(paths.verified_slots_file(), forged).is_ok());
+        assert!(VerifiedSlots::load(&paths).is_empty());
+
+        // As does losing the key
+        assert!(verified.save(&paths).is_ok());
+        assert!(fs::remove_file(paths.verify_key_file()).is_ok());
+        assert!(VerifiedSlots::load(&paths).is_empty());
+    }
+}
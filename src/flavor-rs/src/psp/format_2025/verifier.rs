@@ -1,29 +1,47 @@
 //! PSPF/2025 package verifier
 
-use super::constants::MAGIC_WAND_EMOJI_BYTES;
-use crate::api::VerifyResult;
+use super::attestation::RekorEntry;
+use super::constants::{MAGIC_TRAILER_SIZE, SLOT_DESCRIPTOR_SIZE};
+use super::index::Index;
+use super::metadata::Metadata;
+use super::slots::SlotDescriptor;
+use super::verification_cache::VerifiedSlots;
+use crate::api::{StreamingVerifyResult, VerifyOptions, VerifyResult};
 use crate::exceptions::{FlavorError, Result};
-use adler::Adler32;
 use ed25519_dalek::{Signature, Verifier as _, VerifyingKey};
 use flate2::read::GzDecoder;
 use log::{debug, info};
 use sha2::{Digest, Sha256};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 
 /// Verify a PSPF/2025 package
 pub fn verify(package_path: &Path) -> Result<VerifyResult> {
+    verify_with_options(package_path, VerifyOptions::default())
+}
+
+/// Verify a PSPF/2025 package, including the checks `options` opts into
+pub fn verify_with_options(package_path: &Path, options: VerifyOptions) -> Result<VerifyResult> {
     info!("Verifying PSPF/2025 package: {package_path:?}");
 
     let mut file = File::open(package_path)?;
     let file_size = file.metadata()?.len();
 
+    // A broken trailer leaves nothing else to check, so report it on its own
+    verify_trailer_structure(&mut file, file_size)?;
+    debug!("Trailer structure: ✅ VALID");
+
     // Read the index
     let mut reader = super::reader::Reader::new(package_path)?;
     let index = reader.read_index()?.clone();
     let metadata = reader.read_metadata()?.clone();
 
+    // Reject descriptors that point outside the slot data section
+    reader.read_slot_descriptors()?;
+
+    check_trusted_key(&index, &options)?;
+
     // Verify index checksum
     let index_checksum_valid = verify_index_checksum(&index);
     debug!(
@@ -58,7 +76,9 @@ pub fn verify(package_path: &Path) -> Result<VerifyResult> {
     );
 
     // Verify integrity seal (Ed25519 signature)
-    let integrity_seal_valid = verify_integrity_seal(&mut file, &index)?;
+    let json_bytes = read_metadata_json(&mut file, &index)?;
+    let integrity_seal_valid =
+        verify_integrity_seal(&json_bytes, &index, options.trusted_public_key.as_ref())?;
     debug!(
         "Integrity seal: {}",
         if integrity_seal_valid {
@@ -68,42 +88,455 @@ pub fn verify(package_path: &Path) -> Result<VerifyResult> {
         }
     );
 
-    // Verify trailing magic (8 bytes: 📦🪄)
-    let trailing_magic_valid = verify_trailing_magic(&mut file)?;
+    // Verify launcher bytes against the hash recorded at build time (if any)
+    let launcher_hash_valid = verify_launcher_hash(&mut file, &index)?;
     debug!(
-        "Trailing magic: {}",
-        if trailing_magic_valid {
-            "✅ VALID"
-        } else {
-            "❌ INVALID"
+        "Launcher hash: {}",
+        match launcher_hash_valid {
+            Some(true) => "✅ VALID",
+            Some(false) => "❌ INVALID",
+            None => "⏭️ NOT RECORDED",
         }
     );
 
-    // Overall signature validity
+    // Verify the embedded transparency log entry (opt-in, offline)
+    let attestation_valid = if options.verify_attestation {
+        verify_attestation(&json_bytes, &index)
+    } else {
+        None
+    };
     debug!(
-        "🔍 Verification results: index_checksum={}, metadata_checksum={}, size={}, integrity_seal={}, trailing_magic={}",
-        index_checksum_valid,
-        metadata_checksum_valid,
-        size_valid,
-        integrity_seal_valid,
-        trailing_magic_valid
+        "Attestation: {}",
+        match attestation_valid {
+            Some(true) => "✅ VALID",
+            Some(false) => "❌ INVALID",
+            None => "⏭️ NOT CHECKED",
+        }
     );
-    let signature_valid = index_checksum_valid
-        && metadata_checksum_valid
-        && size_valid
-        && integrity_seal_valid
-        && trailing_magic_valid;
-
-    Ok(VerifyResult {
-        format: "PSPF/2025".to_string(),
-        version: format!("0x{:08x}", super::constants::FORMAT_VERSION),
-        signature_valid,
-        slot_count: metadata.slots.len(),
-        package_name: metadata.package.name.clone(),
-        package_version: metadata.package.version.clone(),
+
+    Ok(Checks {
+        index_checksum: index_checksum_valid,
+        metadata_checksum: metadata_checksum_valid,
+        size: Some(size_valid),
+        integrity_seal: integrity_seal_valid,
+        launcher_hash: launcher_hash_valid,
+        attestation: attestation_valid,
+    }
+    .into_result(&metadata))
+}
+
+/// Outcomes of the individual checks behind a [`VerifyResult`]
+struct Checks {
+    index_checksum: bool,
+    metadata_checksum: bool,
+    /// `None` where a size mismatch already failed the verification
+    size: Option<bool>,
+    integrity_seal: bool,
+    launcher_hash: Option<bool>,
+    attestation: Option<bool>,
+}
+
+impl Checks {
+    fn into_result(self, metadata: &Metadata) -> VerifyResult {
+        debug!(
+            "🔍 Verification results: index_checksum={}, metadata_checksum={}, size={:?}, integrity_seal={}, launcher_hash={:?}",
+            self.index_checksum,
+            self.metadata_checksum,
+            self.size,
+            self.integrity_seal,
+            self.launcher_hash
+        );
+        let signature_valid = self.index_checksum
+            && self.metadata_checksum
+            && self.size != Some(false)
+            && self.integrity_seal
+            && self.launcher_hash != Some(false)
+            && self.attestation != Some(false);
+
+        VerifyResult {
+            format: "PSPF/2025".to_string(),
+            version: format!("0x{:08x}", super::constants::FORMAT_VERSION),
+            signature_valid,
+            index_checksum_valid: self.index_checksum,
+            launcher_hash_valid: self.launcher_hash,
+            attestation_valid: self.attestation,
+            slot_count: metadata.slots.len(),
+            package_name: metadata.package.name.clone(),
+            package_version: metadata.package.version.clone(),
+        }
+    }
+}
+
+/// A package carrying some other key cannot pass against a trusted one
+fn check_trusted_key(index: &Index, options: &VerifyOptions) -> Result<()> {
+    if let Some(trusted) = options.trusted_public_key {
+        if trusted.as_bytes() != &index.public_key {
+            return Err(FlavorError::SignatureVerificationFailed(format!(
+                "package is signed with key {}, not the trusted key {}",
+                hex::encode(index.public_key),
+                hex::encode(trusted.as_bytes())
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Verify a PSPF/2025 package in a single forward pass over the file
+///
+/// Makes the checks [`verify_with_options`] makes and, with `check_slots`,
+/// those of [`verify_slot_checksums`], reading each region once in file
+/// order: launcher, metadata, descriptor table, slots. The one exception is
+/// the MagicTrailer, read first because its index says where everything else
+/// is. Gaps between regions are skipped forward, so on tape or network
+/// storage the cost is one pass rather than a seek per check. A package whose
+/// regions are in some other order, or whose size disagrees with its index,
+/// fails with a `FormatError`.
+pub fn verify_streaming(
+    package_path: &Path,
+    options: VerifyOptions,
+    check_slots: bool,
+) -> Result<StreamingVerifyResult> {
+    info!("Verifying PSPF/2025 package in one pass: {package_path:?}");
+
+    let mut file = File::open(package_path)?;
+    let file_size = file.metadata()?.len();
+    let index = read_trailer_index(&mut file, file_size)?;
+    check_trusted_key(&index, &options)?;
+    let index_checksum = verify_index_checksum(&index);
+
+    file.seek(SeekFrom::Start(0))?;
+    let mut stream = ForwardReader {
+        inner: BufReader::new(file),
+        position: 0,
+        end: file_size - MAGIC_TRAILER_SIZE as u64,
+    };
+
+    let launcher_hash = match index.launcher_hash() {
+        Some(expected) => Some(stream.sha256(0, index.launcher_size, "launcher")? == expected),
+        None => None,
+    };
+
+    let metadata_bytes = stream.read(index.metadata_offset, index.metadata_size, "metadata")?;
+    let metadata_checksum: [u8; 32] = Sha256::digest(&metadata_bytes).into();
+    let json_bytes = metadata_json(&index, metadata_bytes)?;
+    let metadata =
+        Metadata::from_json(std::str::from_utf8(&json_bytes).map_err(|e| {
+            FlavorError::FormatError("metadata is not UTF-8".into()).with_source(e)
+        })?)?;
+    let integrity_seal =
+        verify_integrity_seal(&json_bytes, &index, options.trusted_public_key.as_ref())?;
+    let attestation = if options.verify_attestation {
+        verify_attestation(&json_bytes, &index)
+    } else {
+        None
+    };
+
+    let descriptors = stream.descriptors(&index)?;
+    let mismatched_slots = if check_slots {
+        Some(stream.slot_checksums(&descriptors)?)
+    } else {
+        None
+    };
+
+    let result = Checks {
+        index_checksum,
+        metadata_checksum: metadata_checksum == index.metadata_checksum,
+        // read_trailer_index already failed on a size mismatch
+        size: None,
+        integrity_seal,
+        launcher_hash,
+        attestation,
+    }
+    .into_result(&metadata);
+    Ok(StreamingVerifyResult {
+        result,
+        mismatched_slots,
     })
 }
 
+/// Read and parse the index from the MagicTrailer at the end of `file`
+fn read_trailer_index(file: &mut File, file_size: u64) -> Result<Index> {
+    if file_size < MAGIC_TRAILER_SIZE as u64 {
+        return Err(FlavorError::FormatError(format!(
+            "Invalid MagicTrailer: file is {file_size} bytes, smaller than the {MAGIC_TRAILER_SIZE}-byte trailer"
+        )));
+    }
+    file.seek(SeekFrom::End(-(MAGIC_TRAILER_SIZE as i64)))?;
+    let mut tail = vec![0u8; MAGIC_TRAILER_SIZE];
+    file.read_exact(&mut tail)?;
+    let index = Index::unpack(Index::parse_trailer(&tail)?)?;
+    index.check_compatibility()?;
+    super::reader::check_package_size(&index, file_size)?;
+    Ok(index)
+}
+
+/// Reads regions of a package in increasing offset order, never seeking back
+struct ForwardReader {
+    inner: BufReader<File>,
+    position: u64,
+    /// Where the slot data section ends and the MagicTrailer starts
+    end: u64,
+}
+
+impl ForwardReader {
+    /// Skip ahead to `offset`, checking `len` bytes from there fit before the trailer
+    fn advance_to(&mut self, offset: u64, len: u64, what: &str) -> Result<()> {
+        if offset < self.position {
+            return Err(FlavorError::FormatError(format!(
+                "{what} at {offset:#x} lies before {:#x}; the package is not laid out launcher, metadata, descriptors, slots and can't be verified in one pass",
+                self.position
+            )));
+        }
+        if offset.checked_add(len).is_none_or(|end| end > self.end) {
+            return Err(FlavorError::FormatError(format!(
+                "{what} {offset:#x}+{len} extends past the data section ending at {:#x}",
+                self.end
+            )));
+        }
+        self.inner
+            .seek_relative(i64::try_from(offset - self.position).unwrap_or(i64::MAX))?;
+        self.position = offset;
+        Ok(())
+    }
+
+    fn read(&mut self, offset: u64, len: u64, what: &str) -> Result<Vec<u8>> {
+        self.advance_to(offset, len, what)?;
+        let mut data = vec![0u8; len as usize];
+        self.inner.read_exact(&mut data)?;
+        self.position += len;
+        Ok(data)
+    }
+
+    fn sha256(&mut self, offset: u64, len: u64, what: &str) -> Result<[u8; 32]> {
+        self.advance_to(offset, len, what)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut (&mut self.inner).take(len), &mut hasher)?;
+        self.position += len;
+        Ok(hasher.finalize().into())
+    }
+
+    /// Read the slot descriptor table, rejecting descriptors outside the data section
+    fn descriptors(&mut self, index: &Index) -> Result<Vec<SlotDescriptor>> {
+        let (offset, count) = (index.slot_table_offset, index.slot_count as u64);
+        let table = self.read(offset, count * SLOT_DESCRIPTOR_SIZE as u64, "slot table")?;
+        let mut descriptors = Vec::with_capacity(count as usize);
+        for (slot, data) in table.chunks_exact(SLOT_DESCRIPTOR_SIZE).enumerate() {
+            let descriptor = SlotDescriptor::unpack(data).ok_or_else(|| {
+                FlavorError::FormatError(format!("Slot descriptor {slot} could not be parsed"))
+            })?;
+            if let Some(reason) = super::reader::slot_bounds_problem(&descriptor, self.end) {
+                return Err(FlavorError::DescriptorOutOfBounds { slot, reason });
+            }
+            descriptors.push(descriptor);
+        }
+        Ok(descriptors)
+    }
+
+    /// Hash each slot in file order; returns the indices that don't match
+    fn slot_checksums(&mut self, descriptors: &[SlotDescriptor]) -> Result<Vec<usize>> {
+        let mut order: Vec<usize> = (0..descriptors.len())
+            .filter(|&i| descriptors[i].checksum != 0)
+            .collect();
+        order.sort_by_key(|&i| descriptors[i].offset);
+
+        let mut mismatched = Vec::new();
+        for i in order {
+            let (offset, size, expected) = (
+                descriptors[i].offset,
+                descriptors[i].size,
+                descriptors[i].checksum,
+            );
+            let hash = self.sha256(offset, size, &format!("slot {i}"))?;
+            let mut first = [0u8; 8];
+            first.copy_from_slice(&hash[..8]);
+            if u64::from_le_bytes(first) == expected {
+                debug!("Slot {i} checksum: ✅ VALID");
+            } else {
+                debug!("Slot {i} checksum: ❌ INVALID");
+                mismatched.push(i);
+            }
+        }
+        mismatched.sort_unstable();
+        Ok(mismatched)
+    }
+}
+
+/// Verify every slot's data against its descriptor checksum
+///
+/// Returns the indices of slots whose data does not match. Descriptors with a
+/// zero checksum (placeholders with no stored data) are skipped.
+pub fn verify_slot_checksums(reader: &mut super::reader::Reader) -> Result<Vec<usize>> {
+    check_slots(reader, None)
+}
+
+/// Verify only the slots `verified` doesn't list, adding those that match
+///
+/// A slot counts as already verified when a slot with the same descriptor
+/// checksum and size passed before; see [`super::verification_cache`].
+pub fn verify_changed_slot_checksums(
+    reader: &mut super::reader::Reader,
+    verified: &mut VerifiedSlots,
+) -> Result<Vec<usize>> {
+    check_slots(reader, Some(verified))
+}
+
+fn check_slots(
+    reader: &mut super::reader::Reader,
+    mut verified: Option<&mut VerifiedSlots>,
+) -> Result<Vec<usize>> {
+    let descriptors = reader.read_slot_descriptors()?;
+    let mut mismatched = Vec::new();
+
+    for (i, descriptor) in descriptors.iter().enumerate() {
+        let expected = descriptor.checksum;
+        if expected == 0 {
+            continue;
+        }
+        if verified.as_ref().is_some_and(|v| v.contains(descriptor)) {
+            debug!("Slot {i} checksum: ⏭️ VERIFIED PREVIOUSLY");
+            continue;
+        }
+
+        // Read raw bytes so a mismatch is reported rather than raised
+        let data = reader.backend_mut().read_slot(descriptor)?;
+        let hash = Sha256::digest(&data);
+        let mut first = [0u8; 8];
+        first.copy_from_slice(&hash[..8]);
+        let actual = u64::from_le_bytes(first);
+
+        if actual == expected {
+            debug!("Slot {i} checksum: ✅ VALID");
+            if let Some(verified) = verified.as_deref_mut() {
+                verified.insert(descriptor);
+            }
+        } else {
+            debug!("Slot {i} checksum: ❌ INVALID (expected {expected:016x}, got {actual:016x})");
+            mismatched.push(i);
+        }
+    }
+
+    Ok(mismatched)
+}
+
+/// A package's metadata signature, detached from the package
+#[derive(Debug, Clone)]
+pub struct DetachedSignature {
+    /// Raw Ed25519 signature over the uncompressed metadata JSON
+    pub signature: [u8; ed25519_dalek::SIGNATURE_LENGTH],
+    /// Key the package was signed with
+    pub public_key: VerifyingKey,
+}
+
+impl DetachedSignature {
+    /// The public key as a SubjectPublicKeyInfo PEM
+    pub fn public_key_pem(&self) -> String {
+        super::keys::public_key_pem(&self.public_key)
+    }
+}
+
+/// Export the package's metadata signature as a detached signature
+///
+/// The signature covers the uncompressed metadata JSON, which pins every
+/// slot's SHA-256, so it vouches for the package contents as a whole.
+pub fn export_signature(package_path: &Path) -> Result<DetachedSignature> {
+    let mut reader = super::reader::Reader::new(package_path)?;
+    let index = reader.read_index()?;
+    if index.integrity_signature[..64].iter().all(|&b| b == 0) {
+        return Err(FlavorError::VerificationFailed(
+            "Package is not signed".to_string(),
+        ));
+    }
+
+    let mut signature = [0u8; ed25519_dalek::SIGNATURE_LENGTH];
+    signature.copy_from_slice(&index.integrity_signature[..64]);
+    let public_key = VerifyingKey::from_bytes(&index.public_key)
+        .map_err(|e| FlavorError::VerificationFailed(format!("Invalid public key: {e}")))?;
+    Ok(DetachedSignature {
+        signature,
+        public_key,
+    })
+}
+
+/// Verify a package against a detached signature from [`export_signature`]
+///
+/// `signature_path` holds the raw 64-byte signature and `public_key_path` the
+/// trusted key as a PEM; the key embedded in the package is not used. Slot
+/// data is checked against the signed metadata checksums, which describe
+/// slots before encryption: slots of an encrypted package are decrypted first,
+/// with `decryption_key` or else `FLAVOR_DECRYPTION_KEY`. A slot with data but
+/// no signed checksum fails verification.
+pub fn verify_detached(
+    package_path: &Path,
+    signature_path: &Path,
+    public_key_path: &Path,
+    decryption_key: Option<&super::encryption::EncryptionKey>,
+) -> Result<bool> {
+    let detached = std::fs::read(signature_path)?;
+    let sig_array: [u8; ed25519_dalek::SIGNATURE_LENGTH] =
+        detached.as_slice().try_into().map_err(|_| {
+            FlavorError::VerificationFailed(format!(
+                "{} is not a detached signature ({} bytes, expected {})",
+                signature_path.display(),
+                detached.len(),
+                ed25519_dalek::SIGNATURE_LENGTH
+            ))
+        })?;
+    let public_key = super::keys::load_public_key(public_key_path)?;
+
+    let mut file = File::open(package_path)?;
+    let mut reader = super::reader::Reader::new(package_path)?;
+    let index = reader.read_index()?.clone();
+    let json_bytes = read_metadata_json(&mut file, &index)?;
+    if public_key
+        .verify(&json_bytes, &Signature::from_bytes(&sig_array))
+        .is_err()
+    {
+        debug!("Detached signature does not match the package metadata");
+        return Ok(false);
+    }
+
+    let encrypted = index.encryption_type != super::encryption::ENCRYPTION_NONE;
+    if encrypted {
+        let key = super::encryption::resolve_decryption_key(decryption_key)?.ok_or_else(|| {
+            FlavorError::VerificationFailed(
+                "Package slots are encrypted; set FLAVOR_DECRYPTION_KEY to verify them".to_string(),
+            )
+        })?;
+        reader.set_decryption_key(&key)?;
+    }
+
+    let metadata = reader.read_metadata()?.clone();
+    let descriptors = reader.read_slot_descriptors()?;
+    if metadata.slots.len() != descriptors.len() {
+        return Ok(false);
+    }
+    for (slot, descriptor) in metadata.slots.iter().zip(&descriptors) {
+        if slot.checksum.is_empty() {
+            // Self-referential and inherited slots store no bytes to check
+            if descriptor.size == 0 {
+                continue;
+            }
+            return Err(FlavorError::VerificationFailed(format!(
+                "Slot {} has no signed checksum",
+                slot.index
+            )));
+        }
+        let mut hasher = Sha256::new();
+        if encrypted {
+            hasher.update(reader.read_slot(descriptor)?);
+        } else {
+            file.seek(SeekFrom::Start(descriptor.offset))?;
+            std::io::copy(&mut (&mut file).take(descriptor.size), &mut hasher)?;
+        }
+        if format!("sha256:{}", hex::encode(hasher.finalize())) != slot.checksum {
+            debug!("Slot {} does not match its signed checksum", slot.index);
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
 /// Verify the index checksum
 fn verify_index_checksum(index: &super::index::Index) -> bool {
     // Get the index bytes using the pack method
@@ -112,14 +545,28 @@ fn verify_index_checksum(index: &super::index::Index) -> bool {
     // Zero out the checksum field (offset 4-8 in 8192-byte header)
     index_bytes[4..8].copy_from_slice(&[0u8; 4]);
 
-    // Calculate Adler32 checksum
-    let mut adler = Adler32::new();
-    adler.write_slice(&index_bytes);
-    let calculated = adler.checksum();
+    // Calculate checksum with the algorithm selected by the index flags
+    let calculated = super::index::Index::calculate_checksum(index.flags, &index_bytes);
 
     calculated == index.index_checksum
 }
 
+/// Verify the launcher region against the index launcher hash
+///
+/// Returns `None` for packages built without a launcher hash.
+fn verify_launcher_hash(file: &mut File, index: &super::index::Index) -> Result<Option<bool>> {
+    let Some(expected) = index.launcher_hash() else {
+        return Ok(None);
+    };
+
+    file.seek(SeekFrom::Start(0))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file.take(index.launcher_size), &mut hasher)?;
+    let calculated: [u8; 32] = hasher.finalize().into();
+
+    Ok(Some(calculated == expected))
+}
+
 /// Verify the metadata checksum
 fn verify_metadata_checksum(file: &mut File, index: &super::index::Index) -> Result<bool> {
     // Read metadata bytes
@@ -136,37 +583,67 @@ fn verify_metadata_checksum(file: &mut File, index: &super::index::Index) -> Res
     Ok(calculated == index.metadata_checksum)
 }
 
-/// Verify the trailing magic (4 bytes: 🪄 at the very end)
-fn verify_trailing_magic(file: &mut File) -> Result<bool> {
-    // Seek to end minus 4 bytes (magic wand emoji)
-    file.seek(SeekFrom::End(-4))?;
-
-    // Read the last 4 bytes
-    let mut magic = [0u8; 4];
-    file.read_exact(&mut magic)?;
-
-    // Check if it matches the magic wand emoji
-    Ok(magic == MAGIC_WAND_EMOJI_BYTES)
+/// Check the MagicTrailer's size and structure: 📦, an index block of the
+/// size its format version uses, and 🪄 at the very end
+fn verify_trailer_structure(file: &mut File, file_size: u64) -> Result<()> {
+    let tail_size = file_size.min(MAGIC_TRAILER_SIZE as u64);
+    file.seek(SeekFrom::End(-(tail_size as i64)))?;
+    let mut tail = vec![0u8; tail_size as usize];
+    file.read_exact(&mut tail)?;
+    super::index::Index::parse_trailer(&tail).map(|_| ())
 }
 
-/// Verify the integrity seal (Ed25519 signature)
-fn verify_integrity_seal(file: &mut File, index: &super::index::Index) -> Result<bool> {
+/// Read and decompress the signed metadata JSON
+fn read_metadata_json(file: &mut File, index: &super::index::Index) -> Result<Vec<u8>> {
     // Read metadata
     file.seek(SeekFrom::Start(index.metadata_offset))?;
     let mut metadata_bytes = vec![0u8; index.metadata_size as usize];
     file.read_exact(&mut metadata_bytes)?;
 
-    // Decompress metadata if needed
-    let json_bytes = if true {
-        // Always gzip for now
-        let gz = GzDecoder::new(&metadata_bytes[..]);
-        let mut json_data = Vec::new();
-        gz.take(1024 * 1024).read_to_end(&mut json_data)?;
-        json_data
-    } else {
-        metadata_bytes.clone()
+    metadata_json(index, metadata_bytes)
+}
+
+/// Decompress stored metadata bytes to the signed JSON, if they are compressed
+fn metadata_json(index: &Index, metadata_bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if !index.metadata_compressed() {
+        return Ok(metadata_bytes);
+    }
+    let gz = GzDecoder::new(&metadata_bytes[..]);
+    let mut json_data = Vec::new();
+    gz.take(1024 * 1024).read_to_end(&mut json_data)?;
+    Ok(json_data)
+}
+
+/// Verify the embedded Rekor entry against the metadata signature over `json_bytes`
+///
+/// Returns `None` for packages without an entry. A malformed or mismatched
+/// entry is reported as `Some(false)` rather than raised.
+fn verify_attestation(json_bytes: &[u8], index: &Index) -> Option<bool> {
+    let entry_json = index.attestation()?;
+
+    let mut sig_array = [0u8; 64];
+    sig_array.copy_from_slice(&index.integrity_signature[..64]);
+    let Ok(public_key) = VerifyingKey::from_bytes(&index.public_key) else {
+        return Some(false);
     };
 
+    let result = RekorEntry::from_json(&entry_json).and_then(|entry| {
+        entry.verify(json_bytes, &Signature::from_bytes(&sig_array), &public_key)
+    });
+    if let Err(ref e) = result {
+        debug!("Attestation check failed: {e:#}");
+    }
+    Some(result.is_ok())
+}
+
+/// Verify the integrity seal (Ed25519 signature) over the metadata JSON
+///
+/// Uses `trusted_key` instead of the embedded public key when given.
+fn verify_integrity_seal(
+    json_bytes: &[u8],
+    index: &Index,
+    trusted_key: Option<&VerifyingKey>,
+) -> Result<bool> {
     // Get signature from index
     let sig_bytes = &index.integrity_signature;
 
@@ -188,18 +665,22 @@ fn verify_integrity_seal(file: &mut File, index: &super::index::Index) -> Result
     // Parse signature (Ed25519 signatures are 64 bytes, stored at beginning of 512-byte field)
     let sig_array: [u8; 64] = sig_bytes[..64]
         .try_into()
-        .map_err(|_| FlavorError::Generic("Invalid signature size".to_string()))?;
+        .map_err(|_| FlavorError::FormatError("Invalid signature size".to_string()))?;
     let signature = Signature::from_bytes(&sig_array);
 
     // Parse public key
-    let key_array: [u8; 32] = public_key_bytes[..]
-        .try_into()
-        .map_err(|_| FlavorError::Generic("Invalid public key size".to_string()))?;
-    let public_key = VerifyingKey::from_bytes(&key_array)
-        .map_err(|e| FlavorError::Generic(format!("Invalid public key: {e}")))?;
+    let public_key = if let Some(key) = trusted_key {
+        *key
+    } else {
+        let key_array: [u8; 32] = public_key_bytes[..]
+            .try_into()
+            .map_err(|_| FlavorError::FormatError("Invalid public key size".to_string()))?;
+        VerifyingKey::from_bytes(&key_array)
+            .map_err(|e| FlavorError::FormatError(format!("Invalid public key: {e}")))?
+    };
 
     // Verify signature over JSON metadata
-    let valid = public_key.verify(&json_bytes, &signature).is_ok();
+    let valid = public_key.verify(json_bytes, &signature).is_ok();
 
     if valid {
         debug!("✅ Signature verification successful");
@@ -209,3 +690,311 @@ fn verify_integrity_seal(file: &mut File, index: &super::index::Index) -> Result
 
     Ok(valid)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BuildOptions;
+
+    #[test]
+    fn test_detached_signature_round_trip() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("a.txt"), b"signed contents").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "detached-sig", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = |seed: &str| BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some(seed.to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options("one")).is_ok());
+
+        let sig_path = root.join("pkg.psp.sig");
+        let key_path = root.join("pkg.psp.pem");
+        let exported = export_signature(&package);
+        assert!(exported.is_ok());
+        let Ok(exported) = exported else { return };
+        assert!(std::fs::write(&sig_path, exported.signature).is_ok());
+        assert!(std::fs::write(&key_path, exported.public_key_pem()).is_ok());
+        assert!(
+            super::super::keys::load_public_key(&key_path)
+                .is_ok_and(|key| key == exported.public_key)
+        );
+        assert!(verify_detached(&package, &sig_path, &key_path, None).is_ok_and(|valid| valid));
+
+        // A rebuild under another key no longer matches the exported signature
+        assert!(super::super::builder::build_from_str(&manifest, &package, options("two")).is_ok());
+        assert!(verify_detached(&package, &sig_path, &key_path, None).is_ok_and(|valid| !valid));
+
+        assert!(std::fs::write(&sig_path, b"short").is_ok());
+        assert!(verify_detached(&package, &sig_path, &key_path, None).is_err());
+    }
+
+    #[test]
+    fn test_detached_signature_covers_encrypted_slots() {
+        use super::super::encryption::{EncryptionAlgorithm, EncryptionKey, SlotEncryption};
+
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("a.txt"), b"sealed contents".repeat(10_000)).is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "detached-sealed", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let key = EncryptionKey::from_bytes([0x5a; 32]);
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("sealed".to_string()),
+            encryption: Some(SlotEncryption {
+                algorithm: EncryptionAlgorithm::Aes256Gcm,
+                key: key.clone(),
+            }),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let sig_path = root.join("pkg.psp.sig");
+        let key_path = root.join("pkg.psp.pem");
+        let exported = export_signature(&package);
+        assert!(exported.is_ok());
+        let Ok(exported) = exported else { return };
+        assert!(std::fs::write(&sig_path, exported.signature).is_ok());
+        assert!(std::fs::write(&key_path, exported.public_key_pem()).is_ok());
+        assert!(
+            verify_detached(&package, &sig_path, &key_path, Some(&key)).is_ok_and(|valid| valid)
+        );
+
+        let wrong_key = EncryptionKey::from_bytes([0xa5; 32]);
+        assert!(verify_detached(&package, &sig_path, &key_path, Some(&wrong_key)).is_err());
+    }
+
+    #[test]
+    fn test_trusted_public_key() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("a.txt"), b"trusted contents").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "trusted-key", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("signer".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let trusted = |seed: &str| VerifyOptions {
+            trusted_public_key: Some(super::super::keys::generate_keys_from_seed(seed).1),
+            ..VerifyOptions::default()
+        };
+        assert!(verify_with_options(&package, trusted("signer")).is_ok_and(|r| r.signature_valid));
+        assert!(
+            verify_with_options(&package, trusted("attacker"))
+                .is_err_and(|e| matches!(e, FlavorError::SignatureVerificationFailed(_)))
+        );
+    }
+
+    #[test]
+    fn test_streaming_verify_matches_verify() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("a.txt"), b"first slot").is_ok());
+        assert!(std::fs::write(root.join("b.txt"), b"second slot").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "streaming", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [
+                {"id": "a", "source": root.join("a.txt"), "target": "a.txt"},
+                {"id": "b", "source": root.join("b.txt"), "target": "b.txt"}
+            ]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("streaming".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+
+        let streamed = verify_streaming(&package, VerifyOptions::default(), true);
+        assert!(streamed.as_ref().is_ok_and(|s| s.result.signature_valid
+            && s.result.index_checksum_valid
+            && s.result.slot_count == 2
+            && s.mismatched_slots.as_deref() == Some(&[])));
+        let header_only = verify_streaming(&package, VerifyOptions::default(), false);
+        assert!(
+            header_only.is_ok_and(|s| s.result.signature_valid && s.mismatched_slots.is_none())
+        );
+
+        // A damaged slot is reported as in verify_slot_checksums
+        let slot_offset = super::super::reader::Reader::new(&package)
+            .and_then(|mut reader| Ok(reader.read_slot_descriptors()?[1].offset));
+        assert!(slot_offset.is_ok());
+        let Ok(slot_offset) = slot_offset else {
+            return;
+        };
+        let bytes = std::fs::read(&package);
+        assert!(bytes.is_ok());
+        let Ok(mut bytes) = bytes else {
+            return;
+        };
+        bytes[slot_offset as usize] ^= 0xFF;
+        assert!(std::fs::write(&package, &bytes).is_ok());
+        let streamed = verify_streaming(&package, VerifyOptions::default(), true);
+        assert!(
+            streamed.is_ok_and(
+                |s| s.result.signature_valid && s.mismatched_slots.as_deref() == Some(&[1])
+            )
+        );
+        let reader = super::super::reader::Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else {
+            return;
+        };
+        assert!(verify_slot_checksums(&mut reader).is_ok_and(|bad| bad == [1]));
+
+        // Bytes ahead of the package are a size mismatch, not a passing check
+        let mut prefixed = b"junk".to_vec();
+        prefixed.extend_from_slice(&bytes);
+        assert!(std::fs::write(&package, &prefixed).is_ok());
+        let streamed = verify_streaming(&package, VerifyOptions::default(), false);
+        assert!(streamed.is_err_and(|e| e.to_string().contains("Package size mismatch")));
+    }
+
+    #[test]
+    fn test_malformed_trailer_is_a_format_error() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("a.txt"), b"trailer contents").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = serde_json::json!({
+            "package": {"name": "trailer", "version": "1.0"},
+            "execution": {"command": "/bin/true"},
+            "slots": [{"id": "a", "source": root.join("a.txt"), "target": "a.txt"}]
+        })
+        .to_string();
+        let package = root.join("pkg.psp");
+        let options = BuildOptions {
+            launcher_bin: Some(root.join("launcher")),
+            key_seed: Some("trailer".to_string()),
+            ..BuildOptions::default()
+        };
+        assert!(super::super::builder::build_from_str(&manifest, &package, options).is_ok());
+        assert!(
+            verify_with_options(&package, VerifyOptions::default())
+                .is_ok_and(|r| r.signature_valid && r.index_checksum_valid)
+        );
+        let data = std::fs::read(&package);
+        assert!(data.is_ok());
+        let Ok(data) = data else {
+            return;
+        };
+        let trailer_error = |path: &Path| {
+            verify_with_options(path, VerifyOptions::default()).is_err_and(
+                |e| matches!(e, FlavorError::FormatError(ref m) if m.contains("MagicTrailer")),
+            )
+        };
+
+        // Cut off mid-trailer
+        let truncated = root.join("truncated.psp");
+        assert!(std::fs::write(&truncated, &data[..data.len() - 10]).is_ok());
+        assert!(trailer_error(&truncated));
+
+        // Too small to hold a trailer at all
+        let tiny = root.join("tiny.psp");
+        assert!(std::fs::write(&tiny, b"not a package").is_ok());
+        assert!(trailer_error(&tiny));
+
+        // Closing magic intact but the 📦 clobbered
+        let mut clobbered = data.clone();
+        let start = clobbered.len() - MAGIC_TRAILER_SIZE;
+        clobbered[start] ^= 0xff;
+        let clobbered_path = root.join("clobbered.psp");
+        assert!(std::fs::write(&clobbered_path, &clobbered).is_ok());
+        assert!(trailer_error(&clobbered_path));
+    }
+
+    #[test]
+    fn test_changed_slot_checksums_skip_recorded_slots() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let root = dir.path();
+
+        assert!(std::fs::write(root.join("a.txt"), b"slot a").is_ok());
+        assert!(std::fs::write(root.join("b.txt"), b"slot b").is_ok());
+        assert!(std::fs::write(root.join("launcher"), vec![0u8; 1024]).is_ok());
+        let manifest = |b_source: &str| {
+            serde_json::json!({
+                "package": {"name": "incremental", "version": "1.0"},
+                "execution": {"command": "/bin/true"},
+                "slots": [
+                    {"id": "a", "source": root.join("a.txt"), "target": "a.txt"},
+                    {"id": "b", "source": root.join(b_source), "target": "b.txt"}
+                ]
+            })
+            .to_string()
+        };
+        let package = root.join("pkg.psp");
+        let build = |b_source: &str| {
+            let options = BuildOptions {
+                launcher_bin: Some(root.join("launcher")),
+                key_seed: Some("incremental".to_string()),
+                ..BuildOptions::default()
+            };
+            super::super::builder::build_from_str(&manifest(b_source), &package, options)
+        };
+        assert!(build("b.txt").is_ok());
+
+        let mut verified = VerifiedSlots::default();
+        let reader = super::super::reader::Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let mismatched = verify_changed_slot_checksums(&mut reader, &mut verified);
+        assert!(mismatched.is_ok_and(|m| m.is_empty()));
+        assert_eq!(verified.len(), 2);
+
+        // A rebuild with one changed slot adds only that slot
+        assert!(std::fs::write(root.join("b2.txt"), b"slot b, changed").is_ok());
+        assert!(build("b2.txt").is_ok());
+        let reader = super::super::reader::Reader::new(&package);
+        assert!(reader.is_ok());
+        let Ok(mut reader) = reader else { return };
+        let mismatched = verify_changed_slot_checksums(&mut reader, &mut verified);
+        assert!(mismatched.is_ok_and(|m| m.is_empty()));
+        assert_eq!(verified.len(), 3);
+    }
+}
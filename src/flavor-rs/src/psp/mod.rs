@@ -1,59 +1,83 @@
 //! Package format implementations
+//!
+//! Each format lives in its own module and exposes a `detect` function that
+//! recognizes its packages. Supporting a new format means adding the module,
+//! a [`PackageFormat`] variant and an entry in `FORMATS`; the compiler then
+//! points at every dispatch that needs a branch for it.
 
 pub mod format_2025;
 
 use crate::exceptions::{FlavorError, Result};
+use std::fmt;
+use std::fs::File;
 use std::path::Path;
 
 /// Supported package formats
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum PackageFormat {
     PSPF2025,
 }
 
+impl PackageFormat {
+    /// Format identifier as written in manifests and metadata
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PSPF2025 => "PSPF/2025",
+        }
+    }
+}
+
+impl fmt::Display for PackageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Recognizes a format from an open package and its size
+type Detector = fn(&mut File, u64) -> Result<bool>;
+
+/// Known formats, newest first, with their detectors
+const FORMATS: &[(PackageFormat, Detector)] = &[(PackageFormat::PSPF2025, format_2025::detect)];
+
 /// Detect the format of a package by reading its magic bytes
 pub fn detect_format(package_path: &Path) -> Result<PackageFormat> {
-    use std::fs::File;
-    use std::io::{Read, Seek, SeekFrom};
-
     log::trace!("Detecting format for: {:?}", package_path);
     let mut file = File::open(package_path)?;
     let file_size = file.metadata()?.len();
     log::trace!("File size: {} bytes", file_size);
 
-    // A valid PSPF package MUST have the trailing emoji magic at the end
-    // Check the last 8 bytes for the emoji magic (📦🪄)
-    if file_size >= 8 {
-        file.seek(SeekFrom::End(-8))?;
-        let mut trailing = [0u8; 8];
-        file.read_exact(&mut trailing)?;
-
-        // Check for MagicTrailer (📦 + index + 🪄) using minimal reads
-        // The MagicTrailer is 8200 bytes total at the end of the file
-        if file_size >= format_2025::constants::MAGIC_TRAILER_SIZE as u64 {
-            // First check for 🪄 at the very end (last 4 bytes)
-            file.seek(SeekFrom::End(-4))?;
-            let mut magic_wand = [0u8; 4];
-            file.read_exact(&mut magic_wand)?;
-
-            if magic_wand == *format_2025::constants::MAGIC_WAND_EMOJI_BYTES {
-                // Now check for 📦 at the start of the trailer
-                file.seek(SeekFrom::End(
-                    -(format_2025::constants::MAGIC_TRAILER_SIZE as i64),
-                ))?;
-                let mut package_emoji = [0u8; 4];
-                file.read_exact(&mut package_emoji)?;
-
-                if package_emoji == *format_2025::constants::PACKAGE_EMOJI_BYTES {
-                    log::debug!("Found valid MagicTrailer at end of file");
-                    return Ok(PackageFormat::PSPF2025);
-                }
-            }
+    for &(format, detect) in FORMATS {
+        if detect(&mut file, file_size)? {
+            log::debug!("Detected {format} package");
+            return Ok(format);
         }
-        log::trace!("No valid MagicTrailer found");
     }
 
     Err(FlavorError::UnsupportedFormat(
         "Not a PSPF package".to_string(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use format_2025::Reader;
+
+    #[test]
+    fn test_forced_format_skips_detection() {
+        let dir = tempfile::tempdir();
+        assert!(dir.is_ok());
+        let Ok(dir) = dir else { return };
+        let path = dir.path().join("not-a-package");
+        assert!(std::fs::write(&path, vec![0u8; 16 * 1024]).is_ok());
+
+        assert!(matches!(
+            detect_format(&path),
+            Err(FlavorError::UnsupportedFormat(_))
+        ));
+        assert!(Reader::open(&path).is_err());
+        assert!(Reader::open_with_format(&path, PackageFormat::PSPF2025).is_ok());
+        assert_eq!(PackageFormat::PSPF2025.to_string(), "PSPF/2025");
+    }
+}
@@ -19,6 +19,24 @@ pub fn is_env_true(key: &str) -> bool {
     }
 }
 
+/// Write `contents` to `path` so readers see either the old file or the new one
+///
+/// The data goes to a temporary file in the same directory, is synced, and is
+/// then renamed over `path`; a crash mid-write leaves at most a stray temp file.
+pub fn write_atomic(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => std::path::Path::new("."),
+    };
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    temp.write_all(contents)?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
 /// Get normalized platform string in format 'os_arch'
 ///
 /// Returns strings like:
@@ -40,6 +58,46 @@ pub fn get_platform_string() -> String {
     format!("{os}_{arch}")
 }
 
+/// Get the current user's home directory (`HOME`, or `USERPROFILE` on Windows)
+pub fn get_home_dir() -> Option<std::path::PathBuf> {
+    if let Some(home) = env::var_os("HOME") {
+        return Some(home.into());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(profile) = env::var_os("USERPROFILE") {
+            return Some(profile.into());
+        }
+    }
+
+    None
+}
+
+/// Get the flavor configuration directory for the current platform
+/// Mirrors [`get_cache_dir`] using `XDG_CONFIG_HOME` and `~/.config`
+pub fn get_config_dir() -> std::path::PathBuf {
+    use std::path::PathBuf;
+
+    if let Ok(xdg_config) = env::var("XDG_CONFIG_HOME") {
+        return PathBuf::from(xdg_config).join("flavor");
+    }
+
+    if let Some(home) = env::var_os("HOME") {
+        return PathBuf::from(home).join(".config/flavor");
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Ok(app_data) = env::var("APPDATA") {
+            return PathBuf::from(app_data).join("flavor/config");
+        }
+    }
+
+    // Fallback to temp directory
+    env::temp_dir().join("flavor/config")
+}
+
 /// Get the appropriate cache directory for the current platform
 /// Uses XDG Base Directory Specification for consistency across all platforms
 pub fn get_cache_dir() -> std::path::PathBuf {
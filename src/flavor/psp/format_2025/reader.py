@@ -222,8 +222,7 @@ class PSPFReader:
                 f"Metadata checksum mismatch: expected {expected_checksum.hex()[:16]}..., got {actual_checksum.hex()[:16]}..."
             )
 
-        # Parse metadata (always gzipped JSON in current implementation)
-        # Decompress first
+        # Parse metadata (gzipped JSON, or plain JSON when too small to compress)
         with contextlib.suppress(gzip.BadGzipFile):
             metadata_data = gzip.decompress(metadata_data)
 
@@ -372,10 +371,11 @@ class PSPFReader:
         if isinstance(metadata_compressed, memoryview):
             metadata_compressed = bytes(metadata_compressed)
 
-        # Decompress to get the original JSON that was signed
-        import gzip
-
-        metadata_json = gzip.decompress(metadata_compressed)
+        # Decompress to get the original JSON that was signed; tiny metadata
+        # is stored as plain JSON
+        metadata_json = metadata_compressed
+        with contextlib.suppress(gzip.BadGzipFile):
+            metadata_json = gzip.decompress(metadata_compressed)
 
         verifier = Ed25519Verifier(index.public_key)
         return verifier.verify(metadata_json, signature)  # type: ignore[no-any-return]
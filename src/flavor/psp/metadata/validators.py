@@ -7,6 +7,7 @@
 
 This module contains validation logic for package metadata structures."""
 
+import posixpath
 from typing import Any
 
 
@@ -58,6 +59,9 @@ def _validate_workenv_section(metadata: dict[str, Any]) -> None:
     if "directories" in workenv:
         _validate_workenv_directories(workenv["directories"])
 
+    if "symlinks" in workenv:
+        _validate_workenv_symlinks(workenv["symlinks"])
+
     if "umask" in workenv:
         _validate_umask(workenv["umask"])
 
@@ -71,6 +75,39 @@ def _validate_workenv_directories(directories: list[dict[str, Any]]) -> None:
             _validate_mode(dir_info["mode"])
 
 
+def _validate_workenv_symlinks(symlinks: list[dict[str, Any]]) -> None:
+    """Validate workenv symlinks: both the link and its target stay in the workenv."""
+    for link_info in symlinks:
+        link = link_info.get("link")
+        target = link_info.get("target")
+        if not isinstance(link, str) or not isinstance(target, str):
+            raise ValueError(f"Workenv symlink needs a link and a target: {link_info}")
+
+        link_path = _workenv_relative(link)
+        if link_path is None or link_path == ".":
+            raise ValueError(f"Workenv symlink must be inside the workenv: {link}")
+        if target.startswith("{workenv}"):
+            target_path = _workenv_relative(target)
+        elif posixpath.isabs(target):
+            target_path = None
+        else:
+            target_path = _workenv_relative(posixpath.join(posixpath.dirname(link_path), target))
+        if target_path is None:
+            raise ValueError(f"Workenv symlink target must be inside the workenv: {link} -> {target}")
+
+
+def _workenv_relative(path: str) -> str | None:
+    """Normalize a path to be relative to the workenv, or None if it leaves it."""
+    if path.startswith("{workenv}"):
+        path = path[len("{workenv}") :].lstrip("/")
+    if posixpath.isabs(path):
+        return None
+    normalized = posixpath.normpath(path or ".")
+    if normalized == ".." or normalized.startswith("../"):
+        return None
+    return normalized
+
+
 def _validate_mode(mode: Any) -> None:
     """Validate file/directory mode format."""
     if not isinstance(mode, str):
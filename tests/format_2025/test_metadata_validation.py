@@ -80,6 +80,28 @@ class TestMetadataValidation:
             with pytest.raises(ValueError, match="Invalid umask"):
                 validate_metadata(metadata)
 
+    def test_workenv_symlinks_validation(self) -> None:
+        """Test workenv.symlinks must stay inside the workenv."""
+        valid = [
+            {"link": "{workenv}/bin/python", "target": "python3.11"},
+            {"link": "lib/python", "target": "../bin/python"},
+            {"link": "{workenv}/current", "target": "{workenv}/bin"},
+        ]
+        metadata = {"format": "PSPF/2025", "workenv": {"symlinks": valid}}
+        assert validate_metadata(metadata) is True
+
+        invalid = [
+            {"link": "bin/python", "target": "/usr/bin/python3"},
+            {"link": "bin/passwd", "target": "../../etc/passwd"},
+            {"link": "{workenv}/../python", "target": "bin/python3"},
+            {"link": "{workenv}", "target": "bin"},
+            {"link": "bin/up", "target": "{workenv}/.."},
+        ]
+        for symlink in invalid:
+            metadata = {"format": "PSPF/2025", "workenv": {"symlinks": [symlink]}}
+            with pytest.raises(ValueError, match="must be inside the workenv"):
+                validate_metadata(metadata)
+
     def test_execution_env_renamed(self) -> None:
         """Test that execution.environment was renamed to execution.env."""
         # Old format (should fail)